@@ -0,0 +1,169 @@
+use std::path::Path;
+
+const RED: &str = "\x1b[31m";
+const GREEN: &str = "\x1b[32m";
+const YELLOW: &str = "\x1b[33m";
+const CYAN: &str = "\x1b[36m";
+const BOLD: &str = "\x1b[1m";
+const RESET: &str = "\x1b[0m";
+
+/// Condense a line of xcodebuild output into a short step like `Compiling main.m`, in the spirit
+/// of `xcbeautify`. Returns `None` for lines that are only noise.
+pub fn format_line(line: &str, color: bool) -> Option<String> {
+    let line = line.trim_end();
+    let paint = |style: &str, text: &str| {
+        if color {
+            format!("{}{}{}", style, text, RESET)
+        } else {
+            text.to_string()
+        }
+    };
+
+    if line.contains("error: ") {
+        return Some(paint(RED, line.trim()));
+    }
+    if line.contains("warning: ") {
+        return Some(paint(YELLOW, line.trim()));
+    }
+    match line {
+        "** BUILD SUCCEEDED **" | "** ARCHIVE SUCCEEDED **" | "** TEST SUCCEEDED **" => {
+            let action = line.trim_matches(|c| c == '*' || c == ' ');
+            return Some(paint(GREEN, &capitalize(action)));
+        }
+        "** BUILD FAILED **" | "** ARCHIVE FAILED **" | "** TEST FAILED **" => {
+            let action = line.trim_matches(|c| c == '*' || c == ' ');
+            return Some(paint(RED, &capitalize(action)));
+        }
+        _ => {}
+    }
+
+    let (step, rest) = line.split_once(' ')?;
+    // Steps end with `(in target 'Game' from project 'Game')`.
+    let rest = rest.split(" (in target ").next().unwrap_or(rest);
+    let args = split_args(rest);
+    let (verb, subject) = match step {
+        // CompileC <object> <source> normal <arch> <language> ...
+        "CompileC" => ("Compiling", file_name(args.get(1)?)),
+        // SwiftCompile normal <arch> Compiling\ <files> <source>
+        "SwiftCompile" | "CompileSwift" => ("Compiling", file_name(args.last()?)),
+        "CompileSwiftSources" => ("Compiling", "Swift sources".to_string()),
+        "CompileAssetCatalog" | "CompileAssetCatalogVariant" => (
+            "Compiling",
+            file_name(args.iter().rev().find(|a| a.ends_with(".xcassets"))?),
+        ),
+        "CompileStoryboard" | "CompileXIB" => ("Compiling", file_name(args.first()?)),
+        "LinkStoryboards" => ("Linking", "storyboards".to_string()),
+        // Ld <output> normal <arch>
+        "Ld" => ("Linking", file_name(args.first()?)),
+        "ProcessInfoPlistFile" => ("Processing", file_name(args.last()?)),
+        "CodeSign" => ("Signing", file_name(args.first()?)),
+        "PhaseScriptExecution" => ("Running", format!("script {}", args.first()?)),
+        "GenerateDSYMFile" => ("Generating", file_name(args.first()?)),
+        "CopyPNGFile" | "CpResource" | "CopyStringsFile" => ("Copying", file_name(args.last()?)),
+        _ => return None,
+    };
+    Some(format!(
+        "{} {}",
+        paint(&format!("{}{}", BOLD, CYAN), verb),
+        subject
+    ))
+}
+
+/// Split xcodebuild step arguments at spaces that are not escaped with a backslash.
+fn split_args(rest: &str) -> Vec<String> {
+    let mut args = vec![];
+    let mut current = String::new();
+    let mut chars = rest.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => current.extend(chars.next()),
+            ' ' => {
+                if !current.is_empty() {
+                    args.push(std::mem::take(&mut current));
+                }
+            }
+            _ => current.push(c),
+        }
+    }
+    if !current.is_empty() {
+        args.push(current);
+    }
+    args
+}
+
+fn file_name(path: &str) -> String {
+    Path::new(path)
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| path.to_string())
+}
+
+fn capitalize(action: &str) -> String {
+    let lower = action.to_lowercase();
+    let mut chars = lower.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => lower,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn steps() {
+        let lines = [
+            "CompileC /build/Game.build/Objects-normal/arm64/main.o /game/src/main.m normal arm64 objective-c com.apple.compilers.llvm.clang.1_0.compiler (in target 'Game' from project 'Game')",
+            "    cd /game",
+            "    export LANG\\=en_US.US-ASCII",
+            "SwiftCompile normal arm64 Compiling\\ Bridge.swift /game/src/My\\ Bridge.swift (in target 'Game' from project 'Game')",
+            "CompileAssetCatalog /build/Game.app /game/assets/Assets.xcassets (in target 'Game' from project 'Game')",
+            "ProcessInfoPlistFile /build/Game.app/Info.plist /game/src/Info.plist (in target 'Game' from project 'Game')",
+            "Ld /build/Debug-iphoneos/Game.app/Game normal (in target 'Game' from project 'Game')",
+            "CodeSign /build/Debug-iphoneos/Game.app (in target 'Game' from project 'Game')",
+            "PhaseScriptExecution Build\\ Rust /build/Script-1.sh (in target 'Game' from project 'Game')",
+            "Touch /build/Debug-iphoneos/Game.app",
+            "/game/src/main.m:3:1: warning: unused variable 'x'",
+            "** BUILD SUCCEEDED **",
+        ];
+        let formatted = lines
+            .iter()
+            .filter_map(|line| format_line(line, false))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            formatted,
+            vec![
+                "Compiling main.m",
+                "Compiling My Bridge.swift",
+                "Compiling Assets.xcassets",
+                "Processing Info.plist",
+                "Linking Game",
+                "Signing Game.app",
+                "Running script Build Rust",
+                "/game/src/main.m:3:1: warning: unused variable 'x'",
+                "Build succeeded",
+            ]
+        );
+    }
+
+    #[test]
+    fn colors() {
+        assert_eq!(
+            format_line("** BUILD FAILED **", true).unwrap(),
+            "\x1b[31mBuild failed\x1b[0m"
+        );
+        assert_eq!(
+            format_line("Ld /build/Game.app/Game normal", true).unwrap(),
+            "\x1b[1m\x1b[36mLinking\x1b[0m Game"
+        );
+        assert_eq!(
+            format_line(
+                "error: Signing for \"Game\" requires a development team.",
+                true
+            )
+            .unwrap(),
+            "\x1b[31merror: Signing for \"Game\" requires a development team.\x1b[0m"
+        );
+    }
+}
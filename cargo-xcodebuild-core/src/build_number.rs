@@ -0,0 +1,75 @@
+use anyhow::Context as _;
+use std::path::Path;
+
+/// Counter file next to `Cargo.toml`. Commit it, so every machine continues the sequence.
+pub const BUILD_NUMBER_FILE: &str = ".build-number";
+
+/// Next build number of the persisted counter in `manifest_dir`. The counter is only saved
+/// with [`save_counter`], once the build succeeded.
+pub fn next_counter(manifest_dir: &Path) -> anyhow::Result<String> {
+    let path = manifest_dir.join(BUILD_NUMBER_FILE);
+    let current = if path.exists() {
+        parse_counter(
+            &std::fs::read_to_string(&path)
+                .with_context(|| format!("Failed to read {:?}", path))?,
+        )?
+    } else {
+        0
+    };
+
+    Ok((current + 1).to_string())
+}
+
+/// Persist `build_number` as the counter in `manifest_dir`.
+pub fn save_counter(manifest_dir: &Path, build_number: &str) -> anyhow::Result<()> {
+    let path = manifest_dir.join(BUILD_NUMBER_FILE);
+    std::fs::write(&path, format!("{}\n", build_number))
+        .with_context(|| format!("Failed to write {:?}", path))
+}
+
+/// Number of commits in the current git branch of `dir`.
+pub fn git_commit_count(dir: &Path) -> anyhow::Result<String> {
+    let output = std::process::Command::new("git")
+        .arg("rev-list")
+        .arg("--count")
+        .arg("HEAD")
+        .current_dir(dir)
+        .output()
+        .with_context(|| "Failed to get git output".to_string())?;
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+
+        anyhow::bail!("Failed to count git commits:\n{:?}\n{:?}", stdout, stderr)
+    }
+
+    Ok(stdout.trim().to_string())
+}
+
+fn parse_counter(content: &str) -> anyhow::Result<u64> {
+    content
+        .trim()
+        .parse()
+        .map_err(|_| anyhow::anyhow!("Invalid build number `{}`", content.trim()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counter() {
+        assert_eq!(parse_counter("41\n").unwrap(), 41);
+        assert!(parse_counter("1.0").is_err());
+    }
+
+    #[test]
+    fn counter_is_saved_separately() {
+        let temp = crate::test_util::TempDir::new("build-number");
+        assert_eq!(next_counter(temp.path()).unwrap(), "1");
+        assert_eq!(next_counter(temp.path()).unwrap(), "1");
+        save_counter(temp.path(), "1").unwrap();
+        assert_eq!(next_counter(temp.path()).unwrap(), "2");
+    }
+}
@@ -0,0 +1,81 @@
+use anyhow::Context as _;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Save a screenshot of a connected device to `path`, or to `screenshot-<timestamp>.png` in the
+/// current directory. Returns the path.
+pub fn device_screenshot(md: &md::MobileDevice, path: Option<&Path>) -> anyhow::Result<PathBuf> {
+    let data = md.screenshot()?;
+    let path = path.map_or_else(
+        || capture_path("screenshot", image_extension(&data)),
+        Path::to_path_buf,
+    );
+    std::fs::write(&path, data)
+        .with_context(|| format!("Failed to write screenshot to {:?}", path))?;
+    Ok(path)
+}
+
+/// Save a screenshot of the simulator `udid` to `path`, or to `screenshot-<timestamp>.png` in the
+/// current directory. Returns the path.
+pub fn simulator_screenshot(udid: &str, path: Option<&Path>) -> anyhow::Result<PathBuf> {
+    let path = path.map_or_else(|| capture_path("screenshot", "png"), Path::to_path_buf);
+    crate::simctl::screenshot(udid, &path)?;
+    Ok(path)
+}
+
+/// Record a video of the simulator `udid` to `path`, or to `recording-<timestamp>.mov` in the
+/// current directory, until Enter is pressed. Returns the path.
+pub fn record_simulator(udid: &str, path: Option<&Path>) -> anyhow::Result<PathBuf> {
+    let path = path.map_or_else(|| capture_path("recording", "mov"), Path::to_path_buf);
+    let mut recording = Command::new("xcrun")
+        .args(["simctl", "io", udid, "recordVideo", "--force"])
+        .arg(&path)
+        .spawn()
+        .with_context(|| "Failed to run xcrun simctl io recordVideo".to_string())?;
+    println!("Recording to {:?}. Press Enter to stop.", path);
+    let mut line = String::new();
+    std::io::stdin().read_line(&mut line)?;
+
+    // recordVideo finishes the file on SIGINT.
+    Command::new("kill")
+        .arg("-INT")
+        .arg(recording.id().to_string())
+        .status()
+        .with_context(|| "Failed to stop the recording".to_string())?;
+    let status = recording.wait()?;
+    if !status.success() {
+        anyhow::bail!("Failed to record simulator {}: {}", udid, status)
+    }
+
+    Ok(path)
+}
+
+/// File extension of a device screenshot. Older devices return TIFF images.
+fn image_extension(data: &[u8]) -> &'static str {
+    if data.starts_with(b"II*\0") || data.starts_with(b"MM\0*") {
+        "tiff"
+    } else {
+        "png"
+    }
+}
+
+/// `<prefix>-<unix timestamp>.<extension>` in the current directory.
+fn capture_path(prefix: &str, extension: &str) -> PathBuf {
+    let secs = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+    PathBuf::from(format!("{}-{}.{}", prefix, secs, extension))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn image_extensions() {
+        assert_eq!(image_extension(b"\x89PNG\r\n\x1a\n"), "png");
+        assert_eq!(image_extension(b"II*\0\x08\0\0\0"), "tiff");
+        assert_eq!(image_extension(b"MM\0*\0\0\0\x08"), "tiff");
+    }
+}
@@ -0,0 +1,213 @@
+use anyhow::Context as _;
+
+/// Profile overrides of [`crate::xcodebuild::BuildOptions::small`], without the profile name.
+const SMALL_PROFILE_SETTINGS: &[(&str, &str)] = &[
+    ("OPT_LEVEL", "z"),
+    ("LTO", "true"),
+    ("CODEGEN_UNITS", "1"),
+    ("PANIC", "abort"),
+];
+
+/// Environment overriding `profile` for [`crate::xcodebuild::BuildOptions::small`],
+/// e.g. `CARGO_PROFILE_RELEASE_OPT_LEVEL=z` for `release`.
+pub fn small_profile_env(profile: &str) -> Vec<(String, String)> {
+    let profile = profile.to_uppercase().replace('-', "_");
+    SMALL_PROFILE_SETTINGS
+        .iter()
+        .map(|&(key, value)| {
+            (
+                format!("CARGO_PROFILE_{}_{}", profile, key),
+                value.to_string(),
+            )
+        })
+        .collect()
+}
+
+/// Profile selected in `args` with `--release` or `--profile`, `None` for the default `dev`.
+pub fn selected_profile(args: &[String]) -> Option<String> {
+    let mut iter = args.iter();
+    let mut profile = None;
+    while let Some(arg) = iter.next() {
+        if arg == "--release" {
+            profile = Some("release".to_string());
+        } else if arg == "--profile" {
+            profile = iter.next().cloned();
+        } else if let Some(name) = arg.strip_prefix("--profile=") {
+            profile = Some(name.to_string());
+        }
+    }
+    profile
+}
+
+/// `args` with `--release` added unless a profile is already selected.
+pub fn with_release(args: &[String]) -> Vec<String> {
+    let mut args = args.to_vec();
+    if selected_profile(&args).is_none() {
+        args.push("--release".into());
+    }
+    args
+}
+
+/// Name of the cargo output directory of the profile selected in `args`, e.g. `debug` for
+/// `--profile dev`.
+pub fn profile_dir(args: &[String]) -> String {
+    match selected_profile(args).as_deref() {
+        None | Some("dev") | Some("test") => "debug".into(),
+        Some("bench") => "release".into(),
+        Some(name) => name.into(),
+    }
+}
+
+/// Manifest path of the workspace member `package`, from `cargo metadata` of the workspace
+/// containing `manifest_path`.
+pub fn package_manifest(
+    manifest_path: &std::path::Path,
+    package: &str,
+) -> anyhow::Result<std::path::PathBuf> {
+    let metadata = workspace_metadata(manifest_path)?;
+    find_package_manifest(&metadata, package).with_context(|| {
+        format!(
+            "Package `{}` is not a member of the workspace of {:?}",
+            package, manifest_path
+        )
+    })
+}
+
+/// Manifest paths of all members of the workspace containing `manifest_path`.
+pub fn workspace_manifests(
+    manifest_path: &std::path::Path,
+) -> anyhow::Result<Vec<std::path::PathBuf>> {
+    let metadata = workspace_metadata(manifest_path)?;
+    Ok(package_manifests(&metadata))
+}
+
+/// `cargo metadata` of the workspace members, without dependencies.
+fn workspace_metadata(manifest_path: &std::path::Path) -> anyhow::Result<serde_json::Value> {
+    let output = std::process::Command::new("cargo")
+        .arg("metadata")
+        .arg("--no-deps")
+        .arg("--format-version")
+        .arg("1")
+        .arg("--manifest-path")
+        .arg(manifest_path)
+        .output()
+        .with_context(|| "Failed to get cargo metadata output".to_string())?;
+
+    if !output.status.success() {
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+
+        anyhow::bail!("Failed to get cargo metadata:\n{:?}\n{:?}", stdout, stderr)
+    }
+
+    serde_json::from_slice(&output.stdout)
+        .with_context(|| "Failed to parse cargo metadata".to_string())
+}
+
+fn find_package_manifest(
+    metadata: &serde_json::Value,
+    package: &str,
+) -> Option<std::path::PathBuf> {
+    metadata["packages"]
+        .as_array()?
+        .iter()
+        .find(|p| p["name"].as_str() == Some(package))
+        .and_then(|p| p["manifest_path"].as_str())
+        .map(std::path::PathBuf::from)
+}
+
+fn package_manifests(metadata: &serde_json::Value) -> Vec<std::path::PathBuf> {
+    metadata["packages"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|p| p["manifest_path"].as_str())
+        .map(std::path::PathBuf::from)
+        .collect()
+}
+
+/// Run `cargo <cmd>` with the given arguments, optionally for a specific rustc target.
+pub fn run_cargo(cmd: &str, args: &[String], target: Option<&str>) -> anyhow::Result<()> {
+    run_cargo_with_env::<&str, &str>(cmd, args, target, &[])
+}
+
+/// Same as [`run_cargo`] with additional environment variables.
+pub fn run_cargo_with_env<K, V>(
+    cmd: &str,
+    args: &[String],
+    target: Option<&str>,
+    envs: &[(K, V)],
+) -> anyhow::Result<()>
+where
+    K: AsRef<std::ffi::OsStr>,
+    V: AsRef<std::ffi::OsStr>,
+{
+    let mut command = std::process::Command::new("cargo");
+    command.arg(cmd).envs(envs.iter().map(|(k, v)| (k, v)));
+    if let Some(t) = target {
+        command.arg("--target").arg(t);
+    }
+    let status = command
+        .args(args)
+        .status()
+        .with_context(|| format!("Failed to run cargo {} with args: {:?}", cmd, args,))?;
+    crate::process::check("cargo", status).with_context(|| match target {
+        Some(target) => format!("Failed to run cargo {} for {}", cmd, target),
+        None => format!("Failed to run cargo {}", cmd),
+    })?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn profile_dirs() {
+        let args = |args: &[&str]| args.iter().map(|a| a.to_string()).collect::<Vec<_>>();
+        assert_eq!(profile_dir(&args(&[])), "debug");
+        assert_eq!(profile_dir(&args(&["--release"])), "release");
+        assert_eq!(profile_dir(&args(&["--profile", "dev"])), "debug");
+        assert_eq!(profile_dir(&args(&["--profile=profiling"])), "profiling");
+    }
+
+    #[test]
+    fn release_profiles() {
+        let args = |args: &[&str]| args.iter().map(|a| a.to_string()).collect::<Vec<_>>();
+        assert_eq!(with_release(&args(&[])), args(&["--release"]));
+        assert_eq!(with_release(&args(&["--release"])), args(&["--release"]));
+        assert_eq!(
+            with_release(&args(&["--profile", "distribution"])),
+            args(&["--profile", "distribution"])
+        );
+        assert_eq!(
+            small_profile_env("app-store")[0],
+            (
+                "CARGO_PROFILE_APP_STORE_OPT_LEVEL".to_string(),
+                "z".to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn workspace_member() {
+        let metadata = serde_json::json!({
+            "packages": [
+                { "name": "core", "manifest_path": "/ws/core/Cargo.toml" },
+                { "name": "my_ios_app", "manifest_path": "/ws/ios/Cargo.toml" },
+            ]
+        });
+        assert_eq!(
+            find_package_manifest(&metadata, "my_ios_app"),
+            Some(std::path::PathBuf::from("/ws/ios/Cargo.toml"))
+        );
+        assert_eq!(find_package_manifest(&metadata, "desktop"), None);
+        assert_eq!(
+            package_manifests(&metadata),
+            vec![
+                std::path::PathBuf::from("/ws/core/Cargo.toml"),
+                std::path::PathBuf::from("/ws/ios/Cargo.toml"),
+            ]
+        );
+    }
+}
@@ -0,0 +1,104 @@
+use anyhow::Context as _;
+use std::path::Path;
+
+/// CocoaPods name of `platform` in `Podfile`s.
+fn podfile_platform(platform: crate::toml::AppPlatform) -> &'static str {
+    match platform {
+        crate::toml::AppPlatform::Ios => "ios",
+        crate::toml::AppPlatform::TvOs => "tvos",
+        crate::toml::AppPlatform::WatchOs => "watchos",
+        crate::toml::AppPlatform::MacOs => "osx",
+    }
+}
+
+/// `Podfile` integrating `config` into the `app_name` target of `<app_name>.xcodeproj`
+/// for `platform` with `deployment_target`.
+pub fn podfile(
+    app_name: &str,
+    platform: crate::toml::AppPlatform,
+    deployment_target: &str,
+    config: &crate::toml::CocoaPods,
+) -> String {
+    let mut podfile = format!(
+        "platform :{}, '{}'\nproject '{}.xcodeproj'\n\ntarget '{}' do\n",
+        podfile_platform(platform),
+        deployment_target,
+        app_name,
+        app_name
+    );
+    if config.use_frameworks {
+        podfile.push_str("  use_frameworks!\n");
+    }
+    for (name, version) in &config.pods {
+        if version.is_empty() || version == "*" {
+            podfile.push_str(&format!("  pod '{}'\n", name));
+        } else {
+            podfile.push_str(&format!("  pod '{}', '{}'\n", name, version));
+        }
+    }
+    podfile.push_str("end\n");
+    podfile
+}
+
+/// Write the `Podfile` and run `pod install` in `project_dir`, creating `<app_name>.xcworkspace`.
+pub fn install(
+    project_dir: &Path,
+    app_name: &str,
+    platform: crate::toml::AppPlatform,
+    deployment_target: &str,
+    config: &crate::toml::CocoaPods,
+) -> anyhow::Result<()> {
+    let path = project_dir.join("Podfile");
+    log::debug!("Writing {:?}", path);
+    std::fs::write(
+        &path,
+        podfile(app_name, platform, deployment_target, config),
+    )
+    .with_context(|| format!("Failed to write {:?}", path))?;
+
+    log::info!("Installing pods");
+    let output = std::process::Command::new("pod")
+        .arg("install")
+        .current_dir(project_dir)
+        .output()
+        .with_context(|| "Failed to run `pod install`. Is CocoaPods installed?".to_string())?;
+
+    if !output.status.success() {
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+
+        anyhow::bail!("Failed to install pods:\n{:?}\n{:?}", stdout, stderr)
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn podfile_pods() {
+        let config = crate::toml::CocoaPods {
+            pods: [
+                ("FirebaseAnalytics".to_string(), "~> 10.0".to_string()),
+                ("SDWebImage".to_string(), "*".to_string()),
+            ]
+            .into_iter()
+            .collect(),
+            use_frameworks: true,
+        };
+        assert_eq!(
+            podfile("my_game", crate::toml::AppPlatform::Ios, "13.0", &config),
+            "platform :ios, '13.0'\nproject 'my_game.xcodeproj'\n\ntarget 'my_game' do\n  use_frameworks!\n  pod 'FirebaseAnalytics', '~> 10.0'\n  pod 'SDWebImage'\nend\n"
+        );
+        assert!(
+            podfile("my_game", crate::toml::AppPlatform::MacOs, "11.0", &config)
+                .starts_with("platform :osx, '11.0'\n")
+        );
+        assert!(
+            podfile("my_game", crate::toml::AppPlatform::TvOs, "15.0", &config)
+                .starts_with("platform :tvos, '15.0'\n")
+        );
+    }
+}
@@ -0,0 +1,144 @@
+use anyhow::Context as _;
+use std::path::{Path, PathBuf};
+
+/// Directory next to `Cargo.toml` with app data snapshots.
+pub const SNAPSHOTS_DIR: &str = ".simulator-data";
+
+/// Archive path of the snapshot `name`. Fails for names that aren't a plain file name, like
+/// `../save`, which would point outside of [`SNAPSHOTS_DIR`].
+pub fn snapshot_path(manifest_dir: &Path, name: &str) -> anyhow::Result<PathBuf> {
+    if name.is_empty() || name.starts_with('.') || name.contains(['/', '\\']) {
+        anyhow::bail!(
+            "Invalid snapshot name `{}`, expected a name without `/`, `\\` or a leading `.`",
+            name
+        )
+    }
+    Ok(manifest_dir
+        .join(SNAPSHOTS_DIR)
+        .join(format!("{}.tar.gz", name)))
+}
+
+/// Names of saved snapshots.
+pub fn snapshots(manifest_dir: &Path) -> anyhow::Result<Vec<String>> {
+    let dir = manifest_dir.join(SNAPSHOTS_DIR);
+    if !dir.exists() {
+        return Ok(vec![]);
+    }
+
+    let mut names = std::fs::read_dir(&dir)
+        .with_context(|| format!("Failed to read dir: {:?}", dir))?
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            entry
+                .file_name()
+                .to_str()
+                .and_then(|name| name.strip_suffix(".tar.gz"))
+                .map(String::from)
+        })
+        .collect::<Vec<_>>();
+    names.sort();
+    Ok(names)
+}
+
+/// Archive the data container of the app `bundle_id` on the simulator `udid` as snapshot `name`.
+/// Returns the archive path.
+pub fn save_app(
+    udid: &str,
+    bundle_id: &str,
+    manifest_dir: &Path,
+    name: &str,
+) -> anyhow::Result<PathBuf> {
+    let container = crate::simctl::app_container(udid, bundle_id, "data")?;
+    let archive = snapshot_path(manifest_dir, name)?;
+    log::info!("Saving {:?} to {:?}", container, archive);
+    save(&container, &archive)?;
+    Ok(archive)
+}
+
+/// Replace the data container of the app `bundle_id` on the simulator `udid` with snapshot
+/// `name`, stopping the app first.
+pub fn restore_app(
+    udid: &str,
+    bundle_id: &str,
+    manifest_dir: &Path,
+    name: &str,
+) -> anyhow::Result<()> {
+    let container = crate::simctl::app_container(udid, bundle_id, "data")?;
+    let archive = snapshot_path(manifest_dir, name)?;
+    crate::simctl::terminate(udid, bundle_id);
+    log::info!("Restoring {:?} to {:?}", archive, container);
+    restore(&archive, &container)
+}
+
+/// Archive the contents of a data container.
+pub fn save(container: &Path, archive: &Path) -> anyhow::Result<()> {
+    if let Some(dir) = archive.parent() {
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("Failed to create all dir: {:?}", dir))?;
+    }
+    tar(&[
+        "-czf".as_ref(),
+        archive.as_os_str(),
+        "-C".as_ref(),
+        container.as_os_str(),
+        ".".as_ref(),
+    ])
+}
+
+/// Replace the contents of a data container with an archive.
+pub fn restore(archive: &Path, container: &Path) -> anyhow::Result<()> {
+    if !archive.exists() {
+        anyhow::bail!("Snapshot {:?} does not exist", archive)
+    }
+    for entry in std::fs::read_dir(container)
+        .with_context(|| format!("Failed to read dir: {:?}", container))?
+    {
+        let path = entry?.path();
+        if path.is_dir() {
+            std::fs::remove_dir_all(&path)
+        } else {
+            std::fs::remove_file(&path)
+        }
+        .with_context(|| format!("Failed to remove {:?}", path))?;
+    }
+    tar(&[
+        "-xzf".as_ref(),
+        archive.as_os_str(),
+        "-C".as_ref(),
+        container.as_os_str(),
+    ])
+}
+
+fn tar(args: &[&std::ffi::OsStr]) -> anyhow::Result<()> {
+    let output = std::process::Command::new("tar")
+        .args(args)
+        .output()
+        .with_context(|| "Failed to get tar output".to_string())?;
+
+    if !output.status.success() {
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+
+        anyhow::bail!("Failed to run tar {:?}:\n{:?}\n{:?}", args, stdout, stderr)
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn snapshot_names() {
+        assert_eq!(
+            snapshot_path(Path::new("/game"), "level-3").unwrap(),
+            Path::new("/game/.simulator-data/level-3.tar.gz")
+        );
+        assert!(snapshot_path(Path::new("/game"), "../../x").is_err());
+        assert!(snapshot_path(Path::new("/game"), "saves/1").is_err());
+        assert!(snapshot_path(Path::new("/game"), "saves\\1").is_err());
+        assert!(snapshot_path(Path::new("/game"), "..").is_err());
+        assert!(snapshot_path(Path::new("/game"), "").is_err());
+    }
+}
@@ -0,0 +1,170 @@
+use anyhow::Context as _;
+use std::path::Path;
+
+/// `lldb` commands loading the Rust pretty-printers of the active toolchain, like `rust-lldb`.
+pub fn rust_formatters() -> anyhow::Result<Vec<String>> {
+    let output = std::process::Command::new("rustc")
+        .arg("--print")
+        .arg("sysroot")
+        .output()
+        .with_context(|| "Failed to get rustc output".to_string())?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "Failed to find the Rust sysroot:\n{}",
+            String::from_utf8_lossy(&output.stderr)
+        )
+    }
+    let etc = Path::new(String::from_utf8_lossy(&output.stdout).trim()).join("lib/rustlib/etc");
+    if !etc.join("lldb_lookup.py").exists() {
+        log::warn!(
+            "Rust pretty-printers not found in {:?}, values of Rust types are shown raw",
+            etc
+        );
+        return Ok(vec![]);
+    }
+
+    Ok(vec![
+        format!(
+            "command script import {}",
+            quote(&etc.join("lldb_lookup.py").to_string_lossy())
+        ),
+        format!(
+            "command source -s 0 {}",
+            quote(&etc.join("lldb_commands").to_string_lossy())
+        ),
+    ])
+}
+
+/// Attach to a process waiting for the debugger on a simulator or this Mac.
+pub fn attach(pid: u32) -> Vec<String> {
+    vec![format!("process attach --pid {}", pid)]
+}
+
+/// Attach to a process launched stopped with `devicectl` on an iOS 17 or newer device.
+/// The `device` commands need the `lldb` of Xcode 16.
+pub fn attach_device(identifier: &str, pid: u32) -> Vec<String> {
+    vec![
+        format!("device select {}", identifier),
+        format!("device process attach --pid {}", pid),
+    ]
+}
+
+/// Launch an app stopped at its entry point through `debugserver` forwarded to `port`.
+/// `app_path` is the built app on this Mac, `device_app_path` the installed one on the device.
+pub fn launch_remote(
+    app_path: &Path,
+    device_app_path: &str,
+    port: u16,
+    args: &[String],
+) -> Vec<String> {
+    vec![
+        "platform select remote-ios".to_string(),
+        format!("target create {}", quote(&app_path.to_string_lossy())),
+        format!(
+            "script lldb.target.modules[0].SetPlatformFileSpec(lldb.SBFileSpec({}))",
+            quote(device_app_path)
+        ),
+        format!("process connect connect://127.0.0.1:{}", port),
+        launch_command(args),
+    ]
+}
+
+/// Launch an executable on this Mac stopped at its entry point.
+pub fn launch_local(executable: &Path, args: &[String]) -> Vec<String> {
+    vec![
+        format!("target create {}", quote(&executable.to_string_lossy())),
+        launch_command(args),
+    ]
+}
+
+fn launch_command(args: &[String]) -> String {
+    let mut command = "process launch --stop-at-entry".to_string();
+    if !args.is_empty() {
+        command.push_str(" --");
+        for arg in args {
+            command.push(' ');
+            command.push_str(&quote(arg));
+        }
+    }
+    command
+}
+
+/// Quote an `lldb` command argument.
+fn quote(arg: &str) -> String {
+    format!("\"{}\"", arg.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Process id from the `devicectl device process launch --json-output` file.
+pub fn devicectl_pid(json: &str) -> Option<u32> {
+    let value: serde_json::Value = serde_json::from_str(json).ok()?;
+    let pid = value
+        .get("result")?
+        .get("process")?
+        .get("processIdentifier")?
+        .as_u64()?;
+    u32::try_from(pid).ok()
+}
+
+/// Run `lldb` interactively with `commands` executed on start.
+pub fn run(commands: &[String]) -> anyhow::Result<()> {
+    let mut lldb = std::process::Command::new("xcrun");
+    lldb.arg("lldb");
+    for command in commands {
+        lldb.arg("-o").arg(command);
+    }
+    log::debug!("Running {:?}", lldb);
+    let status = lldb
+        .status()
+        .with_context(|| "Failed to run lldb".to_string())?;
+    if !status.success() {
+        anyhow::bail!("lldb exited with {}", status)
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn commands() {
+        assert_eq!(
+            launch_remote(
+                Path::new("/tmp/My Game.app"),
+                "/private/var/containers/Bundle/Application/1/My Game.app",
+                1234,
+                &["-AppleLanguages".into(), "(de)".into()],
+            ),
+            vec![
+                "platform select remote-ios",
+                "target create \"/tmp/My Game.app\"",
+                "script lldb.target.modules[0].SetPlatformFileSpec(lldb.SBFileSpec(\"/private/var/containers/Bundle/Application/1/My Game.app\"))",
+                "process connect connect://127.0.0.1:1234",
+                "process launch --stop-at-entry -- \"-AppleLanguages\" \"(de)\"",
+            ]
+        );
+        assert_eq!(
+            launch_local(Path::new("/tmp/Game.app/Contents/MacOS/Game"), &[]),
+            vec![
+                "target create \"/tmp/Game.app/Contents/MacOS/Game\"",
+                "process launch --stop-at-entry",
+            ]
+        );
+        assert_eq!(quote(r#"a "b" \c"#), r#""a \"b\" \\c""#);
+    }
+
+    #[test]
+    fn devicectl_output() {
+        let json = r#"{
+            "info": { "outcome": "success" },
+            "result": {
+                "deviceIdentifier": "00008110-000A",
+                "process": { "executable": "file:///private/var/Game.app/Game", "processIdentifier": 4242 }
+            }
+        }"#;
+        assert_eq!(devicectl_pid(json), Some(4242));
+        assert_eq!(devicectl_pid(r#"{"info": {"outcome": "failed"}}"#), None);
+        assert_eq!(devicectl_pid("not json"), None);
+    }
+}
@@ -0,0 +1,571 @@
+use anyhow::Context as _;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+/// Output of `xcrun simctl list devices --json`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct SimulatorDevices {
+    devices: HashMap<String, serde_json::Value>,
+}
+
+impl SimulatorDevices {
+    /// Return all simulators with an iOS runtime.
+    pub fn ios(&self) -> anyhow::Result<Vec<SimulatorDevice>> {
+        self.for_platform(crate::toml::AppPlatform::Ios)
+    }
+
+    /// Return all simulators with a runtime of `platform`, e.g. `SimRuntime.tvOS-17-4`.
+    pub fn for_platform(
+        &self,
+        platform: crate::toml::AppPlatform,
+    ) -> anyhow::Result<Vec<SimulatorDevice>> {
+        let mut all_devices = vec![];
+
+        let runtime_prefix = format!(".{}-", platform.name());
+        let platform_keys = {
+            let mut platform_keys = vec![];
+            for key in self.devices.keys() {
+                if key.contains(&runtime_prefix) {
+                    platform_keys.push(key);
+                }
+            }
+
+            platform_keys
+        };
+
+        for key in platform_keys {
+            if let Some(raw_devices) = self.devices.get(key) {
+                let mut devices: Vec<SimulatorDevice> = serde_json::from_value(raw_devices.clone())
+                    .with_context(|| format!("Failed to parse raw_devices {:?}", raw_devices))?;
+                for device in devices.iter_mut() {
+                    device.runtime = Some(key.clone());
+                }
+                all_devices.extend_from_slice(&devices);
+            }
+        }
+
+        Ok(all_devices)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct SimulatorDevice {
+    pub udid: String,
+    pub name: String,
+    pub state: DeviceState,
+    /// Runtime identifier, e.g. `com.apple.CoreSimulator.SimRuntime.iOS-17-4`.
+    #[serde(default)]
+    pub runtime: Option<String>,
+    /// `false` if the runtime or the device type of the simulator is missing.
+    #[serde(rename = "isAvailable", default = "available")]
+    pub is_available: bool,
+    /// Why the simulator is unavailable, e.g. `runtime profile not found`.
+    #[serde(rename = "availabilityError", default)]
+    pub availability_error: Option<String>,
+    /// Data directory of the simulator, e.g.
+    /// `~/Library/Developer/CoreSimulator/Devices/<udid>/data`. `None` when `simctl` reports an
+    /// empty path.
+    #[serde(rename = "dataPath", default, deserialize_with = "non_empty_path")]
+    pub data_path: Option<std::path::PathBuf>,
+}
+
+fn available() -> bool {
+    true
+}
+
+fn non_empty_path<'de, D>(deserializer: D) -> Result<Option<std::path::PathBuf>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let path = Option::<String>::deserialize(deserializer)?;
+    Ok(path.filter(|path| !path.is_empty()).map(Into::into))
+}
+
+impl SimulatorDevice {
+    /// Runtime platform and version, e.g. `iOS 17.4`.
+    pub fn runtime_name(&self) -> Option<String> {
+        let (_, runtime) = self.runtime.as_ref()?.rsplit_once('.')?;
+        let (platform, version) = runtime.split_once('-')?;
+        Some(format!("{} {}", platform, version.replace('-', ".")))
+    }
+
+    /// Runtime version, e.g. `17.4`.
+    pub fn runtime_version(&self) -> Option<String> {
+        let (_, runtime) = self.runtime.as_ref()?.rsplit_once('.')?;
+        let (_, version) = runtime.split_once('-')?;
+        Some(version.replace('-', "."))
+    }
+}
+
+/// Zero-based index of a 1-based choice from a list of `count` items. Empty selects the first.
+pub fn parse_selection(answer: &str, count: usize) -> Option<usize> {
+    let answer = answer.trim();
+    if answer.is_empty() {
+        return (count > 0).then_some(0);
+    }
+    answer
+        .parse::<usize>()
+        .ok()
+        .filter(|choice| (1..=count).contains(choice))
+        .map(|choice| choice - 1)
+}
+
+/// Index of the name in `names` that matches `query` best, ignoring case and the apostrophe style.
+/// An exact match wins over a prefix, a prefix over a substring and a substring over names
+/// containing every word of `query`. The first of equally good matches is returned.
+pub fn best_name_match<'a>(query: &str, names: impl IntoIterator<Item = &'a str>) -> Option<usize> {
+    let normalize = |name: &str| name.to_lowercase().replace(['\u{2018}', '\u{2019}'], "'");
+    let query = normalize(query.trim());
+    names
+        .into_iter()
+        .enumerate()
+        .filter_map(|(index, name)| {
+            let name = normalize(name);
+            let rank = if name == query {
+                0
+            } else if name.starts_with(&query) {
+                1
+            } else if name.contains(&query) {
+                2
+            } else if query.split_whitespace().all(|word| name.contains(word)) {
+                3
+            } else {
+                return None;
+            };
+            Some((rank, index))
+        })
+        .min()
+        .map(|(_, index)| index)
+}
+
+/// Whether `version` is the `requested` runtime version, e.g. `17.4.1` for `17.4`.
+pub fn runtime_version_matches(version: &str, requested: &str) -> bool {
+    version == requested || version.starts_with(&format!("{}.", requested))
+}
+
+fn version_components(version: &str) -> Vec<u32> {
+    version
+        .split('.')
+        .map(|c| c.parse::<u32>().unwrap_or(0))
+        .collect()
+}
+
+/// Whether `version` is `minimum` or newer, e.g. `17.4` for `15`.
+pub fn runtime_version_at_least(version: &str, minimum: &str) -> bool {
+    let (mut version, mut minimum) = (version_components(version), version_components(minimum));
+    let len = version.len().max(minimum.len());
+    version.resize(len, 0);
+    minimum.resize(len, 0);
+    version >= minimum
+}
+
+/// Simulators grouped by runtime name, e.g. `iOS 17.4`, ordered by platform and version.
+pub fn group_by_runtime(simulators: &[SimulatorDevice]) -> Vec<(String, Vec<&SimulatorDevice>)> {
+    let mut groups = BTreeMap::<_, (String, Vec<_>)>::new();
+    for simulator in simulators {
+        let name = simulator
+            .runtime_name()
+            .unwrap_or_else(|| "Unknown runtime".into());
+        let (platform, version) = name.split_once(' ').unwrap_or((&name, ""));
+        let key = (platform.to_string(), version_components(version));
+        groups
+            .entry(key)
+            .or_insert_with(|| (name.clone(), vec![]))
+            .1
+            .push(simulator);
+    }
+    groups.into_values().collect()
+}
+
+/// Simulator to boot when none is booted: the one with the newest runtime, preferring iPhones.
+pub fn default_simulator(simulators: &[SimulatorDevice]) -> Option<&SimulatorDevice> {
+    simulators.iter().max_by_key(|s| {
+        (
+            s.runtime_version()
+                .map(|v| version_components(&v))
+                .unwrap_or_default(),
+            s.name.starts_with("iPhone"),
+        )
+    })
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub enum DeviceState {
+    Creating,
+    Shutdown,
+    Booting,
+    Booted,
+    #[serde(rename = "Shutting Down")]
+    ShuttingDown,
+}
+
+/// How a [`DeviceInfo`] is reached.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Connection {
+    Simulator,
+    Usb,
+    Network,
+}
+
+/// A simulator or connected device, as printed by `devices --json`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DeviceInfo {
+    pub udid: String,
+    pub name: String,
+    /// OS version, e.g. `17.4`.
+    pub os_version: Option<String>,
+    /// Simulator state. Connected devices are always `Booted`.
+    pub state: DeviceState,
+    pub connection: Connection,
+}
+
+impl From<&SimulatorDevice> for DeviceInfo {
+    fn from(simulator: &SimulatorDevice) -> Self {
+        Self {
+            udid: simulator.udid.clone(),
+            name: simulator.name.clone(),
+            os_version: simulator.runtime_version(),
+            state: simulator.state.clone(),
+            connection: Connection::Simulator,
+        }
+    }
+}
+
+impl From<&md::MobileDevice> for DeviceInfo {
+    fn from(device: &md::MobileDevice) -> Self {
+        Self {
+            udid: device.identifier.clone(),
+            name: device.device_name.clone(),
+            os_version: Some(device.product_version.clone()),
+            state: DeviceState::Booted,
+            connection: match device.connection_type {
+                md::DeviceConnectionType::Usb => Connection::Usb,
+                md::DeviceConnectionType::Network => Connection::Network,
+            },
+        }
+    }
+}
+
+/// Connected devices and simulators at some point in time, used to detect changes.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DeviceSnapshot {
+    /// Connected device identifier -> device name.
+    pub devices: BTreeMap<String, String>,
+    pub simulators: BTreeMap<String, SimulatorDevice>,
+}
+
+impl DeviceSnapshot {
+    pub fn new(devices: &[md::MobileDevice], simulators: &[SimulatorDevice]) -> Self {
+        Self {
+            devices: devices
+                .iter()
+                .map(|d| (d.identifier.clone(), d.device_name.clone()))
+                .collect(),
+            simulators: simulators
+                .iter()
+                .map(|s| (s.udid.clone(), s.clone()))
+                .collect(),
+        }
+    }
+
+    /// Events that happened between `self` and a newer snapshot.
+    pub fn events(&self, new: &Self) -> Vec<DeviceEvent> {
+        let mut events = vec![];
+        for (identifier, name) in &new.devices {
+            if !self.devices.contains_key(identifier) {
+                events.push(DeviceEvent::DeviceAttached {
+                    identifier: identifier.clone(),
+                    name: name.clone(),
+                });
+            }
+        }
+        for (identifier, name) in &self.devices {
+            if !new.devices.contains_key(identifier) {
+                events.push(DeviceEvent::DeviceDetached {
+                    identifier: identifier.clone(),
+                    name: name.clone(),
+                });
+            }
+        }
+        for (udid, simulator) in &new.simulators {
+            let old_state = self.simulators.get(udid).map(|s| &s.state);
+            if old_state != Some(&simulator.state) {
+                events.push(DeviceEvent::SimulatorStateChanged(simulator.clone()));
+            }
+        }
+
+        events
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DeviceEvent {
+    DeviceAttached { identifier: String, name: String },
+    DeviceDetached { identifier: String, name: String },
+    SimulatorStateChanged(SimulatorDevice),
+}
+
+impl std::fmt::Display for DeviceEvent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match *self {
+            DeviceEvent::DeviceAttached {
+                ref identifier,
+                ref name,
+            } => write!(f, "Device attached: {} ({})", name, identifier),
+            DeviceEvent::DeviceDetached {
+                ref identifier,
+                ref name,
+            } => write!(f, "Device detached: {} ({})", name, identifier),
+            DeviceEvent::SimulatorStateChanged(ref s) => {
+                write!(f, "Simulator {:?}: {} ({})", s.state, s.name, s.udid)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn name_match() {
+        let names = [
+            "Alice\u{2019}s iPhone",
+            "iPhone 15 Pro Max",
+            "iPhone 15 Pro",
+            "iPad Pro (12.9-inch)",
+        ];
+        assert_eq!(best_name_match("iphone 15 pro", names), Some(2));
+        assert_eq!(best_name_match("iPhone 15", names), Some(1));
+        assert_eq!(best_name_match("alice's iphone", names), Some(0));
+        assert_eq!(best_name_match("15 max", names), Some(1));
+        assert_eq!(best_name_match("12.9", names), Some(3));
+        assert_eq!(best_name_match("Pixel", names), None);
+    }
+
+    #[test]
+    fn snapshot_events() {
+        let simulator = |state| SimulatorDevice {
+            udid: "4F57337E-1AF2-4D30-9726-87040063C016".into(),
+            name: "iPhone 8".into(),
+            state,
+            runtime: None,
+            is_available: true,
+            availability_error: None,
+            data_path: None,
+        };
+        let old = DeviceSnapshot {
+            devices: BTreeMap::from([("00008030".to_string(), "iPhone".to_string())]),
+            simulators: BTreeMap::from([(
+                "4F57337E-1AF2-4D30-9726-87040063C016".to_string(),
+                simulator(DeviceState::Shutdown),
+            )]),
+        };
+        let new = DeviceSnapshot {
+            devices: BTreeMap::from([("00008101".to_string(), "iPad".to_string())]),
+            simulators: BTreeMap::from([(
+                "4F57337E-1AF2-4D30-9726-87040063C016".to_string(),
+                simulator(DeviceState::Booted),
+            )]),
+        };
+
+        assert!(old.events(&old).is_empty());
+        assert_eq!(
+            old.events(&new),
+            vec![
+                DeviceEvent::DeviceAttached {
+                    identifier: "00008101".into(),
+                    name: "iPad".into()
+                },
+                DeviceEvent::DeviceDetached {
+                    identifier: "00008030".into(),
+                    name: "iPhone".into()
+                },
+                DeviceEvent::SimulatorStateChanged(simulator(DeviceState::Booted)),
+            ]
+        );
+    }
+
+    #[test]
+    fn selection() {
+        assert_eq!(parse_selection("\n", 2), Some(0));
+        assert_eq!(parse_selection("2\n", 2), Some(1));
+        assert_eq!(parse_selection("3", 2), None);
+        assert_eq!(parse_selection("0", 2), None);
+        assert_eq!(parse_selection("abc", 2), None);
+    }
+
+    #[test]
+    fn default_simulators() {
+        let simulator = |name: &str, runtime: &str| SimulatorDevice {
+            udid: format!("{} {}", name, runtime),
+            name: name.into(),
+            state: DeviceState::Shutdown,
+            runtime: Some(format!(
+                "com.apple.CoreSimulator.SimRuntime.iOS-{}",
+                runtime
+            )),
+            is_available: true,
+            availability_error: None,
+            data_path: None,
+        };
+        let simulators = [
+            simulator("iPhone 15", "17-10"),
+            simulator("iPhone 15", "17-4"),
+            simulator("iPad Air", "17-10"),
+        ];
+        assert_eq!(
+            default_simulator(&simulators).map(|s| s.udid.as_str()),
+            Some("iPhone 15 17-10")
+        );
+        assert_eq!(
+            default_simulator(&simulators[1..]).unwrap().name,
+            "iPad Air"
+        );
+        assert!(default_simulator(&[]).is_none());
+    }
+
+    #[test]
+    fn device_info_json() {
+        let simulator = SimulatorDevice {
+            udid: "4F57337E-1AF2-4D30-9726-87040063C016".into(),
+            name: "iPhone 8".into(),
+            state: DeviceState::ShuttingDown,
+            runtime: Some("com.apple.CoreSimulator.SimRuntime.iOS-15-2".into()),
+            is_available: true,
+            availability_error: None,
+            data_path: None,
+        };
+        assert_eq!(
+            serde_json::to_value(DeviceInfo::from(&simulator)).unwrap(),
+            serde_json::json!({
+                "udid": "4F57337E-1AF2-4D30-9726-87040063C016",
+                "name": "iPhone 8",
+                "os_version": "15.2",
+                "state": "Shutting Down",
+                "connection": "simulator",
+            })
+        );
+    }
+
+    #[test]
+    fn from_json() {
+        let contents = r#"{
+            "devices": {
+              "com.apple.CoreSimulator.SimRuntime.tvOS-15-2": [
+                {
+                  "udid": "0D2A9A5C-3C6E-4F43-9C1B-2E0F5B8E7A11",
+                  "isAvailable": true,
+                  "deviceTypeIdentifier": "com.apple.CoreSimulator.SimDeviceType.Apple-TV-4K-2nd-generation-4K",
+                  "state": "Shutdown",
+                  "name": "Apple TV 4K (2nd generation)"
+                }
+              ],
+              "com.apple.CoreSimulator.SimRuntime.watchOS-8-3": [],
+              "com.apple.CoreSimulator.SimRuntime.iOS-15-2": [
+                {
+                  "dataPath": "",
+                  "dataPathSize": 859213824,
+                  "logPath": "",
+                  "udid": "4F57337E-1AF2-4D30-9726-87040063C016",
+                  "isAvailable": true,
+                  "logPathSize": 385024,
+                  "deviceTypeIdentifier": "com.apple.CoreSimulator.SimDeviceType.iPhone-8",
+                  "state": "Booted",
+                  "name": "iPhone 8"
+                },
+                {
+                    "dataPath" : "",
+                    "dataPathSize" : 13312000,
+                    "logPath" : "",
+                    "udid" : "4F8AC01F-F4AD-4550-A853-C535C0BA7AF0",
+                    "isAvailable" : true,
+                    "deviceTypeIdentifier" : "com.apple.CoreSimulator.SimDeviceType.iPhone-8-Plus",
+                    "state" : "Shutdown",
+                    "name" : "iPhone 8 Plus"
+                }
+              ]
+            }
+          }"#;
+        let devices: SimulatorDevices = serde_json::from_str(contents).unwrap();
+        let ios_devices = devices.ios().unwrap();
+
+        assert_eq!(
+            ios_devices,
+            vec![
+                SimulatorDevice {
+                    udid: "4F57337E-1AF2-4D30-9726-87040063C016".into(),
+                    name: "iPhone 8".to_string(),
+                    state: DeviceState::Booted,
+                    runtime: Some("com.apple.CoreSimulator.SimRuntime.iOS-15-2".into()),
+                    is_available: true,
+                    availability_error: None,
+                    data_path: None,
+                },
+                SimulatorDevice {
+                    udid: "4F8AC01F-F4AD-4550-A853-C535C0BA7AF0".into(),
+                    name: "iPhone 8 Plus".to_string(),
+                    state: DeviceState::Shutdown,
+                    runtime: Some("com.apple.CoreSimulator.SimRuntime.iOS-15-2".into()),
+                    is_available: true,
+                    availability_error: None,
+                    data_path: None,
+                }
+            ]
+        );
+        assert_eq!(ios_devices[0].runtime_version().as_deref(), Some("15.2"));
+
+        let tvos_devices = devices
+            .for_platform(crate::toml::AppPlatform::TvOs)
+            .unwrap();
+        assert_eq!(tvos_devices.len(), 1);
+        assert_eq!(tvos_devices[0].name, "Apple TV 4K (2nd generation)");
+        assert_eq!(tvos_devices[0].runtime_version().as_deref(), Some("15.2"));
+    }
+
+    #[test]
+    fn runtime_groups() {
+        let simulator = |runtime: &str| SimulatorDevice {
+            udid: runtime.into(),
+            name: "iPhone 15".into(),
+            state: DeviceState::Shutdown,
+            runtime: Some(format!("com.apple.CoreSimulator.SimRuntime.{}", runtime)),
+            is_available: true,
+            availability_error: None,
+            data_path: None,
+        };
+        let simulators = [
+            simulator("iOS-17-10"),
+            simulator("tvOS-17-4"),
+            simulator("iOS-17-4"),
+            simulator("iOS-17-10"),
+        ];
+        let groups = group_by_runtime(&simulators)
+            .into_iter()
+            .map(|(name, simulators)| (name, simulators.len()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            groups,
+            vec![
+                ("iOS 17.4".to_string(), 1),
+                ("iOS 17.10".to_string(), 2),
+                ("tvOS 17.4".to_string(), 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn runtime_versions() {
+        assert!(runtime_version_matches("17.4", "17.4"));
+        assert!(runtime_version_matches("17.4.1", "17.4"));
+        assert!(runtime_version_matches("17.4", "17"));
+        assert!(!runtime_version_matches("17.40", "17.4"));
+        assert!(!runtime_version_matches("16.4", "17.4"));
+
+        assert!(runtime_version_at_least("17.4", "15"));
+        assert!(runtime_version_at_least("15.0", "15"));
+        assert!(runtime_version_at_least("17.10", "17.4"));
+        assert!(!runtime_version_at_least("14.5", "15"));
+    }
+}
@@ -0,0 +1,210 @@
+use std::process::Command;
+
+/// Result of one check of the development environment.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    /// What was checked, e.g. `Xcode`.
+    pub check: &'static str,
+    /// Found version or the reason the check failed.
+    pub message: String,
+    /// How to fix a failed check. `None` if the check passed.
+    pub fix: Option<String>,
+}
+
+impl Diagnostic {
+    fn ok(check: &'static str, message: impl Into<String>) -> Self {
+        Self {
+            check,
+            message: message.into(),
+            fix: None,
+        }
+    }
+
+    fn failed(check: &'static str, message: impl Into<String>, fix: impl Into<String>) -> Self {
+        Self {
+            check,
+            message: message.into(),
+            fix: Some(fix.into()),
+        }
+    }
+
+    pub fn is_ok(&self) -> bool {
+        self.fix.is_none()
+    }
+}
+
+impl std::fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.check, self.message)
+    }
+}
+
+/// First line of the stdout of a successful command.
+fn first_line(command: &mut Command) -> Option<String> {
+    let output = command.output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .next()
+        .map(|line| line.trim().to_string())
+}
+
+/// Check that Xcode is installed and selected.
+pub fn check_xcode() -> Diagnostic {
+    match first_line(Command::new("xcodebuild").arg("-version")) {
+        Some(version) => Diagnostic::ok("Xcode", version),
+        None => Diagnostic::failed(
+            "Xcode",
+            "xcodebuild is not available",
+            "Install Xcode from the App Store and select it with \
+             `sudo xcode-select -s /Applications/Xcode.app`",
+        ),
+    }
+}
+
+/// Check that the command line developer tools are installed.
+pub fn check_command_line_tools() -> Diagnostic {
+    match first_line(Command::new("xcode-select").arg("-p")) {
+        Some(path) => Diagnostic::ok("Command line tools", path),
+        None => Diagnostic::failed(
+            "Command line tools",
+            "Developer directory is not found",
+            "Run `xcode-select --install`",
+        ),
+    }
+}
+
+/// Check that the `xcodegen` binary is installed. Without it projects are generated with the
+/// built-in generator unless `xcodegen` is `required` by `project_generator`.
+pub fn check_xcodegen(required: bool) -> Diagnostic {
+    match first_line(Command::new("xcodegen").arg("version")) {
+        Some(version) if version.starts_with("Version:") => Diagnostic::ok("Xcodegen", version),
+        _ if !required => Diagnostic::ok(
+            "Xcodegen",
+            "Not found, projects are generated with the built-in generator",
+        ),
+        _ => Diagnostic::failed(
+            "Xcodegen",
+            "xcodegen is not found",
+            "Run `brew install xcodegen` or see https://github.com/yonaskolb/XcodeGen#installing",
+        ),
+    }
+}
+
+/// Targets of `required` missing from the output of `rustup target list --installed`.
+pub fn missing_targets<'a>(installed: &str, required: &[&'a str]) -> Vec<&'a str> {
+    let installed = installed.lines().map(str::trim).collect::<Vec<_>>();
+    required
+        .iter()
+        .copied()
+        .filter(|target| !installed.contains(target))
+        .collect()
+}
+
+/// Check that the rust targets in `required` are installed.
+pub fn check_rust_targets(required: &[&str]) -> Diagnostic {
+    let output = match Command::new("rustup")
+        .arg("target")
+        .arg("list")
+        .arg("--installed")
+        .output()
+    {
+        Ok(output) if output.status.success() => output,
+        _ => {
+            return Diagnostic::failed(
+                "Rust targets",
+                "rustup is not found",
+                "Install rustup from https://rustup.rs",
+            )
+        }
+    };
+
+    let missing = missing_targets(&String::from_utf8_lossy(&output.stdout), required);
+    if missing.is_empty() {
+        Diagnostic::ok("Rust targets", required.join(", "))
+    } else {
+        Diagnostic::failed(
+            "Rust targets",
+            format!("Missing {}", missing.join(", ")),
+            format!("Run `rustup target add {}`", missing.join(" ")),
+        )
+    }
+}
+
+/// Number of identities from the output of `security find-identity -v -p codesigning`,
+/// e.g. `2 valid identities found`.
+pub fn identity_count(find_identity: &str) -> usize {
+    find_identity
+        .lines()
+        .filter_map(|line| line.trim().strip_suffix("valid identities found"))
+        .filter_map(|count| count.trim().parse().ok())
+        .next()
+        .unwrap_or(0)
+}
+
+/// Check that the keychain has at least one code signing identity.
+pub fn check_signing_identity() -> Diagnostic {
+    let count = Command::new("security")
+        .arg("find-identity")
+        .arg("-v")
+        .arg("-p")
+        .arg("codesigning")
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .map(|output| identity_count(&String::from_utf8_lossy(&output.stdout)))
+        .unwrap_or(0);
+
+    if count > 0 {
+        Diagnostic::ok("Signing identity", format!("{} valid identities", count))
+    } else {
+        Diagnostic::failed(
+            "Signing identity",
+            "No valid code signing identity in the keychain",
+            "Add your Apple ID in Xcode > Settings > Accounts and create an Apple Development \
+             certificate with Manage Certificates",
+        )
+    }
+}
+
+/// Check that a simulator of `platform` is booted.
+pub fn check_booted_simulator(platform: crate::toml::AppPlatform) -> Diagnostic {
+    let simulators = crate::xcodebuild::Xcodebuild::get_platform_simulator_device_list(platform)
+        .unwrap_or_default();
+    match simulators
+        .iter()
+        .find(|s| s.state == crate::devices::DeviceState::Booted)
+    {
+        Some(simulator) => Diagnostic::ok("Booted simulator", &simulator.name),
+        None => Diagnostic::failed(
+            "Booted simulator",
+            format!("No booted {} simulator", platform.name()),
+            "Run `open -a Simulator` or `cargo xcodebuild boot <id>` with an id from \
+             `xcrun simctl list devices`",
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rust_targets() {
+        let installed = "aarch64-apple-darwin\naarch64-apple-ios\n";
+        assert_eq!(
+            missing_targets(installed, &["aarch64-apple-ios", "aarch64-apple-ios-sim"]),
+            vec!["aarch64-apple-ios-sim"]
+        );
+    }
+
+    #[test]
+    fn identities() {
+        let output = r#"  1) 0123456789ABCDEF0123456789ABCDEF01234567 "Apple Development: John Doe (ABCDE12345)"
+     1 valid identities found"#;
+        assert_eq!(identity_count(output), 1);
+        assert_eq!(identity_count("     0 valid identities found"), 0);
+    }
+}
@@ -0,0 +1,167 @@
+use serde::Serialize;
+use std::path::PathBuf;
+
+/// Output format of [`Event`]s, mirroring `cargo --message-format`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MessageFormat {
+    /// Only human readable logs.
+    #[default]
+    Human,
+    /// Newline-delimited JSON events on stdout.
+    Json,
+}
+
+impl std::str::FromStr for MessageFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s {
+            "human" => Ok(Self::Human),
+            "json" => Ok(Self::Json),
+            _ => anyhow::bail!("Unknown message format `{}`. Expected `human` or `json`", s),
+        }
+    }
+}
+
+/// How much subprocess output is shown, set with `--quiet` and `--verbose`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Verbosity {
+    /// Only errors and warnings.
+    Quiet,
+    /// Condensed steps.
+    #[default]
+    Normal,
+    /// Everything, unchanged.
+    Verbose,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Phase {
+    CargoBuild,
+    Generate,
+    Xcodebuild,
+    Install,
+    Launch,
+}
+
+impl Phase {
+    /// Shown while the phase runs, e.g. `Installing app`.
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::CargoBuild => "Building Rust libraries",
+            Self::Generate => "Generating Xcode project",
+            Self::Xcodebuild => "Building with xcodebuild",
+            Self::Install => "Installing app",
+            Self::Launch => "Launching app",
+        }
+    }
+
+    /// Whether the phase may print output of its own, e.g. from cargo, xcodebuild or the app.
+    pub fn has_output(self) -> bool {
+        matches!(self, Self::CargoBuild | Self::Xcodebuild | Self::Launch)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DiagnosticLevel {
+    Error,
+    Warning,
+}
+
+/// Progress event of a build or run.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "reason", rename_all = "kebab-case")]
+pub enum Event {
+    PhaseStarted {
+        phase: Phase,
+    },
+    PhaseFinished {
+        phase: Phase,
+        success: bool,
+    },
+    DeviceSelected {
+        destination: crate::xcodebuild::Destination,
+    },
+    Diagnostic {
+        level: DiagnosticLevel,
+        message: String,
+    },
+    Artifact {
+        /// `app`, `dsym`, `ipa`, `xcframework` or `export` for the `.app` or `.pkg` exported for
+        /// macOS.
+        kind: &'static str,
+        path: PathBuf,
+    },
+    BuildFinished {
+        success: bool,
+    },
+}
+
+impl Event {
+    /// Print the event as a JSON line if `format` is [`MessageFormat::Json`].
+    pub fn emit(&self, format: MessageFormat) {
+        if format == MessageFormat::Json {
+            match serde_json::to_string(self) {
+                Ok(json) => println!("{}", json),
+                Err(err) => log::warn!("Failed to serialize {:?}: {}", self, err),
+            }
+        }
+    }
+}
+
+/// Collect `error:` and `warning:` lines from xcodebuild output.
+pub fn parse_diagnostics(output: &str) -> Vec<Event> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let level = if line.contains("error: ") {
+                DiagnosticLevel::Error
+            } else if line.contains("warning: ") {
+                DiagnosticLevel::Warning
+            } else {
+                return None;
+            };
+            Some(Event::Diagnostic {
+                level,
+                message: line.trim().to_string(),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn diagnostics() {
+        let output = "CompileC main.o\n/src/main.m:3:1: warning: unused variable 'x'\n** BUILD FAILED **\nerror: Signing for \"app\" requires a development team.\n";
+        assert_eq!(
+            parse_diagnostics(output),
+            vec![
+                Event::Diagnostic {
+                    level: DiagnosticLevel::Warning,
+                    message: "/src/main.m:3:1: warning: unused variable 'x'".into(),
+                },
+                Event::Diagnostic {
+                    level: DiagnosticLevel::Error,
+                    message: "error: Signing for \"app\" requires a development team.".into(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn json() {
+        let event = Event::PhaseFinished {
+            phase: Phase::CargoBuild,
+            success: true,
+        };
+        assert_eq!(
+            serde_json::to_string(&event).unwrap(),
+            r#"{"reason":"phase-finished","phase":"cargo-build","success":true}"#
+        );
+    }
+}
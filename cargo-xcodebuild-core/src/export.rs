@@ -0,0 +1,248 @@
+use anyhow::Context as _;
+use serde::Serialize;
+use std::path::Path;
+
+/// Export all device variants with the size report.
+pub const THIN_FOR_ALL_VARIANTS: &str = "<thin-for-all-variants>";
+
+/// Options for `xcodebuild -exportArchive`, written to `ExportOptions.plist`.
+#[derive(Debug, Clone)]
+pub struct ExportOptions {
+    pub method: crate::toml::ExportMethod,
+    pub team_id: Option<String>,
+    /// `<none>`, [`THIN_FOR_ALL_VARIANTS`] or a device model identifier like `iPhone10,1`.
+    pub thinning: Option<String>,
+}
+
+impl Default for ExportOptions {
+    fn default() -> Self {
+        Self {
+            method: crate::toml::ExportMethod::Development,
+            team_id: None,
+            thinning: None,
+        }
+    }
+}
+
+impl ExportOptions {
+    pub fn to_plist(&self) -> String {
+        let mut options = serde_json::Map::new();
+        options.insert("method".into(), self.method.as_str().into());
+        options.insert(
+            "signingCertificate".into(),
+            self.method.signing_certificate().into(),
+        );
+        if let Some(ref team_id) = self.team_id {
+            options.insert("teamID".into(), team_id.clone().into());
+        }
+        if let Some(ref thinning) = self.thinning {
+            options.insert("thinning".into(), thinning.clone().into());
+        }
+
+        crate::plist::to_xml(&serde_json::Value::Object(options))
+    }
+}
+
+/// Run `xcodebuild -exportArchive` for `archive_path` into `export_dir`.
+pub fn export_archive(
+    archive_path: &Path,
+    export_dir: &Path,
+    options: &ExportOptions,
+    authentication_key: Option<&crate::signing::AuthenticationKey>,
+) -> anyhow::Result<()> {
+    log::info!("Exporting archive {:?} to {:?}", archive_path, export_dir);
+    std::fs::create_dir_all(export_dir)
+        .with_context(|| format!("Failed to create all dir: {:?}", export_dir))?;
+    let options_path = export_dir.join("ExportOptions.plist");
+    std::fs::write(&options_path, options.to_plist())
+        .with_context(|| format!("Failed to write {:?}", options_path))?;
+
+    let mut command = std::process::Command::new("xcodebuild");
+    command
+        .arg("-exportArchive")
+        .arg("-archivePath")
+        .arg(archive_path)
+        .arg("-exportPath")
+        .arg(export_dir)
+        .arg("-exportOptionsPlist")
+        .arg(&options_path)
+        .arg("-allowProvisioningUpdates");
+    if let Some(key) = authentication_key {
+        command.args(key.xcodebuild_args());
+    }
+    let output = command
+        .output()
+        .with_context(|| "Failed to get xcodebuild output".to_string())?;
+
+    if !output.status.success() {
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+
+        anyhow::bail!("Failed to export archive:\n{:?}\n{:?}", stdout, stderr)
+    };
+
+    Ok(())
+}
+
+/// Export thinned IPAs from `archive_path` into a fresh `export_dir` and return the size of each
+/// device variant. `thinning` is a device model identifier like `iPhone10,1`, or all variants if
+/// `None`.
+pub fn export_thinned(
+    archive_path: &Path,
+    export_dir: &Path,
+    thinning: Option<&str>,
+    authentication_key: Option<&crate::signing::AuthenticationKey>,
+) -> anyhow::Result<Vec<ThinnedVariant>> {
+    if export_dir.exists() {
+        std::fs::remove_dir_all(export_dir)
+            .with_context(|| format!("Failed to remove dir: {:?}", export_dir))?;
+    }
+
+    let options = ExportOptions {
+        thinning: Some(thinning.unwrap_or(THIN_FOR_ALL_VARIANTS).to_string()),
+        ..Default::default()
+    };
+    export_archive(archive_path, export_dir, &options, authentication_key)?;
+    log::info!("Thinned IPAs are exported to {:?}", export_dir);
+
+    read_size_report(export_dir)
+}
+
+/// The product exported to `export_dir`: the `.ipa`, or the `.pkg` or `.app` of macOS apps.
+pub fn find_export(
+    export_dir: &Path,
+    platform: crate::toml::AppPlatform,
+) -> anyhow::Result<std::path::PathBuf> {
+    let extensions: &[&str] = match platform {
+        crate::toml::AppPlatform::MacOs => &["pkg", "app"],
+        _ => &["ipa"],
+    };
+    for entry in std::fs::read_dir(export_dir)
+        .with_context(|| format!("Failed to read dir: {:?}", export_dir))?
+    {
+        let path = entry?.path();
+        if let Some(ext) = path.extension().and_then(|ext| ext.to_str()) {
+            if extensions.contains(&ext) {
+                return Ok(path);
+            }
+        }
+    }
+
+    anyhow::bail!(
+        "No .{} is exported to {:?}",
+        extensions.join(" or ."),
+        export_dir
+    )
+}
+
+/// A device variant from `App Thinning Size Report.txt`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ThinnedVariant {
+    pub name: String,
+    pub descriptors: String,
+    pub app_size: String,
+}
+
+/// Read the size report written by a thinned export.
+pub fn read_size_report(export_dir: &Path) -> anyhow::Result<Vec<ThinnedVariant>> {
+    let path = export_dir.join("App Thinning Size Report.txt");
+    let report = std::fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read app thinning size report {:?}", path))?;
+    Ok(parse_size_report(&report))
+}
+
+pub fn parse_size_report(report: &str) -> Vec<ThinnedVariant> {
+    let mut variants: Vec<ThinnedVariant> = Vec::new();
+    for line in report.lines().map(str::trim) {
+        if let Some(name) = line.strip_prefix("Variant:") {
+            variants.push(ThinnedVariant {
+                name: name.trim().to_string(),
+                descriptors: String::new(),
+                app_size: String::new(),
+            });
+        } else if let Some(variant) = variants.last_mut() {
+            if let Some(descriptors) = line.strip_prefix("Supported variant descriptors:") {
+                variant.descriptors = descriptors.trim().to_string();
+            } else if let Some(size) = line.strip_prefix("App size:") {
+                variant.app_size = size.trim().to_string();
+            }
+        }
+    }
+
+    variants
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn export_options() {
+        let options = ExportOptions {
+            method: crate::toml::ExportMethod::AdHoc,
+            team_id: Some("ABCDE12345".into()),
+            ..Default::default()
+        };
+        let plist = options.to_plist();
+        assert!(plist.contains("<key>method</key>\n\t<string>ad-hoc</string>"));
+        assert!(
+            plist.contains("<key>signingCertificate</key>\n\t<string>Apple Distribution</string>")
+        );
+        assert!(plist.contains("<key>teamID</key>\n\t<string>ABCDE12345</string>"));
+        assert!(!plist.contains("thinning"));
+    }
+
+    #[test]
+    fn exports() {
+        let temp = crate::test_util::TempDir::new("export");
+        let dir = temp.path();
+        std::fs::create_dir_all(dir.join("game.app")).unwrap();
+        std::fs::write(dir.join("ExportOptions.plist"), "").unwrap();
+        assert!(find_export(dir, crate::toml::AppPlatform::Ios).is_err());
+        assert_eq!(
+            find_export(dir, crate::toml::AppPlatform::MacOs).unwrap(),
+            dir.join("game.app")
+        );
+
+        std::fs::write(dir.join("game.ipa"), "").unwrap();
+        assert_eq!(
+            find_export(dir, crate::toml::AppPlatform::Ios).unwrap(),
+            dir.join("game.ipa")
+        );
+    }
+
+    #[test]
+    fn size_report() {
+        let report = r#"
+App Thinning Size Report for All Variants of game
+
+Variant: game-1A2B.ipa
+Supported variant descriptors: [device: iPhone8,4, os-version: 13.0]
+App + On Demand Resources size: 5,4 MB compressed, 12,8 MB uncompressed
+App size: 5,4 MB compressed, 12,8 MB uncompressed
+On Demand Resources size: Zero KB compressed, Zero KB uncompressed
+
+
+Variant: game-3C4D.ipa
+Supported variant descriptors: [device: iPad13,8, os-version: 14.0]
+App + On Demand Resources size: 6,1 MB compressed, 14,2 MB uncompressed
+App size: 6,1 MB compressed, 14,2 MB uncompressed
+On Demand Resources size: Zero KB compressed, Zero KB uncompressed
+"#;
+        assert_eq!(
+            parse_size_report(report),
+            vec![
+                ThinnedVariant {
+                    name: "game-1A2B.ipa".into(),
+                    descriptors: "[device: iPhone8,4, os-version: 13.0]".into(),
+                    app_size: "5,4 MB compressed, 12,8 MB uncompressed".into(),
+                },
+                ThinnedVariant {
+                    name: "game-3C4D.ipa".into(),
+                    descriptors: "[device: iPad13,8, os-version: 14.0]".into(),
+                    app_size: "6,1 MB compressed, 14,2 MB uncompressed".into(),
+                },
+            ]
+        );
+    }
+}
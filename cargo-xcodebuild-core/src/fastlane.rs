@@ -0,0 +1,140 @@
+use anyhow::Context as _;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+/// Build outputs in the shape fastlane lanes expect.
+#[derive(Debug, Clone, Serialize)]
+pub struct FastlaneContext {
+    pub ipa_path: Option<PathBuf>,
+    pub dsym_path: Option<PathBuf>,
+    pub app_path: PathBuf,
+    pub bundle_id: String,
+    pub scheme: String,
+    pub configuration: String,
+    pub team_id: Option<String>,
+}
+
+impl FastlaneContext {
+    pub fn from_artifacts(artifacts: &crate::xcodebuild::BuildArtifacts) -> Self {
+        let dsym_path = artifacts.project_dir.join(artifacts.dsym_path());
+        let dsym_path = if dsym_path.exists() {
+            Some(dsym_path)
+        } else {
+            None
+        };
+        Self {
+            ipa_path: None,
+            dsym_path,
+            app_path: artifacts.project_dir.join(artifacts.app_path()),
+            bundle_id: artifacts.bundle_id(),
+            scheme: artifacts.scheme.clone(),
+            configuration: artifacts.configuration.clone(),
+            team_id: artifacts.team_id.clone(),
+        }
+    }
+
+    /// Outputs of an archive: the app and its dSYM inside the `.xcarchive` and the exported `.ipa`.
+    pub fn from_archive(
+        artifacts: &crate::xcodebuild::ArchiveArtifacts,
+        scheme: &str,
+        configuration: &str,
+    ) -> Self {
+        Self {
+            ipa_path: Some(artifacts.ipa_path.clone()),
+            dsym_path: Some(artifacts.app_dsym_path()).filter(|path| path.exists()),
+            app_path: artifacts.app_path(),
+            bundle_id: artifacts.bundle_id.clone(),
+            scheme: scheme.to_string(),
+            configuration: configuration.to_string(),
+            team_id: artifacts.team_id.clone(),
+        }
+    }
+
+    /// Environment variables read by fastlane actions: `gym`, `pilot`, `deliver`,
+    /// `upload_symbols_to_crashlytics` and `sentry`. The app path has no fastlane variable, lanes
+    /// get it from the JSON output.
+    pub fn env(&self) -> Vec<(&'static str, String)> {
+        let mut env = vec![
+            ("FASTLANE_APP_IDENTIFIER", self.bundle_id.clone()),
+            ("GYM_SCHEME", self.scheme.clone()),
+            ("GYM_CONFIGURATION", self.configuration.clone()),
+        ];
+        if let Some(ref ipa_path) = self.ipa_path {
+            let path = ipa_path.to_string_lossy().into_owned();
+            // macOS apps are uploaded as installer packages.
+            if ipa_path.extension().is_some_and(|ext| ext == "pkg") {
+                env.push(("PILOT_PKG", path.clone()));
+                env.push(("DELIVER_PKG_PATH", path));
+            } else {
+                env.push(("PILOT_IPA", path.clone()));
+                env.push(("DELIVER_IPA_PATH", path));
+            }
+        }
+        if let Some(ref dsym_path) = self.dsym_path {
+            let dsym_path = dsym_path.to_string_lossy().into_owned();
+            env.push((
+                "FL_UPLOAD_SYMBOLS_TO_CRASHLYTICS_DSYM_PATH",
+                dsym_path.clone(),
+            ));
+            env.push(("SENTRY_DSYM_PATH", dsym_path));
+        }
+        if let Some(ref team_id) = self.team_id {
+            env.push(("FASTLANE_TEAM_ID", team_id.clone()));
+        }
+
+        env
+    }
+
+    /// [`FastlaneContext::env`] as `export` lines to `eval` in a shell.
+    pub fn shell_exports(&self) -> String {
+        self.env()
+            .into_iter()
+            .map(|(key, value)| format!("export {}={}\n", key, crate::shell::quote(&value)))
+            .collect()
+    }
+
+    /// Run `fastlane <lane>` in `dir` with [`FastlaneContext::env`].
+    pub fn run_lane(&self, dir: &Path, lane: &str) -> anyhow::Result<()> {
+        log::info!("Running fastlane lane `{}`", lane);
+        let status = std::process::Command::new("fastlane")
+            .current_dir(dir)
+            .arg(lane)
+            .envs(self.env())
+            .status()
+            .with_context(|| format!("Failed to run fastlane lane `{}`", lane))?;
+
+        if !status.success() {
+            anyhow::bail!("fastlane lane `{}` failed with {}", lane, status)
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shell_exports() {
+        let context = FastlaneContext {
+            ipa_path: Some(PathBuf::from("/tmp/My App/export/My App.ipa")),
+            dsym_path: None,
+            app_path: PathBuf::from("/tmp/My App.app"),
+            bundle_id: "com.rust.game".into(),
+            scheme: "it's".into(),
+            configuration: "Release".into(),
+            team_id: None,
+        };
+        assert_eq!(
+            context.shell_exports(),
+            concat!(
+                "export FASTLANE_APP_IDENTIFIER='com.rust.game'\n",
+                "export GYM_SCHEME='it'\\''s'\n",
+                "export GYM_CONFIGURATION='Release'\n",
+                "export PILOT_IPA='/tmp/My App/export/My App.ipa'\n",
+                "export DELIVER_IPA_PATH='/tmp/My App/export/My App.ipa'\n",
+            )
+        );
+    }
+}
@@ -0,0 +1,134 @@
+use anyhow::Context as _;
+use std::hash::{Hash, Hasher};
+use std::path::Path;
+
+/// 64-bit FNV-1a. Unlike `DefaultHasher`, its output doesn't change between Rust releases,
+/// so fingerprints saved by one toolchain stay valid for the next.
+#[derive(Debug)]
+struct Fnv1a(u64);
+
+impl Default for Fnv1a {
+    fn default() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl Hasher for Fnv1a {
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 ^= u64::from(byte);
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+/// Hash of the inputs of a build step, to skip the step when none of them changed.
+#[derive(Debug, Default)]
+pub struct Fingerprint(Fnv1a);
+
+impl Fingerprint {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a value, e.g. a build setting.
+    pub fn value(&mut self, value: impl Hash) -> &mut Self {
+        value.hash(&mut self.0);
+        self
+    }
+
+    /// Add size and modification time of the files under `path`, for large inputs like
+    /// libraries and assets. A missing path is an input too.
+    pub fn metadata(&mut self, path: &Path) -> &mut Self {
+        path.hash(&mut self.0);
+        for (file, modified) in crate::watch::scan(&[path.to_path_buf()]) {
+            file.hash(&mut self.0);
+            modified.hash(&mut self.0);
+            file.metadata().map(|m| m.len()).ok().hash(&mut self.0);
+        }
+        self
+    }
+
+    /// Add the contents of the files under `path`, for generated files rewritten on every build.
+    pub fn contents(&mut self, path: &Path) -> &mut Self {
+        path.hash(&mut self.0);
+        for file in crate::watch::scan(&[path.to_path_buf()]).keys() {
+            file.hash(&mut self.0);
+            std::fs::read(file).ok().hash(&mut self.0);
+        }
+        self
+    }
+
+    pub fn finish(&self) -> String {
+        format!("{:016x}", self.0.finish())
+    }
+}
+
+/// Whether the fingerprint saved to `path` is `fingerprint`.
+pub fn is_up_to_date(path: &Path, fingerprint: &str) -> bool {
+    std::fs::read_to_string(path)
+        .map(|saved| saved.trim() == fingerprint)
+        .unwrap_or(false)
+}
+
+pub fn save(path: &Path, fingerprint: &str) -> anyhow::Result<()> {
+    std::fs::write(path, fingerprint).with_context(|| format!("Failed to write {:?}", path))
+}
+
+/// Remove a saved fingerprint, e.g. before running the step it belongs to.
+pub fn invalidate(path: &Path) {
+    let _ = std::fs::remove_file(path);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fnv1a() {
+        let hash = |bytes: &[u8]| {
+            let mut hasher = Fnv1a::default();
+            hasher.write(bytes);
+            hasher.finish()
+        };
+        assert_eq!(hash(b""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(hash(b"a"), 0xaf63_dc4c_8601_ec8c);
+    }
+
+    #[test]
+    fn fingerprints() {
+        let temp = crate::test_util::TempDir::new("fingerprint");
+        let dir = temp.path();
+        std::fs::create_dir_all(dir.join("src")).unwrap();
+        std::fs::write(dir.join("src/Info.plist"), "a").unwrap();
+        let fingerprint = |setting: &str| {
+            Fingerprint::new()
+                .value(setting)
+                .contents(&dir.join("src"))
+                .metadata(&dir.join("libgame.a"))
+                .finish()
+        };
+
+        let first = fingerprint("Debug");
+        assert_eq!(first, fingerprint("Debug"));
+        assert_ne!(first, fingerprint("Release"));
+
+        std::fs::write(dir.join("src/Info.plist"), "b").unwrap();
+        let second = fingerprint("Debug");
+        assert_ne!(first, second);
+
+        std::fs::write(dir.join("libgame.a"), "lib").unwrap();
+        assert_ne!(second, fingerprint("Debug"));
+
+        let saved = dir.join("xcodebuild.fingerprint");
+        assert!(!is_up_to_date(&saved, &second));
+        save(&saved, &second).unwrap();
+        assert!(is_up_to_date(&saved, &second));
+        invalidate(&saved);
+        assert!(!is_up_to_date(&saved, &second));
+    }
+}
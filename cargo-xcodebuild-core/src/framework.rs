@@ -0,0 +1,123 @@
+use anyhow::Context as _;
+use std::path::{Path, PathBuf};
+
+/// Info.plist of a framework bundle.
+pub fn info_plist(
+    name: &str,
+    bundle_id: &str,
+    version: &str,
+    deployment_target: &str,
+) -> serde_json::Value {
+    serde_json::json!({
+        "CFBundleDevelopmentRegion": "en",
+        "CFBundleExecutable": name,
+        "CFBundleIdentifier": bundle_id,
+        "CFBundleInfoDictionaryVersion": "6.0",
+        "CFBundleName": name,
+        "CFBundlePackageType": "FMWK",
+        "CFBundleShortVersionString": version,
+        "CFBundleVersion": "1",
+        "MinimumOSVersion": deployment_target,
+    })
+}
+
+/// Shell script of a post-build phase copying `<name>.framework` from `FRAMEWORK_SEARCH_PATHS`
+/// into the app bundle and signing it.
+pub fn embed_script(name: &str) -> String {
+    format!(
+        r#"set -e
+for dir in $FRAMEWORK_SEARCH_PATHS; do
+  if [ -d "$dir/{name}.framework" ]; then
+    mkdir -p "$TARGET_BUILD_DIR/$FRAMEWORKS_FOLDER_PATH"
+    rm -rf "$TARGET_BUILD_DIR/$FRAMEWORKS_FOLDER_PATH/{name}.framework"
+    cp -R "$dir/{name}.framework" "$TARGET_BUILD_DIR/$FRAMEWORKS_FOLDER_PATH/"
+    if [ "$CODE_SIGNING_ALLOWED" = "YES" ]; then
+      codesign --force --sign "${{EXPANDED_CODE_SIGN_IDENTITY:--}}" "$TARGET_BUILD_DIR/$FRAMEWORKS_FOLDER_PATH/{name}.framework"
+    fi
+    exit 0
+  fi
+done
+echo "error: {name}.framework is not found in $FRAMEWORK_SEARCH_PATHS"
+exit 1
+"#,
+        name = name
+    )
+}
+
+/// Wrap the `lib<name>.dylib` built by cargo in `dir` into `<name>.framework` next to it,
+/// with an `@rpath` install name. Skipped if the framework is newer than the dylib.
+pub fn wrap_dylib(
+    dir: &Path,
+    name: &str,
+    bundle_id: &str,
+    version: &str,
+    deployment_target: &str,
+) -> anyhow::Result<PathBuf> {
+    let dylib = dir.join(format!("lib{}.dylib", name));
+    let framework_dir = dir.join(format!("{}.framework", name));
+    let binary = framework_dir.join(name);
+    let modified = |path: &Path| std::fs::metadata(path).and_then(|m| m.modified()).ok();
+    let dylib_modified = modified(&dylib).with_context(|| {
+        format!(
+            "{:?} is not found. Is `cdylib` in the `crate-type` of Cargo.toml?",
+            dylib
+        )
+    })?;
+    if matches!(modified(&binary), Some(wrapped) if wrapped >= dylib_modified) {
+        log::debug!("Framework is up to date: {:?}", framework_dir);
+        return Ok(framework_dir);
+    }
+
+    log::info!("Wrapping {:?} into {:?}", dylib, framework_dir);
+    std::fs::create_dir_all(&framework_dir)
+        .with_context(|| format!("Failed to create all dir: {:?}", framework_dir))?;
+    std::fs::copy(&dylib, &binary)
+        .with_context(|| format!("Failed to copy {:?} to {:?}", dylib, binary))?;
+    let info_plist_path = framework_dir.join("Info.plist");
+    std::fs::write(
+        &info_plist_path,
+        crate::plist::to_xml(&info_plist(name, bundle_id, version, deployment_target)),
+    )
+    .with_context(|| format!("Failed to write {:?}", info_plist_path))?;
+
+    let output = std::process::Command::new("install_name_tool")
+        .arg("-id")
+        .arg(format!("@rpath/{}.framework/{}", name, name))
+        .arg(&binary)
+        .output()
+        .with_context(|| "Failed to get install_name_tool output".to_string())?;
+
+    if !output.status.success() {
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+
+        anyhow::bail!(
+            "Failed to set install name of {:?}:\n{:?}\n{:?}",
+            binary,
+            stdout,
+            stderr
+        )
+    }
+
+    Ok(framework_dir)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn framework_info_plist() {
+        let plist = info_plist("my_game", "com.rust.my-game.framework", "0.1.0", "13.0");
+        assert_eq!(plist["CFBundleExecutable"], "my_game");
+        assert_eq!(plist["CFBundlePackageType"], "FMWK");
+        assert_eq!(plist["MinimumOSVersion"], "13.0");
+    }
+
+    #[test]
+    fn script_embeds_framework() {
+        let script = embed_script("my_game");
+        assert!(script.contains(r#"cp -R "$dir/my_game.framework""#));
+        assert!(script.contains("${EXPANDED_CODE_SIGN_IDENTITY:--}"));
+    }
+}
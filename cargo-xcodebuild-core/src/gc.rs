@@ -0,0 +1,210 @@
+use anyhow::Context as _;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+/// Parse an age like `30d`, `12h`, `45m` or `10s`.
+pub fn parse_age(age: &str) -> anyhow::Result<Duration> {
+    let age = age.trim();
+    let unit_index = age.find(|c: char| !c.is_ascii_digit()).unwrap_or(age.len());
+    let (value, unit) = age.split_at(unit_index);
+    let value: u64 = value
+        .parse()
+        .map_err(|_| anyhow::anyhow!("Invalid age `{}`. Expected e.g. `30d`", age))?;
+    let seconds = match unit {
+        "s" | "" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        "w" => 7 * 24 * 60 * 60,
+        _ => anyhow::bail!(
+            "Invalid age unit `{}`. Expected one of `s`, `m`, `h`, `d`, `w`",
+            unit
+        ),
+    };
+
+    Ok(Duration::from_secs(value * seconds))
+}
+
+/// Newest modification time of the files in `path`, or of `path` itself if it is a file or an
+/// empty directory. Changes deep inside a directory don't update the time of the directory.
+pub fn last_modified(path: &Path) -> Option<SystemTime> {
+    let metadata = path.symlink_metadata().ok()?;
+    if !metadata.is_dir() {
+        return metadata.modified().ok();
+    }
+
+    std::fs::read_dir(path)
+        .ok()?
+        .flatten()
+        .filter_map(|entry| last_modified(&entry.path()))
+        .max()
+        .or_else(|| metadata.modified().ok())
+}
+
+/// Whether no file in `path` was modified for at least `age`.
+pub fn is_older_than(path: &Path, age: Duration) -> bool {
+    last_modified(path)
+        .and_then(|modified| modified.elapsed().ok())
+        .map(|elapsed| elapsed >= age)
+        .unwrap_or(false)
+}
+
+/// App names of the crate of `manifest_path` with `toml` and the other members of its workspace,
+/// which are the names of their generated project directories.
+pub fn workspace_app_names(
+    manifest_path: &Path,
+    toml: &crate::toml::Root,
+) -> anyhow::Result<Vec<String>> {
+    let mut app_names = vec![crate::xcodegen::Project::target_name(toml)];
+    for manifest in crate::cargo::workspace_manifests(manifest_path)? {
+        match std::fs::read_to_string(&manifest)
+            .ok()
+            .and_then(|content| toml::from_str::<crate::toml::Root>(&content).ok())
+        {
+            Some(toml) => app_names.push(crate::xcodegen::Project::target_name(&toml)),
+            None => log::debug!("Failed to read workspace member {:?}", manifest),
+        }
+    }
+
+    Ok(app_names)
+}
+
+/// Remove directories in `target_dir` unused for `older_than`, except `keep`. Only the derived
+/// data in `build` is removed from the projects of `app_names`. Returns the removed paths.
+pub fn remove_stale_projects(
+    target_dir: &Path,
+    keep: &[PathBuf],
+    app_names: &[String],
+    older_than: Duration,
+) -> anyhow::Result<Vec<String>> {
+    let mut removed = vec![];
+    if !target_dir.exists() {
+        return Ok(removed);
+    }
+
+    let entries = std::fs::read_dir(target_dir)
+        .with_context(|| format!("Failed to read dir: {:?}", target_dir))?;
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if !path.is_dir() || keep.contains(&path) {
+            continue;
+        }
+
+        let name = entry.file_name().to_string_lossy().into_owned();
+        let stale = if app_names
+            .iter()
+            .any(|app_name| name == *app_name || name == format!("{}-headers", app_name))
+        {
+            // Only derived data of the projects of workspace members
+            path.join("build")
+        } else {
+            path
+        };
+        if stale.exists() && is_older_than(&stale, older_than) {
+            log::debug!("Removing {:?}", stale);
+            std::fs::remove_dir_all(&stale)
+                .with_context(|| format!("Failed to remove dir: {:?}", stale))?;
+            removed.push(format!("{}", stale.display()));
+        }
+    }
+
+    Ok(removed)
+}
+
+/// Delete the shut down `simulators` created by cargo-xcodebuild that weren't booted for
+/// `older_than`. Returns a description of every deleted simulator.
+pub fn remove_unused_simulators(
+    simulators: &[crate::devices::SimulatorDevice],
+    older_than: Duration,
+) -> anyhow::Result<Vec<String>> {
+    let mut removed = vec![];
+    for simulator in simulators {
+        // `device.plist` next to the data of the simulator is written when it boots or shuts
+        // down.
+        let unused = simulator
+            .data_path
+            .as_ref()
+            .and_then(|data_path| data_path.parent())
+            .map(|dir| is_older_than(&dir.join("device.plist"), older_than))
+            .unwrap_or(false);
+        if simulator
+            .name
+            .ends_with(crate::simctl::CREATED_SIMULATOR_SUFFIX)
+            && simulator.state == crate::devices::DeviceState::Shutdown
+            && unused
+        {
+            crate::simctl::delete(&simulator.udid)?;
+            removed.push(format!("Simulator {} ({})", simulator.name, simulator.udid));
+        }
+    }
+
+    Ok(removed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ages() {
+        assert_eq!(parse_age("30d").unwrap(), Duration::from_secs(30 * 86400));
+        assert_eq!(parse_age("12h").unwrap(), Duration::from_secs(12 * 3600));
+        assert_eq!(parse_age("45m").unwrap(), Duration::from_secs(45 * 60));
+        assert_eq!(parse_age("10").unwrap(), Duration::from_secs(10));
+        assert!(parse_age("d").is_err());
+        assert!(parse_age("3y").is_err());
+    }
+
+    #[test]
+    fn file_ages() {
+        let temp = crate::test_util::TempDir::new("gc");
+        let dir = temp.path();
+        std::fs::create_dir_all(dir.join("build/Logs")).unwrap();
+        let log = dir.join("build/Logs/build.log");
+        let day = Duration::from_secs(24 * 60 * 60);
+        std::fs::File::create(&log)
+            .unwrap()
+            .set_modified(SystemTime::now() - 3 * day)
+            .unwrap();
+        assert!(is_older_than(dir, 2 * day));
+
+        // A new file in a nested directory makes the whole directory recent.
+        std::fs::write(dir.join("build/Logs/run.log"), "").unwrap();
+        assert!(!is_older_than(dir, 2 * day));
+        assert!(is_older_than(&log, 2 * day));
+    }
+
+    #[test]
+    fn stale_projects() {
+        let temp = crate::test_util::TempDir::new("gc-projects");
+        let target_dir = temp.path();
+        let day = Duration::from_secs(24 * 60 * 60);
+        let old = |path: &Path| {
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::File::create(path)
+                .unwrap()
+                .set_modified(SystemTime::now() - 3 * day)
+                .unwrap();
+        };
+        old(&target_dir.join("game/build/Logs/build.log"));
+        old(&target_dir.join("game/project.yml"));
+        old(&target_dir.join("removed_game/project.yml"));
+        old(&target_dir.join("src/main.rs"));
+        std::fs::create_dir_all(target_dir.join("recent")).unwrap();
+        std::fs::write(target_dir.join("recent/project.yml"), "").unwrap();
+
+        let removed = remove_stale_projects(
+            target_dir,
+            &[target_dir.join("src")],
+            &["game".to_string()],
+            2 * day,
+        )
+        .unwrap();
+        assert_eq!(removed.len(), 2);
+        assert!(!target_dir.join("game/build").exists());
+        assert!(target_dir.join("game/project.yml").exists());
+        assert!(!target_dir.join("removed_game").exists());
+        assert!(target_dir.join("recent").exists());
+        assert!(target_dir.join("src").exists());
+    }
+}
@@ -0,0 +1,158 @@
+use anyhow::Context as _;
+use std::path::Path;
+
+/// Name of the generated icon set, used as `ASSETCATALOG_COMPILER_APPICON_NAME`.
+pub const APP_ICON_NAME: &str = "AppIcon";
+
+/// Every icon of a universal iOS app: idiom, size and scale.
+const ICONS: &[(&str, &str, &str)] = &[
+    ("iphone", "20x20", "2x"),
+    ("iphone", "20x20", "3x"),
+    ("iphone", "29x29", "2x"),
+    ("iphone", "29x29", "3x"),
+    ("iphone", "40x40", "2x"),
+    ("iphone", "40x40", "3x"),
+    ("iphone", "60x60", "2x"),
+    ("iphone", "60x60", "3x"),
+    ("ipad", "20x20", "1x"),
+    ("ipad", "20x20", "2x"),
+    ("ipad", "29x29", "1x"),
+    ("ipad", "29x29", "2x"),
+    ("ipad", "40x40", "1x"),
+    ("ipad", "40x40", "2x"),
+    ("ipad", "76x76", "1x"),
+    ("ipad", "76x76", "2x"),
+    ("ipad", "83.5x83.5", "2x"),
+    ("ios-marketing", "1024x1024", "1x"),
+];
+
+fn pixel_size(size: &str, scale: &str) -> u32 {
+    let points = size
+        .split_once('x')
+        .and_then(|(width, _)| width.parse::<f32>().ok())
+        .unwrap_or(0.0);
+    let scale = scale.trim_end_matches('x').parse::<f32>().unwrap_or(1.0);
+    (points * scale).round() as u32
+}
+
+fn file_name(pixels: u32) -> String {
+    format!("icon-{}.png", pixels)
+}
+
+/// `Contents.json` of the generated icon set. Icons of the same pixel size share a file.
+pub fn contents_json() -> serde_json::Value {
+    let images = ICONS
+        .iter()
+        .map(|&(idiom, size, scale)| {
+            serde_json::json!({
+                "idiom": idiom,
+                "size": size,
+                "scale": scale,
+                "filename": file_name(pixel_size(size, scale)),
+            })
+        })
+        .collect::<Vec<_>>();
+
+    serde_json::json!({
+        "images": images,
+        "info": { "author": "xcode", "version": 1 },
+    })
+}
+
+/// Generate `<catalog_dir>/AppIcon.appiconset` with every required size resized from `source`
+/// with `sips`. Skipped if the icon set is newer than `source`.
+pub fn generate_app_icon(source: &Path, catalog_dir: &Path) -> anyhow::Result<()> {
+    let icon_set_dir = catalog_dir.join(format!("{}.appiconset", APP_ICON_NAME));
+    let contents_path = icon_set_dir.join("Contents.json");
+    let modified = |path: &Path| std::fs::metadata(path).and_then(|m| m.modified()).ok();
+    let source_modified =
+        modified(source).with_context(|| format!("App icon is not found: {:?}", source))?;
+    if matches!(modified(&contents_path), Some(generated) if generated >= source_modified) {
+        log::debug!("App icon is up to date: {:?}", icon_set_dir);
+        return Ok(());
+    }
+
+    let info = crate::preflight::image_info(source)?;
+    if info.width < 1024 || info.width != info.height {
+        anyhow::bail!(
+            "App icon {:?} must be a square image of at least 1024x1024 pixels, not {}x{}",
+            source,
+            info.width,
+            info.height
+        )
+    }
+    if info.has_alpha {
+        log::warn!(
+            "App icon {:?} has an alpha channel, App Store Connect rejects it",
+            source
+        );
+    }
+
+    log::info!("Generating app icon {:?} from {:?}", icon_set_dir, source);
+    std::fs::create_dir_all(&icon_set_dir)
+        .with_context(|| format!("Failed to create all dir: {:?}", icon_set_dir))?;
+    let mut pixel_sizes = ICONS
+        .iter()
+        .map(|&(_, size, scale)| pixel_size(size, scale))
+        .collect::<Vec<_>>();
+    pixel_sizes.sort_unstable();
+    pixel_sizes.dedup();
+    for pixels in pixel_sizes {
+        let output = std::process::Command::new("sips")
+            .arg("-s")
+            .arg("format")
+            .arg("png")
+            .arg("-z")
+            .arg(pixels.to_string())
+            .arg(pixels.to_string())
+            .arg(source)
+            .arg("--out")
+            .arg(icon_set_dir.join(file_name(pixels)))
+            .output()
+            .with_context(|| "Failed to get sips output".to_string())?;
+
+        if !output.status.success() {
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            let stderr = String::from_utf8_lossy(&output.stderr);
+
+            anyhow::bail!(
+                "Failed to resize {:?} to {}px:\n{:?}\n{:?}",
+                source,
+                pixels,
+                stdout,
+                stderr
+            )
+        }
+    }
+
+    std::fs::write(
+        catalog_dir.join("Contents.json"),
+        serde_json::to_string_pretty(&serde_json::json!({
+            "info": { "author": "xcode", "version": 1 },
+        }))?,
+    )
+    .with_context(|| format!("Failed to write {:?}", catalog_dir))?;
+    std::fs::write(
+        &contents_path,
+        serde_json::to_string_pretty(&contents_json())?,
+    )
+    .with_context(|| format!("Failed to write {:?}", contents_path))?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn icon_set_is_complete() {
+        let icon_set: crate::preflight::IconSet = serde_json::from_value(contents_json()).unwrap();
+        let required = crate::preflight::required_icons(crate::toml::AppPlatform::Ios, "1,2");
+        assert!(icon_set.missing_icons(&required).is_empty());
+        for image in icon_set.images {
+            assert_eq!(image.filename, Some(file_name(image.pixel_size().unwrap())));
+        }
+        assert_eq!(pixel_size("83.5x83.5", "2x"), 167);
+    }
+}
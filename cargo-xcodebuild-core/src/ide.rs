@@ -0,0 +1,215 @@
+use anyhow::Context as _;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// rust-analyzer settings that make editor diagnostics match `cargo xcodebuild check`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RustAnalyzerSettings {
+    /// Rustc target, e.g. `aarch64-apple-ios-sim`.
+    pub target: String,
+    /// Environment of cargo invocations, e.g. `SDKROOT`.
+    pub extra_env: BTreeMap<String, String>,
+    pub features: Features,
+}
+
+/// Cargo feature flags.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Features {
+    pub features: Vec<String>,
+    pub all_features: bool,
+    pub no_default_features: bool,
+}
+
+impl Features {
+    /// Collect `--features`, `-F`, `--all-features` and `--no-default-features` from cargo args.
+    pub fn from_args(args: &[String]) -> Self {
+        let mut features = Self::default();
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            let value = match arg.as_str() {
+                "--all-features" => {
+                    features.all_features = true;
+                    continue;
+                }
+                "--no-default-features" => {
+                    features.no_default_features = true;
+                    continue;
+                }
+                "--features" | "-F" => args.next().map(String::as_str),
+                _ => arg.strip_prefix("--features="),
+            };
+            if let Some(value) = value {
+                features.features.extend(
+                    value
+                        .split(|c: char| c == ',' || c.is_whitespace())
+                        .filter(|f| !f.is_empty())
+                        .map(String::from),
+                );
+            }
+        }
+
+        features
+    }
+
+    /// Same as [`Features::from_args`], also removing the feature flags from `args`.
+    pub fn take_from_args(args: &mut Vec<String>) -> Self {
+        let features = Self::from_args(args);
+        let mut rest = Vec::with_capacity(args.len());
+        let mut iter = args.drain(..);
+        while let Some(arg) = iter.next() {
+            match arg.as_str() {
+                "--all-features" | "--no-default-features" => {}
+                "--features" | "-F" => {
+                    iter.next();
+                }
+                _ if arg.starts_with("--features=") => {}
+                _ => rest.push(arg),
+            }
+        }
+        drop(iter);
+        *args = rest;
+
+        features
+    }
+
+    /// Cargo arguments enabling these features and `extra` ones.
+    pub fn to_args(&self, extra: &[String]) -> Vec<String> {
+        let mut args = Vec::new();
+        if self.all_features {
+            args.push("--all-features".to_string());
+        }
+        if self.no_default_features {
+            args.push("--no-default-features".to_string());
+        }
+        let mut features = self.features.clone();
+        for feature in extra {
+            if !features.contains(feature) {
+                features.push(feature.clone());
+            }
+        }
+        if !features.is_empty() {
+            args.push("--features".to_string());
+            args.push(features.join(","));
+        }
+        args
+    }
+}
+
+impl RustAnalyzerSettings {
+    /// Settings in the format of VS Code `settings.json`.
+    pub fn to_vscode_json(&self) -> serde_json::Value {
+        let features = if self.features.all_features {
+            serde_json::json!("all")
+        } else {
+            serde_json::json!(self.features.features)
+        };
+        serde_json::json!({
+            "rust-analyzer.cargo.target": self.target,
+            "rust-analyzer.cargo.extraEnv": self.extra_env,
+            "rust-analyzer.cargo.features": features,
+            "rust-analyzer.cargo.noDefaultFeatures": self.features.no_default_features,
+        })
+    }
+
+    /// Merge the settings into a VS Code `settings.json`, keeping other settings.
+    pub fn write_vscode_settings(&self, path: &Path) -> anyhow::Result<()> {
+        let mut settings = if path.exists() {
+            let content = std::fs::read_to_string(path)
+                .with_context(|| format!("Failed to read {:?}", path))?;
+            serde_json::from_str::<serde_json::Value>(&content).with_context(|| {
+                format!("Failed to parse {:?}. Comments are not supported", path)
+            })?
+        } else {
+            serde_json::json!({})
+        };
+        let object = settings
+            .as_object_mut()
+            .with_context(|| format!("{:?} is not a JSON object", path))?;
+        if let serde_json::Value::Object(new) = self.to_vscode_json() {
+            object.extend(new);
+        }
+
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)
+                .with_context(|| format!("Failed to create all dir: {:?}", dir))?;
+        }
+        std::fs::write(path, serde_json::to_string_pretty(&settings)? + "\n")
+            .with_context(|| format!("Failed to write {:?}", path))?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn features_from_args() {
+        let args = [
+            "--features",
+            "a,b",
+            "--release",
+            "--features=c",
+            "-F",
+            "d e",
+        ]
+        .iter()
+        .map(|a| a.to_string())
+        .collect::<Vec<_>>();
+        assert_eq!(
+            Features::from_args(&args),
+            Features {
+                features: vec!["a".into(), "b".into(), "c".into(), "d".into(), "e".into()],
+                all_features: false,
+                no_default_features: false,
+            }
+        );
+
+        let args = vec!["--all-features".to_string(), "--no-default-features".into()];
+        let features = Features::from_args(&args);
+        assert!(features.all_features && features.no_default_features);
+    }
+
+    #[test]
+    fn features_to_args() {
+        let mut args = [
+            "--release",
+            "-F",
+            "a",
+            "--no-default-features",
+            "--features=b",
+        ]
+        .iter()
+        .map(|a| a.to_string())
+        .collect::<Vec<_>>();
+        let features = Features::take_from_args(&mut args);
+        assert_eq!(args, ["--release"]);
+        assert_eq!(
+            features.to_args(&["b".to_string(), "metal".to_string()]),
+            ["--no-default-features", "--features", "a,b,metal"]
+        );
+        assert!(Features::default().to_args(&[]).is_empty());
+    }
+
+    #[test]
+    fn vscode_json() {
+        let settings = RustAnalyzerSettings {
+            target: "aarch64-apple-ios-sim".into(),
+            extra_env: BTreeMap::from([("SDKROOT".to_string(), "/sdk".to_string())]),
+            features: Features {
+                features: vec!["metal".into()],
+                ..Default::default()
+            },
+        };
+        assert_eq!(
+            settings.to_vscode_json(),
+            serde_json::json!({
+                "rust-analyzer.cargo.target": "aarch64-apple-ios-sim",
+                "rust-analyzer.cargo.extraEnv": { "SDKROOT": "/sdk" },
+                "rust-analyzer.cargo.features": ["metal"],
+                "rust-analyzer.cargo.noDefaultFeatures": false,
+            })
+        );
+    }
+}
@@ -0,0 +1,182 @@
+use anyhow::Context as _;
+use std::path::Path;
+
+/// Name of the generated storyboard, referenced by `UILaunchStoryboardName`.
+pub const STORYBOARD_NAME: &str = "LaunchScreen";
+/// Bundle image name of the centered launch image.
+const IMAGE_NAME: &str = "LaunchImage";
+
+/// Parse `#RRGGBB` or `#RRGGBBAA` into sRGB components from 0 to 1.
+pub fn parse_hex_color(color: &str) -> anyhow::Result<[f32; 4]> {
+    let hex = color.trim_start_matches('#');
+    if !matches!(hex.len(), 6 | 8) || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        anyhow::bail!(
+            "Invalid color `{}`. Expected `#RRGGBB` or `#RRGGBBAA`.",
+            color
+        )
+    }
+    let component = |i: usize| {
+        hex.get(i * 2..i * 2 + 2)
+            .map(|c| u8::from_str_radix(c, 16).unwrap() as f32 / 255.0)
+            .unwrap_or(1.0)
+    };
+
+    Ok([component(0), component(1), component(2), component(3)])
+}
+
+/// `LaunchScreen.storyboard` with a background color and an optional centered image
+/// of the given size in points.
+pub fn storyboard(background: [f32; 4], image: Option<(u32, u32)>) -> String {
+    let [red, green, blue, alpha] = background;
+    let (subviews, constraints, resources) = match image {
+        Some((width, height)) => (
+            format!(
+                r#"
+                        <subviews>
+                            <imageView clipsSubviews="YES" userInteractionEnabled="NO" contentMode="scaleAspectFit" image="{name}" translatesAutoresizingMaskIntoConstraints="NO" id="img-Ln-Scr"/>
+                        </subviews>"#,
+                name = IMAGE_NAME
+            ),
+            r#"
+                        <constraints>
+                            <constraint firstItem="img-Ln-Scr" firstAttribute="centerX" secondItem="Ze5-6b-2t3" secondAttribute="centerX" id="cnX-Ln-Scr"/>
+                            <constraint firstItem="img-Ln-Scr" firstAttribute="centerY" secondItem="Ze5-6b-2t3" secondAttribute="centerY" id="cnY-Ln-Scr"/>
+                        </constraints>"#
+                .to_string(),
+            format!(
+                r#"
+    <resources>
+        <image name="{}" width="{}" height="{}"/>
+    </resources>"#,
+                IMAGE_NAME, width, height
+            ),
+        ),
+        None => (String::new(), String::new(), String::new()),
+    };
+
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<document type="com.apple.InterfaceBuilder3.CocoaTouch.Storyboard.XIB" version="3.0" toolsVersion="21701" targetRuntime="iOS.CocoaTouch" propertyAccessControl="none" useAutolayout="YES" launchScreen="YES" useTraitCollections="YES" useSafeAreas="YES" colorMatched="YES" initialViewController="01J-lp-oVM">
+    <dependencies>
+        <plugIn identifier="com.apple.InterfaceBuilder.IBCocoaTouchPlugin" version="21678"/>
+        <capability name="documents saved in the Xcode 8 format" minToolsVersion="8.0"/>
+    </dependencies>
+    <scenes>
+        <scene sceneID="EHf-IW-A2E">
+            <objects>
+                <viewController id="01J-lp-oVM" sceneMemberID="viewController">
+                    <view key="view" contentMode="scaleToFill" id="Ze5-6b-2t3">
+                        <rect key="frame" x="0.0" y="0.0" width="393" height="852"/>
+                        <autoresizingMask key="autoresizingMask" widthSizable="YES" heightSizable="YES"/>{}
+                        <color key="backgroundColor" red="{:.3}" green="{:.3}" blue="{:.3}" alpha="{:.3}" colorSpace="custom" customColorSpace="sRGB"/>{}
+                    </view>
+                </viewController>
+                <placeholder placeholderIdentifier="IBFirstResponder" id="iYj-Kq-Ea1" userLabel="First Responder" sceneMemberID="firstResponder"/>
+            </objects>
+        </scene>
+    </scenes>{}
+</document>
+"#,
+        subviews, red, green, blue, alpha, constraints, resources
+    )
+}
+
+/// Pixel size of the `scale` variant of a launch image of `width`x`height` pixels. The source
+/// image is the @3x variant.
+fn scaled_size((width, height): (u32, u32), scale: u32) -> (u32, u32) {
+    let scale = |pixels: u32| ((pixels * scale + 1) / 3).max(1);
+    (scale(width), scale(height))
+}
+
+/// Resize `source` into a PNG of `width`x`height` pixels at `destination` with `sips`.
+fn resize(source: &Path, (width, height): (u32, u32), destination: &Path) -> anyhow::Result<()> {
+    let output = std::process::Command::new("sips")
+        .arg("-s")
+        .arg("format")
+        .arg("png")
+        .arg("-z")
+        .arg(height.to_string())
+        .arg(width.to_string())
+        .arg(source)
+        .arg("--out")
+        .arg(destination)
+        .output()
+        .with_context(|| "Failed to get sips output".to_string())?;
+
+    if !output.status.success() {
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+
+        anyhow::bail!(
+            "Failed to resize {:?} to {}x{}:\n{:?}\n{:?}",
+            source,
+            width,
+            height,
+            stdout,
+            stderr
+        )
+    }
+    Ok(())
+}
+
+/// Write `LaunchScreen.storyboard` and the @1x, @2x and @3x launch images into `src_dir`.
+/// The image is the @3x variant, the others are resized from it with `sips`.
+/// A relative image path is resolved against `manifest_dir`.
+pub fn generate(
+    config: &crate::toml::LaunchScreen,
+    manifest_dir: &Path,
+    src_dir: &Path,
+) -> anyhow::Result<()> {
+    let background = parse_hex_color(config.background_color.as_deref().unwrap_or("#FFFFFF"))?;
+    let image_size = match config.image {
+        Some(ref image) => {
+            let source = manifest_dir.join(image);
+            let info = crate::preflight::image_info(&source)?;
+            let size = (info.width, info.height);
+            for (scale, suffix) in [(1, ""), (2, "@2x"), (3, "@3x")] {
+                let destination = src_dir.join(format!("{}{}.png", IMAGE_NAME, suffix));
+                resize(&source, scaled_size(size, scale), &destination)?;
+            }
+            // The storyboard sizes images in points.
+            Some(scaled_size(size, 1))
+        }
+        None => None,
+    };
+
+    let path = src_dir.join(format!("{}.storyboard", STORYBOARD_NAME));
+    log::debug!("Writing {:?}", path);
+    std::fs::write(&path, storyboard(background, image_size))
+        .with_context(|| format!("Failed to write {:?}", path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hex_colors() {
+        assert_eq!(parse_hex_color("#FF0000").unwrap(), [1.0, 0.0, 0.0, 1.0]);
+        assert_eq!(parse_hex_color("00000000").unwrap(), [0.0, 0.0, 0.0, 0.0]);
+        assert!(parse_hex_color("#FFF").is_err());
+        assert!(parse_hex_color("#GGGGGG").is_err());
+    }
+
+    #[test]
+    fn storyboard_image() {
+        let without_image = storyboard([1.0, 1.0, 1.0, 1.0], None);
+        assert!(without_image.contains(r#"red="1.000" green="1.000" blue="1.000""#));
+        assert!(!without_image.contains("imageView"));
+
+        let with_image = storyboard([0.0, 0.0, 0.0, 1.0], Some((512, 256)));
+        assert!(with_image.contains(r#"image="LaunchImage""#));
+        assert!(with_image.contains(r#"<image name="LaunchImage" width="512" height="256"/>"#));
+    }
+
+    #[test]
+    fn scaled_sizes() {
+        assert_eq!(scaled_size((1536, 768), 1), (512, 256));
+        assert_eq!(scaled_size((1536, 768), 2), (1024, 512));
+        assert_eq!(scaled_size((1536, 768), 3), (1536, 768));
+        assert_eq!(scaled_size((100, 2), 1), (33, 1));
+    }
+}
@@ -0,0 +1,68 @@
+//! Core of `cargo-xcodebuild`: generate, build and run Xcode projects for Rust crates.
+//!
+//! The `cargo xcodebuild` binary is a thin CLI over this crate. The main entry point is
+//! [`xcodebuild::Xcodebuild`], which drives the whole pipeline:
+//!
+//! 1. Read `[package.metadata.ios]` from `Cargo.toml` ([`toml`]).
+//! 2. Build the crate for each configured target ([`cargo`]).
+//! 3. Discover signing teams ([`teams`]) and generate an xcodegen project ([`xcodegen`]).
+//! 4. Select a device or simulator ([`devices`]), build with `xcodebuild` and install the app.
+//!
+//! ```no_run
+//! use cargo_xcodebuild_core::xcodebuild::{BuildType, Xcodebuild};
+//!
+//! # fn main() -> anyhow::Result<()> {
+//! let manifest = std::path::Path::new("Cargo.toml");
+//! let target_dir = std::path::Path::new("target");
+//! let xcode_build = Xcodebuild::new(manifest, target_dir)?;
+//! let artifacts = xcode_build.build(&[], BuildType::Debug)?;
+//! xcode_build.run(&artifacts)?;
+//! # Ok(())
+//! # }
+//! ```
+
+pub mod beautify;
+pub mod build_number;
+pub mod capture;
+pub mod cargo;
+pub mod cocoapods;
+pub mod data;
+pub mod debug;
+pub mod devices;
+pub mod doctor;
+pub mod events;
+pub mod export;
+pub mod fastlane;
+pub mod fingerprint;
+pub mod framework;
+pub mod gc;
+pub mod icon;
+pub mod ide;
+pub mod launch_screen;
+pub mod lipo;
+pub mod localization;
+pub mod logs;
+pub mod monitor;
+pub mod notarize;
+pub mod pbxproj;
+pub mod plist;
+pub mod preflight;
+pub mod process;
+pub mod progress;
+pub mod runtime;
+pub mod screenshots;
+pub mod shell;
+pub mod signing;
+pub mod simctl;
+pub mod source;
+pub mod symbols;
+pub mod teams;
+pub mod test_host;
+#[cfg(test)]
+mod test_util;
+pub mod toml;
+pub mod upload;
+pub mod watch;
+pub mod xcframework;
+pub mod xcodebuild;
+pub mod xcodegen;
@@ -0,0 +1,52 @@
+use anyhow::Context as _;
+use std::path::Path;
+
+/// Directory next to the per-target cargo output with merged simulator libraries.
+pub const UNIVERSAL_SIMULATOR_DIR: &str = "universal-apple-ios-sim";
+
+/// Whether both simulator architectures are built and merged into a universal library.
+pub fn has_universal_simulator(build_targets: &[crate::toml::Target]) -> bool {
+    build_targets.contains(&crate::toml::Target::ArmSim)
+        && build_targets.contains(&crate::toml::Target::Sim)
+}
+
+/// Whether `output` is newer than all of `inputs`.
+pub fn is_up_to_date(inputs: &[&Path], output: &Path) -> bool {
+    let modified = |path: &Path| std::fs::metadata(path).and_then(|m| m.modified()).ok();
+    match modified(output) {
+        Some(merged) => inputs
+            .iter()
+            .all(|input| matches!(modified(input), Some(modified) if modified <= merged)),
+        None => false,
+    }
+}
+
+/// Merge single-architecture `inputs` into a universal binary at `output` with `lipo`.
+pub fn create(inputs: &[&Path], output: &Path) -> anyhow::Result<()> {
+    if let Some(dir) = output.parent() {
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("Failed to create all dir: {:?}", dir))?;
+    }
+    log::debug!("Merging {:?} into {:?}", inputs, output);
+    let output_result = std::process::Command::new("lipo")
+        .arg("-create")
+        .args(inputs)
+        .arg("-output")
+        .arg(output)
+        .output()
+        .with_context(|| "Failed to get lipo output".to_string())?;
+
+    if !output_result.status.success() {
+        let stdout = String::from_utf8_lossy(&output_result.stdout);
+        let stderr = String::from_utf8_lossy(&output_result.stderr);
+
+        anyhow::bail!(
+            "Failed to merge {:?} with lipo:\n{:?}\n{:?}",
+            inputs,
+            stdout,
+            stderr
+        )
+    }
+
+    Ok(())
+}
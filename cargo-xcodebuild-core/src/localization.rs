@@ -0,0 +1,84 @@
+use anyhow::Context as _;
+use std::path::Path;
+
+/// Name of the localized Info.plist strings file in each `.lproj` folder.
+pub const INFO_PLIST_STRINGS: &str = "InfoPlist.strings";
+
+/// Contents of a `.strings` file with the given keys and texts.
+pub fn strings_file(strings: &[(&str, String)]) -> String {
+    strings
+        .iter()
+        .map(|(key, value)| format!("\"{}\" = \"{}\";\n", escape(key), escape(value)))
+        .collect()
+}
+
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// Write a `<language>.lproj/InfoPlist.strings` for each of `localizations` into `src_dir`,
+/// removing folders of languages that are no longer listed.
+pub fn generate(ios_metadata: &crate::toml::IosMetadata, src_dir: &Path) -> anyhow::Result<()> {
+    let localizations = ios_metadata.localizations.clone().unwrap_or_default();
+    let localized = ios_metadata.localized.clone().unwrap_or_default();
+    if let Some(language) = localized
+        .keys()
+        .find(|language| !localizations.contains(language))
+    {
+        anyhow::bail!(
+            "Localized texts for `{}` but it is missing in `localizations`. Please check Cargo.toml.",
+            language
+        )
+    }
+
+    for entry in
+        std::fs::read_dir(src_dir).with_context(|| format!("Failed to read dir: {:?}", src_dir))?
+    {
+        let path = entry?.path();
+        let stale = path.extension().is_some_and(|ext| ext == "lproj")
+            && !path
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .is_some_and(|language| localizations.iter().any(|l| l == language));
+        if stale {
+            log::debug!("Removing {:?}", path);
+            std::fs::remove_dir_all(&path)
+                .with_context(|| format!("Failed to remove {:?}", path))?;
+        }
+    }
+
+    for language in &localizations {
+        let dir = src_dir.join(format!("{}.lproj", language));
+        std::fs::create_dir_all(&dir)
+            .with_context(|| format!("Failed to create all dir: {:?}", dir))?;
+        let strings = localized
+            .get(language)
+            .map(|localization| localization.to_strings())
+            .unwrap_or_default();
+        let path = dir.join(INFO_PLIST_STRINGS);
+        log::debug!("Writing {:?}", path);
+        std::fs::write(&path, strings_file(&strings))
+            .with_context(|| format!("Failed to write {:?}", path))?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strings_escaping() {
+        let strings = strings_file(&[
+            ("CFBundleDisplayName", "My \"Game\"".to_string()),
+            ("NSCameraUsageDescription", "Line\nBreak".to_string()),
+        ]);
+        assert_eq!(
+            strings,
+            "\"CFBundleDisplayName\" = \"My \\\"Game\\\"\";\n\"NSCameraUsageDescription\" = \"Line\\nBreak\";\n"
+        );
+    }
+}
@@ -0,0 +1,100 @@
+use anyhow::Context as _;
+
+/// Stream unified log messages of an app on a simulator until interrupted.
+/// Messages of `bundle_id` subsystem (e.g. `oslog`) and everything logged by the `process` are included.
+pub fn stream_simulator(udid: &str, bundle_id: &str, process: &str) -> anyhow::Result<()> {
+    let predicate = format!(
+        "subsystem == \"{}\" OR process == \"{}\"",
+        bundle_id, process
+    );
+    log::debug!("Streaming logs of simulator {}: {}", udid, predicate);
+    let status = std::process::Command::new("xcrun")
+        .arg("simctl")
+        .arg("spawn")
+        .arg(udid)
+        .arg("log")
+        .arg("stream")
+        .arg("--style")
+        .arg("compact")
+        .arg("--level")
+        .arg("debug")
+        .arg("--predicate")
+        .arg(predicate)
+        .status()
+        .with_context(|| "Failed to run xcrun simctl spawn log stream".to_string())?;
+
+    if !status.success() {
+        anyhow::bail!("Failed to stream logs of simulator {}: {}", udid, status)
+    }
+
+    Ok(())
+}
+
+/// Stream unified log messages of an app running on this Mac until interrupted.
+pub fn stream_mac(bundle_id: &str, process: &str) -> anyhow::Result<()> {
+    let predicate = format!(
+        "subsystem == \"{}\" OR process == \"{}\"",
+        bundle_id, process
+    );
+    log::debug!("Streaming logs of this Mac: {}", predicate);
+    let status = std::process::Command::new("log")
+        .arg("stream")
+        .arg("--style")
+        .arg("compact")
+        .arg("--level")
+        .arg("debug")
+        .arg("--predicate")
+        .arg(predicate)
+        .status()
+        .with_context(|| "Failed to run log stream".to_string())?;
+
+    if !status.success() {
+        anyhow::bail!("Failed to stream logs: {}", status)
+    }
+
+    Ok(())
+}
+
+/// Stream syslog lines of the `process` on a connected device until interrupted.
+pub fn stream_device(device: &md::MobileDevice, process: &str) -> anyhow::Result<()> {
+    log::debug!("Streaming syslog of device {}", device.identifier);
+    device.stream_syslog(|line| {
+        if is_process_line(line, process) {
+            println!("{}", line);
+        }
+    })
+}
+
+/// Whether a syslog line, e.g. `Oct 16 12:00:00 iPhone my-game(libfoo.dylib)[123] <Notice>: ...`,
+/// comes from `process`.
+fn is_process_line(line: &str, process: &str) -> bool {
+    line.split(' ').any(|word| {
+        matches!(word.strip_prefix(process), Some(rest) if rest.starts_with('[') || rest.starts_with('('))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn process_lines() {
+        let process = "my_game";
+        assert!(is_process_line(
+            "Oct 16 12:00:00 iPhone my_game[123] <Notice>: hello",
+            process
+        ));
+        assert!(is_process_line(
+            "Oct 16 12:00:00 iPhone my_game(CoreFoundation)[123] <Error>: oops",
+            process
+        ));
+        assert!(!is_process_line(
+            "Oct 16 12:00:00 iPhone my_game_helper[124] <Notice>: hello",
+            process
+        ));
+        assert!(!is_process_line(
+            "Oct 16 12:00:00 iPhone SpringBoard[55] <Notice>: launched my_game",
+            process
+        ));
+    }
+}
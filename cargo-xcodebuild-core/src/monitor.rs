@@ -0,0 +1,314 @@
+use anyhow::Context as _;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+/// Parse a duration like `500ms`, `30s`, `5m` or `1h`. Plain numbers are seconds.
+pub fn parse_duration(duration: &str) -> anyhow::Result<Duration> {
+    let duration = duration.trim();
+    let split = duration
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(duration.len());
+    let (value, unit) = duration.split_at(split);
+    let value = value
+        .parse::<f64>()
+        .ok()
+        .filter(|value| value.is_finite() && *value > 0.0)
+        .with_context(|| format!("Invalid duration `{}`, expected e.g. `30s`", duration))?;
+    let seconds = match unit {
+        "ms" => value / 1000.0,
+        "" | "s" => value,
+        "m" => value * 60.0,
+        "h" => value * 60.0 * 60.0,
+        _ => anyhow::bail!(
+            "Unknown unit `{}` in duration `{}`, expected `ms`, `s`, `m` or `h`",
+            unit,
+            duration
+        ),
+    };
+
+    Ok(Duration::from_secs_f64(seconds))
+}
+
+/// CPU, memory and GPU usage of a process at some point in time.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Sample {
+    /// CPU usage in percent of a single core.
+    pub cpu: f32,
+    /// Resident memory in kilobytes.
+    pub memory_kb: u64,
+    /// Utilization of the GPU of this Mac in percent. Simulators render with it, so this is the
+    /// whole GPU, not only the app.
+    pub gpu: Option<u32>,
+}
+
+impl std::fmt::Display for Sample {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "CPU {:>6.1}%  Memory {:>8.1} MB",
+            self.cpu,
+            self.memory_kb as f64 / 1024.0
+        )?;
+        if let Some(gpu) = self.gpu {
+            write!(f, "  GPU {:>3}%", gpu)?;
+        }
+        Ok(())
+    }
+}
+
+/// Aggregated samples of a monitored run.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Summary {
+    pub samples: usize,
+    pub duration: Duration,
+    pub cpu_average: f32,
+    pub cpu_max: f32,
+    pub memory_max_kb: u64,
+    pub memory_last_kb: u64,
+    /// Samples with a GPU utilization.
+    pub gpu_samples: usize,
+    pub gpu_average: f32,
+    pub gpu_max: u32,
+}
+
+impl Summary {
+    pub fn add(&mut self, sample: &Sample) {
+        self.cpu_average =
+            (self.cpu_average * self.samples as f32 + sample.cpu) / (self.samples + 1) as f32;
+        self.samples += 1;
+        self.cpu_max = self.cpu_max.max(sample.cpu);
+        self.memory_max_kb = self.memory_max_kb.max(sample.memory_kb);
+        self.memory_last_kb = sample.memory_kb;
+        if let Some(gpu) = sample.gpu {
+            self.gpu_average = (self.gpu_average * self.gpu_samples as f32 + gpu as f32)
+                / (self.gpu_samples + 1) as f32;
+            self.gpu_samples += 1;
+            self.gpu_max = self.gpu_max.max(gpu);
+        }
+    }
+}
+
+impl std::fmt::Display for Summary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "{} samples in {:.1}s",
+            self.samples,
+            self.duration.as_secs_f32()
+        )?;
+        writeln!(
+            f,
+            "CPU: average {:.1}%, max {:.1}%",
+            self.cpu_average, self.cpu_max
+        )?;
+        write!(
+            f,
+            "Memory: peak {:.1} MB, last {:.1} MB",
+            self.memory_max_kb as f64 / 1024.0,
+            self.memory_last_kb as f64 / 1024.0
+        )?;
+        if self.gpu_samples > 0 {
+            write!(
+                f,
+                "\nGPU: average {:.1}%, max {}%",
+                self.gpu_average, self.gpu_max
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Parse the output of `ps -o %cpu= -o rss= -p <pid>`.
+pub fn parse_ps(output: &str) -> Option<Sample> {
+    let mut values = output.split_whitespace();
+    let cpu = values.next()?.parse().ok()?;
+    let memory_kb = values.next()?.parse().ok()?;
+    Some(Sample {
+        cpu,
+        memory_kb,
+        gpu: None,
+    })
+}
+
+/// Parse the highest `Device Utilization %` of the GPUs in `ioreg -r -d 1 -c IOAccelerator`.
+pub fn parse_gpu_utilization(output: &str) -> Option<u32> {
+    const KEY: &str = "\"Device Utilization %\"=";
+    output
+        .match_indices(KEY)
+        .filter_map(|(i, _)| {
+            let value = &output[i + KEY.len()..];
+            let end = value
+                .find(|c: char| !c.is_ascii_digit())
+                .unwrap_or(value.len());
+            value[..end].parse().ok()
+        })
+        .max()
+}
+
+/// Utilization of the GPU of this Mac in percent, `None` if `ioreg` doesn't report it.
+pub fn gpu_utilization() -> Option<u32> {
+    let output = std::process::Command::new("ioreg")
+        .arg("-r")
+        .arg("-d")
+        .arg("1")
+        .arg("-c")
+        .arg("IOAccelerator")
+        .output()
+        .ok()?;
+    parse_gpu_utilization(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// Sample a process of this Mac. Returns `None` if it exited.
+/// Simulator apps are regular macOS processes, so this works for them too.
+pub fn sample(pid: u32) -> anyhow::Result<Option<Sample>> {
+    let output = std::process::Command::new("ps")
+        .arg("-o")
+        .arg("%cpu=")
+        .arg("-o")
+        .arg("rss=")
+        .arg("-p")
+        .arg(pid.to_string())
+        .output()
+        .with_context(|| "Failed to get ps output".to_string())?;
+
+    if !output.status.success() {
+        return Ok(None);
+    }
+
+    Ok(
+        parse_ps(&String::from_utf8_lossy(&output.stdout)).map(|sample| Sample {
+            gpu: gpu_utilization(),
+            ..sample
+        }),
+    )
+}
+
+/// Sample `pid` every `interval` until it exits or `duration` passes.
+pub fn monitor<F>(
+    pid: u32,
+    interval: Duration,
+    duration: Option<Duration>,
+    mut on_sample: F,
+) -> anyhow::Result<Summary>
+where
+    F: FnMut(&Sample),
+{
+    let started = Instant::now();
+    let mut summary = Summary::default();
+    while let Some(sample) = sample(pid)? {
+        summary.add(&sample);
+        on_sample(&sample);
+        if matches!(duration, Some(d) if started.elapsed() >= d) {
+            break;
+        }
+        std::thread::sleep(interval);
+    }
+    summary.duration = started.elapsed();
+
+    Ok(summary)
+}
+
+/// Record CPU, memory and GPU usage of `process` on device `udid` to an Instruments trace at
+/// `output` with `xctrace`, until the process exits or `duration` passes. Processes of devices
+/// can't be sampled with `ps`.
+pub fn record_device(
+    udid: &str,
+    process: &str,
+    duration: Option<Duration>,
+    output: &Path,
+) -> anyhow::Result<()> {
+    if output.exists() {
+        std::fs::remove_dir_all(output)
+            .with_context(|| format!("Failed to remove dir: {:?}", output))?;
+    }
+    let mut command = std::process::Command::new("xcrun");
+    command
+        .arg("xctrace")
+        .arg("record")
+        .arg("--template")
+        .arg("Activity Monitor")
+        .arg("--instrument")
+        .arg("GPU")
+        .arg("--device")
+        .arg(udid)
+        .arg("--attach")
+        .arg(process)
+        .arg("--output")
+        .arg(output);
+    if let Some(duration) = duration {
+        command
+            .arg("--time-limit")
+            .arg(format!("{}ms", duration.as_millis()));
+    }
+    // xctrace reports its progress on stdout.
+    let status = command
+        .stdout(std::io::stderr())
+        .status()
+        .with_context(|| "Failed to run xcrun xctrace record".to_string())?;
+    crate::process::check("xcrun xctrace record", status)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ps_output() {
+        assert_eq!(
+            parse_ps("  12.5  204800\n"),
+            Some(Sample {
+                cpu: 12.5,
+                memory_kb: 204800,
+                gpu: None,
+            })
+        );
+        assert_eq!(parse_ps(""), None);
+    }
+
+    #[test]
+    fn ioreg_output() {
+        let output = r#"+-o AGXAcceleratorG13X  <class AGXAcceleratorG13X>
+    {
+      "PerformanceStatistics" = {"In use system memory"=402653184,"Device Utilization %"=37,"Renderer Utilization %"=35}
+    }
+"#;
+        assert_eq!(parse_gpu_utilization(output), Some(37));
+        assert_eq!(parse_gpu_utilization(""), None);
+    }
+
+    #[test]
+    fn durations() {
+        assert_eq!(parse_duration("30s").unwrap(), Duration::from_secs(30));
+        assert_eq!(parse_duration("30").unwrap(), Duration::from_secs(30));
+        assert_eq!(parse_duration("500ms").unwrap(), Duration::from_millis(500));
+        assert_eq!(parse_duration("1.5m").unwrap(), Duration::from_secs(90));
+        assert_eq!(parse_duration("1h").unwrap(), Duration::from_secs(3600));
+        assert!(parse_duration("1d").is_err());
+        assert!(parse_duration("0s").is_err());
+        assert!(parse_duration("s").is_err());
+    }
+
+    #[test]
+    fn summary() {
+        let mut summary = Summary::default();
+        summary.add(&Sample {
+            cpu: 10.0,
+            memory_kb: 2048,
+            gpu: Some(40),
+        });
+        summary.add(&Sample {
+            cpu: 30.0,
+            memory_kb: 1024,
+            gpu: None,
+        });
+        assert_eq!(summary.samples, 2);
+        assert_eq!(summary.cpu_average, 20.0);
+        assert_eq!(summary.cpu_max, 30.0);
+        assert_eq!(summary.memory_max_kb, 2048);
+        assert_eq!(summary.memory_last_kb, 1024);
+        assert_eq!(summary.gpu_samples, 1);
+        assert_eq!(summary.gpu_max, 40);
+    }
+}
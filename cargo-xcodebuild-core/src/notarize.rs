@@ -0,0 +1,155 @@
+use anyhow::Context as _;
+use std::path::{Path, PathBuf};
+
+/// Credentials for `xcrun notarytool`.
+#[derive(Debug, Clone)]
+pub enum NotaryCredentials {
+    /// Profile stored with `xcrun notarytool store-credentials`.
+    KeychainProfile(String),
+    ApiKey(crate::signing::AuthenticationKey),
+}
+
+impl NotaryCredentials {
+    /// The keychain profile of `[package.metadata.ios.notarization]`, or the App Store Connect
+    /// API key resolved like [`crate::signing::AuthenticationKey::resolve`].
+    pub fn resolve(
+        config: &crate::toml::Notarization,
+        app_store_connect: Option<&crate::toml::AppStoreConnect>,
+        manifest_dir: &Path,
+    ) -> anyhow::Result<Self> {
+        match config.keychain_profile {
+            Some(ref profile) => Ok(NotaryCredentials::KeychainProfile(profile.clone())),
+            None => Ok(NotaryCredentials::ApiKey(
+                crate::signing::AuthenticationKey::resolve(app_store_connect, manifest_dir)?
+                    .with_context(|| {
+                        "Notarization requires `notarization.keychain_profile` or `app_store_connect` in Cargo.toml".to_string()
+                    })?,
+            )),
+        }
+    }
+
+    fn args(&self) -> Vec<String> {
+        match *self {
+            NotaryCredentials::KeychainProfile(ref profile) => {
+                vec!["--keychain-profile".into(), profile.clone()]
+            }
+            NotaryCredentials::ApiKey(ref key) => vec![
+                "--key".into(),
+                key.path.to_string_lossy().into_owned(),
+                "--key-id".into(),
+                key.id.clone(),
+                "--issuer".into(),
+                key.issuer_id.clone(),
+            ],
+        }
+    }
+}
+
+/// Notarize and staple a signed `.app` or `.dmg`. If `dmg` is set, an `.app` is packed into a
+/// `.dmg` first. Returns the path of the distributable.
+pub fn notarize(
+    path: &Path,
+    credentials: &NotaryCredentials,
+    dmg: bool,
+) -> anyhow::Result<PathBuf> {
+    let is_app = path.extension().map(|e| e == "app").unwrap_or(false);
+    let distributable = if is_app && dmg {
+        create_dmg(path)?
+    } else {
+        path.to_path_buf()
+    };
+
+    // notarytool accepts .dmg, .pkg and .zip. An .app has to be zipped.
+    let upload_path = if distributable
+        .extension()
+        .map(|e| e == "app")
+        .unwrap_or(false)
+    {
+        let zip_path = distributable.with_extension("zip");
+        run(
+            "ditto",
+            &[
+                "-c".into(),
+                "-k".into(),
+                "--keepParent".into(),
+                distributable.to_string_lossy().into_owned(),
+                zip_path.to_string_lossy().into_owned(),
+            ],
+        )?;
+        zip_path
+    } else {
+        distributable.clone()
+    };
+
+    log::info!("Submitting {:?} for notarization", upload_path);
+    let mut args = vec![
+        "notarytool".into(),
+        "submit".into(),
+        upload_path.to_string_lossy().into_owned(),
+        "--wait".into(),
+    ];
+    args.extend(credentials.args());
+    let output = run("xcrun", &args)?;
+    if !output.contains("status: Accepted") {
+        anyhow::bail!("Notarization of {:?} failed:\n{}", upload_path, output)
+    }
+
+    log::info!("Stapling {:?}", distributable);
+    run(
+        "xcrun",
+        &[
+            "stapler".into(),
+            "staple".into(),
+            distributable.to_string_lossy().into_owned(),
+        ],
+    )?;
+
+    Ok(distributable)
+}
+
+fn create_dmg(app_path: &Path) -> anyhow::Result<PathBuf> {
+    let dmg_path = app_path.with_extension("dmg");
+    let volume_name = app_path
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    log::info!("Creating {:?}", dmg_path);
+    run(
+        "hdiutil",
+        &[
+            "create".into(),
+            "-volname".into(),
+            volume_name,
+            "-srcfolder".into(),
+            app_path.to_string_lossy().into_owned(),
+            "-ov".into(),
+            "-format".into(),
+            "UDZO".into(),
+            dmg_path.to_string_lossy().into_owned(),
+        ],
+    )?;
+
+    Ok(dmg_path)
+}
+
+fn run(program: &str, args: &[String]) -> anyhow::Result<String> {
+    let output = std::process::Command::new(program)
+        .args(args)
+        .output()
+        .with_context(|| format!("Failed to get output from command: {} {:?}", program, args))?;
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+
+        anyhow::bail!(
+            "Failed to run {} {:?}:\n{:?}\n{:?}",
+            program,
+            args,
+            stdout,
+            stderr
+        )
+    }
+
+    Ok(stdout.into_owned())
+}
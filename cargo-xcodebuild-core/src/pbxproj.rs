@@ -0,0 +1,1247 @@
+//! Built-in generator of `.xcodeproj` bundles from a [`Project`] spec, used instead of the
+//! `xcodegen` binary when it is not installed.
+
+use anyhow::Context as _;
+use serde_json::{json, Map, Value};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+
+use crate::toml::AppPlatform;
+use crate::xcodegen::{DependencyType, Platform, ProductType, Project, Target};
+
+const OBJECT_VERSION: &str = "56";
+const LAST_UPGRADE_CHECK: &str = "1500";
+/// Mask of build files and phases that run for every action.
+const BUILD_ACTION_MASK: &str = "2147483647";
+
+/// Directories added to the project as a single file instead of a group.
+const BUNDLE_EXTENSIONS: &[&str] = &[
+    "xcassets",
+    "bundle",
+    "framework",
+    "xcframework",
+    "scnassets",
+    "xcdatamodeld",
+    "app",
+];
+/// Files of the Compile Sources build phase.
+const SOURCE_EXTENSIONS: &[&str] = &["m", "mm", "c", "cc", "cpp", "swift", "metal"];
+/// Files referenced by the project without a build phase.
+const REFERENCE_EXTENSIONS: &[&str] = &["h", "hpp", "modulemap", "entitlements", "xcconfig"];
+
+/// `lastKnownFileType` of a file reference.
+pub fn file_type(path: &Path) -> &'static str {
+    match path.extension().and_then(|e| e.to_str()).unwrap_or("") {
+        "m" => "sourcecode.c.objc",
+        "mm" => "sourcecode.cpp.objcpp",
+        "c" => "sourcecode.c.c",
+        "cc" | "cpp" => "sourcecode.cpp.cpp",
+        "swift" => "sourcecode.swift",
+        "metal" => "sourcecode.metal",
+        "h" | "hpp" => "sourcecode.c.h",
+        "modulemap" => "sourcecode.module-map",
+        "storyboard" => "file.storyboard",
+        "xib" => "file.xib",
+        "xcassets" => "folder.assetcatalog",
+        "plist" | "entitlements" => "text.plist.xml",
+        "strings" => "text.plist.strings",
+        "xcprivacy" => "text.xml",
+        "json" => "text.json",
+        "png" => "image.png",
+        "jpg" | "jpeg" => "image.jpeg",
+        "framework" => "wrapper.framework",
+        "xcframework" => "wrapper.xcframework",
+        "bundle" => "wrapper.plug-in",
+        "app" => "wrapper.application",
+        "a" => "archive.ar",
+        "dylib" => "compiled.mach-o.dylib",
+        "tbd" => "sourcecode.text-based-dylib-definition",
+        "xcconfig" => "text.xcconfig",
+        _ => "file",
+    }
+}
+
+/// Stable 24 character object identifier derived from `key`, so regenerating the project
+/// keeps identifiers and Xcode state.
+pub fn object_id(key: &str) -> String {
+    let hash = |seed: u64| {
+        key.bytes().fold(seed, |hash, byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(0x100000001b3)
+        })
+    };
+    format!(
+        "{:016X}{:08X}",
+        hash(0xcbf29ce484222325),
+        hash(0x84222325cbf29ce4) as u32
+    )
+}
+
+/// Quote a string of an OpenStep property list if it contains anything but safe characters.
+pub fn quote(s: &str) -> String {
+    let plain = !s.is_empty()
+        && !s.contains("//")
+        && s.chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '$' | '.' | '/'));
+    if plain {
+        return s.to_string();
+    }
+    let mut quoted = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\t' => quoted.push_str("\\t"),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Serialize a JSON value in the OpenStep property list format of `project.pbxproj`.
+/// Booleans are written as `YES` and `NO`.
+pub fn to_pbxproj(value: &Value) -> String {
+    let mut out = String::from("// !$*UTF8*$!\n");
+    write_value(&mut out, value, 0);
+    out.push('\n');
+    out
+}
+
+fn write_value(out: &mut String, value: &Value, indent: usize) {
+    let pad = "\t".repeat(indent);
+    match *value {
+        Value::Null => out.push_str("\"\""),
+        Value::Bool(b) => out.push_str(if b { "YES" } else { "NO" }),
+        Value::Number(ref n) => out.push_str(&n.to_string()),
+        Value::String(ref s) => out.push_str(&quote(s)),
+        Value::Array(ref array) => {
+            out.push_str("(\n");
+            for v in array {
+                out.push_str(&pad);
+                out.push('\t');
+                write_value(out, v, indent + 1);
+                out.push_str(",\n");
+            }
+            out.push_str(&pad);
+            out.push(')');
+        }
+        Value::Object(ref map) => {
+            out.push_str("{\n");
+            for (k, v) in map {
+                out.push_str(&pad);
+                out.push('\t');
+                out.push_str(&quote(k));
+                out.push_str(" = ");
+                write_value(out, v, indent + 1);
+                out.push_str(";\n");
+            }
+            out.push_str(&pad);
+            out.push('}');
+        }
+    }
+}
+
+fn xml_attribute(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn app_platform(platform: &Platform) -> AppPlatform {
+    match *platform {
+        Platform::Ios => AppPlatform::Ios,
+        Platform::TvOS => AppPlatform::TvOs,
+        Platform::WatchOS => AppPlatform::WatchOs,
+        Platform::MacOS => AppPlatform::MacOs,
+    }
+}
+
+/// `copyFiles` destination of xcodegen as `dstSubfolderSpec`.
+fn subfolder_spec(destination: &str) -> anyhow::Result<&'static str> {
+    Ok(match destination {
+        "absolutePath" => "0",
+        "wrapper" => "1",
+        "executables" => "6",
+        "resources" => "7",
+        "frameworks" => "10",
+        "sharedFrameworks" => "11",
+        "sharedSupport" => "12",
+        "plugins" => "13",
+        "productsDirectory" => "16",
+        _ => anyhow::bail!("Unsupported copy files destination `{}`", destination),
+    })
+}
+
+/// Default Info.plist keys of a target, overridden by its `info` properties.
+fn default_info_plist(product_type: &ProductType, platform: AppPlatform) -> Map<String, Value> {
+    let mut plist = json!({
+        "CFBundleDevelopmentRegion": "$(DEVELOPMENT_LANGUAGE)",
+        "CFBundleExecutable": "$(EXECUTABLE_NAME)",
+        "CFBundleIdentifier": "$(PRODUCT_BUNDLE_IDENTIFIER)",
+        "CFBundleInfoDictionaryVersion": "6.0",
+        "CFBundleName": "$(PRODUCT_NAME)",
+        "CFBundlePackageType": "$(PRODUCT_BUNDLE_PACKAGE_TYPE)",
+        "CFBundleShortVersionString": "1.0",
+        "CFBundleVersion": "1",
+    });
+    let plist = plist.as_object_mut().unwrap();
+    if let ProductType::Application = *product_type {
+        match platform {
+            AppPlatform::Ios => {
+                plist.insert("LSRequiresIPhoneOS".into(), json!(true));
+                plist.insert(
+                    "UISupportedInterfaceOrientations".into(),
+                    json!([
+                        "UIInterfaceOrientationPortrait",
+                        "UIInterfaceOrientationLandscapeLeft",
+                        "UIInterfaceOrientationLandscapeRight",
+                    ]),
+                );
+                plist.insert(
+                    "UISupportedInterfaceOrientations~ipad".into(),
+                    json!([
+                        "UIInterfaceOrientationPortrait",
+                        "UIInterfaceOrientationPortraitUpsideDown",
+                        "UIInterfaceOrientationLandscapeLeft",
+                        "UIInterfaceOrientationLandscapeRight",
+                    ]),
+                );
+            }
+            AppPlatform::MacOs => {
+                plist.insert(
+                    "LSMinimumSystemVersion".into(),
+                    json!("$(MACOSX_DEPLOYMENT_TARGET)"),
+                );
+            }
+            AppPlatform::TvOs | AppPlatform::WatchOs => {}
+        }
+    }
+    plist.clone()
+}
+
+/// Project settings of every configuration of `config_type` (`debug` or `release`).
+fn default_project_settings(config_type: &str) -> Map<String, Value> {
+    let mut settings = json!({
+        "ALWAYS_SEARCH_USER_PATHS": "NO",
+        "CLANG_ANALYZER_NONNULL": "YES",
+        "CLANG_CXX_LANGUAGE_STANDARD": "gnu++14",
+        "CLANG_ENABLE_MODULES": "YES",
+        "CLANG_ENABLE_OBJC_ARC": "YES",
+        "CLANG_ENABLE_OBJC_WEAK": "YES",
+        "COPY_PHASE_STRIP": "NO",
+        "ENABLE_STRICT_OBJC_MSGSEND": "YES",
+        "GCC_C_LANGUAGE_STANDARD": "gnu11",
+        "GCC_NO_COMMON_BLOCKS": "YES",
+        "MTL_FAST_MATH": "YES",
+        "PRODUCT_NAME": "$(TARGET_NAME)",
+    });
+    let extra = if config_type == "debug" {
+        json!({
+            "DEBUG_INFORMATION_FORMAT": "dwarf",
+            "ENABLE_TESTABILITY": "YES",
+            "GCC_DYNAMIC_NO_PIC": "NO",
+            "GCC_OPTIMIZATION_LEVEL": "0",
+            "GCC_PREPROCESSOR_DEFINITIONS": ["$(inherited)", "DEBUG=1"],
+            "MTL_ENABLE_DEBUG_INFO": "INCLUDE_SOURCE",
+            "ONLY_ACTIVE_ARCH": "YES",
+            "SWIFT_ACTIVE_COMPILATION_CONDITIONS": "DEBUG",
+            "SWIFT_OPTIMIZATION_LEVEL": "-Onone",
+        })
+    } else {
+        json!({
+            "DEBUG_INFORMATION_FORMAT": "dwarf-with-dsym",
+            "ENABLE_NS_ASSERTIONS": "NO",
+            "MTL_ENABLE_DEBUG_INFO": "NO",
+            "SWIFT_COMPILATION_MODE": "wholemodule",
+            "SWIFT_OPTIMIZATION_LEVEL": "-O",
+        })
+    };
+    let settings_map = settings.as_object_mut().unwrap();
+    settings_map.extend(extra.as_object().unwrap().clone());
+    settings_map.clone()
+}
+
+/// Merge the settings of `value` into `settings`, e.g. the `base` settings of a target.
+fn merge_settings(settings: &mut Map<String, Value>, value: Option<&Value>) {
+    if let Some(Value::Object(map)) = value {
+        for (key, value) in map {
+            settings.insert(key.clone(), value.clone());
+        }
+    }
+}
+
+/// Build phase of a file in a plain (not `copyFiles`) source directory.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Phase {
+    Sources,
+    Resources,
+    None,
+}
+
+fn phase_of(path: &Path) -> Phase {
+    match path.extension().and_then(|e| e.to_str()) {
+        Some(ext) if SOURCE_EXTENSIONS.contains(&ext) => Phase::Sources,
+        Some(ext) if REFERENCE_EXTENSIONS.contains(&ext) => Phase::None,
+        _ => Phase::Resources,
+    }
+}
+
+/// A file reference or variant group added to the project.
+struct Item {
+    id: String,
+    phase: Phase,
+}
+
+/// `includes` and `excludes` globs of a `sources` entry, relative to its path.
+#[derive(Default)]
+struct SourceFilter {
+    includes: Vec<glob::Pattern>,
+    excludes: Vec<glob::Pattern>,
+}
+
+impl SourceFilter {
+    fn parse(value: &Value, key: &str) -> anyhow::Result<Vec<glob::Pattern>> {
+        value[key]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|pattern| pattern.as_str())
+            .map(|pattern| {
+                glob::Pattern::new(pattern)
+                    .with_context(|| format!("Invalid glob pattern `{}`", pattern))
+            })
+            .collect()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.includes.is_empty() && self.excludes.is_empty()
+    }
+
+    /// Whether the file at `relative` to the entry path is part of it.
+    fn matches(&self, relative: &Path) -> bool {
+        let options = glob::MatchOptions {
+            require_literal_separator: true,
+            ..glob::MatchOptions::new()
+        };
+        (self.includes.is_empty()
+            || self
+                .includes
+                .iter()
+                .any(|pattern| pattern.matches_path_with(relative, options)))
+            && !self
+                .excludes
+                .iter()
+                .any(|pattern| pattern.matches_path_with(relative, options))
+    }
+}
+
+/// A `sources` entry of a target.
+struct SourceRoot {
+    path: String,
+    /// `dstSubfolderSpec` of the copy files phase, if any.
+    copy_files: Option<&'static str>,
+    platform_filters: Vec<String>,
+    filter: SourceFilter,
+}
+
+impl SourceRoot {
+    fn parse(value: &Value) -> anyhow::Result<Self> {
+        if let Some(path) = value.as_str() {
+            return Ok(Self {
+                path: path.to_string(),
+                copy_files: None,
+                platform_filters: vec![],
+                filter: SourceFilter::default(),
+            });
+        }
+        let path = value["path"]
+            .as_str()
+            .with_context(|| format!("Source without path: {}", value))?;
+        let copy_files = match value["buildPhase"]["copyFiles"]["destination"].as_str() {
+            Some(destination) => Some(subfolder_spec(destination)?),
+            None => None,
+        };
+        let platform_filters = value["destinationFilters"]
+            .as_array()
+            .map(|filters| {
+                filters
+                    .iter()
+                    .filter_map(|f| f.as_str().map(str::to_lowercase))
+                    .collect()
+            })
+            .unwrap_or_default();
+        let filter = SourceFilter {
+            includes: SourceFilter::parse(value, "includes")?,
+            excludes: SourceFilter::parse(value, "excludes")?,
+        };
+        Ok(Self {
+            path: path.to_string(),
+            copy_files,
+            platform_filters,
+            filter,
+        })
+    }
+}
+
+struct Generator<'a> {
+    project: &'a Project,
+    project_dir: &'a Path,
+    objects: Map<String, Value>,
+    main_group: Vec<String>,
+    frameworks_group: Vec<String>,
+    products_group: Vec<String>,
+    regions: BTreeSet<String>,
+    /// Info.plist files of all targets, excluded from resources.
+    info_plists: BTreeSet<PathBuf>,
+}
+
+impl<'a> Generator<'a> {
+    fn add(&mut self, key: &str, object: Value) -> String {
+        let id = object_id(key);
+        self.objects.insert(id.clone(), object);
+        id
+    }
+
+    /// Reference `path` relative to the enclosing group, or absolute at the root.
+    fn add_file(&mut self, path: &Path, group_path: &str, source_tree: &str) -> String {
+        let mut file = json!({
+            "isa": "PBXFileReference",
+            "lastKnownFileType": file_type(path),
+            "path": group_path,
+            "sourceTree": source_tree,
+        });
+        let name = path.file_name().unwrap().to_string_lossy().to_string();
+        if name != group_path {
+            file["name"] = json!(name);
+        }
+        self.add(&format!("file:{}", path.display()), file)
+    }
+
+    /// Add the files of `dir` recursively, except those `filter` leaves out relative to `base`.
+    /// Returns the children of its group.
+    fn add_dir(
+        &mut self,
+        dir: &Path,
+        base: &Path,
+        filter: &SourceFilter,
+        items: &mut Vec<Item>,
+    ) -> anyhow::Result<Vec<String>> {
+        let mut entries = std::fs::read_dir(dir)
+            .with_context(|| format!("Failed to read dir: {:?}", dir))?
+            .flatten()
+            .map(|e| e.path())
+            .filter(|p| !p.file_name().unwrap().to_string_lossy().starts_with('.'))
+            .collect::<Vec<_>>();
+        entries.sort();
+
+        let mut children = vec![];
+        // Localized files by name, e.g. `InfoPlist.strings` -> [(`en`, path)].
+        let mut variants: BTreeMap<String, Vec<(String, PathBuf)>> = BTreeMap::new();
+        for path in entries {
+            let name = path.file_name().unwrap().to_string_lossy().to_string();
+            let ext = path.extension().and_then(|e| e.to_str()).unwrap_or("");
+            if path.is_dir() && ext == "lproj" {
+                let language = path.file_stem().unwrap().to_string_lossy().to_string();
+                let mut files = std::fs::read_dir(&path)
+                    .with_context(|| format!("Failed to read dir: {:?}", path))?
+                    .flatten()
+                    .map(|e| e.path())
+                    .filter(|p| p.is_file())
+                    .filter(|p| filter.matches(p.strip_prefix(base).unwrap_or(p)))
+                    .collect::<Vec<_>>();
+                files.sort();
+                for file in files {
+                    let file_name = file.file_name().unwrap().to_string_lossy().to_string();
+                    variants
+                        .entry(file_name)
+                        .or_default()
+                        .push((language.clone(), file));
+                }
+            } else if path.is_dir() && !BUNDLE_EXTENSIONS.contains(&ext) {
+                let group_children = self.add_dir(&path, base, filter, items)?;
+                if group_children.is_empty() && !filter.is_empty() {
+                    continue;
+                }
+                let group = json!({
+                    "isa": "PBXGroup",
+                    "children": group_children,
+                    "path": name,
+                    "sourceTree": "<group>",
+                });
+                children.push(self.add(&format!("group:{}", path.display()), group));
+            } else if filter.matches(path.strip_prefix(base).unwrap_or(&path)) {
+                let id = self.add_file(&path, &name, "<group>");
+                if !self.info_plists.contains(&path) {
+                    items.push(Item {
+                        id: id.clone(),
+                        phase: phase_of(&path),
+                    });
+                }
+                children.push(id);
+            }
+        }
+
+        for (name, files) in variants {
+            let mut variant_children = vec![];
+            for (language, file) in files {
+                let file_ref = json!({
+                    "isa": "PBXFileReference",
+                    "lastKnownFileType": file_type(&file),
+                    "name": language,
+                    "path": format!("{}.lproj/{}", language, name),
+                    "sourceTree": "<group>",
+                });
+                variant_children.push(self.add(&format!("file:{}", file.display()), file_ref));
+                self.regions.insert(language);
+            }
+            let group = json!({
+                "isa": "PBXVariantGroup",
+                "children": variant_children,
+                "name": name,
+                "sourceTree": "<group>",
+            });
+            let id = self.add(&format!("variant:{}/{}", dir.display(), name), group);
+            items.push(Item {
+                id: id.clone(),
+                phase: Phase::Resources,
+            });
+            children.push(id);
+        }
+
+        Ok(children)
+    }
+
+    /// Add a `sources` entry to the main group. Returns its files.
+    fn add_source_root(&mut self, root: &SourceRoot) -> anyhow::Result<Vec<Item>> {
+        let path = root.path.trim_end_matches('/');
+        let absolute = Path::new(path).is_absolute();
+        let fs_path = self.project_dir.join(path);
+        let source_tree = if absolute { "<absolute>" } else { "<group>" };
+        let ext = fs_path.extension().and_then(|e| e.to_str()).unwrap_or("");
+
+        let mut items = vec![];
+        let id = if fs_path.is_dir() && !BUNDLE_EXTENSIONS.contains(&ext) {
+            let children = self.add_dir(&fs_path, &fs_path, &root.filter, &mut items)?;
+            let mut group = json!({
+                "isa": "PBXGroup",
+                "children": children,
+                "path": path,
+                "sourceTree": source_tree,
+            });
+            let name = fs_path.file_name().unwrap().to_string_lossy().to_string();
+            if name != path {
+                group["name"] = json!(name);
+            }
+            self.add(&format!("group:{}", fs_path.display()), group)
+        } else if fs_path.exists() {
+            let id = self.add_file(&fs_path, path, source_tree);
+            items.push(Item {
+                id: id.clone(),
+                phase: phase_of(&fs_path),
+            });
+            id
+        } else {
+            anyhow::bail!("Source path does not exist: {:?}", fs_path)
+        };
+        if !self.main_group.contains(&id) {
+            self.main_group.push(id);
+        }
+
+        Ok(items)
+    }
+
+    fn add_build_phase(&mut self, key: &str, isa: &str, files: Vec<String>) -> String {
+        self.add(
+            key,
+            json!({
+                "isa": isa,
+                "buildActionMask": BUILD_ACTION_MASK,
+                "files": files,
+                "runOnlyForDeploymentPostprocessing": "0",
+            }),
+        )
+    }
+
+    fn add_build_file(
+        &mut self,
+        key: &str,
+        file_ref: &str,
+        attributes: &[&str],
+        platform_filters: &[String],
+    ) -> String {
+        let mut build_file = json!({
+            "isa": "PBXBuildFile",
+            "fileRef": file_ref,
+        });
+        if !attributes.is_empty() {
+            build_file["settings"] = json!({ "ATTRIBUTES": attributes });
+        }
+        if !platform_filters.is_empty() {
+            build_file["platformFilters"] = json!(platform_filters);
+        }
+        self.add(key, build_file)
+    }
+
+    /// Configuration list with a configuration per project config.
+    fn add_config_list(
+        &mut self,
+        key: &str,
+        settings: impl Fn(&str, &str) -> Map<String, Value>,
+    ) -> String {
+        let configs = self
+            .project
+            .configs
+            .iter()
+            .collect::<BTreeMap<_, _>>()
+            .into_iter()
+            .map(|(name, config_type)| {
+                let config = json!({
+                    "isa": "XCBuildConfiguration",
+                    "buildSettings": settings(name, config_type),
+                    "name": name,
+                });
+                self.add(&format!("{}:config:{}", key, name), config)
+            })
+            .collect::<Vec<_>>();
+        self.add(
+            &format!("{}:configs", key),
+            json!({
+                "isa": "XCConfigurationList",
+                "buildConfigurations": configs,
+                "defaultConfigurationIsVisible": "0",
+                "defaultConfigurationName": self.config_of_type("release"),
+            }),
+        )
+    }
+
+    /// Name of the configuration used for `config_type`, `Debug` or `Release` by default.
+    fn config_of_type(&self, config_type: &str) -> String {
+        let mut names = self
+            .project
+            .configs
+            .iter()
+            .filter(|(_, t)| t.as_str() == config_type)
+            .map(|(name, _)| name.clone())
+            .collect::<Vec<_>>();
+        names.sort();
+        names
+            .iter()
+            .find(|name| name.eq_ignore_ascii_case(config_type))
+            .or_else(|| names.first())
+            .cloned()
+            .unwrap_or_else(|| config_type.to_string())
+    }
+
+    fn add_target(&mut self, name: &str, target: &Target) -> anyhow::Result<String> {
+        let platform = app_platform(&target.platform);
+        let (product_type, product_ext, product_file_type) = match target.product_type {
+            ProductType::Application => (
+                "com.apple.product-type.application",
+                "app",
+                "wrapper.application",
+            ),
+            ProductType::UnitTest => (
+                "com.apple.product-type.bundle.unit-test",
+                "xctest",
+                "wrapper.cfbundle",
+            ),
+            ProductType::None => anyhow::bail!("Target `{}` has no product type", name),
+        };
+        let key = format!("target:{}", name);
+
+        let mut sources = vec![];
+        let mut resources = vec![];
+        let mut copy_phases = vec![];
+        for (index, source) in target
+            .sources
+            .as_sequence()
+            .cloned()
+            .unwrap_or_default()
+            .iter()
+            .enumerate()
+        {
+            let root = SourceRoot::parse(&serde_json::to_value(source)?)?;
+            let items = self.add_source_root(&root)?;
+            match root.copy_files {
+                Some(subfolder_spec) => {
+                    let files = items
+                        .iter()
+                        .map(|item| {
+                            self.add_build_file(
+                                &format!("{}:copy:{}:{}", key, index, item.id),
+                                &item.id,
+                                &[],
+                                &root.platform_filters,
+                            )
+                        })
+                        .collect::<Vec<_>>();
+                    let phase = json!({
+                        "isa": "PBXCopyFilesBuildPhase",
+                        "buildActionMask": BUILD_ACTION_MASK,
+                        "dstPath": "",
+                        "dstSubfolderSpec": subfolder_spec,
+                        "files": files,
+                        "runOnlyForDeploymentPostprocessing": "0",
+                    });
+                    copy_phases.push(self.add(&format!("{}:copy:{}", key, index), phase));
+                }
+                None => {
+                    for item in items {
+                        let phase = match item.phase {
+                            Phase::Sources => &mut sources,
+                            Phase::Resources => &mut resources,
+                            Phase::None => continue,
+                        };
+                        let build_file = self.add_build_file(
+                            &format!("{}:build:{}", key, item.id),
+                            &item.id,
+                            &[],
+                            &root.platform_filters,
+                        );
+                        phase.push(build_file);
+                    }
+                }
+            }
+        }
+
+        let mut frameworks = vec![];
+        let mut embedded = vec![];
+        let mut framework_search_paths = vec![];
+        for dependency in &target.dependencies {
+            let (file_ref, embed) = match dependency.dependency_type {
+                DependencyType::Sdk(ref sdk) => {
+                    let path = if sdk.ends_with(".framework") {
+                        format!("System/Library/Frameworks/{}", sdk)
+                    } else {
+                        format!("usr/lib/{}", sdk)
+                    };
+                    let file = json!({
+                        "isa": "PBXFileReference",
+                        "lastKnownFileType": file_type(Path::new(sdk)),
+                        "name": sdk,
+                        "path": path,
+                        "sourceTree": "SDKROOT",
+                    });
+                    (self.add(&format!("sdk:{}", sdk), file), false)
+                }
+                DependencyType::Framework(ref path) => {
+                    let path = Path::new(path);
+                    let file_ref = self.add_file(path, &path.to_string_lossy(), "<absolute>");
+                    if path.extension() == Some(std::ffi::OsStr::new("framework")) {
+                        if let Some(parent) = path.parent() {
+                            framework_search_paths.push(parent.to_string_lossy().to_string());
+                        }
+                    }
+                    let embed = dependency
+                        .embed
+                        .unwrap_or(matches!(target.product_type, ProductType::Application));
+                    (file_ref, embed)
+                }
+                DependencyType::Target(ref dependency) => anyhow::bail!(
+                    "Target dependency `{}` is not supported without xcodegen",
+                    dependency
+                ),
+            };
+            if !self.frameworks_group.contains(&file_ref) {
+                self.frameworks_group.push(file_ref.clone());
+            }
+            let attributes: &[&str] = if dependency.weak == Some(true) {
+                &["Weak"]
+            } else {
+                &[]
+            };
+            frameworks.push(self.add_build_file(
+                &format!("{}:link:{}", key, file_ref),
+                &file_ref,
+                attributes,
+                &[],
+            ));
+            if embed {
+                let attributes: &[&str] = if dependency.code_sign.unwrap_or(true) {
+                    &["CodeSignOnCopy", "RemoveHeadersOnCopy"]
+                } else {
+                    &["RemoveHeadersOnCopy"]
+                };
+                embedded.push(self.add_build_file(
+                    &format!("{}:embed:{}", key, file_ref),
+                    &file_ref,
+                    attributes,
+                    &[],
+                ));
+            }
+        }
+
+        let mut phases = vec![
+            self.add_build_phase(&format!("{}:sources", key), "PBXSourcesBuildPhase", sources),
+            self.add_build_phase(
+                &format!("{}:resources", key),
+                "PBXResourcesBuildPhase",
+                resources,
+            ),
+        ];
+        phases.extend(copy_phases);
+        phases.push(self.add_build_phase(
+            &format!("{}:frameworks", key),
+            "PBXFrameworksBuildPhase",
+            frameworks,
+        ));
+        if !embedded.is_empty() {
+            let phase = json!({
+                "isa": "PBXCopyFilesBuildPhase",
+                "buildActionMask": BUILD_ACTION_MASK,
+                "dstPath": "",
+                "dstSubfolderSpec": "10",
+                "files": embedded,
+                "name": "Embed Frameworks",
+                "runOnlyForDeploymentPostprocessing": "0",
+            });
+            phases.push(self.add(&format!("{}:embed", key), phase));
+        }
+        for script in &target.post_build_scripts {
+            let mut phase = json!({
+                "isa": "PBXShellScriptBuildPhase",
+                "buildActionMask": BUILD_ACTION_MASK,
+                "files": [],
+                "inputPaths": [],
+                "name": script.name,
+                "outputPaths": [],
+                "runOnlyForDeploymentPostprocessing": "0",
+                "shellPath": "/bin/sh",
+                "shellScript": script.script,
+            });
+            if !script.based_on_dependency_analysis {
+                phase["alwaysOutOfDate"] = json!("1");
+            }
+            phases.push(self.add(&format!("{}:script:{}", key, script.name), phase));
+        }
+
+        let product = json!({
+            "isa": "PBXFileReference",
+            "explicitFileType": product_file_type,
+            "includeInIndex": "0",
+            "path": format!("{}.{}", name, product_ext),
+            "sourceTree": "BUILT_PRODUCTS_DIR",
+        });
+        let product = self.add(&format!("{}:product", key), product);
+        self.products_group.push(product.clone());
+
+        let info = serde_json::to_value(&target.info)?;
+        let info_path = info["path"].as_str().unwrap_or_default().to_string();
+        let target_settings = serde_json::to_value(&target.settings)?;
+        let bundle_id = format!(
+            "{}.{}",
+            self.project.options.bundle_id_prefix,
+            name.replace('_', "-")
+        );
+        let runpath = match (&target.product_type, platform) {
+            (ProductType::UnitTest, AppPlatform::MacOs) => "@loader_path/../Frameworks",
+            (ProductType::UnitTest, _) => "@loader_path/Frameworks",
+            (_, AppPlatform::MacOs) => "@executable_path/../Frameworks",
+            _ => "@executable_path/Frameworks",
+        };
+        let config_list = self.add_config_list(&key, |config_name, _| {
+            let mut settings = Map::new();
+            settings.insert("PRODUCT_BUNDLE_IDENTIFIER".into(), json!(bundle_id));
+            settings.insert("INFOPLIST_FILE".into(), json!(info_path));
+            settings.insert("SDKROOT".into(), json!(platform.sdk(false)));
+            settings.insert(
+                platform.deployment_target_env().into(),
+                json!(target.deployment_target),
+            );
+            settings.insert(
+                "LD_RUNPATH_SEARCH_PATHS".into(),
+                json!(["$(inherited)", runpath]),
+            );
+            if let Some(device_family) = platform.device_family() {
+                settings.insert("TARGETED_DEVICE_FAMILY".into(), json!(device_family));
+            }
+            if !framework_search_paths.is_empty() {
+                let mut paths = vec![json!("$(inherited)")];
+                paths.extend(framework_search_paths.iter().map(|p| json!(p)));
+                settings.insert("FRAMEWORK_SEARCH_PATHS".into(), Value::Array(paths));
+            }
+            merge_settings(&mut settings, target_settings.get("base"));
+            let config = target_settings["configs"]
+                .as_object()
+                .and_then(|configs| {
+                    configs
+                        .iter()
+                        .find(|(name, _)| name.eq_ignore_ascii_case(config_name))
+                })
+                .map(|(_, config)| config);
+            merge_settings(&mut settings, config);
+            settings
+        });
+
+        let native_target = json!({
+            "isa": "PBXNativeTarget",
+            "buildConfigurationList": config_list,
+            "buildPhases": phases,
+            "buildRules": [],
+            "dependencies": [],
+            "name": name,
+            "productName": name,
+            "productReference": product,
+            "productType": product_type,
+        });
+        Ok(self.add(&key, native_target))
+    }
+
+    fn write_info_plist(&self, target: &Target) -> anyhow::Result<()> {
+        let info = serde_json::to_value(&target.info)?;
+        let path = self
+            .project_dir
+            .join(info["path"].as_str().unwrap_or_default());
+        let mut plist = default_info_plist(&target.product_type, app_platform(&target.platform));
+        merge_settings(&mut plist, info.get("properties"));
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create all dir: {:?}", parent))?;
+        }
+        std::fs::write(&path, crate::plist::to_xml(&Value::Object(plist)))
+            .with_context(|| format!("Failed to write {:?}", path))
+    }
+
+    fn scheme(&self, name: &str, target: &Target) -> String {
+        let container = format!("container:{}.xcodeproj", self.project.name);
+        let reference = |target_name: &str, target: &Target, indent: &str| {
+            let ext = match target.product_type {
+                ProductType::UnitTest => "xctest",
+                _ => "app",
+            };
+            format!(
+                "{indent}<BuildableReference\n\
+                 {indent}   BuildableIdentifier = \"primary\"\n\
+                 {indent}   BlueprintIdentifier = \"{id}\"\n\
+                 {indent}   BuildableName = \"{name}.{ext}\"\n\
+                 {indent}   BlueprintName = \"{name}\"\n\
+                 {indent}   ReferencedContainer = \"{container}\">\n\
+                 {indent}</BuildableReference>\n",
+                indent = indent,
+                id = object_id(&format!("target:{}", target_name)),
+                name = xml_attribute(target_name),
+                ext = ext,
+                container = xml_attribute(&container),
+            )
+        };
+        let test_targets = target
+            .scheme
+            .test_targets
+            .iter()
+            .filter_map(|name| self.project.targets.get(name).map(|t| (name, t)))
+            .collect::<Vec<_>>();
+        let debug = self.config_of_type("debug");
+        let release = self.config_of_type("release");
+        let runnable = matches!(target.product_type, ProductType::Application);
+
+        let mut xml = String::from(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<Scheme\n   LastUpgradeVersion = \"",
+        );
+        xml.push_str(LAST_UPGRADE_CHECK);
+        xml.push_str("\"\n   version = \"1.7\">\n");
+        xml.push_str(
+            "   <BuildAction\n      parallelizeBuildables = \"YES\"\n      buildImplicitDependencies = \"YES\">\n      <BuildActionEntries>\n",
+        );
+        xml.push_str(
+            "         <BuildActionEntry\n            buildForTesting = \"YES\"\n            buildForRunning = \"YES\"\n            buildForProfiling = \"YES\"\n            buildForArchiving = \"YES\"\n            buildForAnalyzing = \"YES\">\n",
+        );
+        xml.push_str(&reference(name, target, "            "));
+        xml.push_str("         </BuildActionEntry>\n");
+        for (test_name, test_target) in &test_targets {
+            xml.push_str(
+                "         <BuildActionEntry\n            buildForTesting = \"YES\"\n            buildForRunning = \"NO\"\n            buildForProfiling = \"NO\"\n            buildForArchiving = \"NO\"\n            buildForAnalyzing = \"NO\">\n",
+            );
+            xml.push_str(&reference(test_name, test_target, "            "));
+            xml.push_str("         </BuildActionEntry>\n");
+        }
+        xml.push_str("      </BuildActionEntries>\n   </BuildAction>\n");
+
+        xml.push_str(&format!(
+            "   <TestAction\n      buildConfiguration = \"{}\"\n      selectedDebuggerIdentifier = \"Xcode.DebuggerFoundation.Debugger.LLDB\"\n      selectedLauncherIdentifier = \"Xcode.DebuggerFoundation.Launcher.LLDB\"\n      shouldUseLaunchSchemeArgsEnv = \"YES\">\n      <Testables>\n",
+            xml_attribute(&debug)
+        ));
+        for (test_name, test_target) in &test_targets {
+            xml.push_str("         <TestableReference\n            skipped = \"NO\">\n");
+            xml.push_str(&reference(test_name, test_target, "            "));
+            xml.push_str("         </TestableReference>\n");
+        }
+        xml.push_str("      </Testables>\n   </TestAction>\n");
+
+        xml.push_str(&format!(
+            "   <LaunchAction\n      buildConfiguration = \"{}\"\n      selectedDebuggerIdentifier = \"Xcode.DebuggerFoundation.Debugger.LLDB\"\n      selectedLauncherIdentifier = \"Xcode.DebuggerFoundation.Launcher.LLDB\"\n      launchStyle = \"0\"\n      useCustomWorkingDirectory = \"NO\"\n      ignoresPersistentStateOnLaunch = \"NO\"\n      debugDocumentVersioning = \"YES\"\n      debugServiceExtension = \"internal\"\n      allowLocationSimulation = \"YES\">\n",
+            xml_attribute(&debug)
+        ));
+        if runnable {
+            xml.push_str(
+                "      <BuildableProductRunnable\n         runnableDebuggingMode = \"0\">\n",
+            );
+            xml.push_str(&reference(name, target, "         "));
+            xml.push_str("      </BuildableProductRunnable>\n");
+        }
+        if !target.scheme.environment_variables.is_empty() {
+            xml.push_str("      <EnvironmentVariables>\n");
+            for variable in &target.scheme.environment_variables {
+                xml.push_str(&format!(
+                    "         <EnvironmentVariable\n            key = \"{}\"\n            value = \"{}\"\n            isEnabled = \"{}\">\n         </EnvironmentVariable>\n",
+                    xml_attribute(&variable.variable),
+                    xml_attribute(&variable.value),
+                    if variable.is_enabled { "YES" } else { "NO" },
+                ));
+            }
+            xml.push_str("      </EnvironmentVariables>\n");
+        }
+        xml.push_str("   </LaunchAction>\n");
+
+        xml.push_str(&format!(
+            "   <ProfileAction\n      buildConfiguration = \"{}\"\n      shouldUseLaunchSchemeArgsEnv = \"YES\"\n      savedToolIdentifier = \"\"\n      useCustomWorkingDirectory = \"NO\"\n      debugDocumentVersioning = \"YES\">\n",
+            xml_attribute(&release)
+        ));
+        if runnable {
+            xml.push_str(
+                "      <BuildableProductRunnable\n         runnableDebuggingMode = \"0\">\n",
+            );
+            xml.push_str(&reference(name, target, "         "));
+            xml.push_str("      </BuildableProductRunnable>\n");
+        }
+        xml.push_str("   </ProfileAction>\n");
+        xml.push_str(&format!(
+            "   <AnalyzeAction\n      buildConfiguration = \"{}\">\n   </AnalyzeAction>\n",
+            xml_attribute(&debug)
+        ));
+        xml.push_str(&format!(
+            "   <ArchiveAction\n      buildConfiguration = \"{}\"\n      revealArchiveInOrganizer = \"YES\">\n   </ArchiveAction>\n",
+            xml_attribute(&release)
+        ));
+        xml.push_str("</Scheme>\n");
+        xml
+    }
+}
+
+/// Generate `<name>.xcodeproj` with shared schemes and the Info.plist of every target
+/// of `project` in `project_dir`, the same way `xcodegen` does for `project.yml`.
+pub fn generate(project: &Project, project_dir: &Path) -> anyhow::Result<()> {
+    log::debug!("Generating xcode project without xcodegen");
+    let info_plists = project
+        .targets
+        .values()
+        .map(|target| {
+            let info = serde_json::to_value(&target.info)?;
+            Ok(project_dir.join(info["path"].as_str().unwrap_or_default()))
+        })
+        .collect::<anyhow::Result<BTreeSet<_>>>()?;
+    let development_region = project
+        .options
+        .development_language
+        .clone()
+        .unwrap_or_else(|| "en".into());
+    let mut generator = Generator {
+        project,
+        project_dir,
+        objects: Map::new(),
+        main_group: vec![],
+        frameworks_group: vec![],
+        products_group: vec![],
+        regions: BTreeSet::from(["Base".to_string(), development_region.clone()]),
+        info_plists,
+    };
+
+    let targets = project.targets.iter().collect::<BTreeMap<_, _>>();
+    let mut target_ids = vec![];
+    for (name, target) in &targets {
+        target_ids.push(generator.add_target(name, target)?);
+        generator.write_info_plist(target)?;
+    }
+
+    let project_settings = serde_json::to_value(&project.settings)?;
+    let config_list = generator.add_config_list("project", |_, config_type| {
+        let mut settings = default_project_settings(config_type);
+        merge_settings(&mut settings, Some(&project_settings));
+        settings
+    });
+
+    let mut main_group = generator.main_group.clone();
+    if !generator.frameworks_group.is_empty() {
+        let group = json!({
+            "isa": "PBXGroup",
+            "children": generator.frameworks_group,
+            "name": "Frameworks",
+            "sourceTree": "<group>",
+        });
+        main_group.push(generator.add("group:Frameworks", group));
+    }
+    let products = json!({
+        "isa": "PBXGroup",
+        "children": generator.products_group,
+        "name": "Products",
+        "sourceTree": "<group>",
+    });
+    let products = generator.add("group:Products", products);
+    main_group.push(products.clone());
+    let main_group = generator.add(
+        "group:main",
+        json!({
+            "isa": "PBXGroup",
+            "children": main_group,
+            "sourceTree": "<group>",
+        }),
+    );
+    let root = generator.add(
+        "project",
+        json!({
+            "isa": "PBXProject",
+            "attributes": {
+                "BuildIndependentTargetsInParallel": "YES",
+                "LastUpgradeCheck": LAST_UPGRADE_CHECK,
+            },
+            "buildConfigurationList": config_list,
+            "compatibilityVersion": "Xcode 14.0",
+            "developmentRegion": development_region,
+            "hasScannedForEncodings": "0",
+            "knownRegions": generator.regions,
+            "mainGroup": main_group,
+            "productRefGroup": products,
+            "projectDirPath": "",
+            "projectRoot": "",
+            "targets": target_ids,
+        }),
+    );
+
+    let xcodeproj = project_dir.join(format!("{}.xcodeproj", project.name));
+    std::fs::create_dir_all(&xcodeproj)
+        .with_context(|| format!("Failed to create all dir: {:?}", xcodeproj))?;
+    let pbxproj = json!({
+        "archiveVersion": "1",
+        "classes": {},
+        "objectVersion": OBJECT_VERSION,
+        "objects": generator.objects,
+        "rootObject": root,
+    });
+    let pbxproj_path = xcodeproj.join("project.pbxproj");
+    std::fs::write(&pbxproj_path, to_pbxproj(&pbxproj))
+        .with_context(|| format!("Failed to write {:?}", pbxproj_path))?;
+
+    // Schemes of renamed targets are removed.
+    let schemes_dir = xcodeproj.join("xcshareddata").join("xcschemes");
+    if schemes_dir.exists() {
+        std::fs::remove_dir_all(&schemes_dir)
+            .with_context(|| format!("Failed to remove dir: {:?}", schemes_dir))?;
+    }
+    std::fs::create_dir_all(&schemes_dir)
+        .with_context(|| format!("Failed to create all dir: {:?}", schemes_dir))?;
+    for (name, target) in &targets {
+        let path = schemes_dir.join(format!("{}.xcscheme", name));
+        std::fs::write(&path, generator.scheme(name, target))
+            .with_context(|| format!("Failed to write {:?}", path))?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quoting() {
+        assert_eq!(quote("../src"), "../src");
+        assert_eq!(quote("$(inherited)"), "\"$(inherited)\"");
+        assert_eq!(quote("<group>"), "\"<group>\"");
+        assert_eq!(quote(""), "\"\"");
+        assert_eq!(quote("echo \"a\"\n"), "\"echo \\\"a\\\"\\n\"");
+    }
+
+    #[test]
+    fn object_ids() {
+        let id = object_id("target:my_game");
+        assert_eq!(id.len(), 24);
+        assert!(id.chars().all(|c| c.is_ascii_hexdigit()));
+        assert_eq!(id, object_id("target:my_game"));
+        assert_ne!(id, object_id("target:my_game2"));
+    }
+
+    #[test]
+    fn pbxproj_format() {
+        let value = json!({
+            "isa": "PBXGroup",
+            "children": ["A", "B"],
+            "sourceTree": "<group>",
+        });
+        assert_eq!(
+            to_pbxproj(&value),
+            "// !$*UTF8*$!\n{\n\tchildren = (\n\t\tA,\n\t\tB,\n\t);\n\tisa = PBXGroup;\n\tsourceTree = \"<group>\";\n}\n"
+        );
+    }
+
+    #[test]
+    fn source_filter() {
+        let value = json!({
+            "path": "../assets",
+            "excludes": ["**/*.psd", "raw/**"],
+        });
+        let root = SourceRoot::parse(&value).unwrap();
+        assert!(root.filter.matches(Path::new("icon.png")));
+        assert!(root.filter.matches(Path::new("levels/1.json")));
+        assert!(!root.filter.matches(Path::new("icon.psd")));
+        assert!(!root.filter.matches(Path::new("art/hero.psd")));
+        assert!(!root.filter.matches(Path::new("raw/hero.png")));
+
+        let value = json!({ "path": "../levels", "includes": ["*.json"] });
+        let root = SourceRoot::parse(&value).unwrap();
+        assert!(root.filter.matches(Path::new("1.json")));
+        assert!(!root.filter.matches(Path::new("nested/1.json")));
+        assert!(!root.filter.matches(Path::new("notes.txt")));
+    }
+
+    #[test]
+    fn project() {
+        let temp = crate::test_util::TempDir::new("pbxproj");
+        let dir = temp.path();
+        let src = dir.join("src");
+        std::fs::create_dir_all(src.join("en.lproj")).unwrap();
+        std::fs::write(src.join("main.m"), "").unwrap();
+        std::fs::write(src.join("bindings.h"), "").unwrap();
+        std::fs::write(src.join("en.lproj/InfoPlist.strings"), "").unwrap();
+        std::fs::write(src.join("Info.plist"), "").unwrap();
+        let project_dir = dir.join("my_game");
+        std::fs::create_dir_all(&project_dir).unwrap();
+
+        let target = crate::xcodegen::TargetBuilder::new(ProductType::Application, Platform::Ios)
+            .deployment_target("13.0")
+            .source("../src/")
+            .setting(
+                "base",
+                json!({ "OTHER_LDFLAGS": ["$(inherited)", "-lmy_game"] }),
+            )
+            .unwrap()
+            .dependency(crate::xcodegen::Dependency::sdk("GLKit.framework".into()))
+            .build();
+        let project = crate::xcodegen::ProjectBuilder::new("my_game")
+            .config("Debug", "debug")
+            .config("Release", "release")
+            .bundle_id_prefix("com.example")
+            .target("my_game", target)
+            .build();
+        generate(&project, &project_dir).unwrap();
+
+        let pbxproj =
+            std::fs::read_to_string(project_dir.join("my_game.xcodeproj/project.pbxproj")).unwrap();
+        assert!(pbxproj.contains("PRODUCT_BUNDLE_IDENTIFIER = \"com.example.my-game\";"));
+        assert!(pbxproj.contains("IPHONEOS_DEPLOYMENT_TARGET = 13.0;"));
+        assert!(pbxproj.contains("\"-lmy_game\","));
+        assert!(pbxproj.contains("isa = PBXVariantGroup;"));
+        assert!(pbxproj.contains("path = System/Library/Frameworks/GLKit.framework;"));
+        // main.m, InfoPlist.strings and GLKit, the Info.plist is not a resource.
+        assert_eq!(pbxproj.matches("isa = PBXBuildFile;").count(), 3);
+        // Only set by the target settings when an icon is configured.
+        assert!(!pbxproj.contains("ASSETCATALOG_COMPILER_APPICON_NAME"));
+        let scheme = std::fs::read_to_string(
+            project_dir.join("my_game.xcodeproj/xcshareddata/xcschemes/my_game.xcscheme"),
+        )
+        .unwrap();
+        assert!(scheme.contains("key = \"RUST_BACKTRACE\""));
+        let info_plist = std::fs::read_to_string(src.join("Info.plist")).unwrap();
+        assert!(info_plist.contains("<key>UILaunchStoryboardName</key>"));
+    }
+}
@@ -0,0 +1,147 @@
+use anyhow::Context as _;
+use std::path::Path;
+
+/// Read a property list of any format as JSON with `plutil`.
+pub fn read(path: &Path) -> anyhow::Result<serde_json::Value> {
+    let output = std::process::Command::new("plutil")
+        .arg("-convert")
+        .arg("json")
+        .arg("-o")
+        .arg("-")
+        .arg(path)
+        .output()
+        .with_context(|| "Failed to get plutil output".to_string())?;
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+
+        anyhow::bail!("Failed to read {:?}:\n{:?}\n{:?}", path, stdout, stderr)
+    }
+
+    serde_json::from_str(&stdout).with_context(|| format!("Failed to parse {:?}", path))
+}
+
+/// Extract a single value with `plutil -extract`, e.g. in `raw` or `json` format.
+/// Works with property lists that can't be converted to JSON as a whole, e.g. with dates or data.
+/// Returns `None` if there is no value at `key_path`.
+pub fn extract(path: &Path, key_path: &str, format: &str) -> anyhow::Result<Option<String>> {
+    let output = std::process::Command::new("plutil")
+        .arg("-extract")
+        .arg(key_path)
+        .arg(format)
+        .arg("-o")
+        .arg("-")
+        .arg(path)
+        .output()
+        .with_context(|| "Failed to get plutil output".to_string())?;
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        if stdout.contains("No value at that key path")
+            || stderr.contains("No value at that key path")
+        {
+            return Ok(None);
+        }
+
+        anyhow::bail!(
+            "Failed to extract `{}` from {:?}:\n{:?}\n{:?}",
+            key_path,
+            path,
+            stdout,
+            stderr
+        )
+    }
+
+    Ok(Some(stdout.trim().to_string()))
+}
+
+/// Serialize a JSON value as an XML property list.
+pub fn to_xml(value: &serde_json::Value) -> String {
+    let mut xml = String::from(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+"#,
+    );
+    write_value(&mut xml, value, 0);
+    xml.push_str("</plist>\n");
+    xml
+}
+
+fn write_value(xml: &mut String, value: &serde_json::Value, indent: usize) {
+    let pad = "\t".repeat(indent);
+    match *value {
+        serde_json::Value::Null => xml.push_str(&format!("{}<string></string>\n", pad)),
+        serde_json::Value::Bool(b) => xml.push_str(&format!("{}<{}/>\n", pad, b)),
+        serde_json::Value::Number(ref n) => {
+            if n.is_f64() {
+                xml.push_str(&format!("{}<real>{}</real>\n", pad, n))
+            } else {
+                xml.push_str(&format!("{}<integer>{}</integer>\n", pad, n))
+            }
+        }
+        serde_json::Value::String(ref s) => {
+            xml.push_str(&format!("{}<string>{}</string>\n", pad, escape(s)))
+        }
+        serde_json::Value::Array(ref array) => {
+            xml.push_str(&format!("{}<array>\n", pad));
+            for v in array {
+                write_value(xml, v, indent + 1);
+            }
+            xml.push_str(&format!("{}</array>\n", pad));
+        }
+        serde_json::Value::Object(ref map) => {
+            xml.push_str(&format!("{}<dict>\n", pad));
+            for (k, v) in map {
+                xml.push_str(&format!("{}\t<key>{}</key>\n", pad, escape(k)));
+                write_value(xml, v, indent + 1);
+            }
+            xml.push_str(&format!("{}</dict>\n", pad));
+        }
+    }
+}
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn to_xml_dict() {
+        let value = serde_json::json!({
+            "method": "ad-hoc",
+            "stripSwiftSymbols": true,
+            "thinning": "<thin-for-all-variants>",
+            "devices": [1, 2.5],
+        });
+
+        assert_eq!(
+            to_xml(&value),
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+	<key>devices</key>
+	<array>
+		<integer>1</integer>
+		<real>2.5</real>
+	</array>
+	<key>method</key>
+	<string>ad-hoc</string>
+	<key>stripSwiftSymbols</key>
+	<true/>
+	<key>thinning</key>
+	<string>&lt;thin-for-all-variants&gt;</string>
+</dict>
+</plist>
+"#
+        );
+    }
+}
@@ -0,0 +1,570 @@
+use anyhow::Context as _;
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+
+/// An icon required by the App Store: idiom, size and scale.
+pub type RequiredIcon = (&'static str, &'static str, &'static str);
+
+const IPHONE_ICONS: &[RequiredIcon] = &[("iphone", "60x60", "2x"), ("iphone", "60x60", "3x")];
+const IPAD_ICONS: &[RequiredIcon] = &[("ipad", "76x76", "2x"), ("ipad", "83.5x83.5", "2x")];
+const MAC_ICONS: &[RequiredIcon] = &[
+    ("mac", "16x16", "1x"),
+    ("mac", "16x16", "2x"),
+    ("mac", "32x32", "1x"),
+    ("mac", "32x32", "2x"),
+    ("mac", "128x128", "1x"),
+    ("mac", "128x128", "2x"),
+    ("mac", "256x256", "1x"),
+    ("mac", "256x256", "2x"),
+    ("mac", "512x512", "1x"),
+    ("mac", "512x512", "2x"),
+];
+
+/// Icons required for `platform` and a `TARGETED_DEVICE_FAMILY` like `1,2`: iPhone (1),
+/// iPad (2) and Mac Catalyst (6) icons. tvOS apps use layered brand assets, not icon sets.
+pub fn required_icons(
+    platform: crate::toml::AppPlatform,
+    device_family: &str,
+) -> Vec<RequiredIcon> {
+    use crate::toml::AppPlatform;
+
+    let families = device_family.split(',').map(str::trim).collect::<Vec<_>>();
+    let mut icons = vec![];
+    match platform {
+        AppPlatform::Ios => {
+            if families.contains(&"1") {
+                icons.extend_from_slice(IPHONE_ICONS);
+            }
+            if families.contains(&"2") {
+                icons.extend_from_slice(IPAD_ICONS);
+            }
+            if families.contains(&"6") {
+                icons.extend_from_slice(MAC_ICONS);
+            }
+            icons.push(("ios-marketing", "1024x1024", "1x"));
+        }
+        AppPlatform::WatchOs => icons.push(("watch-marketing", "1024x1024", "1x")),
+        AppPlatform::MacOs => icons.extend_from_slice(MAC_ICONS),
+        AppPlatform::TvOs => {}
+    }
+    icons
+}
+
+/// Info.plist keys that must be present and non-empty.
+const REQUIRED_PLIST_KEYS: &[&str] = &[
+    "CFBundleIdentifier",
+    "CFBundleExecutable",
+    "CFBundleName",
+    "CFBundleShortVersionString",
+    "CFBundleVersion",
+    "CFBundlePackageType",
+    "UILaunchStoryboardName",
+];
+
+/// Usage description keys required when an app links a framework. One of them must be present.
+const FRAMEWORK_USAGE_DESCRIPTIONS: &[(&str, &[&str])] = &[
+    (
+        "AVFoundation",
+        &["NSCameraUsageDescription", "NSMicrophoneUsageDescription"],
+    ),
+    (
+        "CoreLocation",
+        &[
+            "NSLocationWhenInUseUsageDescription",
+            "NSLocationAlwaysAndWhenInUseUsageDescription",
+        ],
+    ),
+    (
+        "Photos",
+        &[
+            "NSPhotoLibraryUsageDescription",
+            "NSPhotoLibraryAddUsageDescription",
+        ],
+    ),
+    ("Contacts", &["NSContactsUsageDescription"]),
+    (
+        "EventKit",
+        &["NSCalendarsUsageDescription", "NSRemindersUsageDescription"],
+    ),
+    ("CoreBluetooth", &["NSBluetoothAlwaysUsageDescription"]),
+    ("CoreMotion", &["NSMotionUsageDescription"]),
+    ("Speech", &["NSSpeechRecognitionUsageDescription"]),
+    ("HealthKit", &["NSHealthShareUsageDescription"]),
+    ("LocalAuthentication", &["NSFaceIDUsageDescription"]),
+];
+
+/// A problem that would get the app rejected by App Store Connect.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Violation {
+    /// What was checked, e.g. `App icon`.
+    pub check: &'static str,
+    pub message: String,
+}
+
+impl Violation {
+    fn new(check: &'static str, message: impl Into<String>) -> Self {
+        Self {
+            check,
+            message: message.into(),
+        }
+    }
+}
+
+impl std::fmt::Display for Violation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.check, self.message)
+    }
+}
+
+/// `Contents.json` of an `.appiconset`.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct IconSet {
+    #[serde(default)]
+    pub images: Vec<IconImage>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct IconImage {
+    pub idiom: String,
+    pub size: Option<String>,
+    pub scale: Option<String>,
+    pub platform: Option<String>,
+    pub filename: Option<String>,
+}
+
+impl IconImage {
+    /// Expected width and height in pixels, e.g. `167` for `83.5x83.5` at `2x`.
+    pub fn pixel_size(&self) -> Option<u32> {
+        let (width, _) = self.size.as_ref()?.split_once('x')?;
+        let scale = match self.scale {
+            Some(ref scale) => scale.trim_end_matches('x').parse::<f32>().ok()?,
+            None => 1.0,
+        };
+        Some((width.parse::<f32>().ok()? * scale).round() as u32)
+    }
+
+    fn is_single_size(&self) -> bool {
+        self.idiom == "universal" && matches!(self.platform.as_deref(), Some("ios" | "watchos"))
+    }
+
+    fn is_marketing(&self) -> bool {
+        matches!(self.idiom.as_str(), "ios-marketing" | "watch-marketing") || self.is_single_size()
+    }
+}
+
+impl IconSet {
+    /// `required` icons without an image, e.g. `iphone 60x60@3x`.
+    /// Nothing is missing if the set uses a single 1024pt image.
+    pub fn missing_icons(&self, required: &[RequiredIcon]) -> Vec<String> {
+        let with_file = self.images.iter().filter(|i| i.filename.is_some());
+        if with_file.clone().any(IconImage::is_single_size) {
+            return vec![];
+        }
+
+        required
+            .iter()
+            .filter(|&&(idiom, size, scale)| {
+                !with_file.clone().any(|i| {
+                    i.idiom == idiom
+                        && i.size.as_deref() == Some(size)
+                        && i.scale.as_deref().unwrap_or("1x") == scale
+                })
+            })
+            .map(|(idiom, size, scale)| format!("{} {}@{}", idiom, size, scale))
+            .collect()
+    }
+}
+
+/// Pixel size and alpha channel of an image.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ImageInfo {
+    pub width: u32,
+    pub height: u32,
+    pub has_alpha: bool,
+}
+
+/// Parse the output of `sips -g pixelWidth -g pixelHeight -g hasAlpha <image>`.
+pub fn parse_sips(output: &str) -> ImageInfo {
+    let mut info = ImageInfo::default();
+    for line in output.lines() {
+        match line.trim().split_once(':') {
+            Some(("pixelWidth", value)) => info.width = value.trim().parse().unwrap_or(0),
+            Some(("pixelHeight", value)) => info.height = value.trim().parse().unwrap_or(0),
+            Some(("hasAlpha", value)) => info.has_alpha = value.trim() == "yes",
+            _ => {}
+        }
+    }
+
+    info
+}
+
+pub(crate) fn image_info(path: &Path) -> anyhow::Result<ImageInfo> {
+    let output = std::process::Command::new("sips")
+        .arg("-g")
+        .arg("pixelWidth")
+        .arg("-g")
+        .arg("pixelHeight")
+        .arg("-g")
+        .arg("hasAlpha")
+        .arg(path)
+        .output()
+        .with_context(|| "Failed to get sips output".to_string())?;
+
+    if !output.status.success() {
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+
+        anyhow::bail!(
+            "Failed to read image {:?}:\n{:?}\n{:?}",
+            path,
+            stdout,
+            stderr
+        )
+    }
+
+    Ok(parse_sips(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// Find directories with the given extension, e.g. `appiconset`, inside `dirs`.
+pub fn find_dirs_with_extension(dirs: &[PathBuf], extension: &str) -> Vec<PathBuf> {
+    let mut found = vec![];
+    let mut stack = dirs.to_vec();
+    while let Some(dir) = stack.pop() {
+        if dir.extension() == Some(std::ffi::OsStr::new(extension)) {
+            found.push(dir);
+            continue;
+        }
+        if let Ok(entries) = std::fs::read_dir(&dir) {
+            stack.extend(
+                entries
+                    .filter_map(|e| e.ok())
+                    .map(|e| e.path())
+                    .filter(|p| p.is_dir()),
+            );
+        }
+    }
+    found.sort();
+    found
+}
+
+/// Check the app icon sets found in the asset folders for the `required` icons.
+pub fn check_icons(
+    asset_dirs: &[PathBuf],
+    required: &[RequiredIcon],
+) -> anyhow::Result<Vec<Violation>> {
+    const CHECK: &str = "App icon";
+    if required.is_empty() {
+        return Ok(vec![]);
+    }
+    let icon_sets = find_dirs_with_extension(asset_dirs, "appiconset");
+    if icon_sets.is_empty() {
+        return Ok(vec![Violation::new(
+            CHECK,
+            "no `.appiconset` found in `assets`",
+        )]);
+    }
+
+    let mut violations = vec![];
+    for icon_set_dir in icon_sets {
+        let contents_path = icon_set_dir.join("Contents.json");
+        let contents = std::fs::read_to_string(&contents_path)
+            .with_context(|| format!("Failed to read {:?}", contents_path))?;
+        let icon_set: IconSet = serde_json::from_str(&contents)
+            .with_context(|| format!("Failed to parse {:?}", contents_path))?;
+
+        for missing in icon_set.missing_icons(required) {
+            violations.push(Violation::new(
+                CHECK,
+                format!("{:?} has no {} icon", icon_set_dir, missing),
+            ));
+        }
+
+        for image in &icon_set.images {
+            let filename = match image.filename {
+                Some(ref filename) => filename,
+                None => continue,
+            };
+            let path = icon_set_dir.join(filename);
+            if !path.exists() {
+                violations.push(Violation::new(CHECK, format!("{:?} does not exist", path)));
+                continue;
+            }
+
+            let info = image_info(&path)?;
+            if let Some(size) = image.pixel_size() {
+                if info.width != size || info.height != size {
+                    violations.push(Violation::new(
+                        CHECK,
+                        format!(
+                            "{:?} is {}x{}, expected {}x{}",
+                            path, info.width, info.height, size, size
+                        ),
+                    ));
+                }
+            }
+            if image.is_marketing() && info.has_alpha {
+                violations.push(Violation::new(
+                    CHECK,
+                    format!(
+                        "{:?} has an alpha channel, the App Store icon must be opaque",
+                        path
+                    ),
+                ));
+            }
+        }
+    }
+
+    Ok(violations)
+}
+
+/// Check that the launch storyboard named in Info.plist is in the asset folders.
+pub fn check_launch_screen(
+    asset_dirs: &[PathBuf],
+    info_plist: &serde_json::Value,
+) -> Vec<Violation> {
+    const CHECK: &str = "Launch screen";
+    let storyboard = match info_plist
+        .get("UILaunchStoryboardName")
+        .and_then(|v| v.as_str())
+    {
+        Some(storyboard) => storyboard,
+        None => return vec![],
+    };
+    let found = asset_dirs.iter().any(|dir| {
+        dir.join(format!("{}.storyboard", storyboard)).exists()
+            || dir.join(format!("{}.storyboardc", storyboard)).exists()
+    });
+
+    if found {
+        vec![]
+    } else {
+        vec![Violation::new(
+            CHECK,
+            format!("`{}.storyboard` not found in `assets`", storyboard),
+        )]
+    }
+}
+
+/// Check that the required Info.plist keys are present and non-empty.
+pub fn check_info_plist(info_plist: &serde_json::Value) -> Vec<Violation> {
+    REQUIRED_PLIST_KEYS
+        .iter()
+        .filter(|&&key| match info_plist.get(key) {
+            Some(serde_json::Value::String(value)) => value.trim().is_empty(),
+            Some(_) => false,
+            None => true,
+        })
+        .map(|key| Violation::new("Info.plist", format!("missing `{}`", key)))
+        .collect()
+}
+
+/// Check that `MinimumOSVersion` of a built app matches the deployment target.
+pub fn check_minimum_os(info_plist: &serde_json::Value, deployment_target: &str) -> Vec<Violation> {
+    const CHECK: &str = "Minimum OS";
+    match info_plist.get("MinimumOSVersion").and_then(|v| v.as_str()) {
+        None => vec![Violation::new(CHECK, "missing `MinimumOSVersion`")],
+        Some(version)
+            if crate::devices::runtime_version_matches(version, deployment_target)
+                || crate::devices::runtime_version_matches(deployment_target, version) =>
+        {
+            vec![]
+        }
+        Some(version) => vec![Violation::new(
+            CHECK,
+            format!(
+                "`MinimumOSVersion` is {}, but `deployment_target` is {}",
+                version, deployment_target
+            ),
+        )],
+    }
+}
+
+/// Parse framework names from `otool -L <binary>` output.
+pub fn parse_linked_frameworks(output: &str) -> Vec<String> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let path = line.trim().split(" (").next()?;
+            let framework = path.split('/').find_map(|c| c.strip_suffix(".framework"))?;
+            Some(framework.to_string())
+        })
+        .collect()
+}
+
+fn linked_frameworks(binary: &Path) -> anyhow::Result<Vec<String>> {
+    let output = std::process::Command::new("otool")
+        .arg("-L")
+        .arg(binary)
+        .output()
+        .with_context(|| "Failed to get otool output".to_string())?;
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+
+        anyhow::bail!(
+            "Failed to list linked frameworks of {:?}:\n{:?}\n{:?}",
+            binary,
+            stdout,
+            stderr
+        )
+    }
+
+    Ok(parse_linked_frameworks(&stdout))
+}
+
+/// Check that every linked framework that requires a permission has a usage description.
+pub fn check_usage_descriptions(
+    frameworks: &[String],
+    info_plist: &serde_json::Value,
+) -> Vec<Violation> {
+    FRAMEWORK_USAGE_DESCRIPTIONS
+        .iter()
+        .filter(|(framework, _)| frameworks.iter().any(|f| f == *framework))
+        .filter(|(_, keys)| !keys.iter().any(|key| info_plist.get(key).is_some()))
+        .map(|(framework, keys)| {
+            Violation::new(
+                "Usage descriptions",
+                format!("links {} without `{}`", framework, keys.join("` or `")),
+            )
+        })
+        .collect()
+}
+
+/// Check that the export compliance is declared, otherwise every upload asks about it.
+pub fn check_encryption_declaration(info_plist: &serde_json::Value) -> Vec<Violation> {
+    if info_plist.get("ITSAppUsesNonExemptEncryption").is_some() {
+        vec![]
+    } else {
+        vec![Violation::new(
+            "Encryption",
+            "missing `ITSAppUsesNonExemptEncryption`",
+        )]
+    }
+}
+
+/// Check that the app bundle contains a privacy manifest.
+pub fn check_privacy_manifest(app: &Path) -> Vec<Violation> {
+    if app.join("PrivacyInfo.xcprivacy").exists() {
+        vec![]
+    } else {
+        vec![Violation::new(
+            "Privacy manifest",
+            "missing `PrivacyInfo.xcprivacy` in the app bundle",
+        )]
+    }
+}
+
+/// Run every bundle check on a built `.app`.
+pub fn lint_app(app: &Path, deployment_target: &str) -> anyhow::Result<Vec<Violation>> {
+    let info_plist = crate::plist::read(&app.join("Info.plist"))?;
+    let executable = info_plist
+        .get("CFBundleExecutable")
+        .and_then(|v| v.as_str())
+        .with_context(|| format!("Missing `CFBundleExecutable` in {:?}", app))?;
+    let frameworks = linked_frameworks(&app.join(executable))?;
+
+    let mut violations = check_info_plist(&info_plist);
+    violations.extend(check_minimum_os(&info_plist, deployment_target));
+    violations.extend(check_usage_descriptions(&frameworks, &info_plist));
+    violations.extend(check_encryption_declaration(&info_plist));
+    violations.extend(check_privacy_manifest(app));
+
+    Ok(violations)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn icon_sets() {
+        let icon_set: IconSet = serde_json::from_str(
+            r#"{
+              "images": [
+                { "idiom": "iphone", "size": "60x60", "scale": "2x", "filename": "120.png" },
+                { "idiom": "iphone", "size": "60x60", "scale": "3x" },
+                { "idiom": "ipad", "size": "83.5x83.5", "scale": "2x", "filename": "167.png" },
+                { "idiom": "ios-marketing", "size": "1024x1024", "scale": "1x", "filename": "1024.png" }
+              ],
+              "info": { "author": "xcode", "version": 1 }
+            }"#,
+        )
+        .unwrap();
+        let universal = required_icons(crate::toml::AppPlatform::Ios, "1,2");
+        assert_eq!(
+            icon_set.missing_icons(&universal),
+            vec!["iphone 60x60@3x".to_string(), "ipad 76x76@2x".to_string()]
+        );
+        let iphone = required_icons(crate::toml::AppPlatform::Ios, "1");
+        assert_eq!(
+            icon_set.missing_icons(&iphone),
+            vec!["iphone 60x60@3x".to_string()]
+        );
+        let mac = required_icons(crate::toml::AppPlatform::MacOs, "");
+        assert_eq!(icon_set.missing_icons(&mac).len(), 10);
+        assert!(required_icons(crate::toml::AppPlatform::TvOs, "3").is_empty());
+        assert_eq!(icon_set.images[2].pixel_size(), Some(167));
+
+        let single_size: IconSet = serde_json::from_str(
+            r#"{ "images": [ { "idiom": "universal", "platform": "ios", "size": "1024x1024", "filename": "icon.png" } ] }"#,
+        )
+        .unwrap();
+        assert!(single_size.missing_icons(&universal).is_empty());
+        assert_eq!(single_size.images[0].pixel_size(), Some(1024));
+    }
+
+    #[test]
+    fn sips_output() {
+        let output = "/tmp/icon.png\n  pixelWidth: 1024\n  pixelHeight: 1024\n  hasAlpha: yes\n";
+        assert_eq!(
+            parse_sips(output),
+            ImageInfo {
+                width: 1024,
+                height: 1024,
+                has_alpha: true,
+            }
+        );
+    }
+
+    #[test]
+    fn info_plist_keys() {
+        let plist = serde_json::json!({
+            "CFBundleIdentifier": "com.example.app",
+            "CFBundleExecutable": "app",
+            "CFBundleName": "",
+            "CFBundleShortVersionString": "1.0",
+            "CFBundleVersion": "1",
+            "CFBundlePackageType": "APPL",
+        });
+        assert_eq!(
+            check_info_plist(&plist),
+            vec![
+                Violation::new("Info.plist", "missing `CFBundleName`"),
+                Violation::new("Info.plist", "missing `UILaunchStoryboardName`"),
+            ]
+        );
+    }
+
+    #[test]
+    fn bundle_checks() {
+        let output = "/tmp/app.app/app:
+\t/System/Library/Frameworks/CoreLocation.framework/CoreLocation (compatibility version 1.0.0, current version 2780.0.17)
+\t/usr/lib/libobjc.A.dylib (compatibility version 1.0.0, current version 228.0.0)
+\t/System/Library/Frameworks/UIKit.framework/UIKit (compatibility version 1.0.0, current version 7082.1.112)
+";
+        let frameworks = parse_linked_frameworks(output);
+        assert_eq!(frameworks, vec!["CoreLocation", "UIKit"]);
+
+        let plist = serde_json::json!({ "MinimumOSVersion": "12.0" });
+        assert_eq!(
+            check_usage_descriptions(&frameworks, &plist),
+            vec![Violation::new(
+                "Usage descriptions",
+                "links CoreLocation without `NSLocationWhenInUseUsageDescription` or `NSLocationAlwaysAndWhenInUseUsageDescription`"
+            )]
+        );
+        assert!(check_minimum_os(&plist, "12").is_empty());
+        assert_eq!(check_minimum_os(&plist, "13.0").len(), 1);
+    }
+}
@@ -0,0 +1,67 @@
+use std::process::ExitStatus;
+
+/// A tool like cargo, xcodegen, xcodebuild or simctl exited unsuccessfully. It is the source of
+/// the error returned for the failed step, so that `cargo xcodebuild` can exit with the same code.
+#[derive(Debug)]
+pub struct ExitStatusError {
+    program: String,
+    status: ExitStatus,
+}
+
+impl ExitStatusError {
+    /// Exit code of the tool, `None` if it was terminated by a signal.
+    pub fn code(&self) -> Option<i32> {
+        self.status.code()
+    }
+}
+
+impl std::fmt::Display for ExitStatusError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} failed with {}", self.program, self.status)
+    }
+}
+
+impl std::error::Error for ExitStatusError {}
+
+/// `Err` with an [`ExitStatusError`] if `status` of `program` is not a success.
+pub fn check(program: &str, status: ExitStatus) -> Result<(), ExitStatusError> {
+    if status.success() {
+        Ok(())
+    } else {
+        Err(ExitStatusError {
+            program: program.to_string(),
+            status,
+        })
+    }
+}
+
+/// Exit code of the first [`ExitStatusError`] that caused `error`.
+pub fn exit_code(error: &anyhow::Error) -> Option<i32> {
+    error
+        .chain()
+        .find_map(|cause| cause.downcast_ref::<ExitStatusError>())
+        .and_then(ExitStatusError::code)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Context as _;
+    use std::os::unix::process::ExitStatusExt as _;
+
+    #[test]
+    fn exit_codes() {
+        assert!(check("cargo", ExitStatus::from_raw(0)).is_ok());
+
+        let error = check("cargo", ExitStatus::from_raw(101 << 8))
+            .with_context(|| "Failed to build for aarch64-apple-ios".to_string())
+            .unwrap_err();
+        assert_eq!(exit_code(&error), Some(101));
+        assert_eq!(
+            format!("{:#}", error),
+            "Failed to build for aarch64-apple-ios: cargo failed with exit status: 101"
+        );
+
+        assert_eq!(exit_code(&anyhow::anyhow!("No device found")), None);
+    }
+}
@@ -0,0 +1,93 @@
+use std::io::Write as _;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+const SPINNER: &[char] = &['⠋', '⠙', '⠹', '⠸', '⠼', '⠴', '⠦', '⠧', '⠇', '⠏'];
+
+/// Progress of a long step on stderr: a start line, or a spinner with the elapsed time for steps
+/// without output of their own, and a line with the total time when it finishes.
+pub struct Progress {
+    label: String,
+    started: Instant,
+    stop: Arc<AtomicBool>,
+    spinner: Option<std::thread::JoinHandle<()>>,
+}
+
+impl Progress {
+    /// Start reporting `label`, e.g. `Installing`. The spinner is only drawn if `spinner` is set,
+    /// as other output would interleave with it.
+    pub fn start(label: impl Into<String>, spinner: bool) -> Self {
+        let label = label.into();
+        let started = Instant::now();
+        let stop = Arc::new(AtomicBool::new(false));
+        let spinner = if spinner {
+            let label = label.clone();
+            let stop = stop.clone();
+            Some(std::thread::spawn(move || {
+                let mut frames = SPINNER.iter().cycle();
+                while !stop.load(Ordering::Relaxed) {
+                    eprint!(
+                        "\r\x1b[K{} {} ({})",
+                        frames.next().unwrap(),
+                        label,
+                        format_elapsed(started.elapsed())
+                    );
+                    let _ = std::io::stderr().flush();
+                    std::thread::sleep(Duration::from_millis(100));
+                }
+                eprint!("\r\x1b[K");
+            }))
+        } else {
+            eprintln!("{}...", label);
+            None
+        };
+        Self {
+            label,
+            started,
+            stop,
+            spinner,
+        }
+    }
+
+    /// Stop the spinner and print how long the step took.
+    pub fn finish(mut self, success: bool) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(spinner) = self.spinner.take() {
+            let _ = spinner.join();
+        }
+        let elapsed = format_elapsed(self.started.elapsed());
+        let mut label = self.label.chars();
+        let label = label
+            .next()
+            .map(|first| first.to_lowercase().chain(label).collect::<String>())
+            .unwrap_or_default();
+        if success {
+            eprintln!("Finished {} in {}", label, elapsed);
+        } else {
+            eprintln!("Failed {} after {}", label, elapsed);
+        }
+    }
+}
+
+/// `0.4s`, `12.3s` or `2m 05s`.
+pub fn format_elapsed(elapsed: Duration) -> String {
+    let secs = elapsed.as_secs();
+    if secs < 60 {
+        format!("{:.1}s", elapsed.as_secs_f64())
+    } else {
+        format!("{}m {:02}s", secs / 60, secs % 60)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn elapsed() {
+        assert_eq!(format_elapsed(Duration::from_millis(400)), "0.4s");
+        assert_eq!(format_elapsed(Duration::from_millis(12_340)), "12.3s");
+        assert_eq!(format_elapsed(Duration::from_secs(125)), "2m 05s");
+    }
+}
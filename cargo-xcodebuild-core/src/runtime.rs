@@ -0,0 +1,155 @@
+use anyhow::Context as _;
+
+/// Version of the `simulator_runtime` pin, e.g. `17.4` of `iOS 17.4`. A platform in the
+/// pin must be the one of the app.
+pub fn pinned_version(pin: &str, platform: crate::toml::AppPlatform) -> anyhow::Result<String> {
+    match crate::simctl::split_runtime_query(pin) {
+        (_, "") => anyhow::bail!(
+            "`simulator_runtime` `{}` has no version. Please check Cargo.toml.",
+            pin
+        ),
+        (Some(pin_platform), _) if !pin_platform.eq_ignore_ascii_case(platform.name()) => {
+            anyhow::bail!(
+                "`simulator_runtime` `{}` is not a {} runtime. Please check Cargo.toml.",
+                pin,
+                platform.name()
+            )
+        }
+        (_, version) => Ok(version.to_string()),
+    }
+}
+
+/// Find a `platform` simulator with the `runtime` version, preferring a booted one. A
+/// partial version like `17` matches any `17.x`. Creates a simulator with that runtime, an
+/// iPhone for iOS, if there is none.
+pub fn find_simulator(platform: crate::toml::AppPlatform, runtime: &str) -> anyhow::Result<String> {
+    let simulators = crate::xcodebuild::Xcodebuild::get_platform_simulator_device_list(platform)?;
+    let existing = simulators
+        .iter()
+        .filter(|s| {
+            matches!(s.runtime_version(), Some(v) if crate::devices::runtime_version_matches(&v, runtime))
+        })
+        .max_by_key(|s| {
+            (
+                s.state == crate::devices::DeviceState::Booted,
+                s.name.starts_with("iPhone"),
+            )
+        });
+    if let Some(simulator) = existing {
+        return Ok(simulator.udid.clone());
+    }
+
+    let find_runtime = || -> anyhow::Result<Option<crate::simctl::Runtime>> {
+        Ok(crate::simctl::platform_runtimes(platform)?
+            .into_iter()
+            .rev()
+            .find(|r| {
+                r.is_available && crate::devices::runtime_version_matches(&r.version, runtime)
+            }))
+    };
+    let not_installed = format!(
+        "{} {} simulator runtime is not installed",
+        platform.name(),
+        runtime
+    );
+    let platform_runtime = match find_runtime()? {
+        Some(platform_runtime) => platform_runtime,
+        None if platform == crate::toml::AppPlatform::Ios => {
+            offer_download(Some(runtime), &not_installed)?;
+            find_runtime()?.context(not_installed)?
+        }
+        None => anyhow::bail!(
+            "{}. Install it in Xcode > Settings > Platforms or change `simulator_runtime` in Cargo.toml.",
+            not_installed
+        ),
+    };
+    let device_type = platform_runtime
+        .supported_device_types
+        .iter()
+        .rev()
+        .find(|d| d.product_family.as_deref() == Some("iPhone"))
+        .or_else(|| platform_runtime.supported_device_types.last())
+        .with_context(|| {
+            format!(
+                "{} {} runtime has no device types",
+                platform.name(),
+                runtime
+            )
+        })?;
+    let name = format!(
+        "{} {} {}{}",
+        device_type.name,
+        platform.name(),
+        platform_runtime.version,
+        crate::simctl::CREATED_SIMULATOR_SUFFIX
+    );
+    crate::simctl::create(
+        &name,
+        &device_type.identifier,
+        Some(platform_runtime.identifier.as_str()),
+    )
+}
+
+/// Version of the newest installed iOS runtime supporting `deployment_target`. If there is
+/// none, offer to download the newest one.
+pub fn default_ios_runtime(deployment_target: &str) -> anyhow::Result<String> {
+    let supported = |runtimes: &[crate::simctl::Runtime]| {
+        runtimes
+            .iter()
+            .rev()
+            .find(|r| {
+                r.is_available
+                    && crate::devices::runtime_version_at_least(&r.version, deployment_target)
+            })
+            .map(|r| r.version.clone())
+    };
+    if let Some(runtime) = supported(&crate::simctl::ios_runtimes()?) {
+        return Ok(runtime);
+    }
+
+    let reason = format!(
+        "No installed iOS simulator runtime supports deployment target {}",
+        deployment_target
+    );
+    offer_download(None, &reason)?;
+    supported(&crate::simctl::ios_runtimes()?).context(reason)
+}
+
+/// Ask whether to download a simulator runtime and download it with progress.
+/// Fails with the download command if the answer isn't yes.
+fn offer_download(version: Option<&str>, reason: &str) -> anyhow::Result<()> {
+    use std::io::Write as _;
+
+    let command = match version {
+        Some(version) => format!("xcodebuild -downloadPlatform iOS -buildVersion {}", version),
+        None => "xcodebuild -downloadPlatform iOS".to_string(),
+    };
+    eprint!("{}. Download it with `{}`? [y/N] ", reason, command);
+    std::io::stderr().flush()?;
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer)?;
+    if !matches!(answer.trim(), "y" | "Y" | "yes") {
+        anyhow::bail!("{}. Install it with `{}`", reason, command)
+    }
+
+    crate::simctl::download_ios_runtime(version)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pinned_versions() {
+        use crate::toml::AppPlatform;
+
+        assert_eq!(pinned_version("17.4", AppPlatform::Ios).unwrap(), "17.4");
+        assert_eq!(
+            pinned_version("iOS 17.4", AppPlatform::Ios).unwrap(),
+            "17.4"
+        );
+        assert_eq!(pinned_version("tvOS 17", AppPlatform::TvOs).unwrap(), "17");
+        assert!(pinned_version("tvOS 17", AppPlatform::Ios).is_err());
+        assert!(pinned_version("iOS", AppPlatform::Ios).is_err());
+    }
+}
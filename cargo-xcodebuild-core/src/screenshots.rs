@@ -0,0 +1,110 @@
+use anyhow::Context as _;
+use std::path::Path;
+use std::time::Duration;
+
+/// App Store screenshots to capture, from `[package.metadata.ios.screenshots]` with defaults.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Plan {
+    /// Simulator device types, e.g. `iPhone 15 Pro Max`.
+    pub device_types: Vec<String>,
+    /// Screen names with the launch arguments showing them.
+    pub launches: Vec<(String, Vec<String>)>,
+    /// Time to wait after launch before taking a screenshot.
+    pub delay: Duration,
+}
+
+impl Plan {
+    pub fn new(config: crate::toml::Screenshots) -> Self {
+        let device_types = config.devices.unwrap_or_else(|| {
+            vec![
+                "iPhone 15 Pro Max".into(),
+                "iPhone 15".into(),
+                "iPad Pro (12.9-inch) (6th generation)".into(),
+            ]
+        });
+        let screens = config.screens.unwrap_or_default();
+        let launch_argument = config
+            .launch_argument
+            .unwrap_or_else(|| "--screenshot".into());
+        let launches = if screens.is_empty() {
+            vec![("screen".to_string(), vec![])]
+        } else {
+            screens
+                .iter()
+                .map(|s| (s.clone(), vec![launch_argument.clone(), s.clone()]))
+                .collect()
+        };
+
+        Self {
+            device_types,
+            launches,
+            delay: Duration::from_secs(config.delay.unwrap_or(3)),
+        }
+    }
+}
+
+/// Find a simulator named after `device_type` or create a new one.
+pub fn find_or_create_simulator(device_type: &str) -> anyhow::Result<String> {
+    let simulators = crate::xcodebuild::Xcodebuild::get_simulator_device_list()?;
+    let created_name = format!("{}{}", device_type, crate::simctl::CREATED_SIMULATOR_SUFFIX);
+    let existing = simulators
+        .iter()
+        .filter(|s| s.name == device_type || s.name == created_name)
+        .max_by_key(|s| s.state == crate::devices::DeviceState::Booted);
+    match existing {
+        Some(simulator) => Ok(simulator.udid.clone()),
+        None => crate::simctl::create(&created_name, device_type, None),
+    }
+}
+
+/// Install `app_path` on the booted simulator `udid` and save a screenshot of every screen of
+/// `plan` to `device_dir` as `<index>-<screen>.png`.
+pub fn capture(
+    udid: &str,
+    app_path: &Path,
+    bundle_id: &str,
+    device_dir: &Path,
+    plan: &Plan,
+) -> anyhow::Result<()> {
+    std::fs::create_dir_all(device_dir)
+        .with_context(|| format!("Failed to create all dir: {:?}", device_dir))?;
+
+    crate::simctl::install(udid, app_path)?;
+    for (index, (screen, launch_args)) in plan.launches.iter().enumerate() {
+        crate::simctl::launch(udid, bundle_id, launch_args)?;
+        std::thread::sleep(plan.delay);
+        let path = device_dir.join(format!("{:02}-{}.png", index + 1, screen));
+        crate::simctl::screenshot(udid, &path)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plans() {
+        let plan = Plan::new(Default::default());
+        assert_eq!(plan.device_types.len(), 3);
+        assert_eq!(plan.launches, vec![("screen".to_string(), vec![])]);
+        assert_eq!(plan.delay, Duration::from_secs(3));
+
+        let plan = Plan::new(crate::toml::Screenshots {
+            devices: Some(vec!["iPhone 15".into()]),
+            screens: Some(vec!["menu".into(), "level".into()]),
+            launch_argument: Some("-screen".into()),
+            delay: Some(1),
+        });
+        assert_eq!(plan.device_types, vec!["iPhone 15".to_string()]);
+        assert_eq!(
+            plan.launches,
+            vec![
+                ("menu".to_string(), vec!["-screen".into(), "menu".into()]),
+                ("level".to_string(), vec!["-screen".into(), "level".into()]),
+            ]
+        );
+        assert_eq!(plan.delay, Duration::from_secs(1));
+    }
+}
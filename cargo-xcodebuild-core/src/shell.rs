@@ -0,0 +1,16 @@
+/// Quote `value` for a POSIX shell command line, e.g. `it's` becomes `'it'\''s'`.
+pub fn quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', r"'\''"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quoting() {
+        assert_eq!(quote("/tmp/My App.ipa"), "'/tmp/My App.ipa'");
+        assert_eq!(quote("it's"), r"'it'\''s'");
+        assert_eq!(quote("$HOME"), "'$HOME'");
+    }
+}
@@ -0,0 +1,623 @@
+use anyhow::Context as _;
+use std::path::{Path, PathBuf};
+
+const KEY_PATH_ENV: &str = "APP_STORE_CONNECT_KEY_PATH";
+const KEY_ID_ENV: &str = "APP_STORE_CONNECT_KEY_ID";
+const ISSUER_ID_ENV: &str = "APP_STORE_CONNECT_ISSUER_ID";
+const KEYCHAIN_PATH_ENV: &str = "KEYCHAIN_PATH";
+const KEYCHAIN_PASSWORD_ENV: &str = "KEYCHAIN_PASSWORD";
+/// Seconds a keychain unlocked for a build stays unlocked, longer than any build.
+const KEYCHAIN_TIMEOUT: &str = "21600";
+
+/// App Store Connect API key. Lets `xcodebuild -allowProvisioningUpdates` create and download
+/// provisioning profiles on machines without a logged-in Apple ID, e.g. CI runners.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuthenticationKey {
+    pub path: PathBuf,
+    pub id: String,
+    pub issuer_id: String,
+}
+
+impl AuthenticationKey {
+    /// Resolve the key from the environment, falling back to `[package.metadata.ios.app_store_connect]`.
+    /// A relative key path is resolved against the current directory if it comes from the
+    /// environment, e.g. the checkout on CI, and against `manifest_dir` otherwise.
+    pub fn resolve(
+        metadata: Option<&crate::toml::AppStoreConnect>,
+        manifest_dir: &Path,
+    ) -> anyhow::Result<Option<Self>> {
+        let metadata = metadata.cloned().unwrap_or_default();
+        let path = match std::env::var(KEY_PATH_ENV) {
+            Ok(path) => Some(std::env::current_dir()?.join(path)),
+            Err(_) => metadata.key_path.map(|path| manifest_dir.join(path)),
+        };
+        let id = std::env::var(KEY_ID_ENV).ok().or(metadata.key_id);
+        let issuer_id = std::env::var(ISSUER_ID_ENV).ok().or(metadata.issuer_id);
+
+        match (path, id, issuer_id) {
+            (Some(path), Some(id), Some(issuer_id)) => {
+                if !path.is_file() {
+                    anyhow::bail!("App Store Connect API key is not found: {:?}", path)
+                }
+                log::debug!("Using App Store Connect API key {}", id);
+                Ok(Some(Self {
+                    path,
+                    id,
+                    issuer_id,
+                }))
+            }
+            (None, None, None) => Ok(None),
+            _ => anyhow::bail!(
+                "Incomplete App Store Connect API key. `key_path`, `key_id` and `issuer_id` (or {}, {} and {}) are required.",
+                KEY_PATH_ENV,
+                KEY_ID_ENV,
+                ISSUER_ID_ENV
+            ),
+        }
+    }
+
+    /// Arguments for `xcodebuild`.
+    pub fn xcodebuild_args(&self) -> Vec<String> {
+        vec![
+            "-authenticationKeyPath".into(),
+            self.path.to_string_lossy().into_owned(),
+            "-authenticationKeyID".into(),
+            self.id.clone(),
+            "-authenticationKeyIssuerID".into(),
+            self.issuer_id.clone(),
+        ]
+    }
+}
+
+/// Keychain with the signing certificates, e.g. created and imported on a CI runner. Unlocked
+/// before signing so that `codesign` doesn't wait for a password prompt nobody can answer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Keychain {
+    /// Path, or name of a keychain in `~/Library/Keychains` like `build.keychain`.
+    pub path: PathBuf,
+    pub password: String,
+}
+
+impl Keychain {
+    /// Resolve the keychain from the environment, falling back to `[package.metadata.ios.keychain]`
+    /// for the path. Relative paths are resolved like [`AuthenticationKey::resolve`], names are
+    /// kept. The password is only read from the environment.
+    pub fn resolve(
+        metadata: Option<&crate::toml::KeychainConfig>,
+        manifest_dir: &Path,
+    ) -> anyhow::Result<Option<Self>> {
+        let metadata = metadata.cloned().unwrap_or_default();
+        let path = match std::env::var(KEYCHAIN_PATH_ENV) {
+            Ok(path) if path.contains('/') => Some(std::env::current_dir()?.join(path)),
+            Ok(name) => Some(PathBuf::from(name)),
+            Err(_) => metadata.path.map(|path| {
+                if path.contains('/') {
+                    manifest_dir.join(path)
+                } else {
+                    PathBuf::from(path)
+                }
+            }),
+        };
+        let password = std::env::var(KEYCHAIN_PASSWORD_ENV).ok();
+
+        match (path, password) {
+            (Some(path), Some(password)) => Ok(Some(Self { path, password })),
+            (None, None) => Ok(None),
+            (Some(path), None) => anyhow::bail!(
+                "Missing password of keychain {:?}. Set it with {}",
+                path,
+                KEYCHAIN_PASSWORD_ENV
+            ),
+            (None, Some(_)) => anyhow::bail!(
+                "Missing keychain path. Set `path` in `[package.metadata.ios.keychain]` or {}",
+                KEYCHAIN_PATH_ENV
+            ),
+        }
+    }
+
+    /// Unlock the keychain, keep it unlocked for the build, add it to the search list so that
+    /// its identities are found, and allow `codesign` to use its keys without a prompt.
+    pub fn unlock(&self) -> anyhow::Result<()> {
+        log::info!("Unlocking keychain {:?}", self.path);
+        let path = self.path.to_string_lossy();
+        security(&["unlock-keychain", "-p", &self.password, &path])?;
+        security(&["set-keychain-settings", "-lut", KEYCHAIN_TIMEOUT, &path])?;
+
+        let search_list = parse_keychain_list(&security(&["list-keychains", "-d", "user"])?);
+        if !search_list
+            .iter()
+            .any(|keychain| self.is_same_file(Path::new(keychain)))
+        {
+            let mut args = vec!["list-keychains", "-d", "user", "-s", &path];
+            args.extend(search_list.iter().map(String::as_str));
+            security(&args)?;
+        }
+
+        // The partition list is stored in the keychain, setting it again for every `xcodebuild`
+        // of the same run only slows the build down.
+        let mut partitioned = PARTITIONED_KEYCHAINS.lock().unwrap();
+        if !partitioned.contains(&self.path) {
+            security(&[
+                "set-key-partition-list",
+                "-S",
+                "apple-tool:,apple:,codesign:",
+                "-s",
+                "-k",
+                &self.password,
+                &path,
+            ])?;
+            partitioned.push(self.path.clone());
+        }
+
+        Ok(())
+    }
+
+    /// Whether the keychain file at `path` is this keychain. Names refer to
+    /// `~/Library/Keychains`, and `security` lists keychains by their `-db` file.
+    fn is_same_file(&self, path: &Path) -> bool {
+        let own = match std::env::var_os("HOME") {
+            Some(home) if self.path.is_relative() && self.path.components().count() == 1 => {
+                Path::new(&home).join("Library/Keychains").join(&self.path)
+            }
+            _ => self.path.clone(),
+        };
+        let canonical = |path: &Path| path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        let path = canonical(path);
+        let mut db = own.clone().into_os_string();
+        db.push("-db");
+
+        path == canonical(&own) || path == canonical(Path::new(&db))
+    }
+
+    /// Build setting making `codesign` use this keychain. The path is quoted, build settings are
+    /// split at spaces.
+    pub fn xcodebuild_setting(&self) -> String {
+        let path = self.path.to_string_lossy();
+        format!(
+            "OTHER_CODE_SIGN_FLAGS=--keychain \"{}\"",
+            path.replace('\\', "\\\\").replace('"', "\\\"")
+        )
+    }
+}
+
+/// Keychains whose key partition list was set by this process.
+static PARTITIONED_KEYCHAINS: std::sync::Mutex<Vec<PathBuf>> = std::sync::Mutex::new(Vec::new());
+
+/// Run `security <args>` and return its stdout. Arguments aren't logged, they contain passwords.
+fn security(args: &[&str]) -> anyhow::Result<String> {
+    let command = args.first().copied().unwrap_or_default();
+    let output = std::process::Command::new("security")
+        .args(args)
+        .output()
+        .with_context(|| format!("Failed to run security {}", command))?;
+    crate::process::check("security", output.status).with_context(|| {
+        format!(
+            "Failed to run security {}:\n{:?}",
+            command,
+            String::from_utf8_lossy(&output.stderr)
+        )
+    })?;
+
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Keychain paths from `security list-keychains` output, one quoted path per line.
+fn parse_keychain_list(output: &str) -> Vec<String> {
+    output
+        .lines()
+        .map(|line| line.trim().trim_matches('"').to_string())
+        .filter(|line| !line.is_empty())
+        .collect()
+}
+
+/// Verify the signature and embedded provisioning profile of an app before installing it to `device_udid`.
+/// Turns problems that would fail the install with an opaque `kAMD*` error into actionable messages.
+pub fn verify_app_for_device(app_path: &Path, device_udid: &str) -> anyhow::Result<()> {
+    verify_code_signature(app_path)?;
+
+    let profile = ProvisioningProfile::from_app(app_path)?;
+    if profile.is_expired() {
+        anyhow::bail!(
+            "Provisioning profile `{}` expired on {}. Download a new one in Xcode > Settings > Accounts or let automatic signing renew it, then build again.",
+            profile.name,
+            profile.expiration_date
+        )
+    }
+    if !profile.includes_device(device_udid) {
+        anyhow::bail!(
+            "Device {} is not included in provisioning profile `{}`. Register it in your Apple Developer account (automatic signing does it for connected devices), then build again.",
+            device_udid,
+            profile.name
+        )
+    }
+
+    Ok(())
+}
+
+/// Run `codesign --verify --deep --strict` on a bundle.
+pub fn verify_code_signature(app_path: &Path) -> anyhow::Result<()> {
+    let output = std::process::Command::new("codesign")
+        .arg("--verify")
+        .arg("--deep")
+        .arg("--strict")
+        .arg(app_path)
+        .output()
+        .with_context(|| "Failed to get codesign output".to_string())?;
+
+    if !output.status.success() {
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        if let Some(guidance) = codesign_guidance(&stderr) {
+            anyhow::bail!(
+                "Code signature of {:?} is invalid: {}\n{}",
+                app_path,
+                stderr.trim(),
+                guidance
+            )
+        }
+
+        anyhow::bail!(
+            "Failed to verify code signature of {:?}:\n{:?}\n{:?}",
+            app_path,
+            stdout,
+            stderr
+        )
+    }
+
+    Ok(())
+}
+
+/// Sign `app_path` again after its resources changed, with the identity, entitlements and flags
+/// of its current signature.
+pub fn resign(app_path: &Path) -> anyhow::Result<()> {
+    let output = std::process::Command::new("codesign")
+        .arg("--display")
+        .arg("--verbose=2")
+        .arg(app_path)
+        .output()
+        .with_context(|| "Failed to get codesign output".to_string())?;
+    // `codesign --display` prints the signature details to stderr.
+    let details = String::from_utf8_lossy(&output.stderr);
+    let authority = signing_authority(&details)
+        .with_context(|| format!("{:?} is not signed with a signing identity", app_path))?;
+
+    log::info!("Signing {:?} with `{}`", app_path, authority);
+    let output = std::process::Command::new("codesign")
+        .arg("--force")
+        .arg("--sign")
+        .arg(authority)
+        .arg("--preserve-metadata=identifier,entitlements,flags")
+        .arg("--generate-entitlement-der")
+        .arg(app_path)
+        .output()
+        .with_context(|| "Failed to get codesign output".to_string())?;
+
+    if !output.status.success() {
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+
+        anyhow::bail!("Failed to sign {:?}:\n{:?}\n{:?}", app_path, stdout, stderr)
+    }
+
+    Ok(())
+}
+
+/// Leaf certificate name from `codesign --display --verbose=2` output, e.g. `Apple Development: ...`.
+fn signing_authority(details: &str) -> Option<&str> {
+    details
+        .lines()
+        .find_map(|line| line.strip_prefix("Authority="))
+        .map(str::trim)
+}
+
+/// Explain a `codesign --verify` failure.
+fn codesign_guidance(stderr: &str) -> Option<&'static str> {
+    if stderr.contains("code object is not signed at all") {
+        Some("The app is not signed. Set `development_team` in `[package.metadata.ios]` to sign device builds.")
+    } else if stderr.contains("a sealed resource is missing or invalid")
+        || stderr.contains("file added")
+        || stderr.contains("file modified")
+    {
+        Some("The app was modified after signing. Build it again instead of changing the bundle.")
+    } else if stderr.contains("CSSMERR_TP_CERT_REVOKED") {
+        Some(
+            "The signing certificate was revoked. Create a new one in Xcode > Settings > Accounts.",
+        )
+    } else if stderr.contains("CSSMERR_TP_CERT_EXPIRED") {
+        Some("The signing certificate expired. Create a new one in Xcode > Settings > Accounts.")
+    } else if stderr.contains("CSSMERR_TP_NOT_TRUSTED") {
+        Some("The signing certificate is not trusted. Install the Apple WWDR intermediate certificate and check Keychain Access trust settings.")
+    } else {
+        None
+    }
+}
+
+/// Directories of installed provisioning profiles, relative to the home directory.
+/// Xcode 16 moved them from the first to the second.
+const PROFILE_DIRS: [&str; 2] = [
+    "Library/MobileDevice/Provisioning Profiles",
+    "Library/Developer/Xcode/UserData/Provisioning Profiles",
+];
+
+/// A `.mobileprovision` file, installed or embedded in a built app.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct ProvisioningProfile {
+    pub name: String,
+    pub uuid: String,
+    /// `application-identifier` entitlement with the team id prefix, e.g. `ABCDE12345.com.rust.*`.
+    pub app_id: String,
+    pub team_name: String,
+    pub team_id: String,
+    /// ISO 8601, e.g. `2025-01-31T12:00:00Z`.
+    pub expiration_date: String,
+    pub provisioned_devices: Vec<String>,
+    /// Enterprise profiles run on any device.
+    pub provisions_all_devices: bool,
+    pub path: PathBuf,
+}
+
+impl ProvisioningProfile {
+    pub fn from_app(app_path: &Path) -> anyhow::Result<Self> {
+        let profile_path = app_path.join("embedded.mobileprovision");
+        if !profile_path.is_file() {
+            anyhow::bail!(
+                "{:?} has no embedded provisioning profile. Set `development_team` in `[package.metadata.ios]` to sign device builds.",
+                app_path
+            )
+        }
+        Self::from_file(&profile_path)
+    }
+
+    /// Profiles installed by Xcode, sorted by name. Profiles that can't be decoded are skipped
+    /// with a warning, and a profile installed in both directories is listed once.
+    pub fn installed() -> anyhow::Result<Vec<Self>> {
+        let home = std::env::var_os("HOME").with_context(|| "HOME is not set".to_string())?;
+        let mut profiles: Vec<Self> = vec![];
+        for dir in PROFILE_DIRS {
+            let dir = Path::new(&home).join(dir);
+            for entry in std::fs::read_dir(&dir).into_iter().flatten().flatten() {
+                let path = entry.path();
+                if path.extension().and_then(|e| e.to_str()) != Some("mobileprovision") {
+                    continue;
+                }
+                match Self::from_file(&path) {
+                    Ok(profile) if profiles.iter().any(|p| p.uuid == profile.uuid) => {}
+                    Ok(profile) => profiles.push(profile),
+                    Err(err) => log::warn!("Skipping provisioning profile {:?}: {:#}", path, err),
+                }
+            }
+        }
+        profiles.sort_by(|a, b| a.name.cmp(&b.name).then(a.uuid.cmp(&b.uuid)));
+        Ok(profiles)
+    }
+
+    /// Decode the plist signed into a `.mobileprovision` file.
+    pub fn from_file(profile_path: &Path) -> anyhow::Result<Self> {
+        let plist_path =
+            std::env::temp_dir().join(format!("cargo-xcodebuild-{}.plist", std::process::id()));
+        let output = std::process::Command::new("security")
+            .arg("cms")
+            .arg("-D")
+            .arg("-i")
+            .arg(profile_path)
+            .arg("-o")
+            .arg(&plist_path)
+            .output()
+            .with_context(|| "Failed to get security output".to_string())?;
+
+        if !output.status.success() {
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            let stderr = String::from_utf8_lossy(&output.stderr);
+
+            anyhow::bail!(
+                "Failed to decode {:?}:\n{:?}\n{:?}",
+                profile_path,
+                stdout,
+                stderr
+            )
+        }
+
+        let profile = Self::from_plist(&plist_path, profile_path);
+        let _ = std::fs::remove_file(&plist_path);
+        profile
+    }
+
+    fn from_plist(path: &Path, profile_path: &Path) -> anyhow::Result<Self> {
+        let extract = |key| crate::plist::extract(path, key, "raw");
+        let provisioned_devices = match crate::plist::extract(path, "ProvisionedDevices", "json")? {
+            Some(json) => serde_json::from_str(&json)
+                .with_context(|| "Failed to parse ProvisionedDevices".to_string())?,
+            None => Vec::new(),
+        };
+
+        Ok(Self {
+            name: extract("Name")?.unwrap_or_default(),
+            uuid: extract("UUID")?.unwrap_or_default(),
+            app_id: extract("Entitlements.application-identifier")?.unwrap_or_default(),
+            team_name: extract("TeamName")?.unwrap_or_default(),
+            team_id: extract("TeamIdentifier.0")?.unwrap_or_default(),
+            expiration_date: extract("ExpirationDate")?
+                .with_context(|| "Provisioning profile has no ExpirationDate".to_string())?,
+            provisioned_devices,
+            provisions_all_devices: extract("ProvisionsAllDevices")?.as_deref() == Some("true"),
+            path: profile_path.to_path_buf(),
+        })
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.expiration_date.as_str() < now_iso8601().as_str()
+    }
+
+    /// Whether the app id of the profile covers `bundle_id`, exactly or with a `*` wildcard.
+    pub fn matches_bundle_id(&self, bundle_id: &str) -> bool {
+        let pattern = match self.app_id.split_once('.') {
+            Some((_team_id, pattern)) => pattern,
+            None => return false,
+        };
+        match pattern.strip_suffix('*') {
+            Some(prefix) => bundle_id.starts_with(prefix),
+            None => pattern == bundle_id,
+        }
+    }
+
+    pub fn includes_device(&self, udid: &str) -> bool {
+        self.provisions_all_devices
+            || self
+                .provisioned_devices
+                .iter()
+                .any(|device| device.eq_ignore_ascii_case(udid))
+    }
+}
+
+impl std::fmt::Display for ProvisioningProfile {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{} ({})", self.name, self.uuid)?;
+        writeln!(f, "  app id:  {}", self.app_id)?;
+        writeln!(f, "  team:    {} ({})", self.team_name, self.team_id)?;
+        let expired = if self.is_expired() { " EXPIRED" } else { "" };
+        writeln!(f, "  expires: {}{}", self.expiration_date, expired)?;
+        if self.provisions_all_devices {
+            writeln!(f, "  devices: all")?;
+        } else {
+            writeln!(f, "  devices: {}", self.provisioned_devices.len())?;
+            for device in &self.provisioned_devices {
+                writeln!(f, "    {}", device)?;
+            }
+        }
+        write!(f, "  path:    {}", self.path.display())
+    }
+}
+
+/// Current UTC time in the format of `plutil -extract <date> raw`.
+fn now_iso8601() -> String {
+    let secs = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+    iso8601(secs)
+}
+
+fn iso8601(unix_secs: u64) -> String {
+    let days = (unix_secs / 86400) as i64;
+    let secs = unix_secs % 86400;
+
+    // Civil from days, http://howardhinnant.github.io/date_algorithms.html
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        secs / 3600,
+        secs % 3600 / 60,
+        secs % 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn iso8601_dates() {
+        assert_eq!(iso8601(0), "1970-01-01T00:00:00Z");
+        assert_eq!(iso8601(951_782_400), "2000-02-29T00:00:00Z");
+        assert_eq!(iso8601(1_735_689_599), "2024-12-31T23:59:59Z");
+    }
+
+    #[test]
+    fn authority() {
+        let details = "Executable=/tmp/Game.app/Game
+Identifier=com.example.game
+Format=app bundle with Mach-O thin (arm64)
+Authority=Apple Development: Jane Doe (ABCDE12345)
+Authority=Apple Worldwide Developer Relations Certification Authority
+Authority=Apple Root CA
+TeamIdentifier=TEAM123456
+";
+        assert_eq!(
+            signing_authority(details),
+            Some("Apple Development: Jane Doe (ABCDE12345)")
+        );
+        assert_eq!(
+            signing_authority("Executable=/tmp/Game.app/Game\nSignature=adhoc\n"),
+            None
+        );
+    }
+
+    #[test]
+    fn codesign_failures() {
+        assert!(
+            codesign_guidance("Foo.app: code object is not signed at all")
+                .unwrap()
+                .contains("development_team")
+        );
+        assert!(
+            codesign_guidance("Foo.app: a sealed resource is missing or invalid")
+                .unwrap()
+                .contains("modified after signing")
+        );
+        assert_eq!(codesign_guidance("Foo.app: unknown error"), None);
+    }
+
+    #[test]
+    fn keychain_list() {
+        let output = "    \"/Users/runner/Library/Keychains/login.keychain-db\"\n    \"/Library/Keychains/System.keychain\"\n";
+        assert_eq!(
+            parse_keychain_list(output),
+            vec![
+                "/Users/runner/Library/Keychains/login.keychain-db",
+                "/Library/Keychains/System.keychain",
+            ]
+        );
+        assert!(parse_keychain_list("").is_empty());
+
+        let keychain = Keychain {
+            path: PathBuf::from("/tmp/CI Signing/build.keychain"),
+            password: "secret".into(),
+        };
+        assert_eq!(
+            keychain.xcodebuild_setting(),
+            r#"OTHER_CODE_SIGN_FLAGS=--keychain "/tmp/CI Signing/build.keychain""#
+        );
+        assert!(keychain.is_same_file(Path::new("/tmp/CI Signing/build.keychain-db")));
+        assert!(keychain.is_same_file(Path::new("/tmp//CI Signing/build.keychain")));
+        assert!(!keychain.is_same_file(Path::new("/tmp/build.keychain-db")));
+    }
+
+    #[test]
+    fn profile_devices() {
+        let profile = ProvisioningProfile {
+            name: "iOS Team Provisioning Profile".into(),
+            uuid: "5d2c5c4e-0000-0000-0000-000000000000".into(),
+            app_id: "ABCDE12345.com.rust.*".into(),
+            team_name: "Acme Inc".into(),
+            team_id: "ABCDE12345".into(),
+            expiration_date: "2025-01-31T12:00:00Z".into(),
+            provisioned_devices: vec!["00008030-001A2B3C4D5E6F7A".into()],
+            provisions_all_devices: false,
+            path: PathBuf::from("embedded.mobileprovision"),
+        };
+        assert!(profile.includes_device("00008030-001a2b3c4d5e6f7a"));
+        assert!(!profile.includes_device("00008030-FFFFFFFFFFFFFFFF"));
+        assert!(profile.is_expired());
+
+        assert!(profile.matches_bundle_id("com.rust.game"));
+        assert!(!profile.matches_bundle_id("org.rust.game"));
+        let profile = ProvisioningProfile {
+            app_id: "ABCDE12345.com.rust.game".into(),
+            ..profile
+        };
+        assert!(profile.matches_bundle_id("com.rust.game"));
+        assert!(!profile.matches_bundle_id("com.rust.game.staging"));
+    }
+}
@@ -0,0 +1,555 @@
+use anyhow::Context as _;
+use serde::Deserialize;
+use std::ffi::{OsStr, OsString};
+use std::path::{Path, PathBuf};
+
+/// Suffix of simulator names created by cargo-xcodebuild, so they can be cleaned up later.
+pub const CREATED_SIMULATOR_SUFFIX: &str = " (cargo-xcodebuild)";
+
+/// Run `xcrun simctl <args>` and return its stdout.
+pub fn simctl<I, S>(args: I) -> anyhow::Result<String>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<OsStr>,
+{
+    let args = args
+        .into_iter()
+        .map(|arg| arg.as_ref().to_os_string())
+        .collect::<Vec<OsString>>();
+    let output = std::process::Command::new("xcrun")
+        .arg("simctl")
+        .args(&args)
+        .output()
+        .with_context(|| format!("Failed to get output from command: xcrun simctl {:?}", args))?;
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    crate::process::check("xcrun simctl", output.status).with_context(|| {
+        format!(
+            "Failed to run xcrun simctl {:?}:\n{:?}\n{:?}",
+            args,
+            &stdout,
+            String::from_utf8_lossy(&output.stderr),
+        )
+    })?;
+
+    Ok(stdout.into_owned())
+}
+
+/// Simulator runtime from `xcrun simctl list runtimes --json`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Runtime {
+    pub identifier: String,
+    pub version: String,
+    /// Platform and version, e.g. `iOS 17.4`.
+    #[serde(default)]
+    pub name: String,
+    pub is_available: bool,
+    #[serde(default)]
+    pub supported_device_types: Vec<DeviceType>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeviceType {
+    pub name: String,
+    pub identifier: String,
+    #[serde(default)]
+    pub product_family: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Runtimes {
+    runtimes: Vec<Runtime>,
+}
+
+/// Installed iOS simulator runtimes.
+pub fn ios_runtimes() -> anyhow::Result<Vec<Runtime>> {
+    platform_runtimes(crate::toml::AppPlatform::Ios)
+}
+
+/// Installed simulator runtimes of `platform`, oldest first.
+pub fn platform_runtimes(platform: crate::toml::AppPlatform) -> anyhow::Result<Vec<Runtime>> {
+    let stdout = simctl(["list", "runtimes", platform.name(), "--json"])?;
+    let runtimes: Runtimes = serde_json::from_str(&stdout)
+        .with_context(|| format!("Failed to parse simulator runtimes:\n{}", stdout))?;
+    Ok(runtimes.runtimes)
+}
+
+/// Installed simulator runtimes of all platforms, oldest first.
+pub fn runtimes() -> anyhow::Result<Vec<Runtime>> {
+    let stdout = simctl(["list", "runtimes", "--json"])?;
+    let runtimes: Runtimes = serde_json::from_str(&stdout)
+        .with_context(|| format!("Failed to parse simulator runtimes:\n{}", stdout))?;
+    Ok(runtimes.runtimes)
+}
+
+/// Newest available runtime matching `query`: its identifier or a platform and version prefix
+/// with or without a space, e.g. `iOS 17.4`, `iOS17` or `tvOS17.4`. Just a version is an iOS one.
+pub fn find_runtime<'a>(runtimes: &'a [Runtime], query: &str) -> Option<&'a Runtime> {
+    let query = query.trim();
+    let (platform, version) = split_runtime_query(query);
+    let platform = platform.unwrap_or("iOS");
+    runtimes.iter().rev().filter(|r| r.is_available).find(|r| {
+        r.identifier == query
+            || r.name.split_once(' ').is_some_and(|(p, _)| {
+                p.eq_ignore_ascii_case(platform)
+                    && (version.is_empty()
+                        || crate::devices::runtime_version_matches(&r.version, version))
+            })
+    })
+}
+
+/// Split a runtime like `tvOS 17.4`, `iOS17` or `17` into its platform, if any, and version.
+pub fn split_runtime_query(query: &str) -> (Option<&str>, &str) {
+    let query = query.trim();
+    let split = query
+        .find(|c: char| !c.is_ascii_alphabetic())
+        .unwrap_or(query.len());
+    match query.split_at(split) {
+        ("", version) => (None, version.trim()),
+        (platform, version) => (Some(platform), version.trim()),
+    }
+}
+
+/// Device type of `runtime` with the `query` name, ignoring case, or identifier.
+pub fn find_device_type<'a>(runtime: &'a Runtime, query: &str) -> Option<&'a DeviceType> {
+    runtime
+        .supported_device_types
+        .iter()
+        .find(|d| d.identifier == query || d.name.eq_ignore_ascii_case(query.trim()))
+}
+
+/// Download an iOS simulator runtime with `xcodebuild -downloadPlatform iOS`, showing its progress.
+/// Downloads the newest runtime if `version` is `None`.
+pub fn download_ios_runtime(version: Option<&str>) -> anyhow::Result<()> {
+    log::info!(
+        "Downloading iOS {} simulator runtime",
+        version.unwrap_or("latest")
+    );
+    let mut command = std::process::Command::new("xcodebuild");
+    command.arg("-downloadPlatform").arg("iOS");
+    if let Some(version) = version {
+        command.arg("-buildVersion").arg(version);
+    }
+    // Progress and the final "installed" line go to stderr to keep `--message-format json` clean.
+    let status = command
+        .stdout(std::io::stderr())
+        .status()
+        .with_context(|| "Failed to run xcodebuild -downloadPlatform".to_string())?;
+    if !status.success() {
+        anyhow::bail!("Failed to download iOS simulator runtime: {}", status)
+    }
+
+    Ok(())
+}
+
+/// Boot a simulator if it isn't booted yet and wait until it finishes booting.
+pub fn boot_and_wait(udid: &str) -> anyhow::Result<()> {
+    log::info!("Waiting for simulator {} to boot", udid);
+    simctl(["bootstatus", udid, "-b"])?;
+    Ok(())
+}
+
+/// Create a simulator and return its udid. Uses the newest runtime if `runtime` is `None`.
+pub fn create(name: &str, device_type: &str, runtime: Option<&str>) -> anyhow::Result<String> {
+    log::info!("Creating simulator `{}` ({})", name, device_type);
+    let mut args = vec!["create", name, device_type];
+    args.extend(runtime);
+    Ok(simctl(args)?.trim().to_string())
+}
+
+pub fn delete(udid: &str) -> anyhow::Result<()> {
+    log::info!("Deleting simulator {}", udid);
+    simctl(["delete", udid])?;
+    Ok(())
+}
+
+/// Shut down a booted simulator, or every one with `all`.
+pub fn shutdown(udid: &str) -> anyhow::Result<()> {
+    log::info!("Shutting down simulator {}", udid);
+    simctl(["shutdown", udid])?;
+    Ok(())
+}
+
+/// Erase the content and settings of a shutdown simulator, or every one with `all`.
+pub fn erase(udid: &str) -> anyhow::Result<()> {
+    log::info!("Erasing simulator {}", udid);
+    simctl(["erase", udid])?;
+    Ok(())
+}
+
+/// Check that a push notification payload is a JSON object with an `aps` dictionary.
+pub fn validate_push_payload(contents: &str) -> anyhow::Result<()> {
+    let payload: serde_json::Value = serde_json::from_str(contents)?;
+    if !payload.get("aps").is_some_and(serde_json::Value::is_object) {
+        anyhow::bail!("Payload must contain an `aps` dictionary")
+    }
+    Ok(())
+}
+
+/// Send a push notification with the `payload` JSON file to an app.
+pub fn push(udid: &str, bundle_id: &str, payload: &Path) -> anyhow::Result<()> {
+    log::info!(
+        "Sending {:?} to {} on simulator {}",
+        payload,
+        bundle_id,
+        udid
+    );
+    simctl([
+        OsStr::new("push"),
+        OsStr::new(udid),
+        OsStr::new(bundle_id),
+        payload.as_os_str(),
+    ])?;
+    Ok(())
+}
+
+/// Open a URL on a simulator, e.g. a custom URL scheme or universal link.
+pub fn open_url(udid: &str, url: &str) -> anyhow::Result<()> {
+    log::info!("Opening {} on simulator {}", url, udid);
+    simctl(["openurl", udid, url])?;
+    Ok(())
+}
+
+/// Override the status bar with the values used in Apple marketing screenshots.
+pub fn override_status_bar(udid: &str) -> anyhow::Result<()> {
+    simctl([
+        "status_bar",
+        udid,
+        "override",
+        "--time",
+        "9:41",
+        "--dataNetwork",
+        "wifi",
+        "--wifiMode",
+        "active",
+        "--wifiBars",
+        "3",
+        "--cellularMode",
+        "active",
+        "--cellularBars",
+        "4",
+        "--batteryState",
+        "charged",
+        "--batteryLevel",
+        "100",
+    ])?;
+    Ok(())
+}
+
+pub fn clear_status_bar(udid: &str) -> anyhow::Result<()> {
+    simctl(["status_bar", udid, "clear"])?;
+    Ok(())
+}
+
+/// Status bar override of a simulator, cleared when dropped, also when the run using it fails.
+pub struct StatusBarOverride(String);
+
+impl StatusBarOverride {
+    /// Override the status bar of `udid` like [`override_status_bar`].
+    pub fn new(udid: &str) -> anyhow::Result<Self> {
+        override_status_bar(udid)?;
+        Ok(Self(udid.to_string()))
+    }
+}
+
+impl Drop for StatusBarOverride {
+    fn drop(&mut self) {
+        if let Err(err) = clear_status_bar(&self.0) {
+            log::warn!("Failed to clear the status bar of {}: {:#}", self.0, err);
+        }
+    }
+}
+
+pub fn install(udid: &str, app_path: &Path) -> anyhow::Result<()> {
+    log::info!("Installing app {:?} on simulator {}", app_path, udid);
+    simctl([
+        OsStr::new("install"),
+        OsStr::new(udid),
+        app_path.as_os_str(),
+    ])?;
+    Ok(())
+}
+
+/// Content size categories accepted by `simctl ui <udid> content_size`.
+pub const CONTENT_SIZE_CATEGORIES: &[&str] = &[
+    "extra-small",
+    "small",
+    "medium",
+    "large",
+    "extra-large",
+    "extra-extra-large",
+    "extra-extra-extra-large",
+    "accessibility-medium",
+    "accessibility-large",
+    "accessibility-extra-large",
+    "accessibility-extra-extra-large",
+    "accessibility-extra-extra-extra-large",
+];
+
+/// Set the Dynamic Type content size category, e.g. `accessibility-large`.
+pub fn set_content_size(udid: &str, category: &str) -> anyhow::Result<()> {
+    if !CONTENT_SIZE_CATEGORIES.contains(&category) {
+        anyhow::bail!(
+            "Unknown content size `{}`, expected one of: {}",
+            category,
+            CONTENT_SIZE_CATEGORIES.join(", ")
+        )
+    }
+    log::info!("Setting content size of simulator {} to {}", udid, category);
+    simctl(["ui", udid, "content_size", category])?;
+    Ok(())
+}
+
+/// Services accepted by `simctl privacy <udid> grant`.
+pub const PRIVACY_SERVICES: &[&str] = &[
+    "all",
+    "calendar",
+    "contacts-limited",
+    "contacts",
+    "location",
+    "location-always",
+    "photos-add",
+    "photos",
+    "media-library",
+    "microphone",
+    "motion",
+    "reminders",
+    "siri",
+];
+
+/// Permissions that simulators can't grant ahead of time, with the reason.
+const UNGRANTABLE_PERMISSIONS: &[(&str, &str)] = &[
+    (
+        "camera",
+        "simulators have no camera and `simctl privacy` has no service for it",
+    ),
+    (
+        "notifications",
+        "`simctl privacy` has no service for it, accept the prompt once or test with `cargo xcodebuild push`",
+    ),
+];
+
+/// Privacy services of `permissions` that can be granted. Fails on permissions simulators can't
+/// grant and on unknown ones.
+pub fn privacy_services(permissions: &[String]) -> anyhow::Result<Vec<&str>> {
+    let mut services = vec![];
+    for permission in permissions {
+        if PRIVACY_SERVICES.contains(&permission.as_str()) {
+            services.push(permission.as_str());
+        } else if let Some((_, reason)) = UNGRANTABLE_PERMISSIONS
+            .iter()
+            .find(|(name, _)| name == permission)
+        {
+            anyhow::bail!(
+                "`{}` permission can't be granted on simulators: {}. Please check Cargo.toml.",
+                permission,
+                reason
+            )
+        } else {
+            anyhow::bail!(
+                "Unknown permission `{}`, expected one of: {}. Please check Cargo.toml.",
+                permission,
+                PRIVACY_SERVICES.join(", ")
+            )
+        }
+    }
+    Ok(services)
+}
+
+/// Grant an app access to a privacy `service`, e.g. `photos`, without asking.
+pub fn grant_permission(udid: &str, service: &str, bundle_id: &str) -> anyhow::Result<()> {
+    log::info!(
+        "Granting {} access to {} on simulator {}",
+        service,
+        bundle_id,
+        udid
+    );
+    simctl(["privacy", udid, "grant", service, bundle_id])?;
+    Ok(())
+}
+
+/// Write a boolean `com.apple.Accessibility` default inside the simulator,
+/// e.g. `ReduceMotionEnabled`. Read by apps on their next launch.
+pub fn set_accessibility_flag(udid: &str, key: &str, enabled: bool) -> anyhow::Result<()> {
+    log::info!("Setting {} of simulator {} to {}", key, udid, enabled);
+    simctl([
+        "spawn",
+        udid,
+        "defaults",
+        "write",
+        "com.apple.Accessibility",
+        key,
+        "-bool",
+        if enabled { "YES" } else { "NO" },
+    ])?;
+    Ok(())
+}
+
+/// Parse the pid from `simctl launch` output like `com.rust.app: 12345`.
+pub fn parse_launch_pid(output: &str) -> Option<u32> {
+    output.trim().rsplit_once(':')?.1.trim().parse().ok()
+}
+
+/// Launch an app, terminating the running instance first.
+pub fn launch(udid: &str, bundle_id: &str, args: &[String]) -> anyhow::Result<()> {
+    log::info!("Running app {} on simulator {}", bundle_id, udid);
+    let mut simctl_args = vec!["launch", "--terminate-running-process", udid, bundle_id];
+    simctl_args.extend(args.iter().map(String::as_str));
+    simctl(simctl_args)?;
+    Ok(())
+}
+
+/// Path of an installed app's container, e.g. `data` or `app`.
+pub fn app_container(udid: &str, bundle_id: &str, container: &str) -> anyhow::Result<PathBuf> {
+    let stdout = simctl(["get_app_container", udid, bundle_id, container])?;
+    Ok(PathBuf::from(stdout.trim()))
+}
+
+/// Launch an app paused until a debugger attaches, terminating the running instance first.
+/// Returns the process id to attach to.
+pub fn launch_for_debugger(udid: &str, bundle_id: &str, args: &[String]) -> anyhow::Result<u32> {
+    log::info!(
+        "Launching app {} on simulator {} for the debugger",
+        bundle_id,
+        udid
+    );
+    let mut simctl_args = vec![
+        "launch",
+        "--terminate-running-process",
+        "--wait-for-debugger",
+        udid,
+        bundle_id,
+    ];
+    simctl_args.extend(args.iter().map(String::as_str));
+    let stdout = simctl(simctl_args)?;
+    parse_launch_pid(&stdout).with_context(|| {
+        format!(
+            "Failed to parse the process id of {}: {}",
+            bundle_id, stdout
+        )
+    })
+}
+
+/// Terminate an app. Doesn't fail if it isn't running.
+pub fn terminate(udid: &str, bundle_id: &str) {
+    if let Err(err) = simctl(["terminate", udid, bundle_id]) {
+        log::debug!("{} is not terminated: {}", bundle_id, err);
+    }
+}
+
+pub fn screenshot(udid: &str, path: &Path) -> anyhow::Result<()> {
+    log::info!("Saving screenshot of simulator {} to {:?}", udid, path);
+    simctl([
+        OsStr::new("io"),
+        OsStr::new(udid),
+        OsStr::new("screenshot"),
+        path.as_os_str(),
+    ])?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn launch_pid() {
+        assert_eq!(parse_launch_pid("com.rust.app: 12345\n"), Some(12345));
+        assert_eq!(parse_launch_pid("An error was encountered"), None);
+    }
+
+    #[test]
+    fn push_payloads() {
+        assert!(validate_push_payload(r#"{"aps": {"alert": "Hello", "badge": 1}}"#).is_ok());
+        assert!(validate_push_payload(r#"{"alert": "Hello"}"#).is_err());
+        assert!(validate_push_payload(r#"{"aps": "Hello"}"#).is_err());
+        assert!(validate_push_payload("{").is_err());
+    }
+
+    #[test]
+    fn permissions() {
+        let permissions = ["photos", "location-always"].map(String::from);
+        assert_eq!(
+            privacy_services(&permissions).unwrap(),
+            vec!["photos", "location-always"]
+        );
+        assert!(privacy_services(&["photo".to_string()]).is_err());
+        let error = privacy_services(&["photos".to_string(), "camera".to_string()])
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("no camera"));
+    }
+
+    #[test]
+    fn runtime_lookup() {
+        let runtimes: Runtimes = serde_json::from_str(
+            r#"{"runtimes": [
+                {
+                    "identifier": "com.apple.CoreSimulator.SimRuntime.iOS-17-2",
+                    "version": "17.2",
+                    "name": "iOS 17.2",
+                    "isAvailable": true,
+                    "supportedDeviceTypes": []
+                },
+                {
+                    "identifier": "com.apple.CoreSimulator.SimRuntime.iOS-17-4",
+                    "version": "17.4",
+                    "name": "iOS 17.4",
+                    "isAvailable": true,
+                    "supportedDeviceTypes": [{
+                        "name": "iPhone 15",
+                        "identifier": "com.apple.CoreSimulator.SimDeviceType.iPhone-15",
+                        "productFamily": "iPhone"
+                    }]
+                },
+                {
+                    "identifier": "com.apple.CoreSimulator.SimRuntime.tvOS-17-4",
+                    "version": "17.4",
+                    "name": "tvOS 17.4",
+                    "isAvailable": true
+                }
+            ]}"#,
+        )
+        .unwrap();
+        let runtimes = runtimes.runtimes;
+        let find = |query| find_runtime(&runtimes, query).map(|r| r.identifier.as_str());
+
+        assert_eq!(
+            find("iOS17.4"),
+            Some("com.apple.CoreSimulator.SimRuntime.iOS-17-4")
+        );
+        assert_eq!(
+            find("ios 17.2"),
+            Some("com.apple.CoreSimulator.SimRuntime.iOS-17-2")
+        );
+        assert_eq!(
+            find("iOS17"),
+            Some("com.apple.CoreSimulator.SimRuntime.iOS-17-4")
+        );
+        assert_eq!(
+            find("17.2"),
+            Some("com.apple.CoreSimulator.SimRuntime.iOS-17-2")
+        );
+        assert_eq!(
+            find("tvOS17.4"),
+            Some("com.apple.CoreSimulator.SimRuntime.tvOS-17-4")
+        );
+        assert_eq!(find("iOS16"), None);
+
+        let ios = find_runtime(&runtimes, "iOS17.4").unwrap();
+        assert_eq!(
+            find_device_type(ios, "iphone 15").map(|d| d.identifier.as_str()),
+            Some("com.apple.CoreSimulator.SimDeviceType.iPhone-15")
+        );
+        assert!(find_device_type(ios, "iPhone 14").is_none());
+    }
+
+    #[test]
+    fn runtime_queries() {
+        assert_eq!(split_runtime_query("17.4"), (None, "17.4"));
+        assert_eq!(split_runtime_query("iOS17"), (Some("iOS"), "17"));
+        assert_eq!(split_runtime_query(" tvOS 17.4 "), (Some("tvOS"), "17.4"));
+    }
+}
@@ -0,0 +1,92 @@
+use anyhow::Context as _;
+use std::path::Path;
+
+/// Default `bindings.h` declaring the Rust entry point.
+pub const DEFAULT_BINDING_HEADER: &str = r#"void main_rs(void);"#;
+/// Default `main.m` calling the Rust entry point.
+pub const DEFAULT_MAIN_FILE: &str = r#"#import "bindings.h"
+
+int main(int argc, char * argv[]) {
+    main_rs();
+
+    return 0;
+}
+"#;
+/// Module map exposing `bindings.h` to Swift as `RustBindings`.
+pub const MODULE_MAP: &str = r#"module RustBindings {
+    header "bindings.h"
+    export *
+}
+"#;
+/// Default Swift `@main` app delegate calling the Rust entry point on launch.
+pub const DEFAULT_SWIFT_APP_FILE: &str = r#"import RustBindings
+import UIKit
+
+@main
+class AppDelegate: UIResponder, UIApplicationDelegate {
+    var window: UIWindow?
+
+    func application(
+        _ application: UIApplication,
+        didFinishLaunchingWithOptions launchOptions: [UIApplication.LaunchOptionsKey: Any]?
+    ) -> Bool {
+        main_rs()
+
+        return true
+    }
+}
+"#;
+
+/// Name of the directory with the generated XCTest sources, next to `src`.
+pub const XCTEST_DIR: &str = "xctest";
+
+/// Objective-C XCTest case calling each of the exported Rust `functions` in a test method.
+pub fn xctest_source(functions: &[String]) -> String {
+    let mut source = String::from("#import <XCTest/XCTest.h>\n#include <stdbool.h>\n\n");
+    for function in functions {
+        source.push_str(&format!("bool {}(void);\n", function));
+    }
+    source.push_str("\n@interface RustTests : XCTestCase\n@end\n\n@implementation RustTests\n");
+    for function in functions {
+        // XCTest only runs methods starting with `test`.
+        let method = if function.starts_with("test") {
+            function.clone()
+        } else {
+            format!("test_{}", function)
+        };
+        source.push_str(&format!(
+            "\n- (void){} {{\n    XCTAssertTrue({}());\n}}\n",
+            method, function
+        ));
+    }
+    source.push_str("\n@end\n");
+    source
+}
+
+/// Contents of the user-provided file at `path` relative to `manifest_dir`, or `default`.
+pub fn read_or_default(
+    manifest_dir: &Path,
+    path: Option<&str>,
+    default: &str,
+) -> anyhow::Result<String> {
+    match path {
+        Some(path) => {
+            let path = manifest_dir.join(path);
+            std::fs::read_to_string(&path).with_context(|| format!("Failed to read {:?}", path))
+        }
+        None => Ok(default.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn xctest_methods() {
+        let source = xctest_source(&["test_levels".to_string(), "physics".to_string()]);
+        assert!(source.contains("bool test_levels(void);\n"));
+        assert!(source.contains("- (void)test_levels {\n    XCTAssertTrue(test_levels());\n}"));
+        assert!(source.contains("- (void)test_physics {\n    XCTAssertTrue(physics());\n}"));
+    }
+}
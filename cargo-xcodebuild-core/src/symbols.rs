@@ -0,0 +1,118 @@
+use anyhow::Context as _;
+use std::path::{Path, PathBuf};
+
+use crate::toml::{SymbolUpload, SymbolUploadPreset};
+
+/// Run every configured symbol upload step for each dSYM bundle. Steps run in `cwd`,
+/// so relative paths in `Cargo.toml` are resolved against the manifest directory.
+pub fn upload_symbols(
+    steps: &[SymbolUpload],
+    dsym_paths: &[PathBuf],
+    cwd: &Path,
+) -> anyhow::Result<()> {
+    if dsym_paths.is_empty() {
+        anyhow::bail!("No dSYM found. Make sure the project is built with `--release`")
+    }
+
+    for step in steps {
+        for dsym_path in dsym_paths {
+            log::info!("Uploading symbols {:?}", dsym_path);
+            let mut command = step.command(dsym_path)?;
+            command.current_dir(cwd);
+            log::trace!("Symbol upload command: {:?}", command);
+            let status = command
+                .status()
+                .with_context(|| format!("Failed to run symbol upload command: {:?}", command))?;
+
+            if !status.success() {
+                anyhow::bail!("Failed to upload symbols {:?}: {}", dsym_path, status)
+            }
+        }
+    }
+
+    Ok(())
+}
+
+impl SymbolUpload {
+    fn command(&self, dsym_path: &Path) -> anyhow::Result<std::process::Command> {
+        let dsym = dsym_path.to_string_lossy();
+        let args = self.args.clone().unwrap_or_default();
+        let command = match (self.preset, &self.command) {
+            (Some(SymbolUploadPreset::Sentry), None) => {
+                let mut command =
+                    std::process::Command::new(self.program.as_deref().unwrap_or("sentry-cli"));
+                command
+                    .arg("debug-files")
+                    .arg("upload")
+                    .args(args)
+                    .arg(&*dsym);
+                command
+            }
+            (Some(SymbolUploadPreset::Crashlytics), None) => {
+                let mut command =
+                    std::process::Command::new(self.program.as_deref().unwrap_or("upload-symbols"));
+                command.arg("-p").arg("ios").args(args).arg(&*dsym);
+                command
+            }
+            (None, Some(template)) => {
+                let mut command = std::process::Command::new("sh");
+                command
+                    .arg("-c")
+                    .arg(template.replace("{dsym}", &crate::shell::quote(&dsym)));
+                command
+            }
+            _ => anyhow::bail!(
+                "Symbol upload step requires either `preset` or `command`: {:?}",
+                self
+            ),
+        };
+
+        Ok(command)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn command_line(command: &std::process::Command) -> Vec<String> {
+        std::iter::once(command.get_program())
+            .chain(command.get_args())
+            .map(|arg| arg.to_string_lossy().into_owned())
+            .collect()
+    }
+
+    #[test]
+    fn commands() {
+        let dsym = Path::new("/tmp/My App.app.dSYM");
+
+        let sentry = SymbolUpload {
+            preset: Some(SymbolUploadPreset::Sentry),
+            args: Some(vec!["--org".into(), "rust".into()]),
+            ..Default::default()
+        };
+        assert_eq!(
+            command_line(&sentry.command(dsym).unwrap()),
+            vec![
+                "sentry-cli",
+                "debug-files",
+                "upload",
+                "--org",
+                "rust",
+                "/tmp/My App.app.dSYM"
+            ]
+        );
+
+        let template = SymbolUpload {
+            command: Some("upload {dsym}".into()),
+            ..Default::default()
+        };
+        assert_eq!(
+            command_line(&template.command(dsym).unwrap()),
+            vec!["sh", "-c", "upload '/tmp/My App.app.dSYM'"]
+        );
+
+        let invalid = SymbolUpload::default();
+        assert!(invalid.command(dsym).is_err());
+    }
+}
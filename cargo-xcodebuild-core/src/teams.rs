@@ -0,0 +1,249 @@
+// Inspired by https://github.com/BrainiumLLC/cargo-mobile/blob/master/src/apple/teams.rs
+
+use anyhow::Context as _;
+use serde::Serialize;
+use std::collections::BTreeSet;
+use x509_parser::prelude::*;
+
+fn get_pem_list(name_substr: &str) -> anyhow::Result<std::process::Output> {
+    let args = ["find-certificate", "-p", "-a", "-c", name_substr];
+    let output = std::process::Command::new("security")
+        .args(args)
+        .output()
+        .with_context(|| format!("Failed to run security utility with args: {:?}", &args))?;
+
+    if !output.status.success() {
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+
+        anyhow::bail!(
+            "Failed to run security utility with args: {:?}.\n{:?}\n{:?}",
+            &args,
+            &stdout,
+            &stderr,
+        )
+    }
+
+    Ok(output)
+}
+
+fn get_pem_list_old_name_scheme() -> anyhow::Result<Vec<u8>> {
+    Ok(get_pem_list("Developer:")
+        .with_context(|| "Failed to get pem list with substring `Developer:`")?
+        .stdout)
+}
+
+fn get_pem_list_new_name_scheme() -> anyhow::Result<Vec<u8>> {
+    Ok(get_pem_list("Development:")
+        .with_context(|| "Failed to get pem list with substring `Development:`")?
+        .stdout)
+}
+
+/// A signing team found in the keychain.
+#[derive(Debug, Eq, Ord, PartialEq, PartialOrd, Serialize)]
+pub struct Team {
+    pub common_name: String,
+    pub organization: String,
+    /// Team id used as `DEVELOPMENT_TEAM`.
+    #[serde(rename = "team_id")]
+    pub organization_unit: String,
+    /// Expiry of the certificate as RFC 2822 date, e.g. `Tue, 1 Jul 2025 10:52:37 +0000`.
+    pub expires: String,
+}
+
+impl Team {
+    #[allow(clippy::unnecessary_unwrap)]
+    pub fn from_pem(pem: &Pem) -> anyhow::Result<Option<Self>> {
+        log::debug!("Creating team from pem cert");
+        if let Ok(cert) = pem.parse_x509() {
+            if !cert.validity().is_valid() {
+                log::debug!("Invalid cert");
+                return Ok(None);
+            }
+
+            log::debug!("Trying to get team info from x509 cert");
+            let subj = cert.subject();
+            let common_names = subj
+                .iter_common_name()
+                .map(|attr| attr.as_str())
+                .collect::<Result<Vec<_>, X509Error>>()
+                .with_context(|| "Failed to collect cert common name".to_string())?;
+            let common_name = common_names.first();
+
+            let organizations = subj
+                .iter_organization()
+                .map(|attr| attr.as_str())
+                .collect::<Result<Vec<_>, X509Error>>()
+                .with_context(|| "Failed to collect cert organization name".to_string())?;
+            let organization = organizations.first();
+
+            let organization_units = subj
+                .iter_organizational_unit()
+                .map(|attr| attr.as_str())
+                .collect::<Result<Vec<_>, X509Error>>()
+                .with_context(|| "Failed to collect cert organization unit".to_string())?;
+
+            let organization_unit = organization_units.first();
+
+            if common_name.is_some() && organization.is_some() && organization_unit.is_some() {
+                log::debug!(
+                    "Found cert {:?} with organization {:?}",
+                    common_name.unwrap(),
+                    organization.unwrap()
+                );
+                Ok(Some(Self {
+                    common_name: common_name.unwrap().to_string(),
+                    organization: organization.unwrap().to_string(),
+                    organization_unit: organization_unit.unwrap().to_string(),
+                    expires: cert.validity().not_after.to_rfc2822(),
+                }))
+            } else {
+                log::debug!("Failed to get team info");
+                Ok(None)
+            }
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+/// Return a sorted list of development teams with valid certificates in the keychain.
+pub fn find_development_teams() -> Vec<Team> {
+    let pems = {
+        let mut pems = vec![];
+
+        if let Ok(new) = get_pem_list_new_name_scheme() {
+            for pem in Pem::iter_from_buffer(&new).flatten() {
+                pems.push(pem);
+            }
+        }
+
+        if let Ok(old) = get_pem_list_old_name_scheme() {
+            for pem in Pem::iter_from_buffer(&old).flatten() {
+                pems.push(pem);
+            }
+        }
+
+        pems
+    };
+
+    pems.into_iter()
+        .flat_map(|cert| Team::from_pem(&cert))
+        .flatten()
+        // Silly way to sort this and ensure no dupes
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect()
+}
+
+/// Select the signing team: the one with `team_id`, or the only team whose organization or
+/// certificate name contains `team_name`. Without either, the first team is used with a warning
+/// if there are several. An unknown `team_id` selects no team, as it is used for
+/// `DEVELOPMENT_TEAM` as is, e.g. with an account signed in to Xcode.
+pub fn select<'a>(
+    teams: &'a [Team],
+    team_id: Option<&str>,
+    team_name: Option<&str>,
+) -> anyhow::Result<Option<&'a Team>> {
+    if let Some(team_id) = team_id {
+        return Ok(teams.iter().find(|team| team.organization_unit == team_id));
+    }
+    let candidates = match team_name {
+        Some(name) => {
+            let name = name.to_lowercase();
+            teams
+                .iter()
+                .filter(|team| {
+                    team.organization.to_lowercase().contains(&name)
+                        || team.common_name.to_lowercase().contains(&name)
+                })
+                .collect::<Vec<_>>()
+        }
+        None => teams.iter().collect(),
+    };
+    // Several certificates of the same team are no ambiguity.
+    let team_ids = candidates
+        .iter()
+        .map(|team| team.organization_unit.as_str())
+        .collect::<BTreeSet<_>>();
+    match team_name {
+        Some(name) if team_ids.is_empty() => anyhow::bail!(
+            "No signing team matches `{}`. Available teams:\n{}",
+            name,
+            list(teams.iter())
+        ),
+        Some(name) if team_ids.len() > 1 => anyhow::bail!(
+            "Several signing teams match `{}`. Select one with `--team <id>` or `development_team`:\n{}",
+            name,
+            list(candidates.iter().copied())
+        ),
+        None if team_ids.len() > 1 => log::warn!(
+            "Several signing teams found, using {}. Select one with `--team <id>`, `development_team` or `development_team_name`:\n{}",
+            candidates[0].organization_unit,
+            list(candidates.iter().copied())
+        ),
+        _ => {}
+    }
+
+    Ok(candidates.first().copied())
+}
+
+fn list<'a>(teams: impl Iterator<Item = &'a Team>) -> String {
+    teams
+        .map(|team| {
+            format!(
+                "  {}  {} ({})",
+                team.organization_unit, team.organization, team.common_name
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn team(common_name: &str, organization: &str, team_id: &str) -> Team {
+        Team {
+            common_name: common_name.into(),
+            organization: organization.into(),
+            organization_unit: team_id.into(),
+            expires: String::new(),
+        }
+    }
+
+    #[test]
+    fn selection() {
+        let teams = vec![
+            team("Apple Development: Alice (1)", "Acme Inc", "ACME"),
+            team("Apple Development: Alice (2)", "Alice Smith", "ALICE"),
+            team("iPhone Developer: Alice (2)", "Alice Smith", "ALICE"),
+        ];
+        let id = |team: Option<&Team>| team.map(|team| team.organization_unit.clone());
+
+        assert_eq!(
+            id(select(&teams, Some("ALICE"), None).unwrap()),
+            Some("ALICE".into())
+        );
+        assert_eq!(id(select(&teams, Some("XCODE"), None).unwrap()), None);
+        assert_eq!(
+            id(select(&teams, None, Some("acme")).unwrap()),
+            Some("ACME".into())
+        );
+        assert_eq!(
+            id(select(&teams, None, Some("smith")).unwrap()),
+            Some("ALICE".into())
+        );
+        assert_eq!(id(select(&teams, None, None).unwrap()), Some("ACME".into()));
+        assert_eq!(id(select(&[], None, None).unwrap()), None);
+
+        let error = select(&teams, None, Some("alice")).unwrap_err().to_string();
+        assert!(error.contains("Several signing teams match `alice`"));
+        assert!(error.contains("  ACME  Acme Inc (Apple Development: Alice (1))"));
+        let error = select(&teams, None, Some("Globex"))
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("No signing team matches `Globex`"));
+    }
+}
@@ -0,0 +1,146 @@
+use anyhow::Context as _;
+use std::path::{Path, PathBuf};
+
+/// Info.plist of the minimal app hosting a test harness on `platform`.
+pub fn info_plist(
+    name: &str,
+    bundle_id: &str,
+    platform: crate::toml::AppPlatform,
+    deployment_target: &str,
+) -> serde_json::Value {
+    let device_family = platform
+        .device_family()
+        .unwrap_or_default()
+        .split(',')
+        .filter_map(|family| family.parse::<u32>().ok())
+        .collect::<Vec<_>>();
+    serde_json::json!({
+        "CFBundleExecutable": name,
+        "CFBundleIdentifier": bundle_id,
+        "CFBundleInfoDictionaryVersion": "6.0",
+        "CFBundleName": name,
+        "CFBundlePackageType": "APPL",
+        "CFBundleShortVersionString": "1.0",
+        "CFBundleVersion": "1",
+        "MinimumOSVersion": deployment_target,
+        "UIDeviceFamily": device_family,
+    })
+}
+
+/// Test harness executables from the `--message-format json` output of `cargo test --no-run`.
+pub fn test_executables(cargo_output: &str) -> Vec<PathBuf> {
+    cargo_output
+        .lines()
+        .filter_map(|line| serde_json::from_str::<serde_json::Value>(line).ok())
+        .filter(|message| {
+            message["reason"] == "compiler-artifact" && message["profile"]["test"] == true
+        })
+        .filter_map(|message| message["executable"].as_str().map(PathBuf::from))
+        .collect()
+}
+
+/// Compile the test harnesses of the crate for `target` and return their paths.
+pub fn build_tests<K, V>(
+    args: &[String],
+    target: &str,
+    envs: &[(K, V)],
+) -> anyhow::Result<Vec<PathBuf>>
+where
+    K: AsRef<std::ffi::OsStr>,
+    V: AsRef<std::ffi::OsStr>,
+{
+    let output = std::process::Command::new("cargo")
+        .arg("test")
+        .arg("--no-run")
+        .arg("--message-format=json-render-diagnostics")
+        .arg("--target")
+        .arg(target)
+        .args(args)
+        .envs(envs.iter().map(|(k, v)| (k, v)))
+        .stderr(std::process::Stdio::inherit())
+        .output()
+        .with_context(|| format!("Failed to run cargo test with args: {:?}", args))?;
+
+    crate::process::check("cargo", output.status)
+        .with_context(|| format!("Failed to build tests for {}", target))?;
+
+    Ok(test_executables(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// Copy `executable` into `<dir>/<name>.app` with an Info.plist, so it can be installed on a
+/// simulator of `platform`. Returns the path of the app.
+pub fn wrap(
+    executable: &Path,
+    dir: &Path,
+    bundle_id: &str,
+    platform: crate::toml::AppPlatform,
+    deployment_target: &str,
+) -> anyhow::Result<PathBuf> {
+    let name = executable
+        .file_name()
+        .and_then(|name| name.to_str())
+        .with_context(|| format!("Invalid test executable {:?}", executable))?;
+    let app_dir = dir.join(format!("{}.app", name));
+    std::fs::create_dir_all(&app_dir)
+        .with_context(|| format!("Failed to create all dir: {:?}", app_dir))?;
+    let binary = app_dir.join(name);
+    std::fs::copy(executable, &binary)
+        .with_context(|| format!("Failed to copy {:?} to {:?}", executable, binary))?;
+    let info_plist_path = app_dir.join("Info.plist");
+    std::fs::write(
+        &info_plist_path,
+        crate::plist::to_xml(&info_plist(name, bundle_id, platform, deployment_target)),
+    )
+    .with_context(|| format!("Failed to write {:?}", info_plist_path))?;
+
+    Ok(app_dir)
+}
+
+/// Run the harness inside the installed host app on the simulator, forwarding its output.
+/// Returns the exit code of the harness.
+pub fn run(udid: &str, executable: &Path, harness_args: &[String]) -> anyhow::Result<i32> {
+    log::info!("Running {:?} on simulator {}", executable, udid);
+    let status = std::process::Command::new("xcrun")
+        .arg("simctl")
+        .arg("spawn")
+        .arg(udid)
+        .arg(executable)
+        .args(harness_args)
+        .status()
+        .with_context(|| "Failed to run xcrun simctl spawn".to_string())?;
+
+    status
+        .code()
+        .with_context(|| format!("Test harness {:?} was terminated by a signal", executable))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn executables_from_cargo_output() {
+        let output = r#"{"reason":"compiler-artifact","profile":{"test":false},"executable":null}
+{"reason":"compiler-artifact","profile":{"test":true},"executable":"/target/aarch64-apple-ios-sim/debug/deps/my_game-1234"}
+{"reason":"build-finished","success":true}"#;
+        assert_eq!(
+            test_executables(output),
+            vec![PathBuf::from(
+                "/target/aarch64-apple-ios-sim/debug/deps/my_game-1234"
+            )]
+        );
+    }
+
+    #[test]
+    fn device_families() {
+        let plist = |platform| info_plist("tests", "com.rust.tests", platform, "15.0");
+        assert_eq!(
+            plist(crate::toml::AppPlatform::Ios)["UIDeviceFamily"],
+            serde_json::json!([1, 2])
+        );
+        assert_eq!(
+            plist(crate::toml::AppPlatform::TvOs)["UIDeviceFamily"],
+            serde_json::json!([3])
+        );
+    }
+}
@@ -0,0 +1,28 @@
+//! Helpers shared by unit tests.
+
+use std::path::{Path, PathBuf};
+
+/// A fresh directory under the system temp dir, removed when dropped, so tests clean up even
+/// when an assertion fails.
+pub struct TempDir(PathBuf);
+
+impl TempDir {
+    /// Create `cargo-xcodebuild-<name>-<pid>`, removing leftovers of an earlier run.
+    pub fn new(name: &str) -> Self {
+        let path =
+            std::env::temp_dir().join(format!("cargo-xcodebuild-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&path);
+        std::fs::create_dir_all(&path).unwrap();
+        Self(path)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
@@ -0,0 +1,1597 @@
+use anyhow::Context as _;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Subset of `Cargo.toml` used by cargo-xcodebuild.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Root {
+    pub package: Package,
+    pub lib: Option<Lib>,
+    pub profile: Option<BTreeMap<String, CargoProfile>>,
+}
+
+impl Root {
+    /// Whether `crate-type` in the `lib` section contains `crate_type`.
+    pub fn has_crate_type(&self, crate_type: &str) -> bool {
+        self.lib
+            .as_ref()
+            .and_then(|lib| lib.crate_type.as_ref())
+            .is_some_and(|types| types.iter().any(|t| t == crate_type))
+    }
+
+    pub fn validate_crate_type(self) -> anyhow::Result<Self> {
+        let link = self
+            .ios_metadata()
+            .unwrap_or_default()
+            .link
+            .unwrap_or_default();
+        if link == Link::Static && !self.has_crate_type("staticlib") {
+            anyhow::bail!(
+                "Missing `staticlib` crate-type in `lib` section. Please check Cargo.toml."
+            )
+        }
+        if link == Link::Dynamic && !self.has_crate_type("cdylib") {
+            anyhow::bail!(
+                "Missing `cdylib` crate-type in `lib` section required by `link = \"dynamic\"`. Please check Cargo.toml."
+            )
+        }
+
+        Ok(self)
+    }
+
+    pub fn validate_export_method(self) -> anyhow::Result<Self> {
+        let ios_metadata = self.ios_metadata()?;
+        let platform = ios_metadata.platform.unwrap_or_default();
+        let export_method = ios_metadata.export_method.unwrap_or_default();
+        if !export_method.supports(platform) {
+            anyhow::bail!(
+                "Export method `{}` is not supported by platform `{}`. Please check Cargo.toml.",
+                export_method.as_str(),
+                platform.name()
+            )
+        }
+
+        Ok(self)
+    }
+
+    pub fn validate_build_targets(self) -> anyhow::Result<Self> {
+        let ios_metadata = self.ios_metadata()?;
+        let targets = ios_metadata.build_targets.unwrap_or_default();
+        if targets.is_empty() {
+            anyhow::bail!("Missing `build_targets` in `package.metadata.ios` section. Please check Cargo.toml.")
+        }
+        let platform = ios_metadata.platform.unwrap_or_default();
+        if let Some(target) = targets
+            .iter()
+            .find(|target| target.is_catalyst() && !ios_metadata.catalyst)
+        {
+            anyhow::bail!(
+                "Build target `{}` requires `catalyst = true`. Please check Cargo.toml.",
+                target.to_rustc_target()
+            )
+        }
+        if let Some(target) = targets.iter().find(|target| target.platform() != platform) {
+            anyhow::bail!(
+                "Build target `{}` doesn't match platform `{}`. Please check Cargo.toml.",
+                target.to_rustc_target(),
+                platform.name()
+            )
+        }
+
+        Ok(self)
+    }
+
+    /// Select a flavor from `[package.metadata.ios.flavors]` and apply its signing overrides.
+    pub fn with_flavor(mut self, name: &str) -> anyhow::Result<Self> {
+        let ios = self
+            .package
+            .metadata
+            .as_mut()
+            .and_then(|metadata| metadata.ios.as_mut())
+            .with_context(|| "Missing `ios` section. Please check Cargo.toml.".to_string())?;
+        let flavor = ios
+            .flavors
+            .as_ref()
+            .and_then(|flavors| flavors.get(name))
+            .cloned()
+            .with_context(|| format!("Unknown flavor `{}`. Please check Cargo.toml.", name))?;
+
+        if flavor.development_team.is_some() {
+            ios.development_team = flavor.development_team.clone();
+        }
+        if flavor.code_sign_identity.is_some() {
+            ios.code_sign_identity = flavor.code_sign_identity.clone();
+        }
+        if flavor.export_method.is_some() {
+            ios.export_method = flavor.export_method;
+        }
+        ios.flavor = Some(flavor);
+
+        Ok(self)
+    }
+
+    /// Sign with the team `team_id`, overriding `development_team`.
+    pub fn with_development_team(mut self, team_id: &str) -> anyhow::Result<Self> {
+        let ios = self
+            .package
+            .metadata
+            .as_mut()
+            .and_then(|metadata| metadata.ios.as_mut())
+            .with_context(|| "Missing `ios` section. Please check Cargo.toml.".to_string())?;
+        ios.development_team = Some(team_id.to_string());
+
+        Ok(self)
+    }
+
+    /// Custom `[profile.<name>]` sections with the built-in profile they inherit from,
+    /// `dev` or `release`.
+    pub fn custom_profiles(&self) -> Vec<(String, String)> {
+        self.profile
+            .iter()
+            .flatten()
+            .filter(|(name, _)| !matches!(name.as_str(), "dev" | "release" | "test" | "bench"))
+            .map(|(name, profile)| {
+                let inherits = match profile.inherits.as_deref() {
+                    Some("dev") | Some("test") => "dev",
+                    _ => "release",
+                };
+                (name.clone(), inherits.to_string())
+            })
+            .collect()
+    }
+
+    pub fn ios_metadata(&self) -> anyhow::Result<IosMetadata> {
+        self.package
+            .metadata
+            .clone()
+            .with_context(|| "Missing `metadata` section. Please check Cargo.toml.".to_string())?
+            .ios
+            .with_context(|| "Missing `ios` section. Please check Cargo.toml.".to_string())
+    }
+}
+
+/// A `[profile.<name>]` section.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct CargoProfile {
+    pub inherits: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Package {
+    pub name: String,
+    pub version: String,
+    pub metadata: Option<PackageMetadata>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct Lib {
+    pub name: Option<String>,
+    #[serde(rename = "crate-type")]
+    pub crate_type: Option<Vec<String>>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct PackageMetadata {
+    pub ios: Option<IosMetadata>,
+}
+
+/// The `[package.metadata.ios]` section.
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct IosMetadata {
+    /// Platform of the app. `ios` by default.
+    pub platform: Option<AppPlatform>,
+    /// Bundle id of the iOS app a watchOS app is a companion of. Standalone without it.
+    pub watch_companion: Option<String>,
+    /// Also build the iOS app for Mac Catalyst.
+    #[serde(default)]
+    pub catalyst: bool,
+    pub build_targets: Option<Vec<Target>>,
+    /// Cargo features enabled for a single build target, e.g. `aarch64-apple-ios-sim = ["simulator"]`.
+    pub target_features: Option<BTreeMap<String, Vec<String>>>,
+    /// Minimum OS version, e.g. `"13.0"`, or one per platform.
+    pub deployment_target: Option<DeploymentTarget>,
+    /// How the Rust library is linked into the app. `static` by default.
+    pub link: Option<Link>,
+    pub dependencies: Option<Vec<SdkDependency>>,
+    /// Prebuilt `.framework` or `.xcframework` bundles linked into the app.
+    pub frameworks: Option<Vec<Framework>>,
+    /// Language of the generated entry point. `objc` by default.
+    pub entry: Option<Entry>,
+    /// `main.m` (or `App.swift` with the Swift entry) replacing the generated one,
+    /// e.g. `ios/main.m`.
+    pub main_source: Option<String>,
+    /// `bindings.h` replacing the generated one that declares `main_rs`.
+    pub binding_header: Option<String>,
+    /// Pods installed with `pod install`. The `.xcworkspace` is built instead of the project.
+    pub cocoapods: Option<CocoaPods>,
+    /// `UISupportedInterfaceOrientations`.
+    pub supported_interface_orientations: Option<Vec<Orientation>>,
+    /// `UISupportedInterfaceOrientations~ipad`. `supported_interface_orientations` by default.
+    pub supported_interface_orientations_ipad: Option<Vec<Orientation>>,
+    pub bundle_id_prefix: Option<String>,
+    pub code_sign_identity: Option<String>,
+    pub development_team: Option<String>,
+    /// Organization or certificate name of the signing team in the keychain, e.g. `Acme Inc`,
+    /// when several teams are available. `development_team` takes precedence.
+    pub development_team_name: Option<String>,
+    pub device_id: Option<String>,
+    pub device_type: Option<DeviceType>,
+    /// Name of the connected device or simulator to use, e.g. `Alice's iPhone`. Matched fuzzily.
+    pub device_name: Option<String>,
+    /// Only use simulators with this runtime version of the app platform, e.g. `17.4`, `17` or
+    /// `tvOS 17.4`.
+    pub simulator_runtime: Option<String>,
+    /// Override the simulator status bar with 9:41, full battery and full signal before `run`.
+    pub clean_status_bar: Option<bool>,
+    /// Privacy services granted to the app on simulators before `run`, e.g. `photos`.
+    pub permissions: Option<Vec<String>>,
+    pub assets: Option<Vec<Asset>>,
+    /// Square source image of at least 1024x1024 pixels. The `AppIcon` icon set is generated from it.
+    pub icon: Option<String>,
+    pub launch_screen: Option<LaunchScreen>,
+    pub app_store_connect: Option<AppStoreConnect>,
+    pub keychain: Option<KeychainConfig>,
+    /// Distribution of the `.ipa` exported by `archive`. `app-store` by default.
+    pub export_method: Option<ExportMethod>,
+    pub fastlane_lane: Option<String>,
+    pub symbol_upload: Option<Vec<SymbolUpload>>,
+    pub screenshots: Option<Screenshots>,
+    /// How `--bump-build-number` computes the next `CFBundleVersion`.
+    pub build_number: Option<BuildNumber>,
+    /// Generator of the Xcode project. `xcodegen` if it is installed, the built-in one otherwise.
+    pub project_generator: Option<ProjectGenerator>,
+    /// xcodegen project spec deep-merged into the generated `project.yml`, e.g.
+    /// `project_overlay.yml`. Requires xcodegen.
+    pub project_overlay: Option<String>,
+    /// Permission prompt texts, e.g. `NSCameraUsageDescription`.
+    pub usage_descriptions: Option<UsageDescriptions>,
+    /// Languages of the app, e.g. `["en", "de"]`. The first one is the development language.
+    pub localizations: Option<Vec<String>>,
+    /// Localized Info.plist texts by language, written to `<language>.lproj/InfoPlist.strings`.
+    pub localized: Option<BTreeMap<String, Localization>>,
+    /// Privacy manifest, written to `PrivacyInfo.xcprivacy`.
+    pub privacy: Option<Privacy>,
+    /// Custom URL schemes, e.g. `mygame` for `mygame://` deep links and OAuth callbacks.
+    pub url_schemes: Option<Vec<String>>,
+    /// Exported `extern "C" fn() -> bool` Rust functions run as cases of a generated XCTest
+    /// bundle target, passing when they return `true`.
+    pub unit_tests: Option<Vec<String>>,
+    /// `CFBundleDocumentTypes` entries.
+    pub document_types: Option<Vec<DocumentType>>,
+    /// `UTExportedTypeDeclarations` entries.
+    pub exported_types: Option<Vec<TypeDeclaration>>,
+    /// `UTImportedTypeDeclarations` entries.
+    pub imported_types: Option<Vec<TypeDeclaration>>,
+    /// `LSSupportsOpeningDocumentsInPlace`.
+    pub supports_opening_documents_in_place: Option<bool>,
+    /// Arbitrary Info.plist entries, e.g. `UIFileSharingEnabled`. They override generated keys.
+    pub info_plist: Option<BTreeMap<String, toml::Value>>,
+    /// Xcode build settings of the app target, e.g. `DEAD_CODE_STRIPPING`. Tables named after a
+    /// configuration, e.g. `[package.metadata.ios.build_settings.release]`, only apply to it.
+    /// They override generated settings.
+    pub build_settings: Option<BTreeMap<String, toml::Value>>,
+    /// Environment variables of the generated scheme, e.g. `RUST_LOG`. They override the
+    /// `RUST_BACKTRACE=full` and `RUST_LOG=info` defaults.
+    pub env: Option<BTreeMap<String, String>>,
+    /// Named flavors, e.g. `staging`, selected with `--flavor`.
+    pub flavors: Option<BTreeMap<String, Flavor>>,
+    /// Flavor selected with [`Root::with_flavor`].
+    #[serde(skip)]
+    pub flavor: Option<Flavor>,
+    pub notarization: Option<Notarization>,
+}
+
+impl IosMetadata {
+    /// `TARGETED_DEVICE_FAMILY` from `build_settings`, or the default of the app platform.
+    pub fn device_family(&self) -> String {
+        let setting = self
+            .build_settings
+            .as_ref()
+            .and_then(|settings| settings.get("TARGETED_DEVICE_FAMILY"));
+        match setting {
+            Some(toml::Value::String(family)) => family.clone(),
+            Some(toml::Value::Integer(family)) => family.to_string(),
+            _ => self
+                .platform
+                .unwrap_or_default()
+                .device_family()
+                .unwrap_or_default()
+                .to_string(),
+        }
+    }
+
+    /// `deployment_target` of the app platform or its default.
+    pub fn deployment_target_or_default(&self) -> String {
+        self.deployment_target_for(self.platform.unwrap_or_default())
+    }
+
+    /// `deployment_target` of `platform` or the default of the platform. Mac Catalyst requires
+    /// iOS 13.1.
+    pub fn deployment_target_for(&self, platform: AppPlatform) -> String {
+        self.deployment_target
+            .as_ref()
+            .and_then(|target| target.get(platform))
+            .map(str::to_string)
+            .unwrap_or_else(|| {
+                if self.catalyst && platform == AppPlatform::Ios {
+                    return "13.1".to_string();
+                }
+                platform.default_deployment_target().to_string()
+            })
+    }
+
+    /// Whether the app is built and run on this Mac, natively or with Mac Catalyst.
+    pub fn runs_on_mac(&self) -> bool {
+        self.catalyst || self.platform == Some(AppPlatform::MacOs)
+    }
+}
+
+/// `deployment_target`: a version for the platform of the app, or a table with a version per
+/// platform, e.g. `{ ios = "15.0", macos = "12.0" }`.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+// `deny_unknown_fields` applies to the `PerPlatform` table, a typo fails instead of falling back
+// to the default.
+#[serde(untagged, deny_unknown_fields)]
+pub enum DeploymentTarget {
+    Version(String),
+    PerPlatform {
+        ios: Option<String>,
+        tvos: Option<String>,
+        watchos: Option<String>,
+        macos: Option<String>,
+    },
+}
+
+impl DeploymentTarget {
+    /// Version for `platform`. A single version applies to any platform.
+    pub fn get(&self, platform: AppPlatform) -> Option<&str> {
+        match *self {
+            DeploymentTarget::Version(ref version) => Some(version),
+            DeploymentTarget::PerPlatform {
+                ref ios,
+                ref tvos,
+                ref watchos,
+                ref macos,
+            } => match platform {
+                AppPlatform::Ios => ios.as_deref(),
+                AppPlatform::TvOs => tvos.as_deref(),
+                AppPlatform::WatchOs => watchos.as_deref(),
+                AppPlatform::MacOs => macos.as_deref(),
+            },
+        }
+    }
+
+    /// Versions of the per-platform table.
+    pub fn per_platform(&self) -> Vec<(AppPlatform, &str)> {
+        match *self {
+            DeploymentTarget::Version(_) => Vec::new(),
+            DeploymentTarget::PerPlatform { .. } => [
+                AppPlatform::Ios,
+                AppPlatform::TvOs,
+                AppPlatform::WatchOs,
+                AppPlatform::MacOs,
+            ]
+            .into_iter()
+            .filter_map(|platform| Some((platform, self.get(platform)?)))
+            .collect(),
+        }
+    }
+
+    /// Check that every version is like `13`, `13.0` or `13.0.1`.
+    pub fn validate(&self) -> anyhow::Result<()> {
+        let versions = match *self {
+            DeploymentTarget::Version(ref version) => vec![version.as_str()],
+            DeploymentTarget::PerPlatform { .. } => self
+                .per_platform()
+                .into_iter()
+                .map(|(_, version)| version)
+                .collect(),
+        };
+        for version in versions {
+            let parts = version.split('.').collect::<Vec<_>>();
+            if parts.len() > 3
+                || parts
+                    .iter()
+                    .any(|part| part.is_empty() || !part.bytes().all(|b| b.is_ascii_digit()))
+            {
+                anyhow::bail!(
+                    "Invalid `deployment_target` `{}`, expected a version like \"13.0\". Please check Cargo.toml.",
+                    version
+                )
+            }
+        }
+        Ok(())
+    }
+}
+
+/// An entry of `assets`: a path copied into bundle resources, or a table with its destination.
+/// Paths may contain glob patterns, e.g. `assets/**/*.png`.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(untagged)]
+pub enum Asset {
+    Path(String),
+    Detailed {
+        path: String,
+        #[serde(default)]
+        destination: AssetDestination,
+        /// Only copy for these platforms. All platforms by default.
+        platforms: Option<Vec<AssetPlatform>>,
+        /// Globs relative to the asset folder left out of the bundle, e.g. `**/*.psd`.
+        #[serde(default)]
+        exclude: Vec<String>,
+    },
+}
+
+impl Asset {
+    pub fn path(&self) -> &str {
+        match *self {
+            Asset::Path(ref path) | Asset::Detailed { ref path, .. } => path,
+        }
+    }
+
+    /// Folder or file of the asset: `path` up to its first component with a glob pattern.
+    pub fn root(&self) -> &str {
+        split_glob(self.path()).0
+    }
+
+    /// Glob pattern of `path` relative to [`Asset::root`], e.g. `**/*.png` for `assets/**/*.png`.
+    pub fn include(&self) -> Option<&str> {
+        split_glob(self.path()).1
+    }
+
+    /// Fails for globs without a folder, e.g. `*.png`, which would scan the whole crate,
+    /// including `target`.
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if self.include().is_some() && self.root() == "." {
+            anyhow::bail!(
+                "Asset glob `{}` must start with a folder, e.g. `assets/{}`. Please check Cargo.toml.",
+                self.path(),
+                self.path().trim_start_matches("./")
+            )
+        }
+        Ok(())
+    }
+
+    pub fn exclude(&self) -> &[String] {
+        match *self {
+            Asset::Path(_) => &[],
+            Asset::Detailed { ref exclude, .. } => exclude,
+        }
+    }
+
+    /// Whether the asset is an `.xcassets` asset catalog, compiled instead of copied.
+    pub fn is_asset_catalog(&self) -> bool {
+        std::path::Path::new(self.path())
+            .extension()
+            .is_some_and(|ext| ext == "xcassets")
+    }
+
+    pub fn destination(&self) -> AssetDestination {
+        match *self {
+            Asset::Path(_) => AssetDestination::default(),
+            Asset::Detailed { destination, .. } => destination,
+        }
+    }
+
+    pub fn platforms(&self) -> &[AssetPlatform] {
+        match *self {
+            Asset::Detailed {
+                platforms: Some(ref platforms),
+                ..
+            } => platforms,
+            _ => &[],
+        }
+    }
+}
+
+/// Split `path` before its first component with a glob pattern.
+fn split_glob(path: &str) -> (&str, Option<&str>) {
+    let mut offset = 0;
+    for component in path.split('/') {
+        if component.contains(['*', '?', '[']) {
+            let root = path[..offset].trim_end_matches('/');
+            let root = if root.is_empty() { "." } else { root };
+            return (root, Some(&path[offset..]));
+        }
+        offset += component.len() + 1;
+    }
+    (path, None)
+}
+
+/// Apple platform the app is built for.
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum AppPlatform {
+    #[default]
+    Ios,
+    TvOs,
+    WatchOs,
+    MacOs,
+}
+
+impl AppPlatform {
+    /// Name used in Xcode destinations and simulator runtimes, e.g. `tvOS`.
+    pub fn name(self) -> &'static str {
+        match self {
+            AppPlatform::Ios => "iOS",
+            AppPlatform::TvOs => "tvOS",
+            AppPlatform::WatchOs => "watchOS",
+            AppPlatform::MacOs => "macOS",
+        }
+    }
+
+    /// SDK name of devices or simulators, e.g. `appletvsimulator`.
+    pub fn sdk(self, simulator: bool) -> &'static str {
+        match (self, simulator) {
+            (AppPlatform::Ios, false) => "iphoneos",
+            (AppPlatform::Ios, true) => "iphonesimulator",
+            (AppPlatform::TvOs, false) => "appletvos",
+            (AppPlatform::TvOs, true) => "appletvsimulator",
+            (AppPlatform::WatchOs, false) => "watchos",
+            (AppPlatform::WatchOs, true) => "watchsimulator",
+            (AppPlatform::MacOs, _) => "macosx",
+        }
+    }
+
+    /// Platform of an `xcodebuild -destination`, e.g. `tvOS Simulator`.
+    pub fn destination(self, simulator: bool) -> String {
+        if simulator && self != AppPlatform::MacOs {
+            format!("{} Simulator", self.name())
+        } else {
+            self.name().to_string()
+        }
+    }
+
+    /// Deployment target if `deployment_target` is not set. Single-target watchOS apps
+    /// require watchOS 7.
+    pub fn default_deployment_target(self) -> &'static str {
+        match self {
+            AppPlatform::Ios | AppPlatform::TvOs => "12",
+            AppPlatform::WatchOs => "7.0",
+            AppPlatform::MacOs => "11.0",
+        }
+    }
+
+    /// Default `TARGETED_DEVICE_FAMILY`: iPhone and iPad for iOS. macOS apps have none.
+    pub fn device_family(self) -> Option<&'static str> {
+        match self {
+            AppPlatform::Ios => Some("1,2"),
+            AppPlatform::TvOs => Some("3"),
+            AppPlatform::WatchOs => Some("4"),
+            AppPlatform::MacOs => None,
+        }
+    }
+
+    /// Environment variable with the deployment target read by rustc.
+    pub fn deployment_target_env(self) -> &'static str {
+        match self {
+            AppPlatform::Ios => "IPHONEOS_DEPLOYMENT_TARGET",
+            AppPlatform::TvOs => "TVOS_DEPLOYMENT_TARGET",
+            AppPlatform::WatchOs => "WATCHOS_DEPLOYMENT_TARGET",
+            AppPlatform::MacOs => "MACOSX_DEPLOYMENT_TARGET",
+        }
+    }
+
+    /// Build target of physical devices.
+    pub fn device_target(self) -> Target {
+        match self {
+            AppPlatform::Ios => Target::Arm,
+            AppPlatform::TvOs => Target::TvOs,
+            AppPlatform::WatchOs => Target::WatchOs,
+            AppPlatform::MacOs => Target::Mac,
+        }
+    }
+
+    /// Whether the platform has a launch storyboard.
+    pub fn has_launch_screen(self) -> bool {
+        matches!(self, AppPlatform::Ios | AppPlatform::TvOs)
+    }
+}
+
+/// WatchKit Info.plist keys of a single-target watchOS app, standalone unless it is the
+/// companion of the iOS app with `companion_bundle_id`.
+pub fn watchkit_plist(companion_bundle_id: Option<&str>) -> serde_json::Value {
+    match companion_bundle_id {
+        Some(bundle_id) => serde_json::json!({
+            "WKApplication": true,
+            "WKCompanionAppBundleIdentifier": bundle_id,
+        }),
+        None => serde_json::json!({
+            "WKApplication": true,
+            "WKWatchOnly": true,
+        }),
+    }
+}
+
+/// Linking of the Rust library.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Link {
+    /// The `staticlib` is linked into the app executable.
+    #[default]
+    Static,
+    /// The `cdylib` is wrapped into a `.framework` embedded into the app. Relinking the app
+    /// after Rust changes is much faster.
+    Dynamic,
+}
+
+/// Entry point calling the Rust `main_rs` function.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Entry {
+    /// `main.m` calling `main_rs` from `main`.
+    #[default]
+    Objc,
+    /// `App.swift` with a `@main` app delegate calling `main_rs` on launch. `bindings.h` is
+    /// imported as the `RustBindings` module.
+    Swift,
+}
+
+/// An entry of `dependencies`: an SDK framework name, or a table with linking options.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(untagged)]
+pub enum SdkDependency {
+    Name(String),
+    Detailed {
+        name: String,
+        /// Link weakly, so the app still launches on OS versions without the framework.
+        #[serde(default)]
+        weak: bool,
+    },
+}
+
+impl SdkDependency {
+    pub fn name(&self) -> &str {
+        match *self {
+            SdkDependency::Name(ref name) | SdkDependency::Detailed { ref name, .. } => name,
+        }
+    }
+
+    pub fn weak(&self) -> bool {
+        match *self {
+            SdkDependency::Name(_) => false,
+            SdkDependency::Detailed { weak, .. } => weak,
+        }
+    }
+}
+
+/// An entry of `frameworks`: a path, or a table with embedding options.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(untagged)]
+pub enum Framework {
+    Path(String),
+    Detailed {
+        path: String,
+        /// Embed into the app bundle. Xcodegen embeds by default; static frameworks should not be.
+        embed: Option<bool>,
+        /// Sign on embedding. `true` by default.
+        code_sign: Option<bool>,
+    },
+}
+
+impl Framework {
+    pub fn path(&self) -> &str {
+        match *self {
+            Framework::Path(ref path) | Framework::Detailed { ref path, .. } => path,
+        }
+    }
+
+    pub fn embed(&self) -> Option<bool> {
+        match *self {
+            Framework::Path(_) => None,
+            Framework::Detailed { embed, .. } => embed,
+        }
+    }
+
+    pub fn code_sign(&self) -> Option<bool> {
+        match *self {
+            Framework::Path(_) => None,
+            Framework::Detailed { code_sign, .. } => code_sign,
+        }
+    }
+}
+
+/// Where an asset is copied inside the app bundle.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum AssetDestination {
+    /// Bundle resources.
+    #[default]
+    Resources,
+    /// Bundle root, next to the executable.
+    Root,
+    Frameworks,
+    Plugins,
+}
+
+impl AssetDestination {
+    /// Destination of an xcodegen `copyFiles` build phase.
+    pub fn to_xcodegen(self) -> &'static str {
+        match self {
+            AssetDestination::Resources => "resources",
+            AssetDestination::Root => "wrapper",
+            AssetDestination::Frameworks => "frameworks",
+            AssetDestination::Plugins => "plugins",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum AssetPlatform {
+    Ios,
+    MacCatalyst,
+    MacOS,
+    TvOS,
+    WatchOS,
+}
+
+impl AssetPlatform {
+    /// Value of xcodegen `destinationFilters`.
+    pub fn to_xcodegen(self) -> &'static str {
+        match self {
+            AssetPlatform::Ios => "iOS",
+            AssetPlatform::MacCatalyst => "macCatalyst",
+            AssetPlatform::MacOS => "macOS",
+            AssetPlatform::TvOS => "tvOS",
+            AssetPlatform::WatchOS => "watchOS",
+        }
+    }
+}
+
+/// A document type the app can open.
+#[derive(Clone, Debug, Deserialize)]
+pub struct DocumentType {
+    pub name: String,
+    /// Uniform type identifiers, e.g. `public.plain-text`.
+    pub content_types: Vec<String>,
+    /// `Editor`, `Viewer`, `Shell` or `None`. `Editor` by default.
+    pub role: Option<String>,
+    /// `Owner`, `Default`, `Alternate` or `None`. `Default` by default.
+    pub handler_rank: Option<String>,
+}
+
+impl DocumentType {
+    /// Entry of `CFBundleDocumentTypes`.
+    pub fn to_plist(&self) -> serde_json::Value {
+        serde_json::json!({
+            "CFBundleTypeName": self.name,
+            "LSItemContentTypes": self.content_types,
+            "CFBundleTypeRole": self.role.as_deref().unwrap_or("Editor"),
+            "LSHandlerRank": self.handler_rank.as_deref().unwrap_or("Default"),
+        })
+    }
+}
+
+/// The `[package.metadata.ios.privacy]` section.
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct Privacy {
+    /// `NSPrivacyTracking`. `false` by default.
+    #[serde(default)]
+    pub tracking: bool,
+    /// `NSPrivacyTrackingDomains`.
+    #[serde(default)]
+    pub tracking_domains: Vec<String>,
+    #[serde(default)]
+    pub collected_data: Vec<CollectedData>,
+    #[serde(default)]
+    pub accessed_apis: Vec<AccessedApi>,
+}
+
+/// A data type collected by the app or its SDKs.
+#[derive(Clone, Debug, Deserialize)]
+pub struct CollectedData {
+    /// e.g. `NSPrivacyCollectedDataTypeCrashData`.
+    #[serde(rename = "type")]
+    pub data_type: String,
+    /// Linked to the user's identity. `false` by default.
+    #[serde(default)]
+    pub linked: bool,
+    /// Used for tracking. `false` by default.
+    #[serde(default)]
+    pub tracking: bool,
+    /// e.g. `NSPrivacyCollectedDataTypePurposeAppFunctionality`.
+    pub purposes: Vec<String>,
+}
+
+/// A required-reason API used by the app.
+#[derive(Clone, Debug, Deserialize)]
+pub struct AccessedApi {
+    /// e.g. `NSPrivacyAccessedAPICategoryUserDefaults`.
+    #[serde(rename = "type")]
+    pub api_type: String,
+    /// Approved reason codes, e.g. `CA92.1`.
+    pub reasons: Vec<String>,
+}
+
+impl Privacy {
+    /// Contents of `PrivacyInfo.xcprivacy`.
+    pub fn to_plist(&self) -> serde_json::Value {
+        let collected_data = self
+            .collected_data
+            .iter()
+            .map(|data| {
+                serde_json::json!({
+                    "NSPrivacyCollectedDataType": data.data_type,
+                    "NSPrivacyCollectedDataTypeLinked": data.linked,
+                    "NSPrivacyCollectedDataTypeTracking": data.tracking,
+                    "NSPrivacyCollectedDataTypePurposes": data.purposes,
+                })
+            })
+            .collect::<Vec<_>>();
+        let accessed_apis = self
+            .accessed_apis
+            .iter()
+            .map(|api| {
+                serde_json::json!({
+                    "NSPrivacyAccessedAPIType": api.api_type,
+                    "NSPrivacyAccessedAPITypeReasons": api.reasons,
+                })
+            })
+            .collect::<Vec<_>>();
+
+        serde_json::json!({
+            "NSPrivacyTracking": self.tracking,
+            "NSPrivacyTrackingDomains": self.tracking_domains,
+            "NSPrivacyCollectedDataTypes": collected_data,
+            "NSPrivacyAccessedAPITypes": accessed_apis,
+        })
+    }
+}
+
+/// A uniform type declared by the app.
+#[derive(Clone, Debug, Deserialize)]
+pub struct TypeDeclaration {
+    /// Uniform type identifier, e.g. `com.example.drawing`.
+    pub identifier: String,
+    pub description: Option<String>,
+    /// `public.data` by default.
+    pub conforms_to: Option<Vec<String>>,
+    pub extensions: Option<Vec<String>>,
+    pub mime_types: Option<Vec<String>>,
+}
+
+impl TypeDeclaration {
+    /// Entry of `UTExportedTypeDeclarations` or `UTImportedTypeDeclarations`.
+    pub fn to_plist(&self) -> serde_json::Value {
+        let mut tags = serde_json::Map::new();
+        if let Some(ref extensions) = self.extensions {
+            tags.insert(
+                "public.filename-extension".into(),
+                serde_json::json!(extensions),
+            );
+        }
+        if let Some(ref mime_types) = self.mime_types {
+            tags.insert("public.mime-type".into(), serde_json::json!(mime_types));
+        }
+        serde_json::json!({
+            "UTTypeIdentifier": self.identifier,
+            "UTTypeDescription": self.description.as_deref().unwrap_or(&self.identifier),
+            "UTTypeConformsTo": self.conforms_to.clone().unwrap_or_else(|| vec!["public.data".into()]),
+            "UTTypeTagSpecification": tags,
+        })
+    }
+}
+
+/// The `[package.metadata.ios.usage_descriptions]` section. Apps crash when they access a
+/// protected API without the matching description.
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct UsageDescriptions {
+    pub camera: Option<String>,
+    pub microphone: Option<String>,
+    pub photo_library: Option<String>,
+    pub photo_library_add: Option<String>,
+    pub location_when_in_use: Option<String>,
+    pub location_always: Option<String>,
+    pub contacts: Option<String>,
+    pub calendars: Option<String>,
+    pub reminders: Option<String>,
+    pub bluetooth: Option<String>,
+    pub motion: Option<String>,
+    pub speech_recognition: Option<String>,
+    pub face_id: Option<String>,
+    pub health_share: Option<String>,
+    pub health_update: Option<String>,
+    pub local_network: Option<String>,
+    pub tracking: Option<String>,
+}
+
+impl UsageDescriptions {
+    /// Info.plist keys and texts of the set descriptions.
+    pub fn to_plist(&self) -> Vec<(&'static str, String)> {
+        [
+            ("NSCameraUsageDescription", &self.camera),
+            ("NSMicrophoneUsageDescription", &self.microphone),
+            ("NSPhotoLibraryUsageDescription", &self.photo_library),
+            ("NSPhotoLibraryAddUsageDescription", &self.photo_library_add),
+            (
+                "NSLocationWhenInUseUsageDescription",
+                &self.location_when_in_use,
+            ),
+            (
+                "NSLocationAlwaysAndWhenInUseUsageDescription",
+                &self.location_always,
+            ),
+            ("NSContactsUsageDescription", &self.contacts),
+            ("NSCalendarsUsageDescription", &self.calendars),
+            ("NSRemindersUsageDescription", &self.reminders),
+            ("NSBluetoothAlwaysUsageDescription", &self.bluetooth),
+            ("NSMotionUsageDescription", &self.motion),
+            (
+                "NSSpeechRecognitionUsageDescription",
+                &self.speech_recognition,
+            ),
+            ("NSFaceIDUsageDescription", &self.face_id),
+            ("NSHealthShareUsageDescription", &self.health_share),
+            ("NSHealthUpdateUsageDescription", &self.health_update),
+            ("NSLocalNetworkUsageDescription", &self.local_network),
+            ("NSUserTrackingUsageDescription", &self.tracking),
+        ]
+        .iter()
+        .filter_map(|&(key, value)| value.clone().map(|value| (key, value)))
+        .collect()
+    }
+}
+
+/// Localized Info.plist texts of a language in `[package.metadata.ios.localized]`.
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct Localization {
+    /// `CFBundleDisplayName`.
+    pub display_name: Option<String>,
+    pub usage_descriptions: Option<UsageDescriptions>,
+}
+
+impl Localization {
+    /// Info.plist keys and localized texts.
+    pub fn to_strings(&self) -> Vec<(&'static str, String)> {
+        let mut strings = Vec::new();
+        if let Some(ref display_name) = self.display_name {
+            strings.push(("CFBundleDisplayName", display_name.clone()));
+        }
+        if let Some(ref usage_descriptions) = self.usage_descriptions {
+            strings.extend(usage_descriptions.to_plist());
+        }
+        strings
+    }
+}
+
+/// The `[package.metadata.ios.launch_screen]` section.
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct LaunchScreen {
+    /// `#RRGGBB` or `#RRGGBBAA`. White by default.
+    pub background_color: Option<String>,
+    /// Image shown in the center of the screen, the @3x variant.
+    pub image: Option<String>,
+}
+
+/// Overrides of a build flavor, e.g. `staging`.
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct Flavor {
+    /// Appended to the bundle id, e.g. `.staging`, so flavors install side by side.
+    pub bundle_id_suffix: Option<String>,
+    /// `CFBundleDisplayName`.
+    pub display_name: Option<String>,
+    /// App icon set of the asset catalog, e.g. a badged `AppIcon-Staging`.
+    pub app_icon: Option<String>,
+    /// Environment of cargo builds, e.g. API endpoints read with `env!`.
+    pub env: Option<BTreeMap<String, String>>,
+    pub development_team: Option<String>,
+    pub code_sign_identity: Option<String>,
+    pub export_method: Option<ExportMethod>,
+}
+
+/// `method` of `ExportOptions.plist`.
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum ExportMethod {
+    /// Development-signed, for registered devices.
+    Development,
+    /// Distribution-signed, for devices in the provisioning profile.
+    AdHoc,
+    /// For App Store Connect and TestFlight.
+    #[default]
+    AppStore,
+    /// In-house distribution of the Apple Developer Enterprise Program.
+    Enterprise,
+    /// macOS apps distributed outside the Mac App Store, signed with a Developer ID certificate
+    /// and notarized.
+    DeveloperId,
+    /// macOS apps for the Mac App Store.
+    MacApplication,
+}
+
+impl ExportMethod {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Development => "development",
+            Self::AdHoc => "ad-hoc",
+            Self::AppStore => "app-store",
+            Self::Enterprise => "enterprise",
+            Self::DeveloperId => "developer-id",
+            Self::MacApplication => "mac-application",
+        }
+    }
+
+    /// Certificate type automatic signing selects for the export.
+    pub fn signing_certificate(self) -> &'static str {
+        match self {
+            Self::Development => "Apple Development",
+            Self::AdHoc | Self::AppStore | Self::Enterprise | Self::MacApplication => {
+                "Apple Distribution"
+            }
+            Self::DeveloperId => "Developer ID Application",
+        }
+    }
+
+    /// Whether archives of `platform` can be exported with this method. Ad hoc and enterprise
+    /// distribution are for devices, Developer ID and Mac App Store exports for macOS.
+    pub fn supports(self, platform: AppPlatform) -> bool {
+        match self {
+            Self::Development | Self::AppStore => true,
+            Self::AdHoc | Self::Enterprise => platform != AppPlatform::MacOs,
+            Self::DeveloperId | Self::MacApplication => platform == AppPlatform::MacOs,
+        }
+    }
+}
+
+/// Source of automatically incremented build numbers.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum BuildNumber {
+    /// Counter persisted in `.build-number` next to `Cargo.toml`.
+    #[default]
+    Counter,
+    /// Number of git commits.
+    Git,
+}
+
+/// Generator of `.xcodeproj` from the project spec.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ProjectGenerator {
+    /// The `xcodegen` binary.
+    Xcodegen,
+    /// Built-in generator, see [`crate::pbxproj`].
+    Native,
+}
+
+/// The `[package.metadata.ios.cocoapods]` section.
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct CocoaPods {
+    /// Pod names and version requirements, e.g. `"~> 10.0"`. `"*"` for any version.
+    #[serde(default)]
+    pub pods: BTreeMap<String, String>,
+    /// Add `use_frameworks!` to the Podfile.
+    #[serde(default)]
+    pub use_frameworks: bool,
+}
+
+/// Notarization of macOS and Mac Catalyst apps.
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct Notarization {
+    /// Profile stored with `xcrun notarytool store-credentials`. The App Store Connect API key
+    /// is used if not set.
+    pub keychain_profile: Option<String>,
+    /// Pack the app into a `.dmg` before notarization.
+    pub dmg: Option<bool>,
+}
+
+/// App Store screenshots settings.
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct Screenshots {
+    /// Simulator device types, e.g. `iPhone 15 Pro Max`.
+    pub devices: Option<Vec<String>>,
+    /// Screens to capture. Each one is passed to the app as `<launch_argument> <screen>`.
+    pub screens: Option<Vec<String>>,
+    pub launch_argument: Option<String>,
+    /// Seconds to wait after launch before taking a screenshot.
+    pub delay: Option<u64>,
+}
+
+/// A step uploading dSYMs to a crash reporting service.
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct SymbolUpload {
+    pub preset: Option<SymbolUploadPreset>,
+    /// Executable used by the preset, e.g. the path to Crashlytics `upload-symbols`.
+    pub program: Option<String>,
+    /// Extra arguments passed to the preset before the dSYM path.
+    pub args: Option<Vec<String>>,
+    /// Shell command template. `{dsym}` is replaced with the dSYM path.
+    pub command: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum SymbolUploadPreset {
+    Sentry,
+    Crashlytics,
+}
+
+/// App Store Connect API key for signing on CI. See [`crate::signing::AuthenticationKey`].
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct AppStoreConnect {
+    pub key_path: Option<String>,
+    pub key_id: Option<String>,
+    pub issuer_id: Option<String>,
+}
+
+/// Keychain with the signing certificates on CI. See [`crate::signing::Keychain`]. Its password
+/// is only read from the environment, `password` is rejected to keep it out of `Cargo.toml`.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct KeychainConfig {
+    pub path: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum DeviceType {
+    Device,
+    Simulator,
+    /// This Mac, for Mac Catalyst apps.
+    Mac,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, Eq, PartialEq)]
+pub enum Orientation {
+    #[serde(rename = "UIInterfaceOrientationUnknown")]
+    Unknown,
+    #[serde(rename = "UIInterfaceOrientationPortrait", alias = "portrait")]
+    Portrait,
+    #[serde(
+        rename = "UIInterfaceOrientationPortraitUpsideDown",
+        alias = "portrait-upside-down"
+    )]
+    PortraitUpsideDown,
+    #[serde(
+        rename = "UIInterfaceOrientationLandscapeLeft",
+        alias = "landscape-left"
+    )]
+    LandscapeLeft,
+    #[serde(
+        rename = "UIInterfaceOrientationLandscapeRight",
+        alias = "landscape-right"
+    )]
+    LandscapeRight,
+}
+
+impl Orientation {
+    /// All orientations an iPad app needs for Split View and Slide Over.
+    pub const ALL: [Orientation; 4] = [
+        Orientation::Portrait,
+        Orientation::PortraitUpsideDown,
+        Orientation::LandscapeLeft,
+        Orientation::LandscapeRight,
+    ];
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq)]
+pub enum Target {
+    #[serde(rename = "aarch64-apple-ios")]
+    Arm,
+    #[serde(rename = "aarch64-apple-ios-sim")]
+    ArmSim,
+    #[serde(rename = "x86_64-apple-ios")]
+    Sim,
+    #[serde(rename = "aarch64-apple-tvos")]
+    TvOs,
+    #[serde(rename = "aarch64-apple-tvos-sim")]
+    TvOsSim,
+    #[serde(rename = "arm64_32-apple-watchos")]
+    WatchOs,
+    #[serde(rename = "aarch64-apple-watchos-sim")]
+    WatchOsSim,
+    #[serde(rename = "aarch64-apple-darwin")]
+    Mac,
+    #[serde(rename = "x86_64-apple-darwin")]
+    MacIntel,
+    #[serde(rename = "aarch64-apple-ios-macabi")]
+    Catalyst,
+    #[serde(rename = "x86_64-apple-ios-macabi")]
+    CatalystIntel,
+}
+
+impl Target {
+    pub fn to_rustc_target(self) -> &'static str {
+        match self {
+            Target::Arm => "aarch64-apple-ios",
+            Target::ArmSim => "aarch64-apple-ios-sim",
+            Target::Sim => "x86_64-apple-ios",
+            Target::TvOs => "aarch64-apple-tvos",
+            Target::TvOsSim => "aarch64-apple-tvos-sim",
+            Target::WatchOs => "arm64_32-apple-watchos",
+            Target::WatchOsSim => "aarch64-apple-watchos-sim",
+            Target::Mac => "aarch64-apple-darwin",
+            Target::MacIntel => "x86_64-apple-darwin",
+            Target::Catalyst => "aarch64-apple-ios-macabi",
+            Target::CatalystIntel => "x86_64-apple-ios-macabi",
+        }
+    }
+
+    pub fn platform(self) -> AppPlatform {
+        match self {
+            Target::Arm
+            | Target::ArmSim
+            | Target::Sim
+            | Target::Catalyst
+            | Target::CatalystIntel => AppPlatform::Ios,
+            Target::TvOs | Target::TvOsSim => AppPlatform::TvOs,
+            Target::WatchOs | Target::WatchOsSim => AppPlatform::WatchOs,
+            Target::Mac | Target::MacIntel => AppPlatform::MacOs,
+        }
+    }
+
+    pub fn is_simulator(self) -> bool {
+        matches!(
+            self,
+            Target::ArmSim | Target::Sim | Target::TvOsSim | Target::WatchOsSim
+        )
+    }
+
+    pub fn is_catalyst(self) -> bool {
+        matches!(self, Target::Catalyst | Target::CatalystIntel)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_toml() {
+        let contents = r#"[package]
+        name = "xcodebuild_examples"
+        version = "0.1.0"
+        edition = "2021"
+        license = "MIT OR Apache-2.0"
+
+        [lib]
+        crate-type = ["staticlib", "cdylib"]
+
+        [dependencies]
+
+
+        [package.metadata.ios]
+        build_targets = [ "aarch64-apple-ios", "aarch64-apple-ios-sim"]
+        "#;
+        let toml: Root = toml::from_str(contents).unwrap();
+        let ios_metadata = toml
+            .package
+            .metadata
+            .unwrap_or_default()
+            .ios
+            .unwrap_or_default();
+
+        assert_eq!(
+            toml.lib.unwrap_or_default().crate_type.unwrap_or_default(),
+            vec!["staticlib", "cdylib"]
+        );
+        assert_eq!(
+            ios_metadata.build_targets.unwrap_or_default(),
+            vec![Target::Arm, Target::ArmSim]
+        );
+    }
+
+    #[test]
+    fn assets() {
+        let contents = r#"[package]
+        name = "xcodebuild_examples"
+        version = "0.1.0"
+
+        [package.metadata.ios]
+        assets = [
+            "assets/",
+            { path = "GoogleService-Info.plist", destination = "root", platforms = ["ios"] },
+        ]
+        "#;
+        let toml: Root = toml::from_str(contents).unwrap();
+        let assets = toml.ios_metadata().unwrap().assets.unwrap();
+
+        assert_eq!(assets[0], Asset::Path("assets/".into()));
+        assert_eq!(assets[0].destination(), AssetDestination::Resources);
+        assert_eq!(assets[1].path(), "GoogleService-Info.plist");
+        assert_eq!(assets[1].destination().to_xcodegen(), "wrapper");
+        assert_eq!(assets[1].platforms(), &[AssetPlatform::Ios]);
+    }
+
+    #[test]
+    fn flavors() {
+        let contents = r#"[package]
+        name = "xcodebuild_examples"
+        version = "0.1.0"
+
+        [package.metadata.ios]
+        development_team = "PROD"
+
+        [package.metadata.ios.flavors.staging]
+        bundle_id_suffix = ".staging"
+        development_team = "STAGING"
+        export_method = "ad-hoc"
+        env = { API_URL = "https://staging.example.com" }
+        "#;
+        let toml: Root = toml::from_str(contents).unwrap();
+        assert!(toml.clone().with_flavor("prod").is_err());
+
+        let ios_metadata = toml.with_flavor("staging").unwrap().ios_metadata().unwrap();
+        let flavor = ios_metadata.flavor.unwrap();
+        assert_eq!(ios_metadata.development_team.as_deref(), Some("STAGING"));
+        assert_eq!(ios_metadata.export_method, Some(ExportMethod::AdHoc));
+        assert!(ExportMethod::AdHoc.supports(AppPlatform::Ios));
+        assert!(!ExportMethod::AdHoc.supports(AppPlatform::MacOs));
+        assert!(ExportMethod::DeveloperId.supports(AppPlatform::MacOs));
+        assert!(!ExportMethod::MacApplication.supports(AppPlatform::TvOs));
+        assert_eq!(flavor.bundle_id_suffix.as_deref(), Some(".staging"));
+        assert_eq!(
+            flavor.env.unwrap()["API_URL"],
+            "https://staging.example.com"
+        );
+    }
+
+    #[test]
+    fn usage_descriptions() {
+        let contents = r#"[package]
+        name = "xcodebuild_examples"
+        version = "0.1.0"
+
+        [package.metadata.ios.usage_descriptions]
+        camera = "Scan QR codes"
+        location_when_in_use = "Show nearby players"
+        "#;
+        let toml: Root = toml::from_str(contents).unwrap();
+        let usage_descriptions = toml.ios_metadata().unwrap().usage_descriptions.unwrap();
+
+        assert_eq!(
+            usage_descriptions.to_plist(),
+            vec![
+                ("NSCameraUsageDescription", "Scan QR codes".to_string()),
+                (
+                    "NSLocationWhenInUseUsageDescription",
+                    "Show nearby players".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn custom_profiles() {
+        let contents = r#"[package]
+        name = "xcodebuild_examples"
+        version = "0.1.0"
+
+        [profile.release]
+        lto = true
+
+        [profile.profiling]
+        inherits = "release"
+        debug = true
+
+        [profile.fast-dev]
+        inherits = "dev"
+        "#;
+        let toml: Root = toml::from_str(contents).unwrap();
+        assert_eq!(
+            toml.custom_profiles(),
+            [
+                ("fast-dev".to_string(), "dev".to_string()),
+                ("profiling".to_string(), "release".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn weak_dependencies() {
+        let contents = r#"[package]
+        name = "xcodebuild_examples"
+        version = "0.1.0"
+
+        [package.metadata.ios]
+        dependencies = ["UIKit.framework", { name = "ActivityKit.framework", weak = true }]
+        "#;
+        let toml: Root = toml::from_str(contents).unwrap();
+        let dependencies = toml.ios_metadata().unwrap().dependencies.unwrap();
+
+        assert_eq!(dependencies[0].name(), "UIKit.framework");
+        assert!(!dependencies[0].weak());
+        assert_eq!(dependencies[1].name(), "ActivityKit.framework");
+        assert!(dependencies[1].weak());
+    }
+
+    #[test]
+    fn localizations() {
+        let contents = r#"[package]
+        name = "xcodebuild_examples"
+        version = "0.1.0"
+
+        [package.metadata.ios]
+        localizations = ["en", "de"]
+
+        [package.metadata.ios.localized.de]
+        display_name = "Mein Spiel"
+        usage_descriptions = { camera = "Fotos aufnehmen" }
+        "#;
+        let toml: Root = toml::from_str(contents).unwrap();
+        let ios_metadata = toml.ios_metadata().unwrap();
+
+        assert_eq!(ios_metadata.localizations.unwrap(), ["en", "de"]);
+        assert_eq!(
+            ios_metadata.localized.unwrap()["de"].to_strings(),
+            [
+                ("CFBundleDisplayName", "Mein Spiel".to_string()),
+                ("NSCameraUsageDescription", "Fotos aufnehmen".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn privacy() {
+        let contents = r#"[package]
+        name = "xcodebuild_examples"
+        version = "0.1.0"
+
+        [[package.metadata.ios.privacy.collected_data]]
+        type = "NSPrivacyCollectedDataTypeCrashData"
+        purposes = ["NSPrivacyCollectedDataTypePurposeAppFunctionality"]
+
+        [[package.metadata.ios.privacy.accessed_apis]]
+        type = "NSPrivacyAccessedAPICategoryUserDefaults"
+        reasons = ["CA92.1"]
+        "#;
+        let toml: Root = toml::from_str(contents).unwrap();
+        let plist = toml.ios_metadata().unwrap().privacy.unwrap().to_plist();
+
+        assert_eq!(plist["NSPrivacyTracking"], false);
+        assert_eq!(
+            plist["NSPrivacyCollectedDataTypes"][0]["NSPrivacyCollectedDataType"],
+            "NSPrivacyCollectedDataTypeCrashData"
+        );
+        assert_eq!(
+            plist["NSPrivacyCollectedDataTypes"][0]["NSPrivacyCollectedDataTypeLinked"],
+            false
+        );
+        assert_eq!(
+            plist["NSPrivacyAccessedAPITypes"][0]["NSPrivacyAccessedAPITypeReasons"][0],
+            "CA92.1"
+        );
+    }
+
+    #[test]
+    fn document_types() {
+        let contents = r#"[package]
+        name = "xcodebuild_examples"
+        version = "0.1.0"
+
+        [[package.metadata.ios.document_types]]
+        name = "Drawing"
+        content_types = ["com.example.drawing"]
+
+        [[package.metadata.ios.exported_types]]
+        identifier = "com.example.drawing"
+        description = "Drawing"
+        extensions = ["drawing"]
+        "#;
+        let toml: Root = toml::from_str(contents).unwrap();
+        let ios_metadata = toml.ios_metadata().unwrap();
+
+        assert_eq!(
+            ios_metadata.document_types.unwrap()[0].to_plist(),
+            serde_json::json!({
+                "CFBundleTypeName": "Drawing",
+                "LSItemContentTypes": ["com.example.drawing"],
+                "CFBundleTypeRole": "Editor",
+                "LSHandlerRank": "Default",
+            })
+        );
+        assert_eq!(
+            ios_metadata.exported_types.unwrap()[0].to_plist(),
+            serde_json::json!({
+                "UTTypeIdentifier": "com.example.drawing",
+                "UTTypeDescription": "Drawing",
+                "UTTypeConformsTo": ["public.data"],
+                "UTTypeTagSpecification": { "public.filename-extension": ["drawing"] },
+            })
+        );
+    }
+
+    #[test]
+    fn tvos_platform() {
+        let contents = r#"[package]
+        name = "xcodebuild_examples"
+        version = "0.1.0"
+
+        [package.metadata.ios]
+        platform = "tvos"
+        build_targets = ["aarch64-apple-tvos", "aarch64-apple-tvos-sim"]
+        "#;
+        let toml: Root = toml::from_str(contents).unwrap();
+        let ios_metadata = toml.ios_metadata().unwrap();
+        let platform = ios_metadata.platform.unwrap_or_default();
+
+        assert_eq!(platform, AppPlatform::TvOs);
+        assert_eq!(platform.sdk(true), "appletvsimulator");
+        assert_eq!(platform.destination(true), "tvOS Simulator");
+        assert!(toml.validate_build_targets().is_ok());
+
+        let contents = contents.replace("aarch64-apple-tvos-sim", "aarch64-apple-ios-sim");
+        let toml: Root = toml::from_str(&contents).unwrap();
+        assert!(toml.validate_build_targets().is_err());
+    }
+
+    #[test]
+    fn watchkit_keys() {
+        assert_eq!(watchkit_plist(None)["WKWatchOnly"], true);
+        let companion = watchkit_plist(Some("com.rust.my-game"));
+        assert_eq!(
+            companion["WKCompanionAppBundleIdentifier"],
+            "com.rust.my-game"
+        );
+        assert!(companion.get("WKWatchOnly").is_none());
+        assert_eq!(Target::WatchOsSim.platform(), AppPlatform::WatchOs);
+        assert!(Target::WatchOsSim.is_simulator());
+    }
+
+    #[test]
+    fn mac_platforms() {
+        let contents = r#"[package]
+        name = "xcodebuild_examples"
+        version = "0.1.0"
+
+        [package.metadata.ios]
+        build_targets = ["aarch64-apple-ios", "aarch64-apple-ios-macabi"]
+        "#;
+        let toml: Root = toml::from_str(contents).unwrap();
+        assert!(!toml.ios_metadata().unwrap().runs_on_mac());
+        assert!(toml.validate_build_targets().is_err());
+
+        let contents = contents.replace(
+            "[package.metadata.ios]",
+            "[package.metadata.ios]\ncatalyst = true",
+        );
+        let toml: Root = toml::from_str(&contents).unwrap();
+        let ios_metadata = toml.ios_metadata().unwrap();
+        assert!(ios_metadata.runs_on_mac());
+        assert_eq!(ios_metadata.deployment_target_or_default(), "13.1");
+        assert!(toml.validate_build_targets().is_ok());
+
+        assert_eq!(AppPlatform::MacOs.destination(true), "macOS");
+        assert_eq!(Target::MacIntel.platform(), AppPlatform::MacOs);
+    }
+
+    #[test]
+    fn deployment_target() {
+        let contents = r#"[package]
+        name = "xcodebuild_examples"
+        version = "0.1.0"
+
+        [package.metadata.ios]
+        platform = "tvos"
+        deployment_target = { ios = "15.0", tvos = "16.1" }
+        "#;
+        let toml: Root = toml::from_str(contents).unwrap();
+        let ios_metadata = toml.ios_metadata().unwrap();
+        let deployment_target = ios_metadata.deployment_target.clone().unwrap();
+        assert_eq!(ios_metadata.deployment_target_or_default(), "16.1");
+        assert_eq!(
+            ios_metadata.deployment_target_for(AppPlatform::MacOs),
+            "11.0"
+        );
+        assert_eq!(
+            deployment_target.per_platform(),
+            vec![(AppPlatform::Ios, "15.0"), (AppPlatform::TvOs, "16.1")]
+        );
+        assert!(deployment_target.validate().is_ok());
+
+        for invalid in ["15.", "iOS 15", "15.0.1.2"] {
+            let target = DeploymentTarget::Version(invalid.to_string());
+            assert!(target.validate().is_err(), "{}", invalid);
+        }
+
+        let typo = r#"[package]
+        name = "xcodebuild_examples"
+        version = "0.1.0"
+
+        [package.metadata.ios]
+        deployment_target = { ioss = "15.0" }
+        "#;
+        assert!(toml::from_str::<Root>(typo).is_err());
+    }
+}
@@ -0,0 +1,52 @@
+use anyhow::Context as _;
+use std::path::{Path, PathBuf};
+
+/// Upload an `.ipa` to App Store Connect with `xcrun altool`.
+/// The build shows up in TestFlight once App Store Connect has processed it.
+pub fn upload_ipa(ipa_path: &Path, key: &crate::signing::AuthenticationKey) -> anyhow::Result<()> {
+    log::info!("Uploading {:?} to App Store Connect", ipa_path);
+    let output = std::process::Command::new("xcrun")
+        .arg("altool")
+        .arg("--upload-app")
+        .arg("--type")
+        .arg("ios")
+        .arg("--file")
+        .arg(ipa_path)
+        .arg("--apiKey")
+        .arg(&key.id)
+        .arg("--apiIssuer")
+        .arg(&key.issuer_id)
+        .env("API_PRIVATE_KEYS_DIR", private_keys_dir(key)?)
+        .output()
+        .with_context(|| "Failed to get xcrun altool output".to_string())?;
+
+    if !output.status.success() {
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+
+        anyhow::bail!(
+            "Failed to upload {:?}:\n{:?}\n{:?}",
+            ipa_path,
+            stdout,
+            stderr
+        )
+    }
+
+    Ok(())
+}
+
+/// altool only finds keys named `AuthKey_<key id>.p8`. Keys with other names are copied to a
+/// temporary directory under that name.
+fn private_keys_dir(key: &crate::signing::AuthenticationKey) -> anyhow::Result<PathBuf> {
+    let file_name = format!("AuthKey_{}.p8", key.id);
+    if key.path.file_name().and_then(|name| name.to_str()) == Some(file_name.as_str()) {
+        return Ok(key.path.parent().unwrap().to_path_buf());
+    }
+
+    let dir = std::env::temp_dir().join("cargo-xcodebuild-private-keys");
+    std::fs::create_dir_all(&dir)
+        .with_context(|| format!("Failed to create all dir: {:?}", dir))?;
+    std::fs::copy(&key.path, dir.join(&file_name))
+        .with_context(|| format!("Failed to copy {:?} to {:?}", key.path, dir))?;
+    Ok(dir)
+}
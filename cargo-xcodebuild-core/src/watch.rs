@@ -0,0 +1,258 @@
+use anyhow::Context as _;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+/// How often watched paths are scanned for changes.
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Polls files under a set of paths and reports the ones that were added, removed or modified.
+///
+/// Polling instead of FSEvents (e.g. the `notify` crate) keeps the dependency tree small and
+/// sees changes on network and VM shared folders, which FSEvents misses. The cost is up to
+/// `POLL_INTERVAL` of latency and a scan of the watched folders per tick, which is small next to
+/// a rebuild for the `src` and `assets` folders of a game.
+#[derive(Debug)]
+pub struct Watcher {
+    paths: Vec<PathBuf>,
+    files: BTreeMap<PathBuf, SystemTime>,
+}
+
+impl Watcher {
+    /// Start watching `paths`, files or directories. Missing paths are watched for creation.
+    pub fn new(paths: Vec<PathBuf>) -> Self {
+        let files = scan(&paths);
+        Self { paths, files }
+    }
+
+    /// Files changed since the last call, sorted.
+    pub fn changes(&mut self) -> Vec<PathBuf> {
+        let files = scan(&self.paths);
+        let mut changed = files
+            .iter()
+            .filter(|(path, modified)| self.files.get(*path) != Some(modified))
+            .map(|(path, _)| path.clone())
+            .collect::<Vec<_>>();
+        changed.extend(
+            self.files
+                .keys()
+                .filter(|path| !files.contains_key(*path))
+                .cloned(),
+        );
+        changed.sort();
+        self.files = files;
+        changed
+    }
+
+    /// Block until files change, then wait for writes to settle and return all changed files.
+    pub fn wait(&mut self) -> Vec<PathBuf> {
+        loop {
+            std::thread::sleep(POLL_INTERVAL);
+            let mut changed = self.changes();
+            if changed.is_empty() {
+                continue;
+            }
+            // Editors and asset exporters often write several files in a row.
+            loop {
+                std::thread::sleep(POLL_INTERVAL);
+                let more = self.changes();
+                if more.is_empty() {
+                    break;
+                }
+                changed.extend(more);
+            }
+            changed.sort();
+            changed.dedup();
+            return changed;
+        }
+    }
+}
+
+/// Paths relative to the app bundle where `file` from the `asset` path may have been copied to:
+/// inside a folder reference, keeping the folder structure, or flattened into a group.
+pub fn bundle_candidates(asset: &Path, file: &Path) -> Vec<PathBuf> {
+    let relative = match file.strip_prefix(asset) {
+        Ok(relative) => relative,
+        Err(_) => return vec![],
+    };
+    let mut candidates = vec![];
+    if relative.as_os_str().is_empty() {
+        // The asset is a single file.
+        candidates.extend(file.file_name().map(PathBuf::from));
+        return candidates;
+    }
+    if let Some(dir_name) = asset.file_name() {
+        candidates.push(Path::new(dir_name).join(relative));
+    }
+    candidates.push(relative.to_path_buf());
+    if let Some(file_name) = file.file_name() {
+        candidates.push(PathBuf::from(file_name));
+    }
+    candidates.dedup();
+    candidates
+}
+
+/// Paths relative to the app bundle at `app_path` of the `changed` files of `assets` copied into
+/// it as is. `None` if an app build is required instead: for added or removed files, changes
+/// outside these assets and compiled resources like asset catalogs.
+pub fn hot_sync_files(
+    assets: &[crate::toml::Asset],
+    manifest_dir: &Path,
+    app_path: &Path,
+    changed: &[PathBuf],
+) -> Option<Vec<(PathBuf, PathBuf)>> {
+    let roots = assets
+        .iter()
+        .filter(|asset| {
+            matches!(
+                asset.destination(),
+                crate::toml::AssetDestination::Resources | crate::toml::AssetDestination::Root
+            )
+        })
+        .map(|asset| manifest_dir.join(asset.root()))
+        .collect::<Vec<_>>();
+
+    let mut files = vec![];
+    for file in changed {
+        if !file.is_file() {
+            return None;
+        }
+        let bundle_path = roots
+            .iter()
+            .flat_map(|root| bundle_candidates(root, file))
+            .find(|candidate| app_path.join(candidate).is_file())?;
+        files.push((file.clone(), bundle_path));
+    }
+    Some(files)
+}
+
+/// Copy `files` from [`hot_sync_files`] into the app bundle at `bundle`.
+pub fn copy_to_bundle(files: &[(PathBuf, PathBuf)], bundle: &Path) -> anyhow::Result<()> {
+    for (file, bundle_path) in files {
+        log::info!("Syncing {:?} to {:?}", file, bundle_path);
+        std::fs::copy(file, bundle.join(bundle_path))
+            .with_context(|| format!("Failed to copy {:?}", file))?;
+    }
+    Ok(())
+}
+
+/// Modification times of all files under `paths`, skipping hidden files like `.DS_Store`.
+pub(crate) fn scan(paths: &[PathBuf]) -> BTreeMap<PathBuf, SystemTime> {
+    let mut files = BTreeMap::new();
+    for path in paths {
+        scan_path(path, &mut files);
+    }
+    files
+}
+
+fn scan_path(path: &Path, files: &mut BTreeMap<PathBuf, SystemTime>) {
+    let metadata = match path.metadata() {
+        Ok(metadata) => metadata,
+        Err(_) => return,
+    };
+    if metadata.is_dir() {
+        let entries = match std::fs::read_dir(path) {
+            Ok(entries) => entries,
+            Err(_) => return,
+        };
+        for entry in entries.flatten() {
+            if !entry.file_name().to_string_lossy().starts_with('.') {
+                scan_path(&entry.path(), files);
+            }
+        }
+    } else if let Ok(modified) = metadata.modified() {
+        files.insert(path.to_path_buf(), modified);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn changes() {
+        let temp = crate::test_util::TempDir::new("watch");
+        let dir = temp.path();
+        let src = dir.join("src");
+        std::fs::create_dir_all(&src).unwrap();
+        std::fs::write(src.join("lib.rs"), "").unwrap();
+
+        let mut watcher = Watcher::new(vec![src.clone(), dir.join("assets")]);
+        assert!(watcher.changes().is_empty());
+
+        std::fs::write(src.join("game.rs"), "").unwrap();
+        std::fs::write(src.join(".game.rs.swp"), "").unwrap();
+        assert_eq!(watcher.changes(), vec![src.join("game.rs")]);
+
+        std::fs::create_dir_all(dir.join("assets")).unwrap();
+        std::fs::write(dir.join("assets/level.json"), "").unwrap();
+        std::fs::remove_file(src.join("lib.rs")).unwrap();
+        assert_eq!(
+            watcher.changes(),
+            vec![dir.join("assets/level.json"), src.join("lib.rs")]
+        );
+        assert!(watcher.changes().is_empty());
+    }
+
+    #[test]
+    fn bundle_paths() {
+        assert_eq!(
+            bundle_candidates(
+                Path::new("/game/assets"),
+                Path::new("/game/assets/levels/1.json")
+            ),
+            vec![
+                PathBuf::from("assets/levels/1.json"),
+                PathBuf::from("levels/1.json"),
+                PathBuf::from("1.json"),
+            ]
+        );
+        assert_eq!(
+            bundle_candidates(
+                Path::new("/game/assets"),
+                Path::new("/game/assets/icon.png")
+            ),
+            vec![PathBuf::from("assets/icon.png"), PathBuf::from("icon.png")]
+        );
+        assert_eq!(
+            bundle_candidates(
+                Path::new("/game/GoogleService-Info.plist"),
+                Path::new("/game/GoogleService-Info.plist")
+            ),
+            vec![PathBuf::from("GoogleService-Info.plist")]
+        );
+        assert!(
+            bundle_candidates(Path::new("/game/assets"), Path::new("/game/src/lib.rs")).is_empty()
+        );
+    }
+
+    #[test]
+    fn hot_sync() {
+        let temp = crate::test_util::TempDir::new("hot-sync");
+        let dir = temp.path();
+        let app_path = dir.join("target/game.app");
+        std::fs::create_dir_all(app_path.join("assets")).unwrap();
+        std::fs::write(app_path.join("assets/level.json"), "").unwrap();
+        std::fs::create_dir_all(dir.join("assets")).unwrap();
+        std::fs::write(dir.join("assets/level.json"), "").unwrap();
+        std::fs::write(dir.join("assets/new.json"), "").unwrap();
+        let assets = [crate::toml::Asset::Path("assets".into())];
+
+        assert_eq!(
+            hot_sync_files(&assets, dir, &app_path, &[dir.join("assets/level.json")]),
+            Some(vec![(
+                dir.join("assets/level.json"),
+                PathBuf::from("assets/level.json")
+            )])
+        );
+        // Added files and removed files need a build.
+        assert_eq!(
+            hot_sync_files(&assets, dir, &app_path, &[dir.join("assets/new.json")]),
+            None
+        );
+        assert_eq!(
+            hot_sync_files(&assets, dir, &app_path, &[dir.join("assets/old.json")]),
+            None
+        );
+    }
+}
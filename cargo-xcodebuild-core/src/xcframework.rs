@@ -0,0 +1,131 @@
+use anyhow::Context as _;
+use std::path::{Path, PathBuf};
+
+use crate::toml::Target;
+
+/// Group `build_targets` into the slices of an XCFramework, one per platform and variant: device,
+/// simulator or Mac Catalyst. A slice has a single library, so the libraries of a slice with
+/// several architectures are merged with `lipo`.
+pub fn slices(build_targets: &[Target]) -> Vec<Vec<Target>> {
+    let same_slice = |a: Target, b: Target| {
+        a.platform() == b.platform()
+            && a.is_simulator() == b.is_simulator()
+            && a.is_catalyst() == b.is_catalyst()
+    };
+    let mut slices: Vec<Vec<Target>> = Vec::new();
+    for &target in build_targets {
+        match slices.iter_mut().find(|slice| same_slice(slice[0], target)) {
+            Some(slice) => slice.push(target),
+            None => slices.push(vec![target]),
+        }
+    }
+
+    slices
+}
+
+/// Directory next to the per-target cargo output with the merged library of the slice of
+/// `target`, e.g. `universal-apple-ios-sim`.
+pub fn universal_dir(target: Target) -> String {
+    let rustc_target = target.to_rustc_target();
+    let (_arch, rest) = rustc_target.split_once('-').unwrap();
+    format!("universal-{}", rest)
+}
+
+/// `xcodebuild -create-xcframework` arguments for static `libraries` sharing `headers`.
+pub fn create_args(libraries: &[PathBuf], headers: &Path, output: &Path) -> Vec<String> {
+    let mut args = vec!["-create-xcframework".to_string()];
+    for library in libraries {
+        args.push("-library".into());
+        args.push(library.to_string_lossy().into_owned());
+        args.push("-headers".into());
+        args.push(headers.to_string_lossy().into_owned());
+    }
+    args.push("-output".into());
+    args.push(output.to_string_lossy().into_owned());
+    args
+}
+
+/// Create `output` from static `libraries`, one per slice, replacing an existing one.
+pub fn create(libraries: &[PathBuf], headers: &Path, output: &Path) -> anyhow::Result<()> {
+    if output.exists() {
+        std::fs::remove_dir_all(output)
+            .with_context(|| format!("Failed to remove {:?}", output))?;
+    }
+    log::info!("Creating {:?}", output);
+    let result = std::process::Command::new("xcodebuild")
+        .args(create_args(libraries, headers, output))
+        .output()
+        .with_context(|| "Failed to get xcodebuild output".to_string())?;
+
+    if !result.status.success() {
+        let stdout = String::from_utf8_lossy(&result.stdout);
+        let stderr = String::from_utf8_lossy(&result.stderr);
+
+        anyhow::bail!("Failed to create XCFramework:\n{:?}\n{:?}", stdout, stderr)
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn slices_per_platform_and_variant() {
+        let build_targets = [
+            Target::Arm,
+            Target::ArmSim,
+            Target::Sim,
+            Target::Catalyst,
+            Target::CatalystIntel,
+            Target::Mac,
+        ];
+        assert_eq!(
+            slices(&build_targets),
+            vec![
+                vec![Target::Arm],
+                vec![Target::ArmSim, Target::Sim],
+                vec![Target::Catalyst, Target::CatalystIntel],
+                vec![Target::Mac],
+            ]
+        );
+        assert_eq!(
+            universal_dir(Target::ArmSim),
+            crate::lipo::UNIVERSAL_SIMULATOR_DIR
+        );
+        assert_eq!(
+            universal_dir(Target::CatalystIntel),
+            "universal-apple-ios-macabi"
+        );
+        assert_eq!(universal_dir(Target::WatchOs), "universal-apple-watchos");
+    }
+
+    #[test]
+    fn args_per_library() {
+        let args = create_args(
+            &[
+                PathBuf::from("ios/libgame.a"),
+                PathBuf::from("sim/libgame.a"),
+            ],
+            Path::new("headers"),
+            Path::new("game.xcframework"),
+        );
+        assert_eq!(
+            args,
+            [
+                "-create-xcframework",
+                "-library",
+                "ios/libgame.a",
+                "-headers",
+                "headers",
+                "-library",
+                "sim/libgame.a",
+                "-headers",
+                "headers",
+                "-output",
+                "game.xcframework",
+            ]
+        );
+    }
+}
@@ -0,0 +1,3241 @@
+use anyhow::Context as _;
+use serde::{Deserialize, Serialize};
+use std::io::IsTerminal as _;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// A destination to build for and run on.
+#[derive(Debug, Clone)]
+pub enum SelectedDevice {
+    Device(md::MobileDevice),
+    Simulator {
+        udid: String,
+    },
+    /// This Mac, for macOS and Mac Catalyst apps.
+    Mac,
+}
+
+impl SelectedDevice {
+    pub fn destination(&self) -> Destination {
+        match *self {
+            SelectedDevice::Device(ref md) => Destination::Device {
+                identifier: md.identifier.clone(),
+            },
+            SelectedDevice::Simulator { ref udid } => Destination::Simulator { udid: udid.clone() },
+            SelectedDevice::Mac => Destination::Mac,
+        }
+    }
+}
+
+/// Serializable identifier of a [`SelectedDevice`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum Destination {
+    Device { identifier: String },
+    Simulator { udid: String },
+    Mac,
+}
+
+impl Destination {
+    /// Find the connected device or simulator for this destination.
+    pub fn resolve(&self) -> anyhow::Result<SelectedDevice> {
+        match *self {
+            Destination::Device { ref identifier } => md::get_device_list()
+                .into_iter()
+                .find(|d| &d.identifier == identifier)
+                .map(SelectedDevice::Device)
+                .with_context(|| {
+                    format!("Failed to find a connected device with id {}", identifier)
+                }),
+            Destination::Simulator { ref udid } => {
+                Ok(SelectedDevice::Simulator { udid: udid.clone() })
+            }
+            Destination::Mac => Ok(SelectedDevice::Mac),
+        }
+    }
+}
+
+/// Xcode configuration, derived from the cargo profile.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BuildType {
+    Debug,
+    Release,
+}
+
+impl BuildType {
+    /// Name of the generated Xcode configuration.
+    pub fn configuration(self) -> &'static str {
+        match self {
+            BuildType::Debug => "Debug",
+            BuildType::Release => "Release",
+        }
+    }
+}
+
+/// Options of [`Xcodebuild::build_with`].
+#[derive(Debug, Clone, Default)]
+pub struct BuildOptions {
+    /// Destination to build for instead of the automatically selected one.
+    pub device: Option<SelectedDevice>,
+    /// Only select a connected device or only a simulator when `device` is not set.
+    pub device_type: Option<crate::toml::DeviceType>,
+    /// Xcode scheme. The app target scheme by default.
+    pub scheme: Option<String>,
+    /// Xcode configuration. Derived from [`BuildType`] by default.
+    pub configuration: Option<String>,
+    /// `CFBundleVersion` of the app. `1` by default, see [`Xcodebuild::next_build_number`].
+    pub build_number: Option<String>,
+    /// Save `build_number` as the `.build-number` counter once `xcodebuild` succeeded, so a
+    /// failed build doesn't use up a number.
+    pub save_build_number: bool,
+    /// Release build optimized for size on both the cargo and the Xcode side.
+    pub small: bool,
+    /// Custom cargo profile, built with the Xcode configuration of the same name.
+    pub profile: Option<String>,
+}
+
+impl BuildOptions {
+    /// Build settings passed to `xcodebuild`.
+    fn build_settings(&self) -> Vec<String> {
+        let mut build_settings = vec![];
+        if let Some(ref build_number) = self.build_number {
+            log::info!("Build number: {}", build_number);
+            build_settings.push(format!("CURRENT_PROJECT_VERSION={}", build_number));
+        }
+        if self.small {
+            build_settings.extend(SMALL_BUILD_SETTINGS.iter().map(|s| s.to_string()));
+        }
+        build_settings
+    }
+}
+
+/// Fingerprint of the inputs of the last `xcodebuild` run, next to the generated project.
+const XCODEBUILD_FINGERPRINT: &str = "xcodebuild.fingerprint";
+
+/// Xcode build settings of [`BuildOptions::small`].
+pub const SMALL_BUILD_SETTINGS: &[&str] = &[
+    "GCC_OPTIMIZATION_LEVEL=z",
+    "SWIFT_OPTIMIZATION_LEVEL=-Osize",
+    "DEAD_CODE_STRIPPING=YES",
+    "DEPLOYMENT_POSTPROCESSING=YES",
+    "STRIP_INSTALLED_PRODUCT=YES",
+    "STRIP_STYLE=all",
+    "COPY_PHASE_STRIP=YES",
+];
+
+/// Options of [`Xcodebuild::run_with`].
+#[derive(Debug, Clone, Default)]
+pub struct RunOptions {
+    /// Dynamic Type content size category set on the simulator, e.g. `accessibility-large`.
+    pub content_size: Option<String>,
+    /// Turn the Bold Text accessibility setting of the simulator on or off.
+    pub bold_text: Option<bool>,
+    /// Turn the Reduce Motion accessibility setting of the simulator on or off.
+    pub reduce_motion: Option<bool>,
+    /// Print CPU and memory usage of the app until it exits and a summary afterwards.
+    pub monitor: bool,
+    /// Stop monitoring after this duration instead of waiting for the app to exit.
+    pub monitor_duration: Option<std::time::Duration>,
+    /// Forward stdout and stderr of the app on the simulator to this process until the app exits.
+    pub console: bool,
+    /// Override the simulator status bar for screenshots, or clear the override.
+    /// `clean_status_bar` from `Cargo.toml` by default.
+    pub clean_status_bar: Option<bool>,
+    /// `AppleLocale` of this launch, e.g. `de_DE`.
+    pub locale: Option<String>,
+    /// `AppleLanguages` of this launch, e.g. `de`.
+    pub language: Option<String>,
+    /// Process arguments of the app, after the `locale` and `language` launch arguments.
+    pub args: Vec<String>,
+}
+
+impl RunOptions {
+    /// Arguments passed to the app on launch.
+    fn launch_args(&self) -> Vec<String> {
+        let mut args = vec![];
+        if let Some(ref language) = self.language {
+            args.push("-AppleLanguages".to_string());
+            args.push(format!("({})", language));
+        }
+        if let Some(ref locale) = self.locale {
+            args.push("-AppleLocale".to_string());
+            args.push(locale.clone());
+        }
+        args.extend(self.args.iter().cloned());
+        args
+    }
+
+    fn has_accessibility_settings(&self) -> bool {
+        self.content_size.is_some() || self.bold_text.is_some() || self.reduce_motion.is_some()
+    }
+
+    fn apply_accessibility_settings(&self, udid: &str) -> anyhow::Result<()> {
+        if let Some(ref category) = self.content_size {
+            crate::simctl::set_content_size(udid, category)?;
+        }
+        if let Some(enabled) = self.bold_text {
+            crate::simctl::set_accessibility_flag(udid, "EnhancedTextLegibilityEnabled", enabled)?;
+        }
+        if let Some(enabled) = self.reduce_motion {
+            crate::simctl::set_accessibility_flag(udid, "ReduceMotionEnabled", enabled)?;
+        }
+        Ok(())
+    }
+}
+
+/// Everything produced by [`Xcodebuild::build`] that is required to install and launch the app.
+///
+/// Saved next to the generated project, so the run phase doesn't need to build in the same process.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BuildArtifacts {
+    pub app_name: String,
+    pub bundle_id_prefix: String,
+    pub project_dir: PathBuf,
+    pub build_type: BuildType,
+    /// The app name when missing, artifacts saved before schemes were selectable have none.
+    #[serde(default)]
+    pub scheme: String,
+    /// Derived from `build_type` when missing.
+    #[serde(default)]
+    pub configuration: String,
+    pub destination: Destination,
+    #[serde(default)]
+    pub team_id: Option<String>,
+    /// Bundle id suffix of the selected flavor.
+    #[serde(default)]
+    pub bundle_id_suffix: Option<String>,
+    #[serde(default)]
+    pub platform: crate::toml::AppPlatform,
+    /// Fingerprint of the `xcodebuild` inputs of the app, see [`crate::fingerprint`].
+    #[serde(default)]
+    pub fingerprint: Option<String>,
+}
+
+impl BuildArtifacts {
+    const FILE_NAME: &'static str = "artifacts.json";
+
+    /// Full bundle identifier of the app.
+    pub fn bundle_id(&self) -> String {
+        bundle_id(
+            &self.bundle_id_prefix,
+            &self.app_name,
+            self.bundle_id_suffix.as_deref(),
+        )
+    }
+
+    /// Path of the built `.app`, relative to `project_dir`.
+    pub fn app_path(&self) -> String {
+        let products_dir = match self.destination {
+            // macOS products have no SDK suffix.
+            Destination::Mac if self.platform == crate::toml::AppPlatform::MacOs => {
+                self.configuration.clone()
+            }
+            Destination::Mac => format!("{}-maccatalyst", self.configuration),
+            Destination::Device { .. } => {
+                format!("{}-{}", self.configuration, self.platform.sdk(false))
+            }
+            Destination::Simulator { .. } => {
+                format!("{}-{}", self.configuration, self.platform.sdk(true))
+            }
+        };
+        format!(
+            "build/Build/Products/{}/{}.app",
+            products_dir, self.app_name
+        )
+    }
+
+    /// Path of the dSYM bundle, relative to `project_dir`. Only produced for builds with
+    /// `DEBUG_INFORMATION_FORMAT = dwarf-with-dsym`, which is the Release default.
+    pub fn dsym_path(&self) -> String {
+        format!("{}.dSYM", self.app_path())
+    }
+
+    /// Size of the `.app` bundle in bytes.
+    pub fn app_size(&self) -> anyhow::Result<u64> {
+        dir_size(&self.project_dir.join(self.app_path()))
+    }
+
+    /// Where the fingerprint of the app installed on the destination is saved. `None` for Mac apps,
+    /// which run from the build directory.
+    fn installed_fingerprint_path(&self) -> Option<PathBuf> {
+        let id = match self.destination {
+            Destination::Device { ref identifier } => identifier,
+            Destination::Simulator { ref udid } => udid,
+            Destination::Mac => return None,
+        };
+        Some(
+            self.project_dir
+                .join(format!("installed-{}.fingerprint", id)),
+        )
+    }
+
+    /// Whether this build was installed on the destination by the last install.
+    fn is_installed(&self) -> bool {
+        match (
+            self.fingerprint.as_deref(),
+            self.installed_fingerprint_path(),
+        ) {
+            (Some(fingerprint), Some(path)) => {
+                crate::fingerprint::is_up_to_date(&path, fingerprint)
+            }
+            _ => false,
+        }
+    }
+
+    /// Remember that this build is installed on the destination.
+    fn set_installed(&self) -> anyhow::Result<()> {
+        match (
+            self.fingerprint.as_deref(),
+            self.installed_fingerprint_path(),
+        ) {
+            (Some(fingerprint), Some(path)) => crate::fingerprint::save(&path, fingerprint),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn save(&self) -> anyhow::Result<()> {
+        let path = self.project_dir.join(Self::FILE_NAME);
+        let file =
+            std::fs::File::create(&path).with_context(|| format!("Failed to create {:?}", path))?;
+        serde_json::to_writer_pretty(file, self)
+            .with_context(|| format!("Failed to write build artifacts to {:?}", path))?;
+        Ok(())
+    }
+
+    pub fn load(project_dir: &Path) -> anyhow::Result<Self> {
+        let path = project_dir.join(Self::FILE_NAME);
+        let content = std::fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {:?}. Build the project first", path))?;
+        Self::from_json(&content)
+            .with_context(|| format!("Failed to parse build artifacts from {:?}", path))
+    }
+
+    fn from_json(content: &str) -> anyhow::Result<Self> {
+        let mut artifacts: Self = serde_json::from_str(content)?;
+        if artifacts.scheme.is_empty() {
+            artifacts.scheme = artifacts.app_name.clone();
+        }
+        if artifacts.configuration.is_empty() {
+            artifacts.configuration = artifacts.build_type.configuration().to_string();
+        }
+        Ok(artifacts)
+    }
+}
+
+fn bundle_id(prefix: &str, app_name: &str, suffix: Option<&str>) -> String {
+    format!(
+        "{}.{}{}",
+        prefix,
+        app_name.replace('_', "-"),
+        suffix.unwrap_or_default()
+    )
+}
+
+/// Outputs of [`Xcodebuild::archive`], saved next to the generated project.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArchiveArtifacts {
+    pub archive_path: PathBuf,
+    /// The exported `.ipa`, or the `.pkg`, `.app` or notarized `.dmg` of macOS apps.
+    pub ipa_path: PathBuf,
+    pub bundle_id: String,
+    pub team_id: Option<String>,
+    #[serde(default)]
+    pub export_method: crate::toml::ExportMethod,
+}
+
+impl ArchiveArtifacts {
+    const FILE_NAME: &'static str = "archive.json";
+
+    /// Name of the archived `.app` bundle, the archive is named after the app.
+    fn app_bundle_name(&self) -> String {
+        let app_name = self.archive_path.file_stem().unwrap_or_default();
+        format!("{}.app", app_name.to_string_lossy())
+    }
+
+    /// Path of the `.app` inside the archive.
+    pub fn app_path(&self) -> PathBuf {
+        self.archive_path
+            .join("Products/Applications")
+            .join(self.app_bundle_name())
+    }
+
+    /// Path of the dSYM bundle of the app inside the archive.
+    pub fn app_dsym_path(&self) -> PathBuf {
+        self.archive_path
+            .join("dSYMs")
+            .join(format!("{}.dSYM", self.app_bundle_name()))
+    }
+
+    /// All dSYM bundles of the archive: the app and its embedded frameworks.
+    pub fn dsym_paths(&self) -> anyhow::Result<Vec<PathBuf>> {
+        let dsyms_dir = self.archive_path.join("dSYMs");
+        let mut paths = std::fs::read_dir(&dsyms_dir)
+            .with_context(|| format!("Failed to read dir: {:?}", dsyms_dir))?
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "dSYM"))
+            .collect::<Vec<_>>();
+        paths.sort();
+        Ok(paths)
+    }
+
+    pub fn save(&self, project_dir: &Path) -> anyhow::Result<()> {
+        let path = project_dir.join(Self::FILE_NAME);
+        let file =
+            std::fs::File::create(&path).with_context(|| format!("Failed to create {:?}", path))?;
+        serde_json::to_writer_pretty(file, self)
+            .with_context(|| format!("Failed to write archive artifacts to {:?}", path))?;
+        Ok(())
+    }
+
+    pub fn load(project_dir: &Path) -> anyhow::Result<Self> {
+        let path = project_dir.join(Self::FILE_NAME);
+        let content = std::fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {:?}. Archive the project first", path))?;
+        serde_json::from_str(&content)
+            .with_context(|| format!("Failed to parse archive artifacts from {:?}", path))
+    }
+}
+
+fn dir_size(path: &Path) -> anyhow::Result<u64> {
+    let metadata = std::fs::symlink_metadata(path)
+        .with_context(|| format!("Failed to read metadata of {:?}", path))?;
+    if !metadata.is_dir() {
+        return Ok(metadata.len());
+    }
+
+    let mut size = 0;
+    for entry in
+        std::fs::read_dir(path).with_context(|| format!("Failed to read dir: {:?}", path))?
+    {
+        size += dir_size(&entry?.path())?;
+    }
+    Ok(size)
+}
+
+/// Drives cargo, xcodegen, xcodebuild and simctl for a single crate.
+pub struct Xcodebuild {
+    manifest_path: PathBuf,
+    target_dir: PathBuf,
+    src_dir: PathBuf,
+    toml_content: String,
+    message_format: crate::events::MessageFormat,
+    team: Option<String>,
+    raw_xcodebuild_output: bool,
+    verbosity: crate::events::Verbosity,
+    flavor: Option<String>,
+}
+
+impl Xcodebuild {
+    /// Create a new instance for the given manifest. Generated files go to `<target_dir>/xcodegen`.
+    pub fn new(manifest_path: &Path, target_dir: &Path) -> anyhow::Result<Self> {
+        let toml_content = std::fs::read_to_string(manifest_path)?;
+        let target_dir = target_dir.join("xcodegen");
+        let src_dir = target_dir.join("src");
+        log::trace!("cargo-xcodebuild target dir: {:?}", target_dir);
+        Ok(Self {
+            manifest_path: manifest_path.to_path_buf(),
+            target_dir,
+            src_dir,
+            toml_content,
+            message_format: Default::default(),
+            team: None,
+            raw_xcodebuild_output: false,
+            verbosity: Default::default(),
+            flavor: None,
+        })
+    }
+
+    /// Print [`crate::events::Event`]s in this format during builds and runs.
+    pub fn set_message_format(&mut self, message_format: crate::events::MessageFormat) {
+        self.message_format = message_format;
+    }
+
+    /// Show xcodebuild output unchanged instead of condensed into colored steps.
+    pub fn set_raw_xcodebuild_output(&mut self, raw: bool) {
+        self.raw_xcodebuild_output = raw;
+    }
+
+    /// Show only errors and warnings of xcodebuild when quiet, all of its output when verbose.
+    pub fn set_verbosity(&mut self, verbosity: crate::events::Verbosity) {
+        self.verbosity = verbosity;
+    }
+
+    pub fn emit(&self, event: crate::events::Event) {
+        event.emit(self.message_format);
+    }
+
+    /// Run `f` between `phase-started` and `phase-finished` events.
+    fn phase<T>(
+        &self,
+        phase: crate::events::Phase,
+        f: impl FnOnce() -> anyhow::Result<T>,
+    ) -> anyhow::Result<T> {
+        self.emit(crate::events::Event::PhaseStarted { phase });
+        let progress = self.progress(phase.label(), !phase.has_output());
+        let result = f();
+        if let Some(progress) = progress {
+            progress.finish(result.is_ok());
+        }
+        self.emit(crate::events::Event::PhaseFinished {
+            phase,
+            success: result.is_ok(),
+        });
+        result
+    }
+
+    /// Report a long step on stderr with human output, unless quiet.
+    fn progress(&self, label: &str, spinner: bool) -> Option<crate::progress::Progress> {
+        if self.message_format != crate::events::MessageFormat::Human
+            || self.verbosity == crate::events::Verbosity::Quiet
+        {
+            return None;
+        }
+        let spinner = spinner && std::io::stderr().is_terminal();
+        Some(crate::progress::Progress::start(label, spinner))
+    }
+
+    /// `cargo build` for each of `targets`, reporting the time of each when there are several.
+    fn build_targets(
+        &self,
+        args: &[String],
+        targets: &[crate::toml::Target],
+        envs: &[(String, String)],
+    ) -> anyhow::Result<()> {
+        for target in targets {
+            log::info!("Build project for target: {}", target.to_rustc_target());
+            let progress = if targets.len() > 1 {
+                self.progress(
+                    &format!("Building Rust library for {}", target.to_rustc_target()),
+                    false,
+                )
+            } else {
+                None
+            };
+            let result = crate::cargo::run_cargo_with_env(
+                "build",
+                &self.target_cargo_args(args, *target)?,
+                Some(target.to_rustc_target()),
+                envs,
+            );
+            if let Some(progress) = progress {
+                progress.finish(result.is_ok());
+            }
+            result?;
+        }
+        Ok(())
+    }
+
+    /// Parsed `[package.metadata.ios]` section.
+    pub fn ios_metadata(&self) -> anyhow::Result<crate::toml::IosMetadata> {
+        self.toml()?.ios_metadata()
+    }
+
+    /// Build with the named flavor from `[package.metadata.ios.flavors]`.
+    pub fn set_flavor(&mut self, flavor: impl Into<String>) {
+        self.flavor = Some(flavor.into());
+    }
+
+    /// Sign with the team `team_id`, overriding `development_team` in `Cargo.toml` and flavors.
+    pub fn set_team(&mut self, team_id: impl Into<String>) {
+        self.team = Some(team_id.into());
+    }
+
+    /// Parsed `Cargo.toml` with the selected flavor and team applied.
+    fn toml(&self) -> anyhow::Result<crate::toml::Root> {
+        let toml = toml::from_str::<crate::toml::Root>(&self.toml_content)?;
+        let toml = match self.flavor {
+            Some(ref flavor) => toml.with_flavor(flavor)?,
+            None => toml,
+        };
+        match self.team {
+            Some(ref team) => toml.with_development_team(team),
+            None => Ok(toml),
+        }
+    }
+
+    /// Cargo target directory with the build outputs of every rustc target, the parent of the
+    /// generated files.
+    fn cargo_target_dir(&self) -> &Path {
+        self.target_dir.parent().unwrap()
+    }
+
+    /// Cargo build environment of the selected flavor.
+    fn flavor_env(&self) -> anyhow::Result<Vec<(String, String)>> {
+        Ok(self
+            .ios_metadata()?
+            .flavor
+            .and_then(|flavor| flavor.env)
+            .unwrap_or_default()
+            .into_iter()
+            .collect())
+    }
+
+    /// Run `cargo check` for every configured build target.
+    pub fn check(&self, args: &[String]) -> anyhow::Result<()> {
+        let toml = self.toml()?.validate_build_targets()?;
+
+        let build_targets = toml
+            .package
+            .metadata
+            .unwrap_or_default()
+            .ios
+            .unwrap_or_default()
+            .build_targets
+            .unwrap_or_default();
+
+        let flavor_env = self.flavor_env()?;
+        for target in build_targets {
+            crate::cargo::run_cargo_with_env(
+                "check",
+                &self.target_cargo_args(args, target)?,
+                Some(target.to_rustc_target()),
+                &flavor_env,
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// rust-analyzer settings to check the crate like [`Xcodebuild::check`] does. Uses the
+    /// simulator target of this Mac if it is configured, the first build target otherwise.
+    pub fn ide_settings(
+        &self,
+        args: &[String],
+    ) -> anyhow::Result<crate::ide::RustAnalyzerSettings> {
+        use crate::toml::Target;
+
+        let metadata = self.ios_metadata()?;
+        let platform = metadata.platform.unwrap_or_default();
+        let build_targets = metadata.build_targets.unwrap_or_default();
+        let host_simulator = match platform {
+            crate::toml::AppPlatform::TvOs => Target::TvOsSim,
+            crate::toml::AppPlatform::WatchOs => Target::WatchOsSim,
+            crate::toml::AppPlatform::MacOs if cfg!(target_arch = "aarch64") => Target::Mac,
+            crate::toml::AppPlatform::MacOs => Target::MacIntel,
+            _ if cfg!(target_arch = "aarch64") => Target::ArmSim,
+            _ => Target::Sim,
+        };
+        let target = match build_targets.first() {
+            Some(&first) if !build_targets.contains(&host_simulator) => first,
+            _ => host_simulator,
+        };
+        let sdk = platform.sdk(target.is_simulator());
+
+        let mut extra_env = std::collections::BTreeMap::new();
+        extra_env.insert("SDKROOT".to_string(), Self::sdk_path(sdk)?);
+        if let Some(deployment_target) = metadata
+            .deployment_target
+            .as_ref()
+            .and_then(|target| target.get(platform))
+        {
+            extra_env.insert(
+                platform.deployment_target_env().to_string(),
+                deployment_target.to_string(),
+            );
+        }
+
+        Ok(crate::ide::RustAnalyzerSettings {
+            target: target.to_rustc_target().to_string(),
+            extra_env,
+            features: crate::ide::Features::from_args(args),
+        })
+    }
+
+    /// Build the crate for every configured target, generate the Xcode project and build it
+    /// with `xcodebuild` for the selected device.
+    pub fn build(&self, args: &[String], build_type: BuildType) -> anyhow::Result<BuildArtifacts> {
+        self.build_with(args, build_type, BuildOptions::default())
+    }
+
+    /// Same as [`Xcodebuild::build`] with explicit [`BuildOptions`].
+    pub fn build_with(
+        &self,
+        args: &[String],
+        build_type: BuildType,
+        options: BuildOptions,
+    ) -> anyhow::Result<BuildArtifacts> {
+        log::info!("Building project");
+
+        let toml = self
+            .toml()?
+            .validate_crate_type()?
+            .validate_build_targets()?;
+
+        let build_targets = toml.ios_metadata()?.build_targets.unwrap_or_default();
+
+        let mut args = args.to_vec();
+        if let Some(ref profile) = options.profile {
+            if !args
+                .iter()
+                .any(|arg| arg == "--profile" || arg.starts_with("--profile="))
+            {
+                args.push("--profile".into());
+                args.push(profile.clone());
+            }
+        }
+        let mut cargo_env = self.flavor_env()?;
+        let build_type = if options.small {
+            log::info!("Building a size-optimized release");
+            // A profile selected by the user is optimized for size instead of `release`.
+            args = crate::cargo::with_release(&args);
+            let profile = crate::cargo::selected_profile(&args).unwrap_or_default();
+            cargo_env.extend(crate::cargo::small_profile_env(&profile));
+            BuildType::Release
+        } else {
+            build_type
+        };
+
+        let build_settings = options.build_settings();
+        let (project, app_name, project_dir) =
+            self.build_crate_and_generate(toml, &args, &build_targets, &cargo_env)?;
+
+        let selected_device = match options.device {
+            Some(device) => device,
+            None => self.find_device(options.device_type)?,
+        };
+        self.emit(crate::events::Event::DeviceSelected {
+            destination: selected_device.destination(),
+        });
+        let scheme = options.scheme.unwrap_or_else(|| app_name.clone());
+        let configuration = options
+            .configuration
+            .or(options.profile)
+            .unwrap_or_else(|| build_type.configuration().to_string());
+        let fingerprint = self
+            .xcodebuild_fingerprint(&project_dir, &app_name, &args, &build_targets)?
+            .value(selected_device.destination())
+            .value(&configuration)
+            .value(&scheme)
+            .value(&build_settings)
+            .finish();
+
+        let mut artifacts = BuildArtifacts {
+            app_name,
+            bundle_id_prefix: project.options.bundle_id_prefix.clone(),
+            bundle_id_suffix: self
+                .ios_metadata()?
+                .flavor
+                .and_then(|flavor| flavor.bundle_id_suffix),
+            project_dir,
+            build_type,
+            scheme,
+            configuration,
+            destination: selected_device.destination(),
+            team_id: project.team_id(),
+            platform: self.ios_metadata()?.platform.unwrap_or_default(),
+            fingerprint: None,
+        };
+        let fingerprint_path = artifacts.project_dir.join(XCODEBUILD_FINGERPRINT);
+        let app_path = artifacts.project_dir.join(artifacts.app_path());
+        if app_path.exists() && crate::fingerprint::is_up_to_date(&fingerprint_path, &fingerprint) {
+            log::info!("{:?} is up to date, skipping xcodebuild", app_path);
+        } else {
+            crate::fingerprint::invalidate(&fingerprint_path);
+            self.phase(crate::events::Phase::Xcodebuild, || {
+                self.xcode_build_project(
+                    &artifacts.project_dir,
+                    &selected_device,
+                    &artifacts.configuration,
+                    &artifacts.scheme,
+                    &build_settings,
+                )
+            })?;
+            crate::fingerprint::save(&fingerprint_path, &fingerprint)?;
+        }
+        if let Some(ref build_number) = options.build_number {
+            if options.save_build_number {
+                self.save_build_number(build_number)?;
+            }
+        }
+        artifacts.fingerprint = Some(fingerprint);
+        artifacts.save()?;
+        self.emit(crate::events::Event::Artifact {
+            kind: "app",
+            path: artifacts.project_dir.join(artifacts.app_path()),
+        });
+        self.emit(crate::events::Event::Artifact {
+            kind: "dsym",
+            path: artifacts.project_dir.join(artifacts.dsym_path()),
+        });
+
+        Ok(artifacts)
+    }
+
+    /// Build a release archive of the app for devices with `xcodebuild archive`
+    /// and export a signed `.ipa` for App Store distribution.
+    /// `options.device` is ignored, archives are built for any iOS device.
+    pub fn archive(
+        &self,
+        args: &[String],
+        options: BuildOptions,
+    ) -> anyhow::Result<ArchiveArtifacts> {
+        log::info!("Archiving project");
+
+        let toml = self
+            .toml()?
+            .validate_crate_type()?
+            .validate_build_targets()?
+            .validate_export_method()?;
+        let ios_metadata = toml.ios_metadata()?;
+        let platform = ios_metadata.platform.unwrap_or_default();
+        let build_targets = ios_metadata.build_targets.unwrap_or_default();
+        let device_target = platform.device_target();
+        if !build_targets.contains(&device_target) {
+            anyhow::bail!(
+                "Archives are built for devices. Please add `{}` to `build_targets` in Cargo.toml.",
+                device_target.to_rustc_target()
+            )
+        }
+
+        let args = crate::cargo::with_release(args);
+        let mut cargo_env = self.flavor_env()?;
+        if options.small {
+            let profile = crate::cargo::selected_profile(&args).unwrap_or_default();
+            cargo_env.extend(crate::cargo::small_profile_env(&profile));
+        }
+        let (project, app_name, project_dir) =
+            self.build_crate_and_generate(toml, &args, &[device_target], &cargo_env)?;
+
+        let export_method = self.ios_metadata()?.export_method.unwrap_or_default();
+        if matches!(
+            export_method,
+            crate::toml::ExportMethod::AppStore | crate::toml::ExportMethod::MacApplication
+        ) {
+            let violations = self.preflight_checks()?;
+            if !violations.is_empty() {
+                let list = violations
+                    .iter()
+                    .map(|violation| format!("  {}", violation))
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                anyhow::bail!(
+                    "Found {} App Store preflight issues:\n{}",
+                    violations.len(),
+                    list
+                )
+            }
+        }
+
+        let scheme = options.scheme.clone().unwrap_or_else(|| app_name.clone());
+        let configuration = options
+            .configuration
+            .clone()
+            .unwrap_or_else(|| BuildType::Release.configuration().to_string());
+        let archive_path = project_dir.join(format!("{}.xcarchive", app_name));
+        log::info!(
+            "Archiving {} scheme {} to {:?}",
+            configuration,
+            scheme,
+            archive_path
+        );
+        let mut xcodebuild_args = vec![
+            "archive".to_string(),
+            "-derivedDataPath".into(),
+            "build".into(),
+            "-scheme".into(),
+            scheme.clone(),
+            "-configuration".into(),
+            configuration.clone(),
+            "-destination".into(),
+            format!("generic/platform={}", platform.destination(false)),
+            "-archivePath".into(),
+            archive_path.to_string_lossy().into_owned(),
+        ];
+        xcodebuild_args.extend(options.build_settings());
+        self.phase(crate::events::Phase::Xcodebuild, || {
+            self.run_xcodebuild(&project_dir, &xcodebuild_args)
+        })?;
+        if let Some(ref build_number) = options.build_number {
+            if options.save_build_number {
+                self.save_build_number(build_number)?;
+            }
+        }
+
+        let export_dir = project_dir.join("export");
+        if export_dir.exists() {
+            std::fs::remove_dir_all(&export_dir)
+                .with_context(|| format!("Failed to remove dir: {:?}", export_dir))?;
+        }
+        log::info!("Exporting for {} distribution", export_method.as_str());
+        let export_options = crate::export::ExportOptions {
+            method: export_method,
+            team_id: project.team_id(),
+            ..Default::default()
+        };
+        crate::export::export_archive(
+            &archive_path,
+            &export_dir,
+            &export_options,
+            self.authentication_key()?.as_ref(),
+        )?;
+        let mut ipa_path = crate::export::find_export(&export_dir, platform)?;
+        // Gatekeeper only opens Developer ID apps downloaded from the internet once notarized.
+        if export_method == crate::toml::ExportMethod::DeveloperId {
+            ipa_path = self.notarize(&ipa_path)?;
+        }
+
+        let artifacts = ArchiveArtifacts {
+            archive_path,
+            ipa_path,
+            bundle_id: bundle_id(
+                &project.options.bundle_id_prefix,
+                &app_name,
+                self.ios_metadata()?
+                    .flavor
+                    .and_then(|flavor| flavor.bundle_id_suffix)
+                    .as_deref(),
+            ),
+            team_id: project.team_id(),
+            export_method,
+        };
+        artifacts.save(&project_dir)?;
+        self.emit(crate::events::Event::Artifact {
+            kind: match platform {
+                // Exported macOS apps are distributed as they are or in an installer package.
+                crate::toml::AppPlatform::MacOs => "export",
+                _ => "ipa",
+            },
+            path: artifacts.ipa_path.clone(),
+        });
+
+        let ios_metadata = self.ios_metadata()?;
+        if ios_metadata
+            .symbol_upload
+            .is_some_and(|steps| !steps.is_empty())
+        {
+            self.upload_symbols(&artifacts.dsym_paths()?)?;
+        }
+        if let Some(lane) = ios_metadata.fastlane_lane {
+            crate::fastlane::FastlaneContext::from_archive(&artifacts, &scheme, &configuration)
+                .run_lane(self.manifest_path.parent().unwrap(), &lane)?;
+        }
+
+        Ok(artifacts)
+    }
+
+    /// Build the test harnesses of the crate for the simulator of this Mac, wrap each into a
+    /// minimal host app, install it on the `device` simulator, or the one selected like for
+    /// `run`, and run it there with `harness_args`. Returns the first non-zero exit code, `0` if
+    /// every harness passed.
+    pub fn test(
+        &self,
+        args: &[String],
+        harness_args: &[String],
+        device: Option<SelectedDevice>,
+    ) -> anyhow::Result<i32> {
+        use crate::toml::{AppPlatform, Target};
+
+        let platform = self.ios_metadata()?.platform.unwrap_or_default();
+        let target = match platform {
+            AppPlatform::Ios if cfg!(target_arch = "aarch64") => Target::ArmSim,
+            AppPlatform::Ios => Target::Sim,
+            AppPlatform::TvOs => Target::TvOsSim,
+            AppPlatform::WatchOs => Target::WatchOsSim,
+            AppPlatform::MacOs => {
+                anyhow::bail!("Tests of macOS apps run on this Mac, use `cargo test` for them")
+            }
+        };
+        let device = match device {
+            Some(device) => device,
+            None => self.find_device(Some(crate::toml::DeviceType::Simulator))?,
+        };
+        let udid = match device {
+            SelectedDevice::Simulator { udid } => udid,
+            _ => anyhow::bail!(
+                "Tests run on simulators, select one with `--simulator` or `--device-name`"
+            ),
+        };
+        let cargo_args = self.target_cargo_args(args, target)?;
+        let cargo_env = self.flavor_env()?;
+        let executables = self.phase(crate::events::Phase::CargoBuild, || {
+            crate::test_host::build_tests(&cargo_args, target.to_rustc_target(), &cargo_env)
+        })?;
+        if executables.is_empty() {
+            anyhow::bail!("No test harness was built")
+        }
+
+        crate::simctl::boot_and_wait(&udid)?;
+
+        let ios_metadata = self.ios_metadata()?;
+        let deployment_target = ios_metadata.deployment_target_for(platform);
+        let bundle_id_prefix = ios_metadata
+            .bundle_id_prefix
+            .unwrap_or_else(|| "com.rust".into());
+        let hosts_dir = self
+            .cargo_target_dir()
+            .join(target.to_rustc_target())
+            .join("test-hosts");
+        let mut exit_code = 0;
+        for executable in executables {
+            let name = executable
+                .file_name()
+                .unwrap()
+                .to_string_lossy()
+                .into_owned();
+            // Underscores aren't allowed in bundle ids.
+            let bundle_id = format!("{}.tests.{}", bundle_id_prefix, name.replace('_', "-"));
+            let app = crate::test_host::wrap(
+                &executable,
+                &hosts_dir,
+                &bundle_id,
+                platform,
+                &deployment_target,
+            )?;
+            self.phase(crate::events::Phase::Install, || {
+                Self::install_app_to_simulator(&hosts_dir, &app.to_string_lossy(), &udid)
+            })?;
+            let container = crate::simctl::app_container(&udid, &bundle_id, "app")?;
+            let code = self.phase(crate::events::Phase::Launch, || {
+                crate::test_host::run(&udid, &container.join(&name), harness_args)
+            })?;
+            if code != 0 && exit_code == 0 {
+                exit_code = code;
+            }
+        }
+
+        Ok(exit_code)
+    }
+
+    /// Build every target in `build_targets` in release and bundle the static libraries with
+    /// `bindings.h` into `<target_dir>/<lib name>.xcframework`, for embedding into an existing
+    /// Xcode project. Simulator slices are merged with `lipo`.
+    pub fn xcframework(&self, args: &[String]) -> anyhow::Result<PathBuf> {
+        log::info!("Creating XCFramework");
+        let toml = self.toml()?.validate_build_targets()?;
+        if !toml.has_crate_type("staticlib") {
+            anyhow::bail!(
+                "Missing `staticlib` crate-type in `lib` section. Please check Cargo.toml."
+            )
+        }
+        let ios_metadata = toml.ios_metadata()?;
+        let build_targets = ios_metadata.build_targets.clone().unwrap_or_default();
+
+        let mut args = args.to_vec();
+        if !args.iter().any(|arg| arg == "--release") {
+            args.push("--release".into());
+        }
+        let cargo_env = self.flavor_env()?;
+        self.phase(crate::events::Phase::CargoBuild, || {
+            self.build_targets(&args, &build_targets, &cargo_env)
+        })?;
+
+        let lib_name = crate::xcodegen::Project::target_name(&toml);
+        let library = |rustc_target: &str| {
+            self.cargo_target_dir()
+                .join(rustc_target)
+                .join("release")
+                .join(format!("lib{}.a", lib_name))
+        };
+        let mut libraries = Vec::new();
+        for slice in crate::xcframework::slices(&build_targets) {
+            if let [target] = slice[..] {
+                libraries.push(library(target.to_rustc_target()));
+                continue;
+            }
+            let universal = library(&crate::xcframework::universal_dir(slice[0]));
+            let inputs = slice
+                .iter()
+                .map(|target| library(target.to_rustc_target()))
+                .collect::<Vec<_>>();
+            let inputs = inputs.iter().map(PathBuf::as_path).collect::<Vec<_>>();
+            crate::lipo::create(&inputs, &universal)?;
+            libraries.push(universal);
+        }
+
+        let headers_dir = self.target_dir.join(format!("{}-headers", lib_name));
+        std::fs::create_dir_all(&headers_dir)
+            .with_context(|| format!("Failed to create all dir: {:?}", headers_dir))?;
+        std::fs::write(
+            headers_dir.join("bindings.h"),
+            crate::source::read_or_default(
+                self.manifest_path.parent().unwrap(),
+                ios_metadata.binding_header.as_deref(),
+                crate::source::DEFAULT_BINDING_HEADER,
+            )?,
+        )
+        .with_context(|| format!("Failed to write bindings.h: {:?}", headers_dir))?;
+        std::fs::write(
+            headers_dir.join("module.modulemap"),
+            crate::source::MODULE_MAP,
+        )
+        .with_context(|| format!("Failed to write module.modulemap: {:?}", headers_dir))?;
+
+        let output = self
+            .cargo_target_dir()
+            .join(format!("{}.xcframework", lib_name));
+        crate::xcframework::create(&libraries, &headers_dir, &output)?;
+        self.emit(crate::events::Event::Artifact {
+            kind: "xcframework",
+            path: output.clone(),
+        });
+
+        Ok(output)
+    }
+
+    /// Load the artifacts of the last successful [`Xcodebuild::archive`] of this crate.
+    pub fn last_archive_artifacts(&self) -> anyhow::Result<ArchiveArtifacts> {
+        let toml = self.toml()?;
+        let app_name = crate::xcodegen::Project::target_name(&toml);
+        ArchiveArtifacts::load(&self.target_dir.join(app_name))
+    }
+
+    /// Build the crate for `build_targets` and generate the Xcode project.
+    /// Returns the project, its app name and directory.
+    fn build_crate_and_generate(
+        &self,
+        toml: crate::toml::Root,
+        args: &[String],
+        build_targets: &[crate::toml::Target],
+        cargo_env: &[(String, String)],
+    ) -> anyhow::Result<(crate::xcodegen::Project, String, PathBuf)> {
+        self.phase(crate::events::Phase::CargoBuild, || {
+            self.build_targets(args, build_targets, cargo_env)
+        })?;
+        let app_name = crate::xcodegen::Project::target_name(&toml);
+        let ios_metadata = toml.ios_metadata()?;
+        let profile = crate::cargo::profile_dir(args);
+        let profile = profile.as_str();
+        if crate::lipo::has_universal_simulator(build_targets)
+            && ios_metadata.link != Some(crate::toml::Link::Dynamic)
+        {
+            let library = |dir: &str| {
+                self.cargo_target_dir()
+                    .join(dir)
+                    .join(profile)
+                    .join(format!("lib{}.a", app_name))
+            };
+            let inputs = [
+                library(crate::toml::Target::ArmSim.to_rustc_target()),
+                library(crate::toml::Target::Sim.to_rustc_target()),
+            ];
+            let inputs = inputs.iter().map(PathBuf::as_path).collect::<Vec<_>>();
+            let universal = library(crate::lipo::UNIVERSAL_SIMULATOR_DIR);
+            if !crate::lipo::is_up_to_date(&inputs, &universal) {
+                crate::lipo::create(&inputs, &universal)?;
+            }
+        }
+        if ios_metadata.link == Some(crate::toml::Link::Dynamic) {
+            let bundle_id = format!(
+                "{}.{}.framework",
+                ios_metadata
+                    .bundle_id_prefix
+                    .as_deref()
+                    .unwrap_or("com.rust"),
+                app_name.replace('_', "-")
+            );
+            for target in build_targets {
+                crate::framework::wrap_dylib(
+                    &self
+                        .cargo_target_dir()
+                        .join(target.to_rustc_target())
+                        .join(profile),
+                    &app_name,
+                    &bundle_id,
+                    &toml.package.version,
+                    &ios_metadata.deployment_target_or_default(),
+                )?;
+            }
+        }
+        self.unlock_keychain()?;
+        let teams = crate::teams::find_development_teams();
+        let team = Self::select_team(&teams, &toml)?;
+        let project = crate::xcodegen::Project::from_toml(&self.manifest_path, toml, team)?;
+
+        let project_dir = self.target_dir.join(&app_name);
+
+        self.phase(crate::events::Phase::Generate, || {
+            self.prepare_target_dir(&project_dir)?;
+            self.generate_xcode_project(&project, &project_dir)
+        })?;
+
+        Ok((project, app_name, project_dir))
+    }
+
+    /// Fingerprint of the inputs of `xcodebuild`: the Rust libraries, assets and generated files.
+    fn xcodebuild_fingerprint(
+        &self,
+        project_dir: &Path,
+        app_name: &str,
+        args: &[String],
+        build_targets: &[crate::toml::Target],
+    ) -> anyhow::Result<crate::fingerprint::Fingerprint> {
+        let profile = crate::cargo::profile_dir(args);
+        let mut library_dirs = build_targets
+            .iter()
+            .map(|target| target.to_rustc_target())
+            .collect::<Vec<_>>();
+        library_dirs.push(crate::lipo::UNIVERSAL_SIMULATOR_DIR);
+
+        let mut fingerprint = crate::fingerprint::Fingerprint::new();
+        for dir in library_dirs {
+            let dir = self.cargo_target_dir().join(dir).join(&profile);
+            fingerprint
+                .metadata(&dir.join(format!("lib{}.a", app_name)))
+                .metadata(&dir.join(format!("lib{}.dylib", app_name)));
+        }
+        let manifest_dir = self.manifest_path.parent().unwrap();
+        for asset in self.ios_metadata()?.assets.unwrap_or_default() {
+            fingerprint.metadata(&manifest_dir.join(asset.root()));
+        }
+        fingerprint
+            .contents(&self.src_dir)
+            .contents(&project_dir.join("project.yml"))
+            .contents(
+                &project_dir
+                    .join(format!("{}.xcodeproj", app_name))
+                    .join("project.pbxproj"),
+            )
+            .contents(&project_dir.join("Podfile.lock"));
+
+        Ok(fingerprint)
+    }
+
+    /// Cargo arguments for `target`: `args` with its feature flags merged with the
+    /// `target_features` of `Cargo.toml`.
+    fn target_cargo_args(
+        &self,
+        args: &[String],
+        target: crate::toml::Target,
+    ) -> anyhow::Result<Vec<String>> {
+        let mut args = args.to_vec();
+        let features = crate::ide::Features::take_from_args(&mut args);
+        let target_features = self
+            .ios_metadata()?
+            .target_features
+            .unwrap_or_default()
+            .remove(target.to_rustc_target())
+            .unwrap_or_default();
+        args.extend(features.to_args(&target_features));
+        Ok(args)
+    }
+
+    /// Next build number from the `build_number` source in `Cargo.toml`.
+    /// The `.build-number` counter is only saved by [`Xcodebuild::save_build_number`].
+    pub fn next_build_number(&self) -> anyhow::Result<String> {
+        let manifest_dir = self.manifest_path.parent().unwrap();
+        match self.ios_metadata()?.build_number.unwrap_or_default() {
+            crate::toml::BuildNumber::Counter => crate::build_number::next_counter(manifest_dir),
+            crate::toml::BuildNumber::Git => crate::build_number::git_commit_count(manifest_dir),
+        }
+    }
+
+    /// Save `build_number` as the `.build-number` counter. Git commit counts aren't saved.
+    pub fn save_build_number(&self, build_number: &str) -> anyhow::Result<()> {
+        let manifest_dir = self.manifest_path.parent().unwrap();
+        match self.ios_metadata()?.build_number.unwrap_or_default() {
+            crate::toml::BuildNumber::Counter => {
+                crate::build_number::save_counter(manifest_dir, build_number)
+            }
+            crate::toml::BuildNumber::Git => Ok(()),
+        }
+    }
+
+    fn size_baseline_path(&self) -> anyhow::Result<PathBuf> {
+        let toml = self.toml()?;
+        let app_name = crate::xcodegen::Project::target_name(&toml);
+        Ok(self.target_dir.join(app_name).join("size-baseline"))
+    }
+
+    /// App size in bytes saved with [`Xcodebuild::save_size_baseline`], if any.
+    pub fn size_baseline(&self) -> anyhow::Result<Option<u64>> {
+        let path = self.size_baseline_path()?;
+        if !path.exists() {
+            return Ok(None);
+        }
+        let content =
+            std::fs::read_to_string(&path).with_context(|| format!("Failed to read {:?}", path))?;
+        let size = content
+            .trim()
+            .parse()
+            .with_context(|| format!("Failed to parse size baseline {:?}", path))?;
+        Ok(Some(size))
+    }
+
+    /// Save `size` in bytes as the baseline app size compared against by `build --small`.
+    pub fn save_size_baseline(&self, size: u64) -> anyhow::Result<()> {
+        let path = self.size_baseline_path()?;
+        std::fs::write(&path, size.to_string())
+            .with_context(|| format!("Failed to write {:?}", path))
+    }
+
+    /// Load the artifacts of the last successful [`Xcodebuild::build`] of this crate.
+    pub fn last_build_artifacts(&self) -> anyhow::Result<BuildArtifacts> {
+        let toml = self.toml()?;
+        let app_name = crate::xcodegen::Project::target_name(&toml);
+        BuildArtifacts::load(&self.target_dir.join(app_name))
+    }
+
+    /// Install and launch the app built by [`Xcodebuild::build`].
+    pub fn run(&self, artifacts: &BuildArtifacts) -> anyhow::Result<()> {
+        self.run_with(artifacts, &RunOptions::default())
+    }
+
+    /// Same as [`Xcodebuild::run`] with explicit [`RunOptions`].
+    pub fn run_with(&self, artifacts: &BuildArtifacts, options: &RunOptions) -> anyhow::Result<()> {
+        log::info!("Running project");
+
+        let selected_device = artifacts.destination.resolve()?;
+
+        let app_path_str = artifacts.app_path();
+        let full_app_name = artifacts.bundle_id();
+
+        log::debug!("{} path: {}", full_app_name, app_path_str);
+
+        match selected_device {
+            SelectedDevice::Device(ref md) => {
+                log::info!(
+                    "Installing app `{}` {} on connected device {}",
+                    full_app_name,
+                    app_path_str,
+                    md.identifier
+                );
+
+                if options.has_accessibility_settings() {
+                    log::warn!("Accessibility settings are only applied to simulators");
+                }
+                if options.clean_status_bar == Some(true) {
+                    log::warn!("The status bar can only be overridden on simulators");
+                }
+                if artifacts.is_installed() && md.app_bundle_path(&full_app_name).is_ok() {
+                    log::info!(
+                        "{} is up to date on device {}",
+                        full_app_name,
+                        md.identifier
+                    );
+                } else {
+                    let app_path = artifacts.project_dir.join(&app_path_str);
+                    crate::signing::verify_app_for_device(&app_path, &md.identifier)?;
+                    self.phase(crate::events::Phase::Install, || md.install_app(&app_path))?;
+                    artifacts.set_installed()?;
+                }
+                self.phase(crate::events::Phase::Launch, || {
+                    Self::run_app_with_device(md, &full_app_name, &options.launch_args())
+                })?;
+                if options.monitor {
+                    let trace = artifacts.project_dir.join("monitor.trace");
+                    eprintln!(
+                        "Recording CPU, memory and GPU usage of {}",
+                        artifacts.app_name
+                    );
+                    crate::monitor::record_device(
+                        &md.identifier,
+                        &artifacts.app_name,
+                        options.monitor_duration,
+                        &trace,
+                    )?;
+                    eprintln!("Trace is saved to {:?}, open it in Instruments", trace);
+                }
+            }
+            SelectedDevice::Simulator { ref udid } => {
+                if artifacts.is_installed()
+                    && crate::simctl::app_container(udid, &full_app_name, "app").is_ok()
+                {
+                    log::info!("{} is up to date on simulator {}", full_app_name, udid);
+                } else {
+                    self.phase(crate::events::Phase::Install, || {
+                        Self::install_app_to_simulator(&artifacts.project_dir, &app_path_str, udid)
+                    })?;
+                    artifacts.set_installed()?;
+                }
+                options.apply_accessibility_settings(udid)?;
+                let permissions = self.ios_metadata()?.permissions.unwrap_or_default();
+                for service in crate::simctl::privacy_services(&permissions)? {
+                    crate::simctl::grant_permission(udid, service, &full_app_name)?;
+                }
+                match options
+                    .clean_status_bar
+                    .or(self.ios_metadata()?.clean_status_bar)
+                {
+                    Some(true) => crate::simctl::override_status_bar(udid)?,
+                    Some(false) => crate::simctl::clear_status_bar(udid)?,
+                    None => {}
+                }
+                if options.console {
+                    return self.run_app_with_simulator_console(udid, &full_app_name, options);
+                }
+                let pid = self.phase(crate::events::Phase::Launch, || {
+                    Self::run_app_with_simulator(udid, &full_app_name, &options.launch_args())
+                })?;
+                if options.monitor {
+                    let pid = pid.with_context(|| {
+                        format!("Failed to get pid of {} to monitor it", full_app_name)
+                    })?;
+                    Self::monitor_app(pid, options.monitor_duration)?;
+                }
+            }
+            SelectedDevice::Mac => {
+                if options.has_accessibility_settings() {
+                    log::warn!("Accessibility settings are only applied to simulators");
+                }
+                if options.monitor {
+                    log::warn!("Resource monitoring is not supported for Mac apps");
+                }
+                let app_path = artifacts.project_dir.join(&app_path_str);
+                self.phase(crate::events::Phase::Launch, || {
+                    Self::run_app_on_mac(
+                        &app_path,
+                        &artifacts.app_name,
+                        options.console,
+                        &options.launch_args(),
+                    )
+                })?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Build and run the app, then rebuild and relaunch it whenever `src`, `assets`, the asset
+    /// paths from `Cargo.toml` or the manifest itself change. Runs until interrupted.
+    /// Changes of existing files in `assets` are synced into the installed app without a build,
+    /// see [`Xcodebuild::hot_sync`].
+    pub fn watch(
+        &self,
+        args: &[String],
+        build_type: BuildType,
+        options: BuildOptions,
+        run_options: &RunOptions,
+    ) -> anyhow::Result<()> {
+        let manifest_dir = self.manifest_path.parent().unwrap();
+        let mut paths = vec![
+            self.manifest_path.clone(),
+            manifest_dir.join("src"),
+            manifest_dir.join("assets"),
+        ];
+        for asset in self.ios_metadata()?.assets.unwrap_or_default() {
+            let path = manifest_dir.join(asset.root());
+            if !paths.iter().any(|watched| path.starts_with(watched)) {
+                paths.push(path);
+            }
+        }
+        // The app keeps running in the background between rebuilds.
+        let run_options = RunOptions {
+            console: false,
+            ..run_options.clone()
+        };
+
+        let mut watcher = crate::watch::Watcher::new(paths);
+        let mut last_artifacts = None;
+        let mut changed = vec![];
+        loop {
+            let synced = match last_artifacts {
+                Some(ref artifacts) if !changed.is_empty() => self
+                    .hot_sync(artifacts, &changed, &run_options)
+                    .unwrap_or_else(|err| {
+                        log::warn!("Failed to sync assets, rebuilding: {:?}", err);
+                        false
+                    }),
+                _ => false,
+            };
+            if !synced {
+                let result = self.build_with(args, build_type, options.clone());
+                self.emit(crate::events::Event::BuildFinished {
+                    success: result.is_ok(),
+                });
+                last_artifacts = result.as_ref().ok().cloned();
+                match result.and_then(|artifacts| self.run_with(&artifacts, &run_options)) {
+                    Ok(()) => log::info!("Watching for changes"),
+                    Err(err) => log::error!("{:?}", err),
+                }
+            }
+            changed = watcher.wait();
+            log::info!("Changed: {:?}", changed);
+        }
+    }
+
+    /// Copy `changed` files of `assets` folders into the app installed from `artifacts` and
+    /// relaunch it, skipping cargo and xcodebuild. Simulator apps are updated in their container.
+    /// Device apps are read-only once installed, so the built app is updated, signed again and
+    /// reinstalled. Returns `false` if a build is required instead: for changes outside `assets`,
+    /// added or removed files, compiled resources like asset catalogs and Mac apps.
+    pub fn hot_sync(
+        &self,
+        artifacts: &BuildArtifacts,
+        changed: &[PathBuf],
+        options: &RunOptions,
+    ) -> anyhow::Result<bool> {
+        let app_path = artifacts.project_dir.join(artifacts.app_path());
+        let files = match crate::watch::hot_sync_files(
+            &self.ios_metadata()?.assets.unwrap_or_default(),
+            self.manifest_path.parent().unwrap(),
+            &app_path,
+            changed,
+        ) {
+            Some(files) => files,
+            None => return Ok(false),
+        };
+
+        let bundle_id = artifacts.bundle_id();
+        // The installed app no longer matches the build.
+        if let Some(path) = artifacts.installed_fingerprint_path() {
+            crate::fingerprint::invalidate(&path);
+        }
+        match artifacts.destination.resolve()? {
+            SelectedDevice::Simulator { ref udid } => {
+                let container = crate::simctl::app_container(udid, &bundle_id, "app")?;
+                crate::watch::copy_to_bundle(&files, &container)?;
+                Self::run_app_with_simulator(udid, &bundle_id, &options.launch_args())?;
+            }
+            SelectedDevice::Device(ref md) => {
+                crate::watch::copy_to_bundle(&files, &app_path)?;
+                crate::signing::resign(&app_path)?;
+                self.phase(crate::events::Phase::Install, || md.install_app(&app_path))?;
+                Self::run_app_with_device(md, &bundle_id, &options.launch_args())?;
+            }
+            SelectedDevice::Mac => return Ok(false),
+        }
+        log::info!("Watching for changes");
+
+        Ok(true)
+    }
+
+    /// Install the app built by [`Xcodebuild::build`], launch it stopped and attach `lldb` with the
+    /// Rust pretty-printers loaded. Simulator apps are launched waiting for the debugger, iOS 17
+    /// and newer devices with `devicectl` and older devices through `debugserver`.
+    pub fn debug(&self, artifacts: &BuildArtifacts, options: &RunOptions) -> anyhow::Result<()> {
+        let bundle_id = artifacts.bundle_id();
+        let app_path_str = artifacts.app_path();
+        let app_path = artifacts.project_dir.join(&app_path_str);
+        let args = options.launch_args();
+
+        let mut commands = crate::debug::rust_formatters()?;
+        match artifacts.destination.resolve()? {
+            SelectedDevice::Device(ref md) => {
+                crate::signing::verify_app_for_device(&app_path, &md.identifier)?;
+                self.phase(crate::events::Phase::Install, || md.install_app(&app_path))?;
+                if crate::devices::runtime_version_at_least(&md.product_version, "17") {
+                    let pid = Self::launch_stopped_on_device(md, &bundle_id, &args)?;
+                    commands.extend(crate::debug::attach_device(&md.identifier, pid));
+                } else {
+                    let device_app_path = md.app_bundle_path(&bundle_id)?;
+                    let port = md.forward_debugserver()?;
+                    commands.extend(crate::debug::launch_remote(
+                        &app_path,
+                        &device_app_path,
+                        port,
+                        &args,
+                    ));
+                }
+            }
+            SelectedDevice::Simulator { ref udid } => {
+                self.phase(crate::events::Phase::Install, || {
+                    Self::install_app_to_simulator(&artifacts.project_dir, &app_path_str, udid)
+                })?;
+                let pid = crate::simctl::launch_for_debugger(udid, &bundle_id, &args)?;
+                commands.extend(crate::debug::attach(pid));
+            }
+            SelectedDevice::Mac => {
+                let executable = app_path
+                    .join("Contents")
+                    .join("MacOS")
+                    .join(&artifacts.app_name);
+                commands.extend(crate::debug::launch_local(&executable, &args));
+            }
+        }
+
+        println!(
+            "The app is stopped. Set breakpoints, e.g. `b my_crate::update`, then `continue`."
+        );
+        crate::debug::run(&commands)
+    }
+
+    /// Archive the data container of the last built app on its simulator as snapshot `name`.
+    pub fn save_app_data(&self, name: &str) -> anyhow::Result<PathBuf> {
+        let (udid, bundle_id) = self.last_simulator_app("App data snapshots")?;
+        crate::data::save_app(
+            &udid,
+            &bundle_id,
+            self.manifest_path.parent().unwrap(),
+            name,
+        )
+    }
+
+    /// Replace the data container of the last built app on its simulator with snapshot `name`.
+    pub fn restore_app_data(&self, name: &str) -> anyhow::Result<()> {
+        let (udid, bundle_id) = self.last_simulator_app("App data snapshots")?;
+        crate::data::restore_app(
+            &udid,
+            &bundle_id,
+            self.manifest_path.parent().unwrap(),
+            name,
+        )
+    }
+
+    /// Send the push notification `payload` JSON file to the last built app on its simulator.
+    pub fn push(&self, payload: &Path) -> anyhow::Result<()> {
+        let (udid, bundle_id) = self.last_simulator_app("Push notifications")?;
+        let contents = std::fs::read_to_string(payload)
+            .with_context(|| format!("Failed to read {:?}", payload))?;
+        crate::simctl::validate_push_payload(&contents)
+            .with_context(|| format!("Invalid push payload {:?}", payload))?;
+        crate::simctl::push(&udid, &bundle_id, payload)
+    }
+
+    /// Open `url`, e.g. a custom URL scheme or universal link, on the destination of the last
+    /// build. Devices open it in the last built app with `devicectl`, which needs Xcode 15.
+    pub fn open_url(&self, url: &str) -> anyhow::Result<()> {
+        let artifacts = self.last_build_artifacts()?;
+        match artifacts.destination.resolve()? {
+            SelectedDevice::Device(ref md) => {
+                log::info!("Opening {} on device {}", url, md.identifier);
+                let output = Command::new("xcrun")
+                    .arg("devicectl")
+                    .arg("device")
+                    .arg("process")
+                    .arg("launch")
+                    .arg("--device")
+                    .arg(&md.identifier)
+                    .arg("--payload-url")
+                    .arg(url)
+                    .arg(artifacts.bundle_id())
+                    .output()
+                    .with_context(|| "Failed to get xcrun output".to_string())?;
+
+                if !output.status.success() {
+                    let stdout = String::from_utf8_lossy(&output.stdout);
+                    let stderr = String::from_utf8_lossy(&output.stderr);
+
+                    anyhow::bail!("Failed to open {}:\n{:?}\n{:?}", url, stdout, stderr)
+                }
+                Ok(())
+            }
+            SelectedDevice::Simulator { ref udid } => crate::simctl::open_url(udid, url),
+            SelectedDevice::Mac => {
+                log::info!("Opening {}", url);
+                let status = Command::new("open")
+                    .arg(url)
+                    .status()
+                    .with_context(|| "Failed to run open".to_string())?;
+                if !status.success() {
+                    anyhow::bail!("Failed to open {}: {}", url, status)
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// Remove the last built app from its simulator or device.
+    pub fn uninstall(&self) -> anyhow::Result<()> {
+        let artifacts = self.last_build_artifacts()?;
+        let bundle_id = artifacts.bundle_id();
+        match artifacts.destination.resolve()? {
+            SelectedDevice::Device(ref md) => {
+                log::info!("Uninstalling {} from device {}", bundle_id, md.identifier);
+                md.uninstall_app(&bundle_id)
+            }
+            SelectedDevice::Simulator { ref udid } => {
+                log::info!("Uninstalling {} from simulator {}", bundle_id, udid);
+                crate::simctl::simctl(["uninstall", udid.as_str(), bundle_id.as_str()])?;
+                Ok(())
+            }
+            SelectedDevice::Mac => {
+                log::info!(
+                    "{} runs from the build folder, nothing to uninstall",
+                    bundle_id
+                );
+                Ok(())
+            }
+        }
+    }
+
+    /// Stream console output of the last built app from its simulator or device until interrupted.
+    pub fn logs(&self) -> anyhow::Result<()> {
+        let artifacts = self.last_build_artifacts()?;
+        match artifacts.destination.resolve()? {
+            SelectedDevice::Device(ref md) => crate::logs::stream_device(md, &artifacts.app_name),
+            SelectedDevice::Simulator { ref udid } => {
+                crate::logs::stream_simulator(udid, &artifacts.bundle_id(), &artifacts.app_name)
+            }
+            SelectedDevice::Mac => {
+                crate::logs::stream_mac(&artifacts.bundle_id(), &artifacts.app_name)
+            }
+        }
+    }
+
+    /// Save a screenshot of the simulator or device of the last build to `path`, or to
+    /// `screenshot-<timestamp>.png` in the current directory. Returns the path.
+    pub fn screenshot(&self, path: Option<&Path>) -> anyhow::Result<PathBuf> {
+        match self.last_build_artifacts()?.destination.resolve()? {
+            SelectedDevice::Device(ref md) => crate::capture::device_screenshot(md, path),
+            SelectedDevice::Simulator { ref udid } => {
+                crate::capture::simulator_screenshot(udid, path)
+            }
+            SelectedDevice::Mac => {
+                anyhow::bail!("Screenshots are only supported on simulators and devices")
+            }
+        }
+    }
+
+    /// Record a video of the simulator of the last build to `path`, or to
+    /// `recording-<timestamp>.mov` in the current directory, until Enter is pressed.
+    /// Returns the path.
+    pub fn record(&self, path: Option<&Path>) -> anyhow::Result<PathBuf> {
+        match self.last_build_artifacts()?.destination {
+            Destination::Simulator { ref udid } => crate::capture::record_simulator(udid, path),
+            Destination::Device { .. } | Destination::Mac => {
+                anyhow::bail!("Video recording is only supported on simulators")
+            }
+        }
+    }
+
+    /// Names of saved app data snapshots.
+    pub fn app_data_snapshots(&self) -> anyhow::Result<Vec<String>> {
+        crate::data::snapshots(self.manifest_path.parent().unwrap())
+    }
+
+    /// Simulator udid and bundle id of the last build. `feature` names what fails otherwise.
+    fn last_simulator_app(&self, feature: &str) -> anyhow::Result<(String, String)> {
+        let artifacts = self.last_build_artifacts()?;
+        match artifacts.destination {
+            Destination::Simulator { ref udid } => Ok((udid.clone(), artifacts.bundle_id())),
+            Destination::Device { .. } | Destination::Mac => {
+                anyhow::bail!("{} are only supported on simulators", feature)
+            }
+        }
+    }
+
+    /// Build the app for simulators, then capture App Store screenshots for every device type
+    /// and screen configured in `[package.metadata.ios.screenshots]`.
+    /// Returns the directory with the screenshots.
+    pub fn screenshots(&self, args: &[String], build_type: BuildType) -> anyhow::Result<PathBuf> {
+        let plan =
+            crate::screenshots::Plan::new(self.ios_metadata()?.screenshots.unwrap_or_default());
+
+        // Simulator builds run on every simulator, so the app is built only once.
+        let mut built: Option<BuildArtifacts> = None;
+        let mut screenshots_dir = None;
+        for device_type in &plan.device_types {
+            let udid = crate::screenshots::find_or_create_simulator(device_type)?;
+            crate::simctl::boot_and_wait(&udid)?;
+            let _status_bar = crate::simctl::StatusBarOverride::new(&udid)?;
+
+            if built.is_none() {
+                built = Some(self.build_with(
+                    args,
+                    build_type,
+                    BuildOptions {
+                        device: Some(SelectedDevice::Simulator { udid: udid.clone() }),
+                        ..Default::default()
+                    },
+                )?);
+            }
+            let artifacts = built.as_ref().unwrap();
+            let dir = artifacts.project_dir.join("screenshots");
+            crate::screenshots::capture(
+                &udid,
+                &artifacts.project_dir.join(artifacts.app_path()),
+                &artifacts.bundle_id(),
+                &dir.join(device_type),
+                &plan,
+            )?;
+
+            screenshots_dir = Some(dir);
+        }
+
+        screenshots_dir.with_context(|| "No screenshot devices configured".to_string())
+    }
+
+    /// Remove the generated project of this crate with its derived data, and the derived data of
+    /// Xcode for it. With `cargo`, also run `cargo clean` for every configured build target.
+    /// Projects of other workspace members and the editor remembered by `open --with` are kept.
+    /// Returns the removed items.
+    pub fn clean(&self, args: &[String], cargo: bool) -> anyhow::Result<Vec<String>> {
+        let mut removed = vec![];
+        let toml = self.toml()?;
+        let app_name = crate::xcodegen::Project::target_name(&toml);
+
+        // Other workspace members share the directory, only the project of this crate is removed.
+        let project_dir = self.target_dir.join(&app_name);
+        if project_dir.exists() {
+            log::debug!("Removing {:?}", project_dir);
+            std::fs::remove_dir_all(&project_dir)
+                .with_context(|| format!("Failed to remove dir: {:?}", project_dir))?;
+            removed.push(format!("{}", project_dir.display()));
+        }
+
+        // Xcode keeps derived data of projects opened in the IDE in its own folder.
+        if let Some(home) = std::env::var_os("HOME") {
+            let derived_data = Path::new(&home).join("Library/Developer/Xcode/DerivedData");
+            let prefix = format!("{}-", app_name);
+            for entry in std::fs::read_dir(&derived_data)
+                .into_iter()
+                .flatten()
+                .flatten()
+            {
+                let path = entry.path();
+                if !entry.file_name().to_string_lossy().starts_with(&prefix) {
+                    continue;
+                }
+                let workspace = crate::plist::read(&path.join("info.plist"))
+                    .ok()
+                    .and_then(|info| info["WorkspacePath"].as_str().map(PathBuf::from));
+                if matches!(workspace, Some(ref w) if w.starts_with(&project_dir)) {
+                    log::debug!("Removing {:?}", path);
+                    std::fs::remove_dir_all(&path)
+                        .with_context(|| format!("Failed to remove dir: {:?}", path))?;
+                    removed.push(format!("{}", path.display()));
+                }
+            }
+        }
+
+        if cargo {
+            let build_targets = toml.ios_metadata()?.build_targets.unwrap_or_default();
+            for target in build_targets {
+                log::info!("Cleaning target: {}", target.to_rustc_target());
+                crate::cargo::run_cargo("clean", args, Some(target.to_rustc_target()))?;
+                removed.push(format!("cargo build of {}", target.to_rustc_target()));
+            }
+            let universal = self
+                .cargo_target_dir()
+                .join(crate::lipo::UNIVERSAL_SIMULATOR_DIR);
+            if universal.exists() {
+                std::fs::remove_dir_all(&universal)
+                    .with_context(|| format!("Failed to remove dir: {:?}", universal))?;
+                removed.push(format!("{}", universal.display()));
+            }
+        }
+
+        Ok(removed)
+    }
+
+    /// Remove generated projects of other (e.g. renamed) targets and derived data not modified
+    /// for `older_than`, and delete shut down iOS, tvOS and watchOS simulators created by
+    /// cargo-xcodebuild. Returns a description of every removed item.
+    pub fn gc(&self, older_than: std::time::Duration) -> anyhow::Result<Vec<String>> {
+        let app_names = crate::gc::workspace_app_names(&self.manifest_path, &self.toml()?)?;
+        let mut removed = crate::gc::remove_stale_projects(
+            &self.target_dir,
+            &[
+                self.src_dir.clone(),
+                self.target_dir.join(crate::source::XCTEST_DIR),
+            ],
+            &app_names,
+            older_than,
+        )?;
+        removed.extend(crate::gc::remove_unused_simulators(
+            &Self::get_all_simulators()?,
+            older_than,
+        )?);
+
+        Ok(removed)
+    }
+
+    /// Notarize and staple a signed macOS or Mac Catalyst `.app` (or `.dmg`) with settings from
+    /// `[package.metadata.ios.notarization]`. Returns the path of the distributable.
+    pub fn notarize(&self, path: &Path) -> anyhow::Result<PathBuf> {
+        let ios_metadata = self.ios_metadata()?;
+        let config = ios_metadata.notarization.clone().unwrap_or_default();
+        let credentials = crate::notarize::NotaryCredentials::resolve(
+            &config,
+            ios_metadata.app_store_connect.as_ref(),
+            self.manifest_path.parent().unwrap(),
+        )?;
+
+        crate::notarize::notarize(path, &credentials, config.dmg.unwrap_or(false))
+    }
+
+    /// Upload an `.ipa`, the one of the last [`Xcodebuild::archive`] by default, to App Store Connect
+    /// for TestFlight. Returns the uploaded path.
+    pub fn upload(&self, ipa_path: Option<&Path>) -> anyhow::Result<PathBuf> {
+        let ipa_path = match ipa_path {
+            Some(path) => path.to_path_buf(),
+            None => {
+                let artifacts = self.last_archive_artifacts()?;
+                if artifacts.export_method != crate::toml::ExportMethod::AppStore {
+                    anyhow::bail!(
+                        "The last archive was exported for {} distribution. Set `export_method = \"app-store\"` and archive again to upload it",
+                        artifacts.export_method.as_str()
+                    )
+                }
+                artifacts.ipa_path
+            }
+        };
+        let key = self.authentication_key()?.with_context(|| {
+            "Uploading requires `app_store_connect` in Cargo.toml or APP_STORE_CONNECT_* environment variables".to_string()
+        })?;
+
+        crate::upload::upload_ipa(&ipa_path, &key)?;
+        Ok(ipa_path)
+    }
+
+    /// Upload existing dSYM bundles with the `symbol_upload` steps from `Cargo.toml`.
+    pub fn upload_symbols(&self, dsym_paths: &[PathBuf]) -> anyhow::Result<()> {
+        let steps = self.ios_metadata()?.symbol_upload.unwrap_or_default();
+        if steps.is_empty() {
+            anyhow::bail!(
+                "Missing `symbol_upload` in `package.metadata.ios` section. Please check Cargo.toml."
+            )
+        }
+        let dsym_paths = dsym_paths
+            .iter()
+            .filter(|p| p.exists())
+            .cloned()
+            .collect::<Vec<_>>();
+
+        crate::symbols::upload_symbols(&steps, &dsym_paths, self.manifest_path.parent().unwrap())
+    }
+
+    /// Export thinned IPAs from an existing archive and return the size of each device variant.
+    /// `thinning` is a device model identifier like `iPhone10,1`, or all variants if `None`.
+    pub fn export_thinned(
+        &self,
+        archive_path: &Path,
+        thinning: Option<&str>,
+    ) -> anyhow::Result<Vec<crate::export::ThinnedVariant>> {
+        let app_name = crate::xcodegen::Project::target_name(&self.toml()?);
+        crate::export::export_thinned(
+            archive_path,
+            &self.target_dir.join(app_name).join("thinned"),
+            thinning,
+            self.authentication_key()?.as_ref(),
+        )
+    }
+
+    /// Signing team from the keychain for `development_team` or `development_team_name`.
+    fn select_team<'a>(
+        teams: &'a [crate::teams::Team],
+        toml: &crate::toml::Root,
+    ) -> anyhow::Result<Option<&'a crate::teams::Team>> {
+        let ios_metadata = toml.ios_metadata()?;
+        let team = crate::teams::select(
+            teams,
+            ios_metadata.development_team.as_deref(),
+            ios_metadata.development_team_name.as_deref(),
+        )?;
+        log::info!("Selected signing team: {:?}", team);
+        Ok(team)
+    }
+
+    /// Generate the Xcode project without building anything.
+    pub fn generate_project(&self) -> anyhow::Result<()> {
+        log::info!("Generating xcodeproject");
+
+        let toml = self.toml()?.validate_crate_type()?;
+
+        self.unlock_keychain()?;
+        let teams = crate::teams::find_development_teams();
+        let team = Self::select_team(&teams, &toml)?;
+        let app_name = crate::xcodegen::Project::target_name(&toml);
+        let project = crate::xcodegen::Project::from_toml(&self.manifest_path, toml, team)?;
+
+        let project_dir = self.target_dir.join(&app_name);
+        self.prepare_target_dir(&project_dir)?;
+        self.generate_xcode_project(&project, &project_dir)?;
+
+        Ok(())
+    }
+
+    /// Generate the project and check the app icons, launch screen and Info.plist for problems
+    /// that App Store Connect would reject the app for.
+    pub fn preflight(&self) -> anyhow::Result<Vec<crate::preflight::Violation>> {
+        self.generate_project()?;
+        self.preflight_checks()
+    }
+
+    /// Checks of [`Xcodebuild::preflight`] on the generated project. App icons are checked for the
+    /// app platform and `TARGETED_DEVICE_FAMILY`.
+    fn preflight_checks(&self) -> anyhow::Result<Vec<crate::preflight::Violation>> {
+        let project_folder = self.manifest_path.parent().unwrap();
+        let ios_metadata = self.ios_metadata()?;
+        let mut asset_dirs = ios_metadata
+            .assets
+            .as_deref()
+            .unwrap_or_default()
+            .iter()
+            .map(|asset| project_folder.join(asset.root()))
+            .collect::<Vec<_>>();
+        // Generated app icon and launch screen.
+        asset_dirs.push(self.src_dir.clone());
+        let info_plist = crate::plist::read(&self.src_dir.join("Info.plist"))?;
+
+        let required_icons = crate::preflight::required_icons(
+            ios_metadata.platform.unwrap_or_default(),
+            &ios_metadata.device_family(),
+        );
+        let mut violations = crate::preflight::check_icons(&asset_dirs, &required_icons)?;
+        violations.extend(crate::preflight::check_launch_screen(
+            &asset_dirs,
+            &info_plist,
+        ));
+        violations.extend(crate::preflight::check_info_plist(&info_plist));
+
+        Ok(violations)
+    }
+
+    /// Check the tools needed to build and run the app: Xcode, command line tools, xcodegen,
+    /// rust targets of `build_targets`, a signing identity and a booted simulator.
+    pub fn doctor(&self) -> anyhow::Result<Vec<crate::doctor::Diagnostic>> {
+        let ios_metadata = self.ios_metadata()?;
+        let platform = ios_metadata.platform.unwrap_or_default();
+        let build_targets = ios_metadata
+            .build_targets
+            .clone()
+            .unwrap_or_default()
+            .into_iter()
+            .map(crate::toml::Target::to_rustc_target)
+            .collect::<Vec<_>>();
+
+        let mut diagnostics = vec![
+            crate::doctor::check_xcode(),
+            crate::doctor::check_command_line_tools(),
+            crate::doctor::check_xcodegen(
+                ios_metadata.project_generator == Some(crate::toml::ProjectGenerator::Xcodegen)
+                    || ios_metadata.project_overlay.is_some(),
+            ),
+            crate::doctor::check_rust_targets(&build_targets),
+            crate::doctor::check_signing_identity(),
+        ];
+        if !ios_metadata.runs_on_mac() {
+            diagnostics.push(crate::doctor::check_booted_simulator(platform));
+        }
+
+        Ok(diagnostics)
+    }
+
+    /// Check the app of the last build for common App Store requirements:
+    /// Info.plist keys, minimum OS, usage descriptions, encryption declaration and privacy manifest.
+    pub fn lint(&self) -> anyhow::Result<Vec<crate::preflight::Violation>> {
+        let artifacts = self.last_build_artifacts()?;
+        let deployment_target = self.ios_metadata()?.deployment_target_or_default();
+        crate::preflight::lint_app(
+            &artifacts.project_dir.join(artifacts.app_path()),
+            &deployment_target,
+        )
+    }
+
+    /// Boot a simulator and open Simulator.app.
+    pub fn boot_simulator(&self, device_id: &str) -> anyhow::Result<()> {
+        let output = Command::new("xcrun")
+            .arg("simctl")
+            .arg("boot")
+            .arg(device_id)
+            .output()
+            .with_context(|| {
+                format!(
+                    "Failed to get output from command: xcrun simctl boot {}",
+                    device_id
+                )
+            })?;
+
+        if !output.status.success() {
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            let stderr = String::from_utf8_lossy(&output.stderr);
+
+            anyhow::bail!(
+                "Failed to boot simulator with id: {}\n{:?}\n{:?}",
+                &device_id,
+                &stdout,
+                &stderr,
+            )
+        }
+
+        let output = Command::new("open")
+            .arg("-a")
+            .arg("Simulator.app")
+            .output()
+            .with_context(|| "Failed to open Simulator.app".to_string())?;
+
+        if !output.status.success() {
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            let stderr = String::from_utf8_lossy(&output.stderr);
+
+            anyhow::bail!(
+                "Failed to open Simulator.app: {}\n{:?}\n{:?}",
+                &device_id,
+                &stdout,
+                &stderr,
+            )
+        }
+
+        Ok(())
+    }
+
+    /// Create a simulator of `device_type`, e.g. `iPhone 15`, with `runtime`, e.g. `iOS17.4`, and
+    /// return its udid. Uses the newest runtime supporting the device type if `runtime` is `None`
+    /// and the device type name if `name` is `None`.
+    pub fn create_simulator(
+        device_type: &str,
+        runtime: Option<&str>,
+        name: Option<&str>,
+    ) -> anyhow::Result<String> {
+        let runtimes = crate::simctl::runtimes()?;
+        let runtime = match runtime {
+            Some(runtime) => {
+                crate::simctl::find_runtime(&runtimes, runtime).with_context(|| {
+                    format!(
+                        "Simulator runtime {} is not installed. Installed runtimes: {}",
+                        runtime,
+                        runtimes
+                            .iter()
+                            .filter(|r| r.is_available)
+                            .map(|r| r.name.as_str())
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    )
+                })?
+            }
+            None => runtimes
+                .iter()
+                .rev()
+                .filter(|r| r.is_available)
+                .find(|r| crate::simctl::find_device_type(r, device_type).is_some())
+                .with_context(|| {
+                    format!("No installed simulator runtime supports {}", device_type)
+                })?,
+        };
+        let device_type =
+            crate::simctl::find_device_type(runtime, device_type).with_context(|| {
+                format!(
+                    "{} doesn't support {}. Supported device types: {}",
+                    runtime.name,
+                    device_type,
+                    runtime
+                        .supported_device_types
+                        .iter()
+                        .map(|d| d.name.as_str())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                )
+            })?;
+        crate::simctl::create(
+            name.unwrap_or(&device_type.name),
+            &device_type.identifier,
+            Some(runtime.identifier.as_str()),
+        )
+    }
+
+    /// Shut down a booted simulator, or every booted simulator if `udid` is `None`.
+    pub fn shutdown_simulator(udid: Option<&str>) -> anyhow::Result<()> {
+        crate::simctl::shutdown(udid.unwrap_or("all"))
+    }
+
+    /// Erase the content and settings of a simulator, or of every simulator if `udid` is `None`.
+    /// Booted simulators are shut down first.
+    pub fn erase_simulator(udid: Option<&str>) -> anyhow::Result<()> {
+        let udid = match udid {
+            Some(udid) => udid,
+            None => {
+                crate::simctl::shutdown("all")?;
+                return crate::simctl::erase("all");
+            }
+        };
+        let booted = Self::get_all_devices()?.iter().any(|d| {
+            d.udid == udid
+                && d.connection == crate::devices::Connection::Simulator
+                && d.state == crate::devices::DeviceState::Booted
+        });
+        if booted {
+            crate::simctl::shutdown(udid)?;
+        }
+        crate::simctl::erase(udid)
+    }
+
+    /// Open the generated project with Xcode.
+    ///
+    /// `with` selects the application, e.g. `/Applications/Xcode-beta.app`. The choice is
+    /// remembered for the next calls without `with`. The default `open` association is used
+    /// if nothing was ever chosen.
+    pub fn open_xcode(&self, with: Option<&Path>) -> anyhow::Result<()> {
+        log::trace!("Opening xcode project");
+        let open_with_path = self.target_dir.join("open_with");
+        let app = match with {
+            Some(app) => {
+                if !app.exists() {
+                    anyhow::bail!("Application is not found: {:?}", app)
+                }
+                std::fs::create_dir_all(&self.target_dir)
+                    .with_context(|| format!("Failed to create all dir: {:?}", self.target_dir))?;
+                std::fs::write(&open_with_path, app.to_string_lossy().as_bytes())
+                    .with_context(|| format!("Failed to write {:?}", open_with_path))?;
+                Some(app.to_path_buf())
+            }
+            None => std::fs::read_to_string(&open_with_path)
+                .ok()
+                .map(|app| PathBuf::from(app.trim())),
+        };
+        let app = app.as_deref();
+
+        // First searching for provided manifest path. Then search Cargo.toml in cwd.
+        let target_dir_str = self.target_dir.to_str().unwrap();
+        let provided_toml = self.toml()?;
+        let provided_project_path_str = Self::get_xcode_project_path(target_dir_str, provided_toml);
+        let provided_project_path = std::path::PathBuf::from(&provided_project_path_str);
+
+        if provided_project_path.exists() && provided_project_path.is_dir() {
+            Self::open_xcode_project(&provided_project_path_str, app)?;
+        } else {
+            log::debug!("xcodeproj not exists {:?}", provided_project_path_str);
+            let cwd =
+                std::env::current_dir().with_context(|| "Failed to get cwd when open xcode")?;
+            log::trace!("Searching Cargo.toml in current directory: {:?}", cwd);
+            let possible_manifest_path = cwd.join("Cargo.toml");
+            if possible_manifest_path.exists() && possible_manifest_path.is_file() {
+                let toml = self.toml()?;
+                let project_path_str = Self::get_xcode_project_path(target_dir_str, toml);
+                let project_path = std::path::PathBuf::from(&project_path_str);
+                if project_path.exists() && project_path.is_dir() {
+                    Self::open_xcode_project(&project_path_str, app)?;
+                } else {
+                    log::debug!("xcodeproj not exists {:?}", project_path_str);
+                    anyhow::bail!("Can't find xcodeproj. Build it first")
+                }
+            } else {
+                anyhow::bail!("Can't find xcodeproj. Build it first")
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Check that the `xcodegen` binary is installed.
+    pub fn check_xcodegen() -> anyhow::Result<()> {
+        log::debug!("Checking xcodegen version");
+        let xcodegen_version_output = Command::new("xcodegen")
+            .arg("version")
+            .output()
+            .with_context(|| "Failed to get output".to_string())?;
+
+        if xcodegen_version_output.status.success() {
+            let stdout = String::from_utf8_lossy(&xcodegen_version_output.stdout);
+            if stdout.starts_with("Version:") {
+                log::info!("Xcodegen {}", stdout);
+            } else {
+                anyhow::bail!("Xcodegen is not found")
+            }
+        } else {
+            anyhow::bail!("Xcodegen is not found")
+        }
+
+        Ok(())
+    }
+
+    /// Return a list of iOS simulators.
+    pub fn get_simulator_device_list() -> anyhow::Result<Vec<crate::devices::SimulatorDevice>> {
+        Self::get_platform_simulator_device_list(crate::toml::AppPlatform::Ios)
+    }
+
+    /// Return iOS, tvOS and watchOS simulators in any state.
+    pub fn get_all_simulators() -> anyhow::Result<Vec<crate::devices::SimulatorDevice>> {
+        let mut simulators = vec![];
+        for platform in [
+            crate::toml::AppPlatform::Ios,
+            crate::toml::AppPlatform::TvOs,
+            crate::toml::AppPlatform::WatchOs,
+        ] {
+            simulators.extend(Self::get_platform_simulator_device_list(platform)?);
+        }
+
+        Ok(simulators)
+    }
+
+    /// Return iOS, tvOS and watchOS simulators in any state and connected devices.
+    pub fn get_all_devices() -> anyhow::Result<Vec<crate::devices::DeviceInfo>> {
+        let mut devices = Self::get_all_simulators()?
+            .iter()
+            .map(crate::devices::DeviceInfo::from)
+            .collect::<Vec<_>>();
+        devices.extend(
+            md::get_device_list()
+                .iter()
+                .map(crate::devices::DeviceInfo::from),
+        );
+
+        Ok(devices)
+    }
+
+    /// Return a list of simulators of `platform`.
+    pub fn get_platform_simulator_device_list(
+        platform: crate::toml::AppPlatform,
+    ) -> anyhow::Result<Vec<crate::devices::SimulatorDevice>> {
+        let output = std::process::Command::new("xcrun")
+            .arg("simctl")
+            .arg("list")
+            .arg("devices")
+            .arg(platform.name())
+            .arg("--json")
+            .output()
+            .with_context(|| format!("Failed to get {} simulators list", platform.name()))?;
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            anyhow::bail!(
+                "Failed to get booted simulators:\n{:?}\n{:?}",
+                &stdout,
+                &stderr
+            )
+        } else {
+            let devices: crate::devices::SimulatorDevices = serde_json::from_str(&stdout)
+                .with_context(|| {
+                    format!(
+                        "Failed to create typed device list from output:\n{}",
+                        stdout
+                    )
+                })?;
+
+            Ok(devices
+                .for_platform(platform)
+                .with_context(|| format!("Failed to get {} device list", platform.name()))?)
+        }
+    }
+
+    /// Poll connected devices and simulators every `interval` and call `on_event` for every
+    /// device attach/detach and simulator state change. Runs until `on_event` returns an error.
+    pub fn watch_devices(
+        interval: std::time::Duration,
+        mut on_event: impl FnMut(&crate::devices::DeviceEvent) -> anyhow::Result<()>,
+    ) -> anyhow::Result<()> {
+        let take_snapshot = |previous: &crate::devices::DeviceSnapshot| {
+            let simulators = Self::get_simulator_device_list().unwrap_or_else(|e| {
+                log::warn!("Failed to get simulators: {:?}", e);
+                previous.simulators.values().cloned().collect()
+            });
+            crate::devices::DeviceSnapshot::new(&md::get_device_list(), &simulators)
+        };
+
+        let mut snapshot = take_snapshot(&Default::default());
+        loop {
+            std::thread::sleep(interval);
+            let new_snapshot = take_snapshot(&snapshot);
+            for event in snapshot.events(&new_snapshot) {
+                on_event(&event)?;
+            }
+            snapshot = new_snapshot;
+        }
+    }
+
+    fn sdk_path(sdk: &str) -> anyhow::Result<String> {
+        let output = Command::new("xcrun")
+            .arg("--sdk")
+            .arg(sdk)
+            .arg("--show-sdk-path")
+            .output()
+            .with_context(|| "Failed to get xcrun output".to_string())?;
+
+        if !output.status.success() {
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            let stderr = String::from_utf8_lossy(&output.stderr);
+
+            anyhow::bail!("Failed to find {} SDK:\n{:?}\n{:?}", sdk, stdout, stderr)
+        }
+
+        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    }
+
+    fn get_xcode_project_path(target_dir: &str, toml: crate::toml::Root) -> String {
+        let app_name = toml
+            .lib
+            .unwrap_or_default()
+            .name
+            .unwrap_or(toml.package.name)
+            .replace("-", "_");
+        // Pods are only integrated into the workspace.
+        let workspace = format!("{}/{}/{}.xcworkspace", target_dir, app_name, app_name);
+        if Path::new(&workspace).is_dir() {
+            return workspace;
+        }
+        format!("{}/{}/{}.xcodeproj", target_dir, app_name, app_name)
+    }
+
+    fn open_xcode_project(project_path: &str, app: Option<&Path>) -> anyhow::Result<()> {
+        log::trace!("Opening xcode project: {} with {:?}", project_path, app);
+        let mut command = Command::new("open");
+        if let Some(app) = app {
+            command.arg("-a").arg(app);
+        }
+        let output = command
+            .arg(project_path)
+            .output()
+            .with_context(|| format!("Failed to open xcodeproject: {}", project_path))?;
+
+        if !output.status.success() {
+            anyhow::bail!("Failed to open xcodeproject: {}", project_path)
+        }
+        Ok(())
+    }
+
+    /// Resolve a device id given on the command line to a connected device or a simulator of
+    /// any platform with that udid. Overrides `device_id` in `Cargo.toml`.
+    pub fn select_device(&self, device_id: &str) -> anyhow::Result<SelectedDevice> {
+        if let Some(md) = md::get_device_list()
+            .into_iter()
+            .find(|d| d.identifier == device_id)
+        {
+            return Ok(SelectedDevice::Device(md));
+        }
+        let is_simulator = Self::get_all_devices()?
+            .iter()
+            .any(|d| d.connection == crate::devices::Connection::Simulator && d.udid == device_id);
+        anyhow::ensure!(
+            is_simulator,
+            "Failed to find a connected device or simulator with id {}. Run `cargo xcodebuild devices` to list them",
+            device_id
+        );
+        log::info!("Device is specified on the command line {}", device_id);
+        Ok(SelectedDevice::Simulator {
+            udid: device_id.to_string(),
+        })
+    }
+
+    /// Find the connected device or simulator of the app platform whose name matches `name`
+    /// best, see [`crate::devices::best_name_match`]. Connected devices are preferred over
+    /// simulators of the same name and booted simulators over shutdown ones, which are booted.
+    pub fn select_device_by_name(
+        &self,
+        name: &str,
+        only: Option<crate::toml::DeviceType>,
+    ) -> anyhow::Result<SelectedDevice> {
+        let platform = self.ios_metadata()?.platform.unwrap_or_default();
+        let mut devices = match only {
+            Some(crate::toml::DeviceType::Simulator) | Some(crate::toml::DeviceType::Mac) => {
+                vec![]
+            }
+            _ => md::get_device_list(),
+        };
+        let mut simulators = match only {
+            Some(crate::toml::DeviceType::Device) | Some(crate::toml::DeviceType::Mac) => vec![],
+            _ => Self::get_platform_simulator_device_list(platform)?,
+        };
+        simulators.sort_by_key(|s| s.state != crate::devices::DeviceState::Booted);
+
+        let names = devices
+            .iter()
+            .map(|d| d.device_name.as_str())
+            .chain(simulators.iter().map(|s| s.name.as_str()));
+        let index = crate::devices::best_name_match(name, names).with_context(|| {
+            format!(
+                "Failed to find a device or simulator named {:?}. Run `cargo xcodebuild devices` to list them",
+                name
+            )
+        })?;
+        if index < devices.len() {
+            let device = devices.swap_remove(index);
+            log::info!("Selected {} by name", device.device_name);
+            return Ok(SelectedDevice::Device(device));
+        }
+        let simulator = simulators.swap_remove(index - devices.len());
+        log::info!("Selected simulator {} by name", simulator.name);
+        if simulator.state != crate::devices::DeviceState::Booted {
+            crate::simctl::boot_and_wait(&simulator.udid)?;
+        }
+        Ok(SelectedDevice::Simulator {
+            udid: simulator.udid,
+        })
+    }
+
+    #[allow(clippy::unnecessary_unwrap)]
+    fn find_device(&self, only: Option<crate::toml::DeviceType>) -> anyhow::Result<SelectedDevice> {
+        log::debug!("Finding device");
+        let toml = self.toml()?.validate_build_targets()?;
+        let toml_device_id = toml.ios_metadata().unwrap_or_default().device_id;
+        let toml_device_type = toml.ios_metadata().unwrap_or_default().device_type;
+        let toml_device_name = toml.ios_metadata().unwrap_or_default().device_name;
+        let platform = toml
+            .ios_metadata()
+            .unwrap_or_default()
+            .platform
+            .unwrap_or_default();
+        // Default simulators are only looked up by runtime and created for iOS.
+        let is_ios = platform == crate::toml::AppPlatform::Ios;
+        // macOS apps only run on this Mac, Mac Catalyst apps when it is selected explicitly.
+        let mac_selected = match only {
+            Some(device_type) => device_type == crate::toml::DeviceType::Mac,
+            None => toml_device_type == Some(crate::toml::DeviceType::Mac),
+        };
+        if platform == crate::toml::AppPlatform::MacOs {
+            return Ok(SelectedDevice::Mac);
+        } else if mac_selected {
+            anyhow::ensure!(
+                toml.ios_metadata().unwrap_or_default().catalyst,
+                "Only macOS and Mac Catalyst apps run on this Mac. Please check Cargo.toml."
+            );
+            return Ok(SelectedDevice::Mac);
+        }
+        let simulator_runtime = toml
+            .ios_metadata()
+            .unwrap_or_default()
+            .simulator_runtime
+            .map(|pin| crate::runtime::pinned_version(&pin, platform))
+            .transpose()?;
+
+        if toml_device_id.is_some()
+            && toml_device_type.is_some()
+            && (only.is_none() || only == toml_device_type)
+        {
+            let device_id = toml_device_id.unwrap();
+            let is_simulator = toml_device_type.unwrap() == crate::toml::DeviceType::Simulator;
+            log::info!("Device is specified in Cargo.toml {}", device_id);
+
+            if is_simulator {
+                Ok(SelectedDevice::Simulator { udid: device_id })
+            } else if let Some(md) = md::get_device_list()
+                .iter()
+                .find(|e| e.identifier == device_id)
+            {
+                Ok(SelectedDevice::Device(md.clone()))
+            } else {
+                anyhow::bail!(
+                    "Failed to find a connected device with specified id {}",
+                    device_id
+                )
+            }
+        } else if let Some(name) = toml_device_name {
+            log::info!("Device name is specified in Cargo.toml {}", name);
+            self.select_device_by_name(&name, only)
+        } else {
+            // Let's check connected device first, then simulators
+            let is_watch = platform == crate::toml::AppPlatform::WatchOs;
+            let devices = match only {
+                Some(crate::toml::DeviceType::Simulator) => vec![],
+                _ => md::get_device_list(),
+            };
+            let mut candidates = devices
+                .into_iter()
+                .filter(|d| (d.device_class == "Watch") == is_watch)
+                .map(|d| {
+                    let label = format!(
+                        "{} ({} {}, {:?})",
+                        d.device_name,
+                        platform.name(),
+                        d.product_version,
+                        d.connection_type
+                    );
+                    (label, SelectedDevice::Device(d))
+                })
+                .collect::<Vec<_>>();
+            if only == Some(crate::toml::DeviceType::Device) {
+                anyhow::ensure!(!candidates.is_empty(), "Failed to find a connected device");
+            } else if simulator_runtime.is_none() {
+                // With `simulator_runtime` the simulator is chosen by runtime below.
+                let booted = Self::get_platform_simulator_device_list(platform)
+                    .unwrap_or_default()
+                    .into_iter()
+                    .filter(|d| d.state == crate::devices::DeviceState::Booted);
+                for d in booted {
+                    let label = format!(
+                        "{} (Simulator, {} {})",
+                        d.name,
+                        platform.name(),
+                        d.runtime_version().unwrap_or_default()
+                    );
+                    candidates.push((label, SelectedDevice::Simulator { udid: d.udid }));
+                }
+            }
+            if candidates.len() > 1 && std::io::stdin().is_terminal() {
+                let index = Self::pick_device(&candidates)?;
+                Ok(candidates.swap_remove(index).1)
+            } else if !candidates.is_empty() {
+                let (label, device) = candidates.swap_remove(0);
+                log::info!("Selected {}", label);
+                Ok(device)
+            } else if let Some(runtime) = simulator_runtime {
+                log::info!(
+                    "Failed to find connected device. Searching a {} {} simulator",
+                    platform.name(),
+                    runtime
+                );
+                let udid = crate::runtime::find_simulator(platform, &runtime)?;
+                crate::simctl::boot_and_wait(&udid)?;
+                Ok(SelectedDevice::Simulator { udid })
+            } else {
+                log::info!("Failed to find connected device or booted simulator");
+                let udid = if is_ios {
+                    let runtime = self.default_runtime_for_deployment_target()?;
+                    log::info!("Booting an iOS {} simulator", runtime);
+                    crate::runtime::find_simulator(platform, &runtime)?
+                } else {
+                    let simulators = Self::get_platform_simulator_device_list(platform)?;
+                    let simulator =
+                        crate::devices::default_simulator(&simulators).with_context(|| {
+                            format!("Failed to find a {} simulator", platform.name())
+                        })?;
+                    log::info!("Booting simulator {}", simulator.name);
+                    simulator.udid.clone()
+                };
+                crate::simctl::boot_and_wait(&udid)?;
+                Ok(SelectedDevice::Simulator { udid })
+            }
+        }
+    }
+
+    /// Version of the newest installed iOS runtime supporting the deployment target.
+    fn default_runtime_for_deployment_target(&self) -> anyhow::Result<String> {
+        let deployment_target = self
+            .ios_metadata()?
+            .deployment_target_for(crate::toml::AppPlatform::Ios);
+        crate::runtime::default_ios_runtime(&deployment_target)
+    }
+
+    /// Ask which of the labelled `candidates` to use and return its index.
+    fn pick_device(candidates: &[(String, SelectedDevice)]) -> anyhow::Result<usize> {
+        use std::io::Write as _;
+
+        // Prompts go to stderr to keep stdout clean for `--message-format json`.
+        eprintln!("Multiple devices are available:");
+        for (i, (label, _)) in candidates.iter().enumerate() {
+            eprintln!("  {}) {}", i + 1, label);
+        }
+        loop {
+            eprint!("Select a device [1]: ");
+            std::io::stderr().flush()?;
+            let mut answer = String::new();
+            if std::io::stdin().read_line(&mut answer)? == 0 {
+                anyhow::bail!("No device selected")
+            }
+            match crate::devices::parse_selection(&answer, candidates.len()) {
+                Some(index) => return Ok(index),
+                None => eprintln!("Enter a number from 1 to {}", candidates.len()),
+            }
+        }
+    }
+
+    fn xcode_build_project(
+        &self,
+        project_dir: &Path,
+        device: &SelectedDevice,
+        configuration: &str,
+        scheme_name: &str,
+        build_settings: &[String],
+    ) -> anyhow::Result<()> {
+        let ios_metadata = self.ios_metadata()?;
+        let app_platform = ios_metadata.platform.unwrap_or_default();
+        let destination = match *device {
+            SelectedDevice::Simulator { ref udid } => {
+                format!("platform={},id={}", app_platform.destination(true), udid)
+            }
+            SelectedDevice::Device(ref md) => {
+                format!(
+                    "platform={},id={}",
+                    app_platform.destination(false),
+                    md.identifier
+                )
+            }
+            SelectedDevice::Mac => {
+                let arch = if cfg!(target_arch = "aarch64") {
+                    "arm64"
+                } else {
+                    "x86_64"
+                };
+                let variant = if ios_metadata.catalyst {
+                    ",variant=Mac Catalyst"
+                } else {
+                    ""
+                };
+                format!("platform=macOS,arch={}{}", arch, variant)
+            }
+        };
+        log::info!(
+            "Building {} scheme {} for destination: {}",
+            configuration,
+            scheme_name,
+            destination,
+        );
+        let base_args = [
+            "-derivedDataPath",
+            "build",
+            "-scheme",
+            scheme_name,
+            "-configuration",
+            configuration,
+        ];
+        let additional_args = match *device {
+            SelectedDevice::Device(ref md) => {
+                let arch = if md.cpu_architecture == "arm64e" {
+                    "arm64"
+                } else {
+                    &md.cpu_architecture
+                };
+                vec!["-sdk", app_platform.sdk(false), "-arch", arch]
+            }
+            SelectedDevice::Simulator { .. } | SelectedDevice::Mac => {
+                vec!["-destination", &destination]
+            }
+        };
+
+        let args = base_args
+            .iter()
+            .chain(&additional_args)
+            .map(|arg| arg.to_string())
+            .chain(build_settings.iter().cloned())
+            .collect::<Vec<_>>();
+        self.run_xcodebuild(project_dir, &args)
+    }
+
+    /// Run `xcodebuild` in `project_dir` with provisioning updates and the App Store Connect key,
+    /// emitting its diagnostics.
+    fn run_xcodebuild(&self, project_dir: &Path, args: &[String]) -> anyhow::Result<()> {
+        let mut command = std::process::Command::new("xcodebuild");
+        command.current_dir(project_dir);
+        if self.ios_metadata()?.cocoapods.is_some() {
+            let app_name = project_dir.file_name().unwrap().to_string_lossy();
+            command
+                .arg("-workspace")
+                .arg(format!("{}.xcworkspace", app_name));
+        }
+        command.args(args).arg("-allowProvisioningUpdates");
+        if let Some(key) = self.authentication_key()? {
+            command.args(key.xcodebuild_args());
+        }
+        if let Some(keychain) = self.unlock_keychain()? {
+            command.arg(keychain.xcodebuild_setting());
+        }
+        log::trace!("cwd: {:?}", project_dir);
+        log::trace!("xcodebuild command: {:?}", command);
+        let mut child = command
+            .stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::piped())
+            .spawn()
+            .with_context(|| "Failed to run xcodebuild".to_string())?;
+
+        // JSON events own stdout.
+        let to_stderr = self.message_format == crate::events::MessageFormat::Json;
+        let raw = self.raw_xcodebuild_output || self.verbosity == crate::events::Verbosity::Verbose;
+        let quiet = self.verbosity == crate::events::Verbosity::Quiet;
+        let stdout = child.stdout.take().unwrap();
+        let stderr = child.stderr.take().unwrap();
+        let forward_stderr = std::thread::spawn(move || {
+            Self::forward_xcodebuild_output(stderr, raw, quiet, to_stderr)
+        });
+        let stdout = Self::forward_xcodebuild_output(stdout, raw, quiet, to_stderr);
+        let stderr = forward_stderr.join().unwrap_or_default();
+        let status = child
+            .wait()
+            .with_context(|| "Failed to wait for xcodebuild".to_string())?;
+
+        for output in [&stdout, &stderr] {
+            for diagnostic in crate::events::parse_diagnostics(output) {
+                self.emit(diagnostic);
+            }
+        }
+
+        crate::process::check("xcodebuild", status).with_context(|| {
+            if raw {
+                "Failed to build project with xcodebuild".to_string()
+            } else {
+                format!(
+                    "Failed to build project with xcodebuild:\n{:?}\n{:?}",
+                    stdout, stderr
+                )
+            }
+        })?;
+
+        Ok(())
+    }
+
+    /// Print xcodebuild output while it runs, condensed by [`crate::beautify`] unless `raw`,
+    /// and return all of it. `quiet` only prints errors and warnings.
+    fn forward_xcodebuild_output(
+        output: impl std::io::Read,
+        raw: bool,
+        quiet: bool,
+        to_stderr: bool,
+    ) -> String {
+        use std::io::BufRead as _;
+
+        let color = if to_stderr {
+            std::io::stderr().is_terminal()
+        } else {
+            std::io::stdout().is_terminal()
+        };
+        let mut all = String::new();
+        for line in std::io::BufReader::new(output).lines() {
+            let line = match line {
+                Ok(line) => line,
+                Err(_) => break,
+            };
+            let shown = if quiet {
+                if line.contains("error: ") || line.contains("warning: ") {
+                    crate::beautify::format_line(&line, color)
+                } else {
+                    None
+                }
+            } else if raw {
+                Some(line.clone())
+            } else {
+                crate::beautify::format_line(&line, color)
+            };
+            if let Some(shown) = shown {
+                if to_stderr {
+                    eprintln!("{}", shown);
+                } else {
+                    println!("{}", shown);
+                }
+            }
+            all.push_str(&line);
+            all.push('\n');
+        }
+        all
+    }
+
+    /// Unlock the keychain from `[package.metadata.ios.keychain]` or the environment, if any.
+    fn unlock_keychain(&self) -> anyhow::Result<Option<crate::signing::Keychain>> {
+        let keychain = crate::signing::Keychain::resolve(
+            self.ios_metadata()?.keychain.as_ref(),
+            self.manifest_path.parent().unwrap(),
+        )?;
+        if let Some(ref keychain) = keychain {
+            keychain.unlock()?;
+        }
+        Ok(keychain)
+    }
+
+    fn authentication_key(&self) -> anyhow::Result<Option<crate::signing::AuthenticationKey>> {
+        crate::signing::AuthenticationKey::resolve(
+            self.ios_metadata()?.app_store_connect.as_ref(),
+            self.manifest_path.parent().unwrap(),
+        )
+    }
+
+    fn install_app_to_simulator(
+        project_dir: &Path,
+        app_path: &str,
+        device_id: &str,
+    ) -> anyhow::Result<()> {
+        log::info!("Installing app {} on simulator {}", app_path, device_id);
+        let output = std::process::Command::new("xcrun")
+            .current_dir(project_dir)
+            .arg("simctl")
+            .arg("install")
+            .arg(device_id)
+            .arg(app_path)
+            .output()
+            .with_context(|| "Failed to get xcrun output".to_string())?;
+
+        crate::process::check("xcrun simctl install", output.status).with_context(|| {
+            format!(
+                "Failed to install app:\n{:?}\n{:?}",
+                String::from_utf8_lossy(&output.stdout),
+                String::from_utf8_lossy(&output.stderr)
+            )
+        })?;
+
+        Ok(())
+    }
+
+    /// Launch the app with `open`, or run its executable attached to the terminal with `console`.
+    fn run_app_on_mac(
+        app_path: &Path,
+        app_name: &str,
+        console: bool,
+        args: &[String],
+    ) -> anyhow::Result<()> {
+        log::info!("Running app {:?} on this Mac", app_path);
+        if console {
+            let executable = app_path.join("Contents").join("MacOS").join(app_name);
+            let status = std::process::Command::new(&executable)
+                .args(args)
+                .status()
+                .with_context(|| format!("Failed to run {:?}", executable))?;
+            if !status.success() {
+                anyhow::bail!("{} exited with {}", app_name, status)
+            }
+            return Ok(());
+        }
+
+        let output = std::process::Command::new("open")
+            .arg("-n")
+            .arg(app_path)
+            .arg("--args")
+            .args(args)
+            .output()
+            .with_context(|| "Failed to get open output".to_string())?;
+
+        if !output.status.success() {
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            let stderr = String::from_utf8_lossy(&output.stderr);
+
+            anyhow::bail!("Failed to run app:\n{:?}\n{:?}", stdout, stderr)
+        };
+
+        Ok(())
+    }
+
+    /// Launch with `devicectl` on iOS 17 and newer devices, with `debugserver` before.
+    fn run_app_with_device(
+        md: &md::MobileDevice,
+        full_app_name: &str,
+        args: &[String],
+    ) -> anyhow::Result<()> {
+        log::info!("Running app {} on device {}", full_app_name, md.identifier);
+        // debugserver is only reachable through CoreDevice tunnels since iOS 17.
+        if crate::devices::runtime_version_at_least(&md.product_version, "17") {
+            let output = std::process::Command::new("xcrun")
+                .arg("devicectl")
+                .arg("device")
+                .arg("process")
+                .arg("launch")
+                .arg("--device")
+                .arg(&md.identifier)
+                .arg("--terminate-existing")
+                .arg(full_app_name)
+                .args(args)
+                .output()
+                .with_context(|| "Failed to get xcrun output".to_string())?;
+
+            crate::process::check("xcrun devicectl", output.status).with_context(|| {
+                format!(
+                    "Failed to run app:\n{:?}\n{:?}",
+                    String::from_utf8_lossy(&output.stdout),
+                    String::from_utf8_lossy(&output.stderr)
+                )
+            })?;
+
+            return Ok(());
+        }
+
+        if !args.is_empty() {
+            log::warn!("Launch arguments are only passed to apps on iOS 17 and newer devices");
+        }
+        md.launch_app(full_app_name)
+    }
+
+    /// Launch an app stopped with `devicectl` on an iOS 17 or newer device. Returns its process id.
+    fn launch_stopped_on_device(
+        md: &md::MobileDevice,
+        full_app_name: &str,
+        args: &[String],
+    ) -> anyhow::Result<u32> {
+        log::info!(
+            "Launching app {} on device {} for the debugger",
+            full_app_name,
+            md.identifier
+        );
+        let json_output = std::env::temp_dir().join(format!(
+            "cargo-xcodebuild-devicectl-{}.json",
+            std::process::id()
+        ));
+        let output = std::process::Command::new("xcrun")
+            .arg("devicectl")
+            .arg("device")
+            .arg("process")
+            .arg("launch")
+            .arg("--device")
+            .arg(&md.identifier)
+            .arg("--terminate-existing")
+            .arg("--start-stopped")
+            .arg("--json-output")
+            .arg(&json_output)
+            .arg(full_app_name)
+            .args(args)
+            .output()
+            .with_context(|| "Failed to get xcrun output".to_string())?;
+
+        if !output.status.success() {
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            let stderr = String::from_utf8_lossy(&output.stderr);
+
+            anyhow::bail!("Failed to launch app:\n{:?}\n{:?}", stdout, stderr)
+        };
+
+        let json = std::fs::read_to_string(&json_output)
+            .with_context(|| format!("Failed to read {:?}", json_output))?;
+        let _ = std::fs::remove_file(&json_output);
+        crate::debug::devicectl_pid(&json)
+            .with_context(|| format!("Failed to parse the process id of {}", full_app_name))
+    }
+
+    /// Returns the pid of the launched app.
+    fn run_app_with_simulator(
+        device_id: &str,
+        full_app_name: &str,
+        args: &[String],
+    ) -> anyhow::Result<Option<u32>> {
+        log::info!("Running app {} on simulator {}", full_app_name, device_id);
+        let output = std::process::Command::new("xcrun")
+            .arg("simctl")
+            .arg("launch")
+            .arg("--terminate-running-process")
+            .arg(device_id)
+            .arg(full_app_name)
+            .args(args)
+            .output()
+            .with_context(|| "Failed to get xcrun output".to_string())?;
+
+        crate::process::check("xcrun simctl launch", output.status).with_context(|| {
+            format!(
+                "Failed to run app:\n{:?}\n{:?}",
+                String::from_utf8_lossy(&output.stdout),
+                String::from_utf8_lossy(&output.stderr)
+            )
+        })?;
+
+        Ok(crate::simctl::parse_launch_pid(&String::from_utf8_lossy(
+            &output.stdout,
+        )))
+    }
+
+    /// Launch with `simctl launch --console-pty` and forward the app output until it exits.
+    /// The output goes to stderr with `--message-format json` to keep stdout machine-readable.
+    fn run_app_with_simulator_console(
+        &self,
+        device_id: &str,
+        full_app_name: &str,
+        options: &RunOptions,
+    ) -> anyhow::Result<()> {
+        use std::io::{BufRead as _, Write as _};
+
+        let (mut child, mut stdout, pid) = self.phase(crate::events::Phase::Launch, || {
+            log::info!("Running app {} on simulator {}", full_app_name, device_id);
+            let mut child = std::process::Command::new("xcrun")
+                .arg("simctl")
+                .arg("launch")
+                .arg("--console-pty")
+                .arg(device_id)
+                .arg(full_app_name)
+                .args(options.launch_args())
+                .stdout(std::process::Stdio::piped())
+                .spawn()
+                .with_context(|| "Failed to run xcrun simctl launch".to_string())?;
+            let mut stdout = std::io::BufReader::new(child.stdout.take().unwrap());
+
+            // The first line is `<bundle id>: <pid>`.
+            let mut line = String::new();
+            stdout.read_line(&mut line)?;
+            match crate::simctl::parse_launch_pid(&line) {
+                Some(pid) => Ok((child, stdout, pid)),
+                None => {
+                    let status = child.wait()?;
+                    anyhow::bail!("Failed to run app ({}):\n{:?}", status, line)
+                }
+            }
+        })?;
+
+        let to_stderr = self.message_format == crate::events::MessageFormat::Json;
+        let forward = std::thread::spawn(move || {
+            let mut line = Vec::new();
+            while matches!(stdout.read_until(b'\n', &mut line), Ok(n) if n > 0) {
+                let _ = if to_stderr {
+                    std::io::stderr().write_all(&line)
+                } else {
+                    std::io::stdout().write_all(&line)
+                };
+                line.clear();
+            }
+        });
+
+        if options.monitor {
+            Self::monitor_app(pid, options.monitor_duration)?;
+        }
+        let status = child
+            .wait()
+            .with_context(|| "Failed to wait for xcrun simctl launch".to_string())?;
+        let _ = forward.join();
+        log::info!("{} exited: {}", full_app_name, status);
+
+        Ok(())
+    }
+
+    /// Print samples of `pid` until it exits or `duration` passes, then a summary. Everything
+    /// goes to stderr to keep stdout clean for `--message-format json`.
+    fn monitor_app(pid: u32, duration: Option<std::time::Duration>) -> anyhow::Result<()> {
+        use std::io::Write as _;
+
+        eprintln!("Monitoring app process {}", pid);
+        let summary =
+            crate::monitor::monitor(pid, std::time::Duration::from_secs(1), duration, |sample| {
+                eprint!("\r{}", sample);
+                let _ = std::io::stderr().flush();
+            })?;
+        eprintln!("\n{}", summary);
+
+        Ok(())
+    }
+
+    fn prepare_target_dir(&self, project_dir: &Path) -> anyhow::Result<()> {
+        log::debug!("Creating required target dirs");
+        std::fs::create_dir_all(&self.src_dir)
+            .with_context(|| format!("Failed to create all dir: {:?}", &self.src_dir))?;
+
+        std::fs::create_dir_all(project_dir)
+            .with_context(|| format!("Failed to create all dir: {:?}", project_dir))?;
+
+        Ok(())
+    }
+
+    fn generate_xcode_project(
+        &self,
+        project: &crate::xcodegen::Project,
+        project_dir: &Path,
+    ) -> anyhow::Result<()> {
+        log::debug!("Writing all files required for xcodegen");
+        let ios_metadata = self.ios_metadata()?;
+        let manifest_dir = self.manifest_path.parent().unwrap();
+        let overlay = ios_metadata
+            .project_overlay
+            .as_ref()
+            .map(|path| crate::xcodegen::read_overlay(&manifest_dir.join(path)))
+            .transpose()?;
+        let has_overlay = overlay.is_some();
+        match overlay {
+            Some(overlay) => project.write_with_overlay(project_dir, overlay)?,
+            None => project.write_to(project_dir)?,
+        }
+        std::fs::write(
+            self.src_dir.join("bindings.h"),
+            crate::source::read_or_default(
+                manifest_dir,
+                ios_metadata.binding_header.as_deref(),
+                crate::source::DEFAULT_BINDING_HEADER,
+            )?,
+        )
+        .with_context(|| format!("Failed to write bindings.h: {:?}", &self.src_dir))?;
+        let entry = ios_metadata.entry.unwrap_or_default();
+        let (main_name, default_main) = match entry {
+            crate::toml::Entry::Objc => ("main.m", crate::source::DEFAULT_MAIN_FILE),
+            crate::toml::Entry::Swift => ("App.swift", crate::source::DEFAULT_SWIFT_APP_FILE),
+        };
+        std::fs::write(
+            self.src_dir.join(main_name),
+            crate::source::read_or_default(
+                manifest_dir,
+                ios_metadata.main_source.as_deref(),
+                default_main,
+            )?,
+        )
+        .with_context(|| format!("Failed to write {}: {:?}", main_name, &self.src_dir))?;
+        if entry == crate::toml::Entry::Swift {
+            std::fs::write(
+                self.src_dir.join("module.modulemap"),
+                crate::source::MODULE_MAP,
+            )
+            .with_context(|| format!("Failed to write module.modulemap: {:?}", &self.src_dir))?;
+        }
+        // Remove the other entry point left over after switching.
+        let stale_files: &[&str] = match entry {
+            crate::toml::Entry::Objc => &["App.swift", "module.modulemap"],
+            crate::toml::Entry::Swift => &["main.m"],
+        };
+        for name in stale_files {
+            let path = self.src_dir.join(name);
+            if path.exists() {
+                std::fs::remove_file(&path)
+                    .with_context(|| format!("Failed to remove {:?}", path))?;
+            }
+        }
+        let xctest_dir = self.target_dir.join(crate::source::XCTEST_DIR);
+        match ios_metadata.unit_tests {
+            Some(ref unit_tests) if !unit_tests.is_empty() => {
+                std::fs::create_dir_all(&xctest_dir)
+                    .with_context(|| format!("Failed to create all dir: {:?}", xctest_dir))?;
+                let path = xctest_dir.join("RustTests.m");
+                std::fs::write(&path, crate::source::xctest_source(unit_tests))
+                    .with_context(|| format!("Failed to write {:?}", path))?;
+            }
+            _ if xctest_dir.exists() => {
+                std::fs::remove_dir_all(&xctest_dir)
+                    .with_context(|| format!("Failed to remove dir: {:?}", xctest_dir))?;
+            }
+            _ => {}
+        }
+        if let Some(ref icon) = ios_metadata.icon {
+            crate::icon::generate_app_icon(
+                &manifest_dir.join(icon),
+                &self.src_dir.join("Assets.xcassets"),
+            )?;
+        }
+        crate::localization::generate(&ios_metadata, &self.src_dir)?;
+        if let Some(ref privacy) = ios_metadata.privacy {
+            let path = self.src_dir.join("PrivacyInfo.xcprivacy");
+            std::fs::write(&path, crate::plist::to_xml(&privacy.to_plist()))
+                .with_context(|| format!("Failed to write {:?}", path))?;
+        }
+        // Keep a storyboard from `assets` unless the launch screen is configured.
+        let storyboard = format!("{}.storyboard", crate::launch_screen::STORYBOARD_NAME);
+        let has_storyboard = ios_metadata
+            .assets
+            .iter()
+            .flatten()
+            .any(|asset| manifest_dir.join(asset.root()).join(&storyboard).exists());
+        if !ios_metadata
+            .platform
+            .unwrap_or_default()
+            .has_launch_screen()
+        {
+            let path = self.src_dir.join(&storyboard);
+            if path.exists() {
+                log::debug!("Removing {:?}", path);
+                std::fs::remove_file(&path)
+                    .with_context(|| format!("Failed to remove {:?}", path))?;
+            }
+        } else if ios_metadata.launch_screen.is_some() || !has_storyboard {
+            crate::launch_screen::generate(
+                &ios_metadata.launch_screen.clone().unwrap_or_default(),
+                manifest_dir,
+                &self.src_dir,
+            )?;
+        }
+
+        log::info!("Generating xcode project");
+        let use_xcodegen = match ios_metadata.project_generator {
+            Some(crate::toml::ProjectGenerator::Xcodegen) => {
+                Self::check_xcodegen()?;
+                true
+            }
+            Some(crate::toml::ProjectGenerator::Native) if has_overlay => {
+                anyhow::bail!(
+                    "`project_overlay` requires the `xcodegen` project generator. Please check Cargo.toml."
+                )
+            }
+            Some(crate::toml::ProjectGenerator::Native) => false,
+            // The overlay only applies to `project.yml`.
+            None if has_overlay => {
+                Self::check_xcodegen()?;
+                true
+            }
+            None => Self::check_xcodegen().is_ok(),
+        };
+        if use_xcodegen {
+            let output = std::process::Command::new("xcodegen")
+                .arg("--use-cache")
+                .current_dir(project_dir)
+                .output()
+                .with_context(|| "Failed to get output".to_string())?;
+
+            crate::process::check("xcodegen", output.status).with_context(|| {
+                format!(
+                    "Failed to generate xcode project:\n{:?}\n{:?}",
+                    String::from_utf8_lossy(&output.stdout),
+                    String::from_utf8_lossy(&output.stderr)
+                )
+            })?;
+        } else {
+            crate::pbxproj::generate(project, project_dir)?;
+        }
+
+        if let Some(ref cocoapods) = ios_metadata.cocoapods {
+            let platform = ios_metadata.platform.unwrap_or_default();
+            let deployment_target = ios_metadata.deployment_target_for(platform);
+            crate::cocoapods::install(
+                project_dir,
+                &project.name,
+                platform,
+                &deployment_target,
+                cocoapods,
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn build_artifacts_without_scheme() {
+        let artifacts = BuildArtifacts::from_json(
+            r#"{
+                "app_name": "my_game",
+                "bundle_id_prefix": "com.rust",
+                "project_dir": "target/xcodegen/my_game",
+                "build_type": "Release",
+                "destination": { "type": "mac" }
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(artifacts.scheme, "my_game");
+        assert_eq!(artifacts.configuration, "Release");
+    }
+}
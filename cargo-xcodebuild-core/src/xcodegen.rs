@@ -0,0 +1,1747 @@
+use anyhow::Context;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+
+const INHERITED: &str = "$(INHERITED)";
+
+/// An xcodegen project spec, serialized to `project.yml`.
+#[derive(Debug, Serialize)]
+pub struct Project {
+    pub name: String,
+    pub configs: HashMap<String, String>,
+    pub settings: HashMap<String, serde_yaml::Value>,
+    pub options: Options,
+    pub targets: HashMap<String, Target>,
+}
+
+impl Project {
+    /// Name of the app target: the lib name (or package name) with `-` replaced by `_`.
+    pub fn target_name(toml: &crate::toml::Root) -> String {
+        toml.lib
+            .clone()
+            .unwrap_or_default()
+            .name
+            .unwrap_or_else(|| toml.package.name.clone())
+            .replace("-", "_")
+    }
+
+    /// Create a project from the `[package.metadata.ios]` section of `Cargo.toml`.
+    pub fn from_toml(
+        manifest_path: &std::path::Path,
+        toml: crate::toml::Root,
+        team: Option<&crate::teams::Team>,
+    ) -> anyhow::Result<Self> {
+        log::debug!("Creating xcodegen project from Cargo.toml");
+
+        let ios_metadata = toml.ios_metadata()?;
+        let target_name = Self::target_name(&toml);
+        if let Some(ref deployment_target) = ios_metadata.deployment_target {
+            deployment_target.validate()?;
+        }
+
+        let dynamic = ios_metadata.link == Some(crate::toml::Link::Dynamic);
+        let build_targets = ios_metadata.build_targets.clone().unwrap_or_default();
+        // The framework of a `cdylib` is searched per architecture.
+        let universal_simulator = !dynamic && crate::lipo::has_universal_simulator(&build_targets);
+        let platform = ios_metadata.platform.unwrap_or_default();
+        let catalyst = ios_metadata.catalyst;
+        let runs_on_mac = ios_metadata.runs_on_mac();
+        let config = |profile: &str| {
+            let mut config = TargetConfig::for_platform(platform, profile);
+            if catalyst {
+                config = config.with_catalyst(profile);
+            }
+            if dynamic {
+                config = config.with_frameworks();
+            }
+            if universal_simulator {
+                config = config.with_universal_simulator(profile);
+            }
+            config
+        };
+        let mut configs = HashMap::from([
+            ("release".to_string(), config("release")),
+            ("debug".to_string(), config("debug")),
+        ]);
+        // An Xcode configuration per custom cargo profile, built with `--profile <name>`.
+        let custom_profiles = toml.custom_profiles();
+        for (profile, _) in &custom_profiles {
+            configs.insert(profile.clone(), config(profile));
+        }
+        let deployment_target = ios_metadata.deployment_target_or_default();
+        // Project defaults of the platforms configured explicitly.
+        let deployment_targets = ios_metadata
+            .deployment_target
+            .as_ref()
+            .map(|target| {
+                target
+                    .per_platform()
+                    .into_iter()
+                    .map(|(platform, version)| (platform, version.to_string()))
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+        let bundle_id_prefix = ios_metadata
+            .bundle_id_prefix
+            .unwrap_or_else(|| "com.rust".into());
+        let flavor = ios_metadata.flavor.unwrap_or_default();
+
+        let mut base_setting = TargetSetting::base(&target_name, "../src/");
+        if ios_metadata.icon.is_some() {
+            base_setting.set(
+                "ASSETCATALOG_COMPILER_APPICON_NAME",
+                crate::icon::APP_ICON_NAME,
+            );
+        }
+        if let Some(suffix) = flavor.bundle_id_suffix {
+            base_setting.set(
+                "PRODUCT_BUNDLE_IDENTIFIER",
+                format!(
+                    "{}.{}{}",
+                    bundle_id_prefix,
+                    target_name.replace('_', "-"),
+                    suffix
+                ),
+            );
+        }
+        if dynamic {
+            base_setting.link_framework(&target_name);
+            let runpath = if runs_on_mac {
+                "@executable_path/../Frameworks"
+            } else {
+                "@executable_path/Frameworks"
+            };
+            base_setting.set(
+                "LD_RUNPATH_SEARCH_PATHS",
+                serde_yaml::to_value([INHERITED, runpath])?,
+            );
+            // The embed script reads frameworks outside of the project.
+            base_setting.set("ENABLE_USER_SCRIPT_SANDBOXING", "NO");
+        }
+        if catalyst {
+            base_setting.set("SUPPORTS_MACCATALYST", "YES");
+        }
+        if platform == crate::toml::AppPlatform::MacOs {
+            base_setting.set("ENABLE_HARDENED_RUNTIME", "YES");
+        }
+        if ios_metadata.entry == Some(crate::toml::Entry::Swift) {
+            base_setting.set("SWIFT_VERSION", "5.0");
+            base_setting.set("SWIFT_INCLUDE_PATHS", "../src/");
+        }
+        if let Some(app_icon) = flavor.app_icon {
+            base_setting.set("ASSETCATALOG_COMPILER_APPICON_NAME", app_icon);
+        }
+
+        for (key, value) in ios_metadata.build_settings.unwrap_or_default() {
+            match value {
+                toml::Value::Table(settings) => {
+                    let config = configs.get_mut(&key).with_context(|| {
+                        format!(
+                            "Unknown configuration `{}` in `build_settings`. Please check Cargo.toml.",
+                            key
+                        )
+                    })?;
+                    for (key, value) in settings {
+                        config.set(key, serde_yaml::to_value(value)?);
+                    }
+                }
+                value => base_setting.set(key, serde_yaml::to_value(value)?),
+            }
+        }
+
+        let mut target = TargetBuilder::new(ProductType::Application, Platform::from(platform))
+            .deployment_target(deployment_target.clone())
+            .setting("base", base_setting)?
+            .setting("configs", configs)?
+            .source("../src/")
+            .plist_property("CFBundleVersion", "$(CURRENT_PROJECT_VERSION)")?;
+        if dynamic {
+            target = target.post_build_script(BuildScript::new(
+                "Embed Rust framework",
+                crate::framework::embed_script(&target_name),
+            ));
+        }
+
+        let toml_assets = ios_metadata.assets.unwrap_or_default();
+        let project_folder = manifest_path.parent().unwrap();
+        for asset in toml_assets {
+            asset.validate()?;
+            let full_path = project_folder.join(asset.root());
+            let mut source_path = if asset.is_asset_catalog()
+                && asset.destination() == crate::toml::AssetDestination::Resources
+            {
+                SourcesPath::resources(full_path.to_str().unwrap())
+            } else {
+                SourcesPath::copy_files(
+                    full_path.to_str().unwrap(),
+                    asset.destination().to_xcodegen(),
+                )?
+            };
+            source_path.includes = asset.include().map(str::to_string).into_iter().collect();
+            source_path.excludes = asset.exclude().to_vec();
+            if !asset.platforms().is_empty() {
+                source_path.destination_filters = Some(
+                    asset
+                        .platforms()
+                        .iter()
+                        .map(|p| p.to_xcodegen().to_string())
+                        .collect(),
+                );
+            }
+            target = target.source(serde_yaml::to_value(source_path)?);
+        }
+
+        for (key, description) in ios_metadata
+            .usage_descriptions
+            .unwrap_or_default()
+            .to_plist()
+        {
+            target = target.plist_property(key, description)?;
+        }
+        if let Some(url_schemes) = ios_metadata.url_schemes {
+            target = target.plist_property(
+                "CFBundleURLTypes",
+                serde_json::json!([{
+                    "CFBundleURLName": "$(PRODUCT_BUNDLE_IDENTIFIER)",
+                    "CFBundleURLSchemes": url_schemes,
+                }]),
+            )?;
+        }
+        if let Some(document_types) = ios_metadata.document_types {
+            let document_types = document_types
+                .iter()
+                .map(|d| d.to_plist())
+                .collect::<Vec<_>>();
+            target = target.plist_property("CFBundleDocumentTypes", document_types)?;
+        }
+        if let Some(exported_types) = ios_metadata.exported_types {
+            let exported_types = exported_types
+                .iter()
+                .map(|t| t.to_plist())
+                .collect::<Vec<_>>();
+            target = target.plist_property("UTExportedTypeDeclarations", exported_types)?;
+        }
+        if let Some(imported_types) = ios_metadata.imported_types {
+            let imported_types = imported_types
+                .iter()
+                .map(|t| t.to_plist())
+                .collect::<Vec<_>>();
+            target = target.plist_property("UTImportedTypeDeclarations", imported_types)?;
+        }
+        if let Some(in_place) = ios_metadata.supports_opening_documents_in_place {
+            target = target.plist_property("LSSupportsOpeningDocumentsInPlace", in_place)?;
+        }
+        if let Some(orientations) = ios_metadata.supported_interface_orientations {
+            let ipad_orientations = ios_metadata
+                .supported_interface_orientations_ipad
+                .unwrap_or_else(|| orientations.clone());
+            // iPad apps without every orientation are rejected unless they opt out of multitasking.
+            if !crate::toml::Orientation::ALL
+                .iter()
+                .all(|o| ipad_orientations.contains(o))
+            {
+                target = target.plist_property("UIRequiresFullScreen", true)?;
+            }
+            target = target
+                .plist_property("UISupportedInterfaceOrientations", orientations)?
+                .plist_property("UISupportedInterfaceOrientations~ipad", ipad_orientations)?;
+        }
+        if platform == crate::toml::AppPlatform::WatchOs {
+            let watchkit = crate::toml::watchkit_plist(ios_metadata.watch_companion.as_deref());
+            for (key, value) in watchkit.as_object().unwrap() {
+                target = target.plist_property(key, value)?;
+            }
+        }
+        let localizations = ios_metadata.localizations.unwrap_or_default();
+        if !localizations.is_empty() {
+            target = target
+                .plist_property("CFBundleDevelopmentRegion", "$(DEVELOPMENT_LANGUAGE)")?
+                .plist_property("CFBundleLocalizations", &localizations)?;
+        }
+        for (key, value) in ios_metadata.info_plist.unwrap_or_default() {
+            target = target.plist_property(key, value)?;
+        }
+        if let Some(display_name) = flavor.display_name {
+            target = target.plist_property("CFBundleDisplayName", display_name)?;
+        }
+
+        for d in ios_metadata.dependencies.unwrap_or_default() {
+            let mut dependency = Dependency::sdk(d.name().to_string());
+            if d.weak() {
+                dependency.weak = Some(true);
+            }
+            target = target.dependency(dependency);
+        }
+        for framework in ios_metadata.frameworks.unwrap_or_default() {
+            let path = project_folder.join(framework.path());
+            let mut dependency = Dependency::framework(path.to_str().unwrap().to_string());
+            dependency.embed = framework.embed();
+            dependency.code_sign = framework.code_sign();
+            target = target.dependency(dependency);
+        }
+
+        let project_settings = {
+            let user_code_sign_identity = ios_metadata.code_sign_identity;
+            let user_team_id = ios_metadata.development_team;
+
+            let code_sign_identity = user_code_sign_identity.unwrap_or_else(|| {
+                if team.is_some() && runs_on_mac {
+                    "Apple Development".into()
+                } else if team.is_some() {
+                    "iPhone Developer".into()
+                } else {
+                    "".into()
+                }
+            });
+
+            let team_id = user_team_id.unwrap_or_else(|| {
+                if let Some(team) = team {
+                    team.organization_unit.clone()
+                } else {
+                    "".into()
+                }
+            });
+
+            if code_sign_identity.is_empty() && team_id.is_empty() && runs_on_mac {
+                // Apps on Apple silicon must be signed, so sign to run locally.
+                log::debug!("Code sign to run locally");
+                HashMap::from([
+                    (
+                        "CODE_SIGN_IDENTITY".to_string(),
+                        serde_yaml::Value::String("-".into()),
+                    ),
+                    (
+                        "CODE_SIGN_STYLE".to_string(),
+                        serde_yaml::Value::String("Manual".into()),
+                    ),
+                ])
+            } else if code_sign_identity.is_empty() && team_id.is_empty() {
+                // No team, no user info. Disable code sign at all.
+                log::debug!("Code sign is disabled");
+                HashMap::from([
+                    (
+                        "CODE_SIGN_IDENTITY".to_string(),
+                        serde_yaml::Value::String("".into()),
+                    ),
+                    (
+                        "CODE_SIGNING_REQUIRED".to_string(),
+                        serde_yaml::from_str("NO")?,
+                    ),
+                    (
+                        "CODE_SIGN_ENTITLEMENTS".to_string(),
+                        serde_yaml::Value::String("".into()),
+                    ),
+                    (
+                        "CODE_SIGNING_ALLOWED".to_string(),
+                        serde_yaml::from_str("NO")?,
+                    ),
+                ])
+            } else {
+                log::debug!(
+                    "Code sign with identity `{}` and team id `{}`.",
+                    code_sign_identity,
+                    team_id
+                );
+                HashMap::from([
+                    (
+                        "CODE_SIGN_STYLE".to_string(),
+                        serde_yaml::Value::String("Automatic".into()),
+                    ),
+                    (
+                        "CODE_SIGN_IDENTITY".to_string(),
+                        serde_yaml::Value::String(code_sign_identity),
+                    ),
+                    (
+                        "DEVELOPMENT_TEAM".to_string(),
+                        serde_yaml::Value::String(team_id),
+                    ),
+                ])
+            }
+        };
+
+        let mut scheme = TargetScheme::default().with_env(ios_metadata.env.unwrap_or_default());
+
+        // A logic test bundle linking the Rust library, without a host app running `main_rs`.
+        let unit_tests = ios_metadata.unit_tests.unwrap_or_default();
+        let test_target = if unit_tests.is_empty() {
+            None
+        } else {
+            let test_target_name = format!("{}Tests", target_name);
+            let mut test_setting = TargetSetting::base(&target_name, "../src/");
+            test_setting.set(
+                "PRODUCT_BUNDLE_IDENTIFIER",
+                format!(
+                    "{}.{}.tests",
+                    bundle_id_prefix,
+                    target_name.replace('_', "-")
+                ),
+            );
+            let test_configs = HashMap::from([
+                (
+                    "release".to_string(),
+                    TargetConfig::for_platform(platform, "release"),
+                ),
+                (
+                    "debug".to_string(),
+                    TargetConfig::for_platform(platform, "debug"),
+                ),
+            ]);
+            scheme.test_targets = vec![test_target_name.clone()];
+            let test_target = TargetBuilder::new(ProductType::UnitTest, Platform::from(platform))
+                .deployment_target(deployment_target)
+                .setting("base", test_setting)?
+                .setting("configs", test_configs)?
+                .source(format!("../{}/", crate::source::XCTEST_DIR))
+                .info(Plist::new(format!(
+                    "../{}/Info.plist",
+                    crate::source::XCTEST_DIR
+                )))
+                .build();
+            Some((test_target_name, test_target))
+        };
+        target = target.scheme(scheme);
+
+        let mut project = ProjectBuilder::new(target_name.clone())
+            .config("Debug", "debug")
+            .config("Release", "release")
+            .bundle_id_prefix(bundle_id_prefix)
+            .target(target_name, target.build());
+        if let Some((name, test_target)) = test_target {
+            project = project.target(name, test_target);
+        }
+        if let Some(language) = localizations.first() {
+            project = project.development_language(language.clone());
+        }
+        for (platform, version) in deployment_targets {
+            project = project.deployment_target(Platform::from(platform), version);
+        }
+        for (profile, inherits) in custom_profiles {
+            let config_type = if inherits == "dev" {
+                "debug"
+            } else {
+                "release"
+            };
+            project = project.config(profile, config_type);
+        }
+        // Overridden with `--build-number`.
+        project = project.setting("CURRENT_PROJECT_VERSION", "1")?;
+        for (key, value) in project_settings {
+            project = project.setting(key, value)?;
+        }
+        let project = project.build();
+
+        Ok(project)
+    }
+
+    /// The `DEVELOPMENT_TEAM` used for signing, if any.
+    pub fn team_id(&self) -> Option<String> {
+        self.settings
+            .get("DEVELOPMENT_TEAM")
+            .and_then(|v| v.as_str())
+            .map(|v| v.to_string())
+    }
+
+    /// Write `project.yml` into `dir`.
+    pub fn write_to(&self, dir: &std::path::Path) -> anyhow::Result<()> {
+        write_yaml(dir, self)
+    }
+
+    /// Write `project.yml` into `dir` with `overlay` deep-merged into it.
+    pub fn write_with_overlay(
+        &self,
+        dir: &std::path::Path,
+        overlay: serde_yaml::Value,
+    ) -> anyhow::Result<()> {
+        let mut yaml = serde_yaml::to_value(self)?;
+        merge_yaml(&mut yaml, overlay);
+        write_yaml(dir, &yaml)
+    }
+}
+
+fn write_yaml(dir: &std::path::Path, value: &impl Serialize) -> anyhow::Result<()> {
+    let file = std::fs::File::create(dir.join("project.yml"))
+        .with_context(|| format!("Failed to create project.yml in {:?}", dir))?;
+    let w = std::io::BufWriter::new(file);
+    serde_yaml::to_writer(w, value)?;
+    Ok(())
+}
+
+/// Read an xcodegen project spec overlay, e.g. `project_overlay.yml`.
+pub fn read_overlay(path: &std::path::Path) -> anyhow::Result<serde_yaml::Value> {
+    let contents = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read project overlay {:?}", path))?;
+    serde_yaml::from_str(&contents)
+        .with_context(|| format!("Failed to parse project overlay {:?}", path))
+}
+
+/// Deep-merge `overlay` into `base`: mappings are merged key by key, other values replace.
+pub fn merge_yaml(base: &mut serde_yaml::Value, overlay: serde_yaml::Value) {
+    match (base, overlay) {
+        (serde_yaml::Value::Mapping(base), serde_yaml::Value::Mapping(overlay)) => {
+            for (key, value) in overlay {
+                match base.get_mut(&key) {
+                    Some(existing) => merge_yaml(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, overlay) => *base = overlay,
+    }
+}
+
+/// Builder for [`Project`], independent of `Cargo.toml` parsing.
+///
+/// ```
+/// use cargo_xcodebuild_core::xcodegen::{Platform, ProductType, ProjectBuilder, TargetBuilder};
+///
+/// let target = TargetBuilder::new(ProductType::Application, Platform::Ios)
+///     .deployment_target("13.0")
+///     .source("../src/")
+///     .plist_property("UIFileSharingEnabled", true)
+///     .unwrap()
+///     .build();
+/// let project = ProjectBuilder::new("my_app")
+///     .config("Debug", "debug")
+///     .config("Release", "release")
+///     .bundle_id_prefix("com.example")
+///     .target("my_app", target)
+///     .build();
+/// assert_eq!(project.targets.len(), 1);
+/// ```
+#[derive(Debug)]
+pub struct ProjectBuilder {
+    name: String,
+    configs: HashMap<String, String>,
+    settings: HashMap<String, serde_yaml::Value>,
+    bundle_id_prefix: String,
+    development_language: Option<String>,
+    deployment_targets: BTreeMap<String, String>,
+    targets: HashMap<String, Target>,
+}
+
+impl ProjectBuilder {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            configs: HashMap::new(),
+            settings: HashMap::new(),
+            bundle_id_prefix: "com.rust".into(),
+            development_language: None,
+            deployment_targets: BTreeMap::new(),
+            targets: HashMap::new(),
+        }
+    }
+
+    /// Add a build configuration, e.g. `("Debug", "debug")`.
+    pub fn config(mut self, name: impl Into<String>, config_type: impl Into<String>) -> Self {
+        self.configs.insert(name.into(), config_type.into());
+        self
+    }
+
+    /// Add a project-wide build setting.
+    pub fn setting(
+        mut self,
+        key: impl Into<String>,
+        value: impl Serialize,
+    ) -> anyhow::Result<Self> {
+        self.settings
+            .insert(key.into(), serde_yaml::to_value(value)?);
+        Ok(self)
+    }
+
+    pub fn bundle_id_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.bundle_id_prefix = prefix.into();
+        self
+    }
+
+    /// Development region of the project, e.g. `en`.
+    pub fn development_language(mut self, language: impl Into<String>) -> Self {
+        self.development_language = Some(language.into());
+        self
+    }
+
+    /// Default deployment target of targets for `platform`, e.g. `(Platform::Ios, "15.0")`.
+    pub fn deployment_target(mut self, platform: Platform, version: impl Into<String>) -> Self {
+        self.deployment_targets
+            .insert(platform.name().to_string(), version.into());
+        self
+    }
+
+    pub fn target(mut self, name: impl Into<String>, target: Target) -> Self {
+        self.targets.insert(name.into(), target);
+        self
+    }
+
+    pub fn build(self) -> Project {
+        Project {
+            name: self.name,
+            configs: self.configs,
+            settings: self.settings,
+            options: Options {
+                bundle_id_prefix: self.bundle_id_prefix,
+                development_language: self.development_language,
+                deployment_target: self.deployment_targets,
+            },
+            targets: self.targets,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct Options {
+    #[serde(rename(serialize = "bundleIdPrefix"))]
+    pub bundle_id_prefix: String,
+    #[serde(
+        rename(serialize = "developmentLanguage"),
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub development_language: Option<String>,
+    /// Deployment target per platform name, e.g. `iOS`.
+    #[serde(
+        rename(serialize = "deploymentTarget"),
+        skip_serializing_if = "BTreeMap::is_empty"
+    )]
+    pub deployment_target: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Target {
+    #[serde(rename(serialize = "type"))]
+    pub product_type: ProductType,
+    pub platform: Platform,
+    #[serde(rename(serialize = "deploymentTarget"))]
+    pub deployment_target: String,
+    pub sources: serde_yaml::Value,
+    pub settings: HashMap<String, serde_yaml::Value>,
+    pub dependencies: Vec<Dependency>,
+    pub info: Plist,
+    pub scheme: TargetScheme,
+    #[serde(
+        rename(serialize = "postBuildScripts"),
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub post_build_scripts: Vec<BuildScript>,
+}
+
+/// A run script build phase.
+#[derive(Debug, Serialize)]
+pub struct BuildScript {
+    pub name: String,
+    pub script: String,
+    #[serde(rename(serialize = "basedOnDependencyAnalysis"))]
+    pub based_on_dependency_analysis: bool,
+}
+
+impl BuildScript {
+    /// A script running on every build.
+    pub fn new(name: impl Into<String>, script: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            script: script.into(),
+            based_on_dependency_analysis: false,
+        }
+    }
+}
+
+/// Builder for [`Target`].
+#[derive(Debug)]
+pub struct TargetBuilder {
+    product_type: ProductType,
+    platform: Platform,
+    deployment_target: String,
+    sources: Vec<serde_yaml::Value>,
+    settings: HashMap<String, serde_yaml::Value>,
+    dependencies: Vec<Dependency>,
+    info: Plist,
+    scheme: TargetScheme,
+    post_build_scripts: Vec<BuildScript>,
+}
+
+impl TargetBuilder {
+    pub fn new(product_type: ProductType, platform: Platform) -> Self {
+        Self {
+            product_type,
+            platform,
+            deployment_target: crate::toml::AppPlatform::Ios
+                .default_deployment_target()
+                .into(),
+            sources: Vec::new(),
+            settings: HashMap::new(),
+            dependencies: Vec::new(),
+            info: Default::default(),
+            scheme: Default::default(),
+            post_build_scripts: Vec::new(),
+        }
+    }
+
+    pub fn deployment_target(mut self, deployment_target: impl Into<String>) -> Self {
+        self.deployment_target = deployment_target.into();
+        self
+    }
+
+    /// Add a source entry: either a plain path or a [`SourcesPath`].
+    pub fn source(mut self, source: impl Into<serde_yaml::Value>) -> Self {
+        self.sources.push(source.into());
+        self
+    }
+
+    /// Add a target build setting, e.g. `base` or `configs`.
+    pub fn setting(
+        mut self,
+        key: impl Into<String>,
+        value: impl Serialize,
+    ) -> anyhow::Result<Self> {
+        self.settings
+            .insert(key.into(), serde_yaml::to_value(value)?);
+        Ok(self)
+    }
+
+    pub fn dependency(mut self, dependency: Dependency) -> Self {
+        self.dependencies.push(dependency);
+        self
+    }
+
+    /// Replace the generated Info.plist.
+    pub fn info(mut self, info: Plist) -> Self {
+        self.info = info;
+        self
+    }
+
+    /// Add a single Info.plist property.
+    pub fn plist_property(
+        mut self,
+        key: impl Into<String>,
+        value: impl Serialize,
+    ) -> anyhow::Result<Self> {
+        self.info = self.info.property(key, value)?;
+        Ok(self)
+    }
+
+    pub fn scheme(mut self, scheme: TargetScheme) -> Self {
+        self.scheme = scheme;
+        self
+    }
+
+    pub fn post_build_script(mut self, script: BuildScript) -> Self {
+        self.post_build_scripts.push(script);
+        self
+    }
+
+    pub fn build(self) -> Target {
+        Target {
+            product_type: self.product_type,
+            platform: self.platform,
+            deployment_target: self.deployment_target,
+            sources: serde_yaml::Value::Sequence(self.sources),
+            settings: self.settings,
+            dependencies: self.dependencies,
+            info: self.info,
+            scheme: self.scheme,
+            post_build_scripts: self.post_build_scripts,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct Dependency {
+    #[serde(flatten)]
+    pub dependency_type: DependencyType,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub embed: Option<bool>,
+    #[serde(
+        rename(serialize = "codeSign"),
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub code_sign: Option<bool>,
+    /// Weak linking, for frameworks missing on older deployment targets.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub weak: Option<bool>,
+}
+
+impl Dependency {
+    pub fn sdk(name: String) -> Self {
+        Dependency {
+            dependency_type: DependencyType::Sdk(name),
+            embed: None,
+            code_sign: None,
+            weak: None,
+        }
+    }
+
+    /// A prebuilt `.framework` or `.xcframework` at `path`.
+    pub fn framework(path: String) -> Self {
+        Dependency {
+            dependency_type: DependencyType::Framework(path),
+            embed: None,
+            code_sign: None,
+            weak: None,
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn target(name: String, embed: bool) -> Self {
+        Dependency {
+            dependency_type: DependencyType::Target(name),
+            embed: Some(embed),
+            code_sign: None,
+            weak: None,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DependencyType {
+    Sdk(String),
+    Framework(String),
+    #[allow(dead_code)]
+    Target(String),
+}
+
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub struct TargetSetting {
+    #[serde(serialize_with = "bool_to_word")]
+    enable_bitcode: bool,
+    clang_cxx_language_standard: String,
+    clang_cxx_library: String,
+    other_ldflags: Vec<String>,
+    header_search_paths: Vec<String>,
+    #[serde(flatten)]
+    extra: BTreeMap<String, serde_yaml::Value>,
+}
+
+impl TargetSetting {
+    pub fn base(lib_name: &str, header_path: &str) -> Self {
+        Self {
+            enable_bitcode: false,
+            header_search_paths: vec![INHERITED.into(), header_path.into()],
+            clang_cxx_language_standard: "c++11".into(),
+            clang_cxx_library: "libc++".into(),
+            other_ldflags: vec![
+                INHERITED.into(),
+                "-lc++abi".into(),
+                format!("-l{}", lib_name),
+            ],
+            extra: BTreeMap::new(),
+        }
+    }
+
+    /// Link `<lib_name>.framework` instead of the `lib<lib_name>.a` static library.
+    pub fn link_framework(&mut self, lib_name: &str) {
+        let lib_flag = format!("-l{}", lib_name);
+        self.other_ldflags.retain(|flag| *flag != lib_flag);
+        self.other_ldflags
+            .extend(["-framework".to_string(), lib_name.to_string()]);
+    }
+
+    /// Set an additional build setting, e.g. `PRODUCT_BUNDLE_IDENTIFIER`.
+    pub fn set(&mut self, key: impl Into<String>, value: impl Into<serde_yaml::Value>) {
+        self.extra.insert(key.into(), value.into());
+    }
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct TargetConfig {
+    #[serde(
+        rename = "LIBRARY_SEARCH_PATHS[sdk=iphoneos*]",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    library_search_paths_arm: Vec<String>,
+    #[serde(
+        rename = "LIBRARY_SEARCH_PATHS[sdk=iphonesimulator*]",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    library_search_paths_sim: Vec<String>,
+    #[serde(
+        rename = "LIBRARY_SEARCH_PATHS[sdk=iphonesimulator*][arch=arm64]",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    library_search_paths_arm_sim: Vec<String>,
+    #[serde(
+        rename = "LIBRARY_SEARCH_PATHS[sdk=iphonesimulator*][arch=x86_64]",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    library_search_paths_x86: Vec<String>,
+    #[serde(
+        rename = "FRAMEWORK_SEARCH_PATHS[sdk=iphoneos*]",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    framework_search_paths_arm: Vec<String>,
+    #[serde(
+        rename = "FRAMEWORK_SEARCH_PATHS[sdk=iphonesimulator*][arch=arm64]",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    framework_search_paths_arm_sim: Vec<String>,
+    #[serde(
+        rename = "FRAMEWORK_SEARCH_PATHS[sdk=iphonesimulator*][arch=x86_64]",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    framework_search_paths_x86: Vec<String>,
+    #[serde(
+        rename = "LIBRARY_SEARCH_PATHS[sdk=appletvos*]",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    library_search_paths_tvos: Vec<String>,
+    #[serde(
+        rename = "LIBRARY_SEARCH_PATHS[sdk=appletvsimulator*]",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    library_search_paths_tvos_sim: Vec<String>,
+    #[serde(
+        rename = "FRAMEWORK_SEARCH_PATHS[sdk=appletvos*]",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    framework_search_paths_tvos: Vec<String>,
+    #[serde(
+        rename = "FRAMEWORK_SEARCH_PATHS[sdk=appletvsimulator*]",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    framework_search_paths_tvos_sim: Vec<String>,
+    #[serde(
+        rename = "LIBRARY_SEARCH_PATHS[sdk=watchos*]",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    library_search_paths_watchos: Vec<String>,
+    #[serde(
+        rename = "LIBRARY_SEARCH_PATHS[sdk=watchsimulator*]",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    library_search_paths_watchos_sim: Vec<String>,
+    #[serde(
+        rename = "FRAMEWORK_SEARCH_PATHS[sdk=watchos*]",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    framework_search_paths_watchos: Vec<String>,
+    #[serde(
+        rename = "FRAMEWORK_SEARCH_PATHS[sdk=watchsimulator*]",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    framework_search_paths_watchos_sim: Vec<String>,
+    #[serde(
+        rename = "LIBRARY_SEARCH_PATHS[sdk=macosx*][arch=arm64]",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    library_search_paths_mac_arm: Vec<String>,
+    #[serde(
+        rename = "LIBRARY_SEARCH_PATHS[sdk=macosx*][arch=x86_64]",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    library_search_paths_mac_x86: Vec<String>,
+    #[serde(
+        rename = "FRAMEWORK_SEARCH_PATHS[sdk=macosx*][arch=arm64]",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    framework_search_paths_mac_arm: Vec<String>,
+    #[serde(
+        rename = "FRAMEWORK_SEARCH_PATHS[sdk=macosx*][arch=x86_64]",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    framework_search_paths_mac_x86: Vec<String>,
+    #[serde(flatten)]
+    extra: BTreeMap<String, serde_yaml::Value>,
+}
+
+fn profile_search_paths(target: crate::toml::Target, profile: &str) -> Vec<String> {
+    vec![
+        INHERITED.into(),
+        format!("../../{}/{}", target.to_rustc_target(), profile),
+    ]
+}
+
+impl TargetConfig {
+    /// Search paths of the cargo output directory of `profile`, e.g. `target/<triple>/release`.
+    pub fn new(profile: &str) -> Self {
+        Self {
+            library_search_paths_arm: profile_search_paths(crate::toml::Target::Arm, profile),
+            library_search_paths_arm_sim: profile_search_paths(
+                crate::toml::Target::ArmSim,
+                profile,
+            ),
+            library_search_paths_x86: profile_search_paths(crate::toml::Target::Sim, profile),
+            ..Default::default()
+        }
+    }
+
+    /// Search paths of the cargo output directory of `profile` for the SDKs of `platform`.
+    pub fn for_platform(platform: crate::toml::AppPlatform, profile: &str) -> Self {
+        match platform {
+            crate::toml::AppPlatform::Ios => Self::new(profile),
+            crate::toml::AppPlatform::TvOs => Self {
+                library_search_paths_tvos: profile_search_paths(crate::toml::Target::TvOs, profile),
+                library_search_paths_tvos_sim: profile_search_paths(
+                    crate::toml::Target::TvOsSim,
+                    profile,
+                ),
+                ..Default::default()
+            },
+            crate::toml::AppPlatform::WatchOs => Self {
+                library_search_paths_watchos: profile_search_paths(
+                    crate::toml::Target::WatchOs,
+                    profile,
+                ),
+                library_search_paths_watchos_sim: profile_search_paths(
+                    crate::toml::Target::WatchOsSim,
+                    profile,
+                ),
+                ..Default::default()
+            },
+            crate::toml::AppPlatform::MacOs => Self {
+                library_search_paths_mac_arm: profile_search_paths(
+                    crate::toml::Target::Mac,
+                    profile,
+                ),
+                library_search_paths_mac_x86: profile_search_paths(
+                    crate::toml::Target::MacIntel,
+                    profile,
+                ),
+                ..Default::default()
+            },
+        }
+    }
+
+    /// Search the Mac Catalyst libraries of `profile` when building for the macOS SDK.
+    pub fn with_catalyst(mut self, profile: &str) -> Self {
+        self.library_search_paths_mac_arm =
+            profile_search_paths(crate::toml::Target::Catalyst, profile);
+        self.library_search_paths_mac_x86 =
+            profile_search_paths(crate::toml::Target::CatalystIntel, profile);
+        self
+    }
+
+    pub fn release() -> Self {
+        Self::new("release")
+    }
+
+    pub fn debug() -> Self {
+        Self::new("debug")
+    }
+
+    /// Also search the library directories for the framework of a `cdylib`.
+    pub fn with_frameworks(mut self) -> Self {
+        self.framework_search_paths_arm = self.library_search_paths_arm.clone();
+        self.framework_search_paths_arm_sim = self.library_search_paths_arm_sim.clone();
+        self.framework_search_paths_x86 = self.library_search_paths_x86.clone();
+        self.framework_search_paths_tvos = self.library_search_paths_tvos.clone();
+        self.framework_search_paths_tvos_sim = self.library_search_paths_tvos_sim.clone();
+        self.framework_search_paths_watchos = self.library_search_paths_watchos.clone();
+        self.framework_search_paths_watchos_sim = self.library_search_paths_watchos_sim.clone();
+        self.framework_search_paths_mac_arm = self.library_search_paths_mac_arm.clone();
+        self.framework_search_paths_mac_x86 = self.library_search_paths_mac_x86.clone();
+        self
+    }
+
+    /// Search the universal simulator library merged with `lipo` for the cargo `profile`
+    /// instead of per-architecture directories, so the project builds on Intel and Apple silicon.
+    pub fn with_universal_simulator(mut self, profile: &str) -> Self {
+        self.library_search_paths_sim = vec![
+            INHERITED.into(),
+            format!("../../{}/{}", crate::lipo::UNIVERSAL_SIMULATOR_DIR, profile),
+        ];
+        self.library_search_paths_arm_sim.clear();
+        self.library_search_paths_x86.clear();
+        self
+    }
+
+    /// Set an additional build setting of the configuration, e.g. `OTHER_SWIFT_FLAGS`.
+    pub fn set(&mut self, key: impl Into<String>, value: impl Into<serde_yaml::Value>) {
+        self.extra.insert(key.into(), value.into());
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct SourcesPath {
+    pub path: String,
+    /// `resources`, or a `copyFiles` phase with its destination.
+    #[serde(rename = "buildPhase")]
+    pub build_phase: serde_yaml::Value,
+    #[serde(rename = "destinationFilters", skip_serializing_if = "Option::is_none")]
+    pub destination_filters: Option<Vec<String>>,
+    /// Globs relative to `path` of the only files to add, e.g. `**/*.png`.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub includes: Vec<String>,
+    /// Globs relative to `path` of files left out, e.g. `**/*.psd`.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub excludes: Vec<String>,
+}
+
+impl SourcesPath {
+    pub fn assets_folder(path: &str) -> anyhow::Result<Self> {
+        Self::copy_files(path, "resources")
+    }
+
+    /// Copy `path` into the bundle with a `copyFiles` build phase, e.g. to `wrapper` (bundle root).
+    pub fn copy_files(path: &str, destination: &str) -> anyhow::Result<Self> {
+        let mut copy_files: HashMap<serde_yaml::Value, serde_yaml::Value> = HashMap::new();
+        copy_files.insert(
+            serde_yaml::Value::String("destination".to_string()),
+            serde_yaml::Value::String(destination.to_string()),
+        );
+        let build_phase = serde_yaml::to_value(HashMap::from([(
+            "copyFiles".to_string(),
+            serde_yaml::to_value(copy_files)?,
+        )]))?;
+
+        Ok(Self {
+            path: path.to_string(),
+            build_phase,
+            destination_filters: None,
+            includes: Vec::new(),
+            excludes: Vec::new(),
+        })
+    }
+
+    /// Add `path` to the resources build phase, so asset catalogs are compiled by actool.
+    pub fn resources(path: &str) -> Self {
+        Self {
+            path: path.to_string(),
+            build_phase: serde_yaml::Value::String("resources".to_string()),
+            destination_filters: None,
+            includes: Vec::new(),
+            excludes: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct Plist {
+    path: String,
+    properties: HashMap<String, serde_yaml::Value>,
+}
+
+impl Plist {
+    /// Create an empty plist generated at `path`.
+    pub fn new(path: impl Into<String>) -> Self {
+        Self {
+            path: path.into(),
+            properties: HashMap::new(),
+        }
+    }
+
+    pub fn property(
+        mut self,
+        key: impl Into<String>,
+        value: impl Serialize,
+    ) -> anyhow::Result<Self> {
+        self.properties
+            .insert(key.into(), serde_yaml::to_value(value)?);
+        Ok(self)
+    }
+}
+
+impl Default for Plist {
+    fn default() -> Self {
+        Self {
+            path: "../src/Info.plist".into(),
+            properties: HashMap::from([(
+                "UILaunchStoryboardName".to_string(),
+                serde_yaml::Value::String("LaunchScreen".into()),
+            )]),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct TargetScheme {
+    #[serde(rename(serialize = "environmentVariables"))]
+    pub environment_variables: Vec<EnvironmentVariable>,
+    /// Test targets run by `xcodebuild test` with this scheme.
+    #[serde(
+        rename(serialize = "testTargets"),
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub test_targets: Vec<String>,
+}
+
+impl Default for TargetScheme {
+    fn default() -> Self {
+        Self {
+            environment_variables: vec![
+                EnvironmentVariable {
+                    variable: "RUST_BACKTRACE".into(),
+                    value: "full".into(),
+                    is_enabled: true,
+                },
+                EnvironmentVariable {
+                    variable: "RUST_LOG".into(),
+                    value: "info".into(),
+                    is_enabled: true,
+                },
+            ],
+            test_targets: Vec::new(),
+        }
+    }
+}
+
+impl TargetScheme {
+    /// Add `env` to the environment variables, overriding the defaults with the same name.
+    pub fn with_env(mut self, env: BTreeMap<String, String>) -> Self {
+        for (variable, value) in env {
+            match self
+                .environment_variables
+                .iter_mut()
+                .find(|existing| existing.variable == variable)
+            {
+                Some(existing) => existing.value = value,
+                None => self.environment_variables.push(EnvironmentVariable {
+                    variable,
+                    value,
+                    is_enabled: true,
+                }),
+            }
+        }
+        self
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct EnvironmentVariable {
+    pub variable: String,
+    pub value: String,
+    #[serde(rename(serialize = "isEnabled"))]
+    pub is_enabled: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub enum ProductType {
+    #[serde(rename(serialize = "application"))]
+    Application,
+    #[serde(rename(serialize = "bundle.unit-test"))]
+    UnitTest,
+    #[serde(rename(serialize = ""))]
+    #[allow(dead_code)]
+    None,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+#[allow(dead_code)]
+pub enum Platform {
+    #[serde(rename = "iOS")]
+    Ios,
+    MacOS,
+    TvOS,
+    WatchOS,
+}
+
+impl Platform {
+    /// Name in xcodegen specs, e.g. `tvOS`.
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Platform::Ios => "iOS",
+            Platform::MacOS => "macOS",
+            Platform::TvOS => "tvOS",
+            Platform::WatchOS => "watchOS",
+        }
+    }
+}
+
+impl From<crate::toml::AppPlatform> for Platform {
+    fn from(platform: crate::toml::AppPlatform) -> Self {
+        match platform {
+            crate::toml::AppPlatform::Ios => Platform::Ios,
+            crate::toml::AppPlatform::TvOs => Platform::TvOS,
+            crate::toml::AppPlatform::WatchOs => Platform::WatchOS,
+            crate::toml::AppPlatform::MacOs => Platform::MacOS,
+        }
+    }
+}
+
+fn bool_to_word<S>(value: &bool, ser: S) -> Result<S::Ok, S::Error>
+where
+    S: serde::Serializer,
+{
+    if *value {
+        ser.serialize_str("YES")
+    } else {
+        ser.serialize_str("NO")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The project of a `my_game` package with `metadata` appended to its manifest.
+    fn project(metadata: &str) -> anyhow::Result<Project> {
+        let contents = format!(
+            "[package]\nname = \"my_game\"\nversion = \"0.1.0\"\n{}",
+            metadata
+        );
+        let toml: crate::toml::Root = toml::from_str(&contents).unwrap();
+        Project::from_toml(std::path::Path::new("/game/Cargo.toml"), toml, None)
+    }
+
+    fn project_yaml(metadata: &str) -> serde_yaml::Value {
+        serde_yaml::to_value(project(metadata).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn info_plist() {
+        let yaml = project_yaml(
+            r#"
+            [package.metadata.ios]
+            build_targets = ["aarch64-apple-ios"]
+
+            [package.metadata.ios.info_plist]
+            UIFileSharingEnabled = true
+            UILaunchStoryboardName = "Splash"
+            UIRequiredDeviceCapabilities = ["metal"]
+            "#,
+        );
+
+        let properties = &yaml["targets"]["my_game"]["info"]["properties"];
+        assert_eq!(properties["UIFileSharingEnabled"].as_bool(), Some(true));
+        assert_eq!(
+            properties["UILaunchStoryboardName"].as_str(),
+            Some("Splash")
+        );
+        assert_eq!(
+            properties["UIRequiredDeviceCapabilities"][0].as_str(),
+            Some("metal")
+        );
+    }
+
+    #[test]
+    fn url_schemes() {
+        let yaml = project_yaml(
+            r#"
+            [package.metadata.ios]
+            url_schemes = ["mygame"]
+            "#,
+        );
+
+        let url_type = &yaml["targets"]["my_game"]["info"]["properties"]["CFBundleURLTypes"][0];
+        assert_eq!(
+            url_type["CFBundleURLName"].as_str(),
+            Some("$(PRODUCT_BUNDLE_IDENTIFIER)")
+        );
+        assert_eq!(url_type["CFBundleURLSchemes"][0].as_str(), Some("mygame"));
+    }
+
+    #[test]
+    fn custom_profile_config() {
+        let yaml = project_yaml(
+            r#"
+            [package.metadata.ios]
+
+            [profile.profiling]
+            inherits = "release"
+            "#,
+        );
+
+        assert_eq!(yaml["configs"]["profiling"].as_str(), Some("release"));
+        assert_eq!(
+            yaml["targets"]["my_game"]["settings"]["configs"]["profiling"]
+                ["LIBRARY_SEARCH_PATHS[sdk=iphoneos*]"][1]
+                .as_str(),
+            Some("../../aarch64-apple-ios/profiling")
+        );
+    }
+
+    #[test]
+    fn universal_simulator() {
+        let yaml = project_yaml(
+            r#"
+            [package.metadata.ios]
+            build_targets = ["aarch64-apple-ios", "aarch64-apple-ios-sim", "x86_64-apple-ios"]
+            "#,
+        );
+
+        let release = &yaml["targets"]["my_game"]["settings"]["configs"]["release"];
+        assert_eq!(
+            release["LIBRARY_SEARCH_PATHS[sdk=iphonesimulator*]"][1].as_str(),
+            Some("../../universal-apple-ios-sim/release")
+        );
+        assert!(release
+            .get("LIBRARY_SEARCH_PATHS[sdk=iphonesimulator*][arch=arm64]")
+            .is_none());
+    }
+
+    #[test]
+    fn tvos_platform() {
+        let yaml = project_yaml(
+            r#"
+            [package.metadata.ios]
+            platform = "tvos"
+            build_targets = ["aarch64-apple-tvos", "aarch64-apple-tvos-sim"]
+            "#,
+        );
+
+        let target = &yaml["targets"]["my_game"];
+        assert_eq!(target["platform"].as_str(), Some("tvOS"));
+        let release = &target["settings"]["configs"]["release"];
+        assert_eq!(
+            release["LIBRARY_SEARCH_PATHS[sdk=appletvsimulator*]"][1].as_str(),
+            Some("../../aarch64-apple-tvos-sim/release")
+        );
+        assert!(release.get("LIBRARY_SEARCH_PATHS[sdk=iphoneos*]").is_none());
+    }
+
+    #[test]
+    fn watchos_platform() {
+        let yaml = project_yaml(
+            r#"
+            [package.metadata.ios]
+            platform = "watchos"
+            build_targets = ["arm64_32-apple-watchos", "aarch64-apple-watchos-sim"]
+            watch_companion = "com.rust.my-game"
+            "#,
+        );
+
+        let target = &yaml["targets"]["my_game"];
+        assert_eq!(target["platform"].as_str(), Some("watchOS"));
+        assert_eq!(target["deploymentTarget"].as_str(), Some("7.0"));
+        assert_eq!(
+            target["settings"]["configs"]["debug"]["LIBRARY_SEARCH_PATHS[sdk=watchos*]"][1]
+                .as_str(),
+            Some("../../arm64_32-apple-watchos/debug")
+        );
+        let properties = &target["info"]["properties"];
+        assert_eq!(properties["WKApplication"].as_bool(), Some(true));
+        assert_eq!(
+            properties["WKCompanionAppBundleIdentifier"].as_str(),
+            Some("com.rust.my-game")
+        );
+    }
+
+    #[test]
+    fn mac_catalyst() {
+        let yaml = project_yaml(
+            r#"
+            [package.metadata.ios]
+            catalyst = true
+            build_targets = ["aarch64-apple-ios", "aarch64-apple-ios-macabi"]
+            "#,
+        );
+
+        let target = &yaml["targets"]["my_game"];
+        assert_eq!(target["platform"].as_str(), Some("iOS"));
+        assert_eq!(
+            target["settings"]["base"]["SUPPORTS_MACCATALYST"].as_str(),
+            Some("YES")
+        );
+        assert_eq!(
+            target["settings"]["configs"]["release"]
+                ["LIBRARY_SEARCH_PATHS[sdk=macosx*][arch=arm64]"][1]
+                .as_str(),
+            Some("../../aarch64-apple-ios-macabi/release")
+        );
+        assert_eq!(yaml["settings"]["CODE_SIGN_IDENTITY"].as_str(), Some("-"));
+    }
+
+    #[test]
+    fn unit_test_target() {
+        let yaml = project_yaml(
+            r#"
+            [package.metadata.ios]
+            unit_tests = ["test_levels"]
+            "#,
+        );
+
+        let test_target = &yaml["targets"]["my_gameTests"];
+        assert_eq!(test_target["type"].as_str(), Some("bundle.unit-test"));
+        assert_eq!(test_target["sources"][0].as_str(), Some("../xctest/"));
+        assert_eq!(
+            yaml["targets"]["my_game"]["scheme"]["testTargets"][0].as_str(),
+            Some("my_gameTests")
+        );
+    }
+
+    #[test]
+    fn scheme_env() {
+        let yaml = project_yaml(
+            r#"
+            [package.metadata.ios.env]
+            RUST_LOG = "my_game=debug"
+            API_URL = "https://staging.example.com"
+            "#,
+        );
+
+        let variables = yaml["targets"]["my_game"]["scheme"]["environmentVariables"]
+            .as_sequence()
+            .unwrap()
+            .iter()
+            .map(|v| {
+                (
+                    v["variable"].as_str().unwrap(),
+                    v["value"].as_str().unwrap(),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            variables,
+            vec![
+                ("RUST_BACKTRACE", "full"),
+                ("RUST_LOG", "my_game=debug"),
+                ("API_URL", "https://staging.example.com"),
+            ]
+        );
+    }
+
+    #[test]
+    fn asset_catalogs() {
+        let yaml = project_yaml(
+            r#"
+            [package.metadata.ios]
+            assets = ["assets/", "ios/Assets.xcassets/"]
+            "#,
+        );
+
+        let sources = &yaml["targets"]["my_game"]["sources"];
+        assert_eq!(
+            sources[1]["buildPhase"]["copyFiles"]["destination"].as_str(),
+            Some("resources")
+        );
+        assert_eq!(
+            sources[2]["path"].as_str(),
+            Some("/game/ios/Assets.xcassets/")
+        );
+        assert_eq!(sources[2]["buildPhase"].as_str(), Some("resources"));
+    }
+
+    #[test]
+    fn asset_globs() {
+        let yaml = project_yaml(
+            r#"
+            [package.metadata.ios]
+            assets = [
+                { path = "assets", exclude = ["**/*.psd", "raw/**"] },
+                "levels/*.json",
+            ]
+            "#,
+        );
+
+        let sources = &yaml["targets"]["my_game"]["sources"];
+        assert_eq!(sources[1]["path"].as_str(), Some("/game/assets"));
+        assert_eq!(sources[1]["excludes"][0].as_str(), Some("**/*.psd"));
+        assert_eq!(sources[1]["excludes"][1].as_str(), Some("raw/**"));
+        assert!(sources[1].get("includes").is_none());
+        assert_eq!(sources[2]["path"].as_str(), Some("/game/levels"));
+        assert_eq!(sources[2]["includes"][0].as_str(), Some("*.json"));
+    }
+
+    #[test]
+    fn asset_root_globs() {
+        let error = project(
+            r#"
+            [package.metadata.ios]
+            assets = ["*.png"]
+            "#,
+        )
+        .unwrap_err()
+        .to_string();
+        assert!(error.contains("assets/*.png"));
+    }
+
+    #[test]
+    fn build_settings() {
+        let yaml = project_yaml(
+            r#"
+            [package.metadata.ios.build_settings]
+            DEAD_CODE_STRIPPING = true
+            ENABLE_BITCODE = "YES"
+
+            [package.metadata.ios.build_settings.release]
+            OTHER_SWIFT_FLAGS = ["$(inherited)", "-Osize"]
+            "#,
+        );
+
+        let settings = &yaml["targets"]["my_game"]["settings"];
+        assert_eq!(
+            settings["base"]["DEAD_CODE_STRIPPING"].as_bool(),
+            Some(true)
+        );
+        assert_eq!(settings["base"]["ENABLE_BITCODE"].as_str(), Some("YES"));
+        assert_eq!(
+            settings["configs"]["release"]["OTHER_SWIFT_FLAGS"][1].as_str(),
+            Some("-Osize")
+        );
+        assert!(settings["configs"]["debug"]
+            .get("OTHER_SWIFT_FLAGS")
+            .is_none());
+    }
+
+    #[test]
+    fn dynamic_link() {
+        let yaml = project_yaml(
+            r#"
+            [package.metadata.ios]
+            link = "dynamic"
+            "#,
+        );
+
+        let target = &yaml["targets"]["my_game"];
+        let ldflags = &target["settings"]["base"]["OTHER_LDFLAGS"];
+        assert!(!ldflags.as_sequence().unwrap().contains(&"-lmy_game".into()));
+        assert!(ldflags
+            .as_sequence()
+            .unwrap()
+            .contains(&"-framework".into()));
+        assert_eq!(
+            target["settings"]["configs"]["debug"]["FRAMEWORK_SEARCH_PATHS[sdk=iphoneos*]"][1]
+                .as_str(),
+            Some("../../aarch64-apple-ios/debug")
+        );
+        assert_eq!(
+            target["postBuildScripts"][0]["name"].as_str(),
+            Some("Embed Rust framework")
+        );
+    }
+
+    #[test]
+    fn frameworks() {
+        let yaml = project_yaml(
+            r#"
+            [package.metadata.ios]
+            frameworks = [
+                "vendor/Dynamic.xcframework",
+                { path = "vendor/Static.xcframework", embed = false },
+            ]
+            "#,
+        );
+
+        let dependencies = &yaml["targets"]["my_game"]["dependencies"];
+        assert_eq!(
+            dependencies[0]["framework"].as_str(),
+            Some("/game/vendor/Dynamic.xcframework")
+        );
+        assert!(dependencies[0].get("embed").is_none());
+        assert_eq!(dependencies[1]["embed"].as_bool(), Some(false));
+    }
+
+    #[test]
+    fn localizations() {
+        let yaml = project_yaml(
+            r#"
+            [package.metadata.ios]
+            localizations = ["de", "en"]
+            "#,
+        );
+
+        assert_eq!(yaml["options"]["developmentLanguage"].as_str(), Some("de"));
+        let properties = &yaml["targets"]["my_game"]["info"]["properties"];
+        assert_eq!(properties["CFBundleLocalizations"][1].as_str(), Some("en"));
+    }
+
+    #[test]
+    fn orientations() {
+        let yaml = project_yaml(
+            r#"
+            [package.metadata.ios]
+            supported_interface_orientations = ["landscape-left", "UIInterfaceOrientationLandscapeRight"]
+            "#,
+        );
+
+        let properties = &yaml["targets"]["my_game"]["info"]["properties"];
+        for key in [
+            "UISupportedInterfaceOrientations",
+            "UISupportedInterfaceOrientations~ipad",
+        ] {
+            assert_eq!(
+                properties[key][0].as_str(),
+                Some("UIInterfaceOrientationLandscapeLeft")
+            );
+            assert_eq!(
+                properties[key][1].as_str(),
+                Some("UIInterfaceOrientationLandscapeRight")
+            );
+        }
+        assert_eq!(properties["UIRequiresFullScreen"].as_bool(), Some(true));
+    }
+
+    #[test]
+    fn deployment_targets() {
+        let yaml = project_yaml(
+            r#"
+            [package.metadata.ios]
+            catalyst = true
+            deployment_target = { macos = "12.0" }
+            "#,
+        );
+
+        assert_eq!(
+            yaml["targets"]["my_game"]["deploymentTarget"].as_str(),
+            Some("13.1")
+        );
+        assert_eq!(
+            yaml["options"]["deploymentTarget"]["macOS"].as_str(),
+            Some("12.0")
+        );
+        assert!(yaml["options"]["deploymentTarget"].get("iOS").is_none());
+
+        assert!(project(
+            r#"
+            [package.metadata.ios]
+            deployment_target = "fifteen"
+            "#
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn project_overlay() {
+        let mut yaml = project_yaml("[package.metadata.ios]");
+        let overlay: serde_yaml::Value = serde_yaml::from_str(
+            r#"
+            options:
+              createIntermediateGroups: true
+            targets:
+              my_game:
+                deploymentTarget: "15.0"
+                sources: ["../src/", "../extra/"]
+            "#,
+        )
+        .unwrap();
+        merge_yaml(&mut yaml, overlay);
+
+        assert_eq!(yaml["options"]["bundleIdPrefix"].as_str(), Some("com.rust"));
+        assert_eq!(
+            yaml["options"]["createIntermediateGroups"].as_bool(),
+            Some(true)
+        );
+        let target = &yaml["targets"]["my_game"];
+        assert_eq!(target["deploymentTarget"].as_str(), Some("15.0"));
+        assert_eq!(target["sources"][1].as_str(), Some("../extra/"));
+        assert_eq!(target["type"].as_str(), Some("application"));
+    }
+
+    #[test]
+    fn builder_to_yaml() {
+        let target = TargetBuilder::new(ProductType::Application, Platform::Ios)
+            .deployment_target("13.0")
+            .source("../src/")
+            .dependency(Dependency::sdk("UIKit.framework".into()))
+            .plist_property("UIFileSharingEnabled", true)
+            .unwrap()
+            .build();
+        let project = ProjectBuilder::new("app")
+            .config("Debug", "debug")
+            .bundle_id_prefix("com.example")
+            .target("app", target)
+            .build();
+        let yaml = serde_yaml::to_value(&project).unwrap();
+
+        assert_eq!(yaml["name"].as_str(), Some("app"));
+        assert_eq!(yaml["configs"]["Debug"].as_str(), Some("debug"));
+        assert_eq!(
+            yaml["options"]["bundleIdPrefix"].as_str(),
+            Some("com.example")
+        );
+        let target = &yaml["targets"]["app"];
+        assert_eq!(target["type"].as_str(), Some("application"));
+        assert_eq!(target["platform"].as_str(), Some("iOS"));
+        assert_eq!(target["deploymentTarget"].as_str(), Some("13.0"));
+        assert_eq!(target["sources"][0].as_str(), Some("../src/"));
+        assert_eq!(
+            target["dependencies"][0]["sdk"].as_str(),
+            Some("UIKit.framework")
+        );
+        assert_eq!(
+            target["info"]["properties"]["UIFileSharingEnabled"].as_bool(),
+            Some(true)
+        );
+    }
+}
@@ -0,0 +1,139 @@
+// Flags handled by cargo-xcodebuild itself. They are removed from the argument list,
+// so that the rest can be passed to cargo.
+
+/// Remove `name` from `args`. Returns `true` if it was present.
+pub fn take_flag(args: &mut Vec<String>, name: &str) -> bool {
+    let len = args.len();
+    args.retain(|arg| arg != name);
+    args.len() != len
+}
+
+/// Remove `name value` or `name=value` from `args` and return the value.
+pub fn take_value(args: &mut Vec<String>, name: &str) -> anyhow::Result<Option<String>> {
+    let prefix = format!("{}=", name);
+    if let Some(index) = args.iter().position(|arg| arg.starts_with(&prefix)) {
+        let arg = args.remove(index);
+        return Ok(Some(arg[prefix.len()..].to_string()));
+    }
+
+    if let Some(index) = args.iter().position(|arg| arg == name) {
+        if index + 1 >= args.len() {
+            anyhow::bail!("Missing value for `{}`", name)
+        }
+        let value = args.remove(index + 1);
+        args.remove(index);
+        return Ok(Some(value));
+    }
+
+    Ok(None)
+}
+
+/// Remove `name`, `name value` or `name=value` from `args`. Returns `Some(None)` if `name` is
+/// followed by another flag or nothing.
+pub fn take_optional_value(args: &mut Vec<String>, name: &str) -> Option<Option<String>> {
+    let prefix = format!("{}=", name);
+    if let Some(index) = args.iter().position(|arg| arg.starts_with(&prefix)) {
+        let arg = args.remove(index);
+        return Some(Some(arg[prefix.len()..].to_string()));
+    }
+
+    let index = args.iter().position(|arg| arg == name)?;
+    args.remove(index);
+    if index < args.len() && !args[index].starts_with('-') {
+        Some(Some(args.remove(index)))
+    } else {
+        Some(None)
+    }
+}
+
+/// Remove `--` and everything after it from `args`. Returns the arguments after `--`.
+pub fn take_trailing(args: &mut Vec<String>) -> Vec<String> {
+    match args.iter().position(|arg| arg == "--") {
+        Some(index) => args.split_off(index).split_off(1),
+        None => vec![],
+    }
+}
+
+/// `-1` for `-q`/`--quiet`, otherwise the number of `-v`/`--verbose` flags (`-vv` counts twice).
+/// The flags stay in `args`, so that cargo gets them too.
+pub fn verbosity(args: &[String]) -> i8 {
+    let mut verbosity = 0;
+    for arg in args.iter().take_while(|arg| *arg != "--") {
+        match arg.as_str() {
+            "-q" | "--quiet" => return -1,
+            "--verbose" => verbosity += 1,
+            _ if arg.len() > 1 && arg.starts_with('-') && arg[1..].chars().all(|c| c == 'v') => {
+                verbosity += arg.len() as i8 - 1
+            }
+            _ => {}
+        }
+    }
+    verbosity
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|a| a.to_string()).collect()
+    }
+
+    #[test]
+    fn verbosity_flags() {
+        assert_eq!(verbosity(&args(&["build", "--release"])), 0);
+        assert_eq!(verbosity(&args(&["build", "-v", "--verbose"])), 2);
+        assert_eq!(verbosity(&args(&["run", "-vv", "--", "-v"])), 2);
+        assert_eq!(verbosity(&args(&["build", "-v", "--quiet"])), -1);
+        assert_eq!(verbosity(&args(&["run", "--", "-q"])), 0);
+    }
+
+    #[test]
+    fn flags() {
+        let mut a = args(&["--json", "--release", "--json"]);
+        assert!(take_flag(&mut a, "--json"));
+        assert!(!take_flag(&mut a, "--json"));
+        assert_eq!(a, args(&["--release"]));
+    }
+
+    #[test]
+    fn values() {
+        let mut a = args(&["--lane", "beta", "--release", "--device=XXXX"]);
+        assert_eq!(take_value(&mut a, "--lane").unwrap(), Some("beta".into()));
+        assert_eq!(take_value(&mut a, "--device").unwrap(), Some("XXXX".into()));
+        assert_eq!(take_value(&mut a, "--team").unwrap(), None);
+        assert_eq!(a, args(&["--release"]));
+
+        let mut a = args(&["--lane"]);
+        assert!(take_value(&mut a, "--lane").is_err());
+    }
+
+    #[test]
+    fn optional_values() {
+        let mut a = args(&["--device", "--release"]);
+        assert_eq!(take_optional_value(&mut a, "--device"), Some(None));
+        assert_eq!(a, args(&["--release"]));
+
+        let mut a = args(&["--device", "XXXX", "--release"]);
+        assert_eq!(
+            take_optional_value(&mut a, "--device"),
+            Some(Some("XXXX".into()))
+        );
+        assert_eq!(a, args(&["--release"]));
+
+        let mut a = args(&["--release", "--device=XXXX"]);
+        assert_eq!(
+            take_optional_value(&mut a, "--device"),
+            Some(Some("XXXX".into()))
+        );
+        assert_eq!(take_optional_value(&mut a, "--device"), None);
+    }
+
+    #[test]
+    fn trailing() {
+        let mut a = args(&["--release", "--", "--level", "3", "--"]);
+        assert_eq!(take_trailing(&mut a), args(&["--level", "3", "--"]));
+        assert_eq!(a, args(&["--release"]));
+        assert!(take_trailing(&mut a).is_empty());
+    }
+}
@@ -1,12 +1,10 @@
 use anyhow::Context;
 
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+mod args;
+#[cfg(target_os = "macos")]
 mod cargo;
-mod devices;
-mod source;
-mod teams;
-mod toml;
-mod xcodebuild;
-mod xcodegen;
+mod remote;
 
 fn print_help() {
     println!(
@@ -14,30 +12,190 @@ fn print_help() {
 Helps cargo build and run apps for iOS
 
 USAGE:
-    cargo xcodebuild [SUBCOMMAND]
+    cargo xcodebuild [OPTIONS] [SUBCOMMAND]
+
+OPTIONS:
+    --remote <host>         Sync the workspace to another Mac over SSH and run the subcommand there
+    --remote-dir <path>     Directory on the remote Mac, `~/cargo-xcodebuild-remote/<workspace>` by default
+    --message-format <fmt>  `human` (default) or `json`: print progress as newline-delimited JSON events
+    -q, --quiet             Only show errors and warnings of cargo-xcodebuild, cargo and xcodebuild
+    -v, --verbose           Show info logs and unchanged xcodebuild output, `-vv` for debug and `-vvv` for trace logs
+    --raw-xcodebuild-output Show xcodebuild output unchanged instead of condensed compile, link and sign steps
+    -p, --package <name>    Workspace member to build, with the `[lib]` and `[package.metadata.ios]` sections
+    --flavor <name>         Build flavor from `[package.metadata.ios.flavors]`, e.g. `staging`
+    --team <id>             Signing team id, overrides `development_team` (see `cargo xcodebuild teams`)
 
 SUBCOMMAND:
-    check, c     Checks that the current package builds without creating xcodeproject
-    build, b     Compiles the current package and create xcodeproject
-    run, r       Run a project on device or simulator
-    generate, g  Generate xcodeproject without building it
-    open, o      Open generated project with Xcode
-    devices, d   List of booted simulator and connected devices
-    teams, t     List of signing teams
-    boot [id]    Boot a simulator with specific device id
+    check, c          Checks that the current package builds without creating xcodeproject
+    build, b          Compiles the current package and create xcodeproject
+                      --open: open the project with Xcode after a successful build
+    run, r            Run a project on device or simulator
+                      build and run options:
+                      --scheme <name>: Xcode scheme to build
+                      --configuration <name>: Xcode configuration to build
+                      --build-number <n>: `CFBundleVersion` of the app
+                      --bump-build-number: use the next number from `build_number` in Cargo.toml
+                      --device <udid>: device or simulator to use instead of `device_id` in Cargo.toml
+                      --device-name <name>: device or simulator to use by name, e.g. `iPhone 15 Pro`
+                      --device, --simulator: only use a connected device or a booted simulator
+                      --mac: run a Mac Catalyst app on this Mac
+                      --small: size-optimized release build, `build` prints the app size delta
+                      --save-size-baseline: `build` saves the app size to compare `--small` builds to
+                      --profile <name>: custom cargo profile, built with the Xcode configuration of the same name
+                      --features <list>, --no-default-features, --all-features: cargo features of every target
+                      run options (simulators only, settings persist on the simulator):
+                      --content-size <category>: Dynamic Type size, e.g. `accessibility-large`
+                      --bold-text, --no-bold-text: turn Bold Text on or off
+                      --reduce-motion, --no-reduce-motion: turn Reduce Motion on or off
+                      --monitor [--monitor-for <duration>]: print CPU, memory and GPU usage of the app until it exits, or record a trace on devices
+                      --locale <id>, --language <code>: launch with `AppleLocale` and `AppleLanguages`, e.g. `de_DE` and `de`
+                      --detach: don't forward app stdout and stderr, return right after launch
+                      --clean-status-bar, --no-clean-status-bar: show 9:41, full battery and signal on the simulator
+                      arguments after `--` are passed to the app, e.g. `-- --level 3`
+    watch             Run the app, then rebuild and relaunch it when `src`, `assets` or Cargo.toml change
+                      accepts build and run options, the app output is not forwarded
+    debug             Build, install and launch the app stopped, then attach lldb with Rust pretty-printers
+                      accepts build options, `--locale`, `--language` and app arguments after `--`
+    archive           Build a release archive for devices and export a signed .ipa for the App Store
+                      accepts build options, `--bump-build-number` is the default (`--no-bump-build-number`)
+    test              Run the tests of the crate on a simulator, wrapped into a minimal host app
+                      arguments after `--` are passed to the test harness, e.g. `-- --nocapture`
+    xcframework       Build all targets in release and bundle the static libraries into an .xcframework
+                      with `bindings.h` as the `RustBindings` module, for existing Xcode projects
+    upload [ipa]      Upload the .ipa of the last archive to App Store Connect for TestFlight
+    generate, g       Generate xcodeproject without building it
+                      --open: open the generated project with Xcode
+    ide               Print rust-analyzer settings for VS Code that check the crate for an iOS target
+                      --write: merge them into `.vscode/settings.json`
+    doctor            Check Xcode, command line tools, xcodegen, rust targets, signing identity and a booted simulator
+    preflight         Check app icons, launch screen and Info.plist keys before submitting to the App Store
+    lint              Check the last built app for plist keys, minimum OS, usage descriptions, encryption declaration and privacy manifest
+    open, o           Open generated project with Xcode
+                      --with <app>: open with a specific Xcode or editor and remember the choice
+    devices, d        List of booted simulator and connected devices
+                      --watch: keep running and print device and simulator changes
+                      --all: list simulators in any state grouped by runtime
+                      --json: print all simulators and connected devices as JSON
+    teams, t          List of signing teams
+                      --json: print teams with `team_id` and certificate expiry as JSON
+    profiles          List installed provisioning profiles with app id, team, expiry and devices
+                      --bundle-id <id>: only profiles whose app id covers the bundle id
+                      --json: print them as JSON
+    data save <name>  Save the data container of the last built app on its simulator
+                      data restore <name>: replace it with a saved snapshot
+                      data list: list saved snapshots
+    uninstall         Remove the last built app from its simulator or device
+    logs              Stream console output of the last built app from its simulator or device
+    push <payload>    Send a push notification JSON payload to the last built app on its simulator
+    openurl <url>     Open a URL scheme or universal link on the simulator or device of the last build
+    screenshot        Save a screenshot of the simulator or device of the last build to the given path or a timestamped file
+    record            Record a video of the simulator of the last build to the given path or a timestamped file until Enter is pressed
+    boot [id]         Boot a simulator with specific device id
+    simulators        Manage simulators
+                      simulators create <device type> [runtime] [--name <name>]: create a simulator, e.g.
+                      `"iPhone 15" iOS17.4`, and print its udid. The newest runtime and the device type name by default
+                      simulators erase <udid>|--all: erase content and settings, shutting booted ones down
+                      simulators shutdown <udid>|--all: shut down a booted simulator or all of them
+    gc                Remove stale generated projects, derived data and simulators created by cargo-xcodebuild
+                      --older-than <age>: only remove items older than `age`, e.g. `7d`. 30 days by default
+    clean             Remove generated projects and their derived data
+                      --cargo: also run `cargo clean` for the configured build targets
+    notarize [path]   Notarize and staple a signed macOS or Mac Catalyst .app or .dmg
+    screenshots       Capture App Store screenshots on simulators configured in `screenshots`
+    upload-symbols    Upload dSYMs of the last archive with `symbol_upload` steps
+    thin [archive]    Export thinned IPAs from an .xcarchive and print per-variant sizes
+                      --device-model <model>: thin for a single device, e.g. `iPhone10,1`
+    fastlane          Print build outputs for fastlane as JSON
+                      --env: print them as shell exports instead
+                      --run [--lane <lane>]: run a fastlane lane (`fastlane_lane` from Cargo.toml by default)
 "#
     );
 }
 
-#[cfg(not(target_os = "macos"))]
 fn main() {
-    compile_error!("Unfortunately, only MacOS is supported.");
+    if let Err(err) = try_main() {
+        eprintln!("Error: {:?}", err);
+        std::process::exit(exit_code(&err));
+    }
 }
 
+/// Exit code of the failed cargo, xcodegen, xcodebuild or simctl step, otherwise 1.
 #[cfg(target_os = "macos")]
-fn main() -> anyhow::Result<()> {
-    env_logger::try_init().with_context(|| "Failed to init env_logger".to_string())?;
+fn exit_code(err: &anyhow::Error) -> i32 {
+    xcb::process::exit_code(err).unwrap_or(1)
+}
+
+#[cfg(not(target_os = "macos"))]
+fn exit_code(_err: &anyhow::Error) -> i32 {
+    1
+}
 
+fn try_main() -> anyhow::Result<()> {
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().map(String::as_str) == Some("xcodebuild") {
+        args.remove(0);
+    }
+    // `RUST_LOG` still takes precedence.
+    let log_level = match args::verbosity(&args) {
+        i8::MIN..=-1 => "error",
+        0 => "warn",
+        1 => "info",
+        2 => "debug",
+        _ => "trace",
+    };
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or(log_level))
+        .try_init()
+        .with_context(|| "Failed to init env_logger".to_string())?;
+    if let Some(host) = args::take_value(&mut args, "--remote")? {
+        let remote_dir = args::take_value(&mut args, "--remote-dir")?;
+        return remote::run(&host, remote_dir.as_deref(), &args);
+    }
+
+    run_local()
+}
+
+#[cfg(target_os = "macos")]
+fn print_size_delta(baseline: Option<u64>, current: u64) {
+    let mb = |bytes: u64| bytes as f64 / 1024.0 / 1024.0;
+    match baseline {
+        Some(baseline) => println!(
+            "App size: {:.2} MB ({:+.2} MB compared to the baseline)",
+            mb(current),
+            mb(current) - mb(baseline)
+        ),
+        None => println!(
+            "App size: {:.2} MB. Save it as the baseline with `--save-size-baseline`",
+            mb(current)
+        ),
+    }
+}
+
+/// `Some(true)` for `--name`, `Some(false)` for `--no-name`.
+#[cfg(target_os = "macos")]
+fn switch(args: &mut Vec<String>, name: &str) -> Option<bool> {
+    let disabled = args::take_flag(args, &name.replacen("--", "--no-", 1));
+    if args::take_flag(args, name) {
+        Some(true)
+    } else if disabled {
+        Some(false)
+    } else {
+        None
+    }
+}
+
+#[cfg(not(target_os = "macos"))]
+fn run_local() -> anyhow::Result<()> {
+    if std::env::args().any(|arg| arg == "--help") {
+        print_help();
+        return Ok(());
+    }
+    anyhow::bail!(
+        "Unfortunately, only MacOS is supported. Use `--remote <host>` to build on a Mac over SSH."
+    )
+}
+
+#[cfg(target_os = "macos")]
+fn run_local() -> anyhow::Result<()> {
     let args = std::env::args();
     let cmd = cargo_subcommand::Subcommand::new(args, "xcodebuild", |_, _| Ok(false));
     if cmd.is_err() {
@@ -45,65 +203,503 @@ fn main() -> anyhow::Result<()> {
         std::process::exit(0);
     }
     let cmd = cmd?;
-    let mut xcode_build = xcodebuild::Xcodebuild::new(cmd.manifest(), cmd.target_dir())?;
+    let mut args = cmd.args().to_vec();
+    // Passed to the app by `run`, `watch` and `debug`, and to the harness by `test`.
+    let trailing_args = args::take_trailing(&mut args);
+    // In a workspace the manifest of the selected member holds `[lib]` and the iOS metadata.
+    let package = match args::take_value(&mut args, "--package")? {
+        Some(package) => Some(package),
+        None => args::take_value(&mut args, "-p")?,
+    };
+    let manifest = match package {
+        Some(ref package) => xcb::cargo::package_manifest(cmd.manifest(), package)?,
+        None => cmd.manifest().to_path_buf(),
+    };
+    let mut xcode_build = xcb::xcodebuild::Xcodebuild::new(&manifest, cmd.target_dir())?;
     let build_type = match *cmd.profile() {
-        cargo_subcommand::Profile::Dev => crate::xcodebuild::BuildType::Debug,
-        cargo_subcommand::Profile::Release => crate::xcodebuild::BuildType::Release,
-        cargo_subcommand::Profile::Custom(ref str) => {
-            log::warn!("Custrom Profile: `{}`. Return debug build type", str);
-            crate::xcodebuild::BuildType::Debug
+        cargo_subcommand::Profile::Dev => xcb::xcodebuild::BuildType::Debug,
+        cargo_subcommand::Profile::Release => xcb::xcodebuild::BuildType::Release,
+        // Built with the Xcode configuration of the same name, see `BuildOptions::profile`.
+        cargo_subcommand::Profile::Custom(_) => xcb::xcodebuild::BuildType::Debug,
+    };
+
+    if let Some(message_format) = args::take_value(&mut args, "--message-format")? {
+        let message_format = message_format.parse()?;
+        xcode_build.set_message_format(message_format);
+        if message_format == xcb::events::MessageFormat::Json {
+            // Cargo messages are part of the stream too.
+            args.push("--message-format=json".into());
         }
+    }
+    // `-q` and `-v` are passed on to cargo as well.
+    xcode_build.set_verbosity(match args::verbosity(&args) {
+        i8::MIN..=-1 => xcb::events::Verbosity::Quiet,
+        0 => xcb::events::Verbosity::Normal,
+        _ => xcb::events::Verbosity::Verbose,
+    });
+    if args::take_flag(&mut args, "--raw-xcodebuild-output") {
+        xcode_build.set_raw_xcodebuild_output(true);
+    }
+    if let Some(package) = package {
+        // Only build the selected member with cargo.
+        args.push("--package".into());
+        args.push(package);
+    }
+    if let Some(flavor) = args::take_value(&mut args, "--flavor")? {
+        xcode_build.set_flavor(flavor);
+    }
+    if let Some(team) = args::take_value(&mut args, "--team")? {
+        xcode_build.set_team(team);
+    }
+    let build_options = match cmd.cmd() {
+        "build" | "b" | "run" | "r" | "watch" | "debug" | "archive" | "test" => {
+            let device = args::take_optional_value(&mut args, "--device");
+            let device_name = args::take_value(&mut args, "--device-name")?;
+            let device_type = match (&device, args::take_flag(&mut args, "--simulator")) {
+                (Some(None), true) => {
+                    anyhow::bail!("`--device` and `--simulator` can't be used together")
+                }
+                (Some(None), false) => Some(xcb::toml::DeviceType::Device),
+                (_, true) => Some(xcb::toml::DeviceType::Simulator),
+                (_, false) => None,
+            };
+            let device_type = if args::take_flag(&mut args, "--mac") {
+                anyhow::ensure!(
+                    device.is_none() && device_name.is_none() && device_type.is_none(),
+                    "`--mac` can't be used with `--device`, `--device-name` or `--simulator`"
+                );
+                Some(xcb::toml::DeviceType::Mac)
+            } else {
+                device_type
+            };
+            let build_number = args::take_value(&mut args, "--build-number")?;
+            // App Store Connect requires a new build number for every upload.
+            let bump_build_number =
+                switch(&mut args, "--bump-build-number").unwrap_or(cmd.cmd() == "archive");
+            let save_build_number = build_number.is_none() && bump_build_number;
+            xcb::xcodebuild::BuildOptions {
+                scheme: args::take_value(&mut args, "--scheme")?,
+                configuration: args::take_value(&mut args, "--configuration")?,
+                build_number: match (build_number, bump_build_number) {
+                    (Some(build_number), _) => Some(build_number),
+                    (None, true) => Some(xcode_build.next_build_number()?),
+                    (None, false) => None,
+                },
+                save_build_number,
+                small: args::take_flag(&mut args, "--small"),
+                device: match (device, device_name) {
+                    (Some(Some(device_id)), _) => Some(xcode_build.select_device(&device_id)?),
+                    (_, Some(name)) => Some(xcode_build.select_device_by_name(&name, device_type)?),
+                    _ => None,
+                },
+                device_type,
+                profile: match *cmd.profile() {
+                    cargo_subcommand::Profile::Custom(ref profile) => Some(profile.clone()),
+                    _ => None,
+                },
+            }
+        }
+        _ => Default::default(),
+    };
+    let run_options = match cmd.cmd() {
+        "run" | "r" | "watch" => xcb::xcodebuild::RunOptions {
+            content_size: args::take_value(&mut args, "--content-size")?,
+            bold_text: switch(&mut args, "--bold-text"),
+            reduce_motion: switch(&mut args, "--reduce-motion"),
+            monitor: args::take_flag(&mut args, "--monitor"),
+            monitor_duration: args::take_value(&mut args, "--monitor-for")?
+                .map(|duration| xcb::monitor::parse_duration(&duration))
+                .transpose()?,
+            console: !args::take_flag(&mut args, "--detach"),
+            clean_status_bar: switch(&mut args, "--clean-status-bar"),
+            locale: args::take_value(&mut args, "--locale")?,
+            language: args::take_value(&mut args, "--language")?,
+            args: trailing_args.clone(),
+        },
+        "debug" => xcb::xcodebuild::RunOptions {
+            locale: args::take_value(&mut args, "--locale")?,
+            language: args::take_value(&mut args, "--language")?,
+            args: trailing_args.clone(),
+            ..Default::default()
+        },
+        _ => Default::default(),
     };
 
     match cmd.cmd() {
         "check" | "c" => {
-            xcode_build.check(cmd.args())?;
+            xcode_build.check(&args)?;
         }
         "build" | "b" => {
-            xcode_build.build(cmd.args(), build_type)?;
+            let open = args::take_flag(&mut args, "--open");
+            let small = build_options.small;
+            let save_size_baseline = args::take_flag(&mut args, "--save-size-baseline");
+            let result = xcode_build.build_with(&args, build_type, build_options);
+            xcode_build.emit(xcb::events::Event::BuildFinished {
+                success: result.is_ok(),
+            });
+            let artifacts = result?;
+            if small || save_size_baseline {
+                let size = artifacts.app_size()?;
+                if save_size_baseline {
+                    xcode_build.save_size_baseline(size)?;
+                    println!(
+                        "Saved {:.2} MB as the size baseline",
+                        size as f64 / 1024.0 / 1024.0
+                    );
+                } else {
+                    print_size_delta(xcode_build.size_baseline()?, size);
+                }
+            }
+            if open {
+                xcode_build.open_xcode(None)?;
+            }
         }
         "run" | "r" => {
-            xcode_build.build(cmd.args(), build_type)?;
-            xcode_build.run(build_type)?;
+            let result = xcode_build.build_with(&args, build_type, build_options);
+            xcode_build.emit(xcb::events::Event::BuildFinished {
+                success: result.is_ok(),
+            });
+            xcode_build.run_with(&result?, &run_options)?;
+        }
+        "watch" => {
+            xcode_build.watch(&args, build_type, build_options, &run_options)?;
+        }
+        "debug" => {
+            let result = xcode_build.build_with(&args, build_type, build_options);
+            xcode_build.emit(xcb::events::Event::BuildFinished {
+                success: result.is_ok(),
+            });
+            xcode_build.debug(&result?, &run_options)?;
+        }
+        "archive" => {
+            let result = xcode_build.archive(&args, build_options);
+            xcode_build.emit(xcb::events::Event::BuildFinished {
+                success: result.is_ok(),
+            });
+            println!("Exported {:?}", result?.ipa_path);
+        }
+        "test" => {
+            let exit_code = xcode_build.test(&args, &trailing_args, build_options.device)?;
+            if exit_code != 0 {
+                std::process::exit(exit_code);
+            }
+        }
+        "xcframework" => {
+            let output = xcode_build.xcframework(&args)?;
+            println!("Created {:?}", output);
+        }
+        "upload" => {
+            let ipa_path = args.get(0).map(std::path::Path::new);
+            let ipa_path = xcode_build.upload(ipa_path)?;
+            println!(
+                "Uploaded {:?}. It will be available in TestFlight after processing.",
+                ipa_path
+            );
+        }
+        "ide" => {
+            let write = args::take_flag(&mut args, "--write");
+            let settings = xcode_build.ide_settings(&args)?;
+            if write {
+                let path = cmd
+                    .manifest()
+                    .parent()
+                    .unwrap()
+                    .join(".vscode/settings.json");
+                settings.write_vscode_settings(&path)?;
+                println!("rust-analyzer settings are written to {:?}", path);
+            } else {
+                println!(
+                    "{}",
+                    serde_json::to_string_pretty(&settings.to_vscode_json())?
+                );
+            }
+        }
+        "preflight" | "lint" => {
+            let violations = if cmd.cmd() == "lint" {
+                xcode_build.lint()?
+            } else {
+                xcode_build.preflight()?
+            };
+            if violations.is_empty() {
+                println!("No App Store {} issues found", cmd.cmd());
+            } else {
+                for violation in &violations {
+                    println!("[ ] {}", violation);
+                }
+                anyhow::bail!("Found {} App Store {} issues", violations.len(), cmd.cmd())
+            }
+        }
+        "doctor" => {
+            let diagnostics = xcode_build.doctor()?;
+            for diagnostic in &diagnostics {
+                match diagnostic.fix {
+                    None => println!("[x] {}", diagnostic),
+                    Some(ref fix) => println!("[ ] {}\n    Fix: {}", diagnostic, fix),
+                }
+            }
+            let failed = diagnostics.iter().filter(|d| !d.is_ok()).count();
+            if failed > 0 {
+                anyhow::bail!("Found {} problems with the development environment", failed)
+            }
+        }
+        "data" => {
+            let action = args.get(0).map(String::as_str);
+            let name = args.get(1);
+            match (action, name) {
+                (Some("save"), Some(name)) => {
+                    let archive = xcode_build.save_app_data(name)?;
+                    println!("App data is saved to {:?}", archive);
+                }
+                (Some("restore"), Some(name)) => {
+                    xcode_build.restore_app_data(name)?;
+                    println!("App data is restored from `{}`", name);
+                }
+                (Some("list"), _) => {
+                    for name in xcode_build.app_data_snapshots()? {
+                        println!("{}", name);
+                    }
+                }
+                _ => print_help(),
+            }
+        }
+        "simulators" => {
+            let all = args::take_flag(&mut args, "--all");
+            match args.get(0).map(String::as_str) {
+                Some("create") => {
+                    // `--name <name>` may come before the positional arguments.
+                    let name = args::take_value(&mut args, "--name")?;
+                    let device_type = match args.get(1) {
+                        Some(device_type) => device_type,
+                        None => anyhow::bail!(
+                            "Usage: cargo xcodebuild simulators create <device type> [runtime] [--name <name>]"
+                        ),
+                    };
+                    let udid = xcb::xcodebuild::Xcodebuild::create_simulator(
+                        device_type,
+                        args.get(2).map(String::as_str),
+                        name.as_deref(),
+                    )?;
+                    println!("{}", udid);
+                }
+                Some("erase") if all => {
+                    xcb::xcodebuild::Xcodebuild::erase_simulator(None)?;
+                    println!("Erased all simulators");
+                }
+                Some("erase") if args.len() > 1 => {
+                    xcb::xcodebuild::Xcodebuild::erase_simulator(Some(&args[1]))?;
+                    println!("Erased {}", args[1]);
+                }
+                Some("shutdown") if all => {
+                    xcb::xcodebuild::Xcodebuild::shutdown_simulator(None)?;
+                    println!("Shut down all simulators");
+                }
+                Some("shutdown") if args.len() > 1 => {
+                    xcb::xcodebuild::Xcodebuild::shutdown_simulator(Some(&args[1]))?;
+                    println!("Shut down {}", args[1]);
+                }
+                _ => print_help(),
+            }
+        }
+        "push" => match args.get(0) {
+            Some(payload) => {
+                xcode_build.push(std::path::Path::new(payload))?;
+                println!("Sent {}", payload);
+            }
+            None => print_help(),
+        },
+        "openurl" => match args.get(0) {
+            Some(url) => xcode_build.open_url(url)?,
+            None => print_help(),
+        },
+        "screenshot" => {
+            let path = xcode_build.screenshot(args.get(0).map(std::path::Path::new))?;
+            println!("Screenshot is saved to {:?}", path);
+        }
+        "record" => {
+            let path = xcode_build.record(args.get(0).map(std::path::Path::new))?;
+            println!("Recording is saved to {:?}", path);
+        }
+        "uninstall" => {
+            xcode_build.uninstall()?;
+        }
+        "logs" => {
+            xcode_build.logs()?;
         }
         "generate" | "g" => {
             xcode_build.generate_project()?;
+            if args::take_flag(&mut args, "--open") {
+                xcode_build.open_xcode(None)?;
+            }
         }
         "devices" | "d" => {
-            let simulators = xcodebuild::Xcodebuild::get_simulator_device_list()?;
-            println!("Booted simulators: ");
-            for device in simulators {
-                if device.state == crate::devices::DeviceState::Booted {
+            let watch = args::take_flag(&mut args, "--watch");
+            if args::take_flag(&mut args, "--json") {
+                if watch {
+                    anyhow::bail!("`--json` can't be combined with `--watch`")
+                }
+                let devices = xcb::xcodebuild::Xcodebuild::get_all_devices()?;
+                println!("{}", serde_json::to_string_pretty(&devices)?);
+            } else if args::take_flag(&mut args, "--all") {
+                let simulators = xcb::xcodebuild::Xcodebuild::get_all_simulators()?;
+                for (runtime, simulators) in xcb::devices::group_by_runtime(&simulators) {
+                    println!("{}:", runtime);
+                    for s in simulators {
+                        print!("    {} ({}) {:?}", s.name, s.udid, s.state);
+                        match (s.is_available, &s.availability_error) {
+                            (true, _) => println!(),
+                            (false, Some(error)) => println!(", unavailable: {}", error),
+                            (false, None) => println!(", unavailable"),
+                        }
+                    }
+                }
+
+                let devices = md::get_device_list();
+                println!("Connected devices: {}", devices.len());
+                for device in devices {
+                    println!("{:?}", device);
+                }
+            } else {
+                let simulators = xcb::xcodebuild::Xcodebuild::get_simulator_device_list()?;
+                println!("Booted simulators: ");
+                for device in simulators {
+                    if device.state == xcb::devices::DeviceState::Booted {
+                        println!("{:?}", device);
+                    }
+                }
+
+                let devices = md::get_device_list();
+                println!("Connected devices: {}", devices.len());
+                for device in devices {
                     println!("{:?}", device);
                 }
             }
 
-            let devices = md::get_device_list();
-            println!("Connected devices: {}", devices.len());
-            for device in devices {
-                println!("{:?}", device);
+            if watch {
+                println!("Watching for device changes. Press Ctrl+C to stop.");
+                xcb::xcodebuild::Xcodebuild::watch_devices(
+                    std::time::Duration::from_secs(1),
+                    |event| {
+                        println!("{}", event);
+                        Ok(())
+                    },
+                )?;
             }
         }
         "teams" | "t" => {
-            println!("Signing teams:");
-            let teams = teams::find_development_teams();
-            for t in teams {
-                println!("{:?}", t);
+            let teams = xcb::teams::find_development_teams();
+            if args::take_flag(&mut args, "--json") {
+                println!("{}", serde_json::to_string_pretty(&teams)?);
+            } else {
+                println!("Signing teams:");
+                for t in teams {
+                    println!("{:?}", t);
+                }
+            }
+        }
+        "profiles" => {
+            let mut profiles = xcb::signing::ProvisioningProfile::installed()?;
+            if let Some(bundle_id) = args::take_value(&mut args, "--bundle-id")? {
+                profiles.retain(|profile| profile.matches_bundle_id(&bundle_id));
+            }
+            if args::take_flag(&mut args, "--json") {
+                println!("{}", serde_json::to_string_pretty(&profiles)?);
+            } else if profiles.is_empty() {
+                println!("No provisioning profiles found");
+            } else {
+                for profile in profiles {
+                    println!("{}\n", profile);
+                }
             }
         }
         "boot" => {
-            if let Some(arg) = cmd.args().get(0) {
+            if let Some(arg) = args.get(0) {
                 xcode_build.boot_simulator(arg)?;
             } else {
                 println!("Simulator device id is required. List of avaliable devices:");
-                let devices = xcodebuild::Xcodebuild::get_simulator_device_list()?;
+                let devices = xcb::xcodebuild::Xcodebuild::get_simulator_device_list()?;
                 for d in devices {
                     println!("{:?}", d)
                 }
                 std::process::exit(1);
             }
         }
-        "open" | "o" => xcode_build.open_xcode()?,
+        "open" | "o" => {
+            let with = args::take_value(&mut args, "--with")?;
+            xcode_build.open_xcode(with.as_deref().map(std::path::Path::new))?
+        }
+        "gc" => {
+            let older_than =
+                args::take_value(&mut args, "--older-than")?.unwrap_or_else(|| "30d".into());
+            let removed = xcode_build.gc(xcb::gc::parse_age(&older_than)?)?;
+            println!("Removed: {}", removed.len());
+            for r in removed {
+                println!("{}", r);
+            }
+        }
+        "clean" => {
+            let cargo = args::take_flag(&mut args, "--cargo");
+            let removed = xcode_build.clean(&args, cargo)?;
+            println!("Removed: {}", removed.len());
+            for r in removed {
+                println!("{}", r);
+            }
+        }
+        "notarize" => {
+            let path = match args.get(0) {
+                Some(path) => std::path::PathBuf::from(path),
+                None => anyhow::bail!("Path to .app or .dmg is required"),
+            };
+            let distributable = xcode_build.notarize(&path)?;
+            println!("Notarized: {:?}", distributable);
+        }
+        "screenshots" => {
+            let dir = xcode_build.screenshots(&args, build_type)?;
+            println!("Screenshots are saved to {:?}", dir);
+        }
+        "upload-symbols" => {
+            let artifacts = xcode_build.last_archive_artifacts()?;
+            xcode_build.upload_symbols(&artifacts.dsym_paths()?)?;
+        }
+        "thin" => {
+            let device_model = args::take_value(&mut args, "--device-model")?;
+            let archive_path = match args.get(0) {
+                Some(path) => std::path::PathBuf::from(path),
+                None => anyhow::bail!("Path to .xcarchive is required"),
+            };
+            let variants = xcode_build.export_thinned(&archive_path, device_model.as_deref())?;
+            println!("App thinning variants: {}", variants.len());
+            for variant in variants {
+                println!(
+                    "{}\n    {}\n    App size: {}",
+                    variant.name, variant.descriptors, variant.app_size
+                );
+            }
+        }
+        "fastlane" => {
+            let env = args::take_flag(&mut args, "--env");
+            let run = args::take_flag(&mut args, "--run");
+            let lane = args::take_value(&mut args, "--lane")?;
+
+            let artifacts = xcode_build.last_build_artifacts()?;
+            let mut context = xcb::fastlane::FastlaneContext::from_artifacts(&artifacts);
+            context.ipa_path = xcode_build
+                .last_archive_artifacts()
+                .ok()
+                .map(|archive| archive.ipa_path);
+            if run {
+                let lane = match lane.or(xcode_build.ios_metadata()?.fastlane_lane) {
+                    Some(lane) => lane,
+                    None => anyhow::bail!(
+                        "Lane is not specified. Use `--lane` or `fastlane_lane` in Cargo.toml"
+                    ),
+                };
+                context.run_lane(manifest.parent().unwrap(), &lane)?;
+            } else if env {
+                print!("{}", context.shell_exports());
+            } else {
+                println!("{}", serde_json::to_string_pretty(&context)?);
+            }
+        }
         "--help" => {
             if let Some(arg) = cmd.args().get(0) {
                 match &**arg {
@@ -0,0 +1,148 @@
+use anyhow::Context as _;
+use std::path::{Path, PathBuf};
+
+/// Sync the workspace to `host` over SSH and run `cargo xcodebuild <args>` there.
+/// Output of the remote command is streamed to the terminal.
+pub fn run(host: &str, remote_dir: Option<&str>, args: &[String]) -> anyhow::Result<()> {
+    let mut args = args.to_vec();
+    let manifest_path = match crate::args::take_value(&mut args, "--manifest-path")? {
+        Some(path) => PathBuf::from(path),
+        None => std::env::current_dir()?.join("Cargo.toml"),
+    };
+    let manifest_path = manifest_path
+        .canonicalize()
+        .with_context(|| format!("Failed to find manifest: {:?}", manifest_path))?;
+    let workspace_root = workspace_root(&manifest_path)?;
+    let remote_root = match remote_dir {
+        Some(dir) => home_relative(dir),
+        None => format!(
+            "cargo-xcodebuild-remote/{}",
+            workspace_root
+                .file_name()
+                .map(|n| n.to_string_lossy().into_owned())
+                .unwrap_or_default()
+        ),
+    };
+
+    sync(&workspace_root, host, &remote_root)?;
+
+    let manifest_dir = manifest_path
+        .parent()
+        .unwrap()
+        .strip_prefix(&workspace_root)
+        .unwrap_or_else(|_| Path::new(""));
+    let remote_manifest_dir = Path::new(&remote_root).join(manifest_dir);
+    let command = remote_command(&remote_manifest_dir, &args);
+    log::info!("Running on {}: {}", host, command);
+    let status = std::process::Command::new("ssh")
+        .arg(host)
+        .arg(&command)
+        .status()
+        .with_context(|| format!("Failed to run ssh {}", host))?;
+
+    if !status.success() {
+        // The remote command printed its error already, keep its exit code for CI.
+        eprintln!("Error: Remote command failed on {}: {}", host, status);
+        std::process::exit(status.code().unwrap_or(1));
+    }
+
+    Ok(())
+}
+
+fn workspace_root(manifest_path: &Path) -> anyhow::Result<PathBuf> {
+    let output = std::process::Command::new("cargo")
+        .arg("locate-project")
+        .arg("--workspace")
+        .arg("--message-format")
+        .arg("plain")
+        .arg("--manifest-path")
+        .arg(manifest_path)
+        .output()
+        .with_context(|| "Failed to run cargo locate-project".to_string())?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("Failed to find workspace root:\n{:?}", stderr)
+    }
+
+    let root_manifest = PathBuf::from(String::from_utf8_lossy(&output.stdout).trim());
+    Ok(root_manifest.parent().unwrap().to_path_buf())
+}
+
+fn sync(workspace_root: &Path, host: &str, remote_root: &str) -> anyhow::Result<()> {
+    log::info!("Syncing {:?} to {}:{}", workspace_root, host, remote_root);
+    let status = std::process::Command::new("ssh")
+        .arg(host)
+        .arg(format!("mkdir -p {}", shell_quote(remote_root)))
+        .status()
+        .with_context(|| format!("Failed to run ssh {}", host))?;
+    if !status.success() {
+        anyhow::bail!("Failed to create {} on {}: {}", remote_root, host, status)
+    }
+
+    let status = std::process::Command::new("rsync")
+        .arg("-az")
+        .arg("--delete")
+        .arg("--exclude")
+        .arg("/target")
+        .arg("--exclude")
+        .arg(".git")
+        .arg(format!("{}/", workspace_root.display()))
+        .arg(format!("{}:{}/", host, remote_root))
+        .status()
+        .with_context(|| "Failed to run rsync".to_string())?;
+    if !status.success() {
+        anyhow::bail!("Failed to sync sources to {}: {}", host, status)
+    }
+
+    Ok(())
+}
+
+fn remote_command(manifest_dir: &Path, args: &[String]) -> String {
+    let mut command = format!(
+        "cd {} && cargo xcodebuild",
+        shell_quote(&manifest_dir.to_string_lossy())
+    );
+    for arg in args {
+        command.push(' ');
+        command.push_str(&shell_quote(arg));
+    }
+
+    command
+}
+
+/// Strip a leading `~/` from `dir`. Quoting disables `~` expansion, but `ssh` and `rsync` resolve
+/// relative paths against the home directory anyway.
+fn home_relative(dir: &str) -> String {
+    match dir {
+        "~" => ".".to_string(),
+        _ => dir.strip_prefix("~/").unwrap_or(dir).to_string(),
+    }
+}
+
+/// Same as `xcb::shell::quote`, which isn't available here: remote mode also runs on Linux, where
+/// the core crate doesn't build.
+fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', r"'\''"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn command() {
+        let args = vec!["run".to_string(), "--release".into(), "it's".into()];
+        assert_eq!(
+            remote_command(Path::new("cargo-xcodebuild-remote/game/ios"), &args),
+            r#"cd 'cargo-xcodebuild-remote/game/ios' && cargo xcodebuild 'run' '--release' 'it'\''s'"#
+        );
+    }
+
+    #[test]
+    fn remote_dirs() {
+        assert_eq!(home_relative("~/src/game"), "src/game");
+        assert_eq!(home_relative("~"), ".");
+        assert_eq!(home_relative("/Users/ci/game"), "/Users/ci/game");
+    }
+}
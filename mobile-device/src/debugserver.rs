@@ -0,0 +1,139 @@
+//! Minimal client of the GDB remote serial protocol spoken by `debugserver`.
+
+use crate::service::ServiceConnection;
+use anyhow::Context;
+
+pub(crate) struct Debugserver {
+    connection: ServiceConnection,
+    buf: Vec<u8>,
+}
+
+impl Debugserver {
+    pub(crate) fn new(connection: ServiceConnection) -> Self {
+        Self {
+            connection,
+            buf: Vec::new(),
+        }
+    }
+
+    /// Send a packet and wait for its reply.
+    pub(crate) fn request(&mut self, data: &str) -> anyhow::Result<String> {
+        log::trace!("debugserver <- {}", data);
+        self.connection.send(&encode_packet(data))?;
+        loop {
+            if let Some((reply, consumed)) = decode_packet(&self.buf) {
+                self.buf.drain(..consumed);
+                self.connection.send(b"+")?;
+                log::trace!("debugserver -> {}", reply);
+                return Ok(reply);
+            }
+            let mut chunk = [0; 1024];
+            let received = self
+                .connection
+                .receive(&mut chunk)
+                .with_context(|| format!("No reply to `{}`", data))?;
+            self.buf.extend_from_slice(&chunk[..received]);
+        }
+    }
+
+    /// Launch the executable at `path` on the device, then detach to let it run.
+    pub(crate) fn launch(&mut self, path: &str) -> anyhow::Result<()> {
+        let reply = self.request(&launch_packet(&[path]))?;
+        if reply != "OK" {
+            anyhow::bail!("debugserver failed to start {}: {}", path, reply)
+        }
+        let reply = self.request("qLaunchSuccess")?;
+        if reply != "OK" {
+            anyhow::bail!("Failed to launch {}: {}", path, reply)
+        }
+        self.request("D")?;
+
+        Ok(())
+    }
+}
+
+/// How long [`forward`] waits for data from one side before checking the other.
+const POLL_INTERVAL: std::time::Duration = std::time::Duration::from_millis(10);
+
+/// Accept a single debugger connection on `listener` and forward it to `connection` in both
+/// directions until either side closes it. Both sides are polled from this thread, the
+/// connection doesn't support sending and receiving concurrently.
+pub(crate) fn forward(
+    connection: ServiceConnection,
+    listener: std::net::TcpListener,
+) -> anyhow::Result<()> {
+    use std::io::{Read as _, Write as _};
+
+    let (mut client, _) = listener
+        .accept()
+        .with_context(|| "Failed to accept the debugger".to_string())?;
+    client.set_read_timeout(Some(POLL_INTERVAL))?;
+    connection.set_receive_timeout(POLL_INTERVAL)?;
+
+    let mut chunk = [0; 16 * 1024];
+    loop {
+        match client.read(&mut chunk) {
+            Ok(0) => break,
+            Ok(received) => connection.send(&chunk[..received])?,
+            Err(err)
+                if matches!(
+                    err.kind(),
+                    std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut
+                ) => {}
+            Err(err) => return Err(err.into()),
+        }
+        // Stops when the device closes the connection.
+        if let Some(received) = connection.try_receive(&mut chunk)? {
+            client.write_all(&chunk[..received])?;
+        }
+    }
+    Ok(())
+}
+
+fn encode_packet(data: &str) -> Vec<u8> {
+    let checksum = data.bytes().fold(0u8, |sum, b| sum.wrapping_add(b));
+    format!("${}#{:02x}", data, checksum).into_bytes()
+}
+
+/// Payload of the first complete packet in `buf` and the number of bytes it takes.
+/// Acknowledgements before the packet are skipped.
+fn decode_packet(buf: &[u8]) -> Option<(String, usize)> {
+    let start = buf.iter().position(|&b| b == b'$')?;
+    let end = start + buf[start..].iter().position(|&b| b == b'#')?;
+    if buf.len() < end + 3 {
+        return None;
+    }
+    let payload = String::from_utf8_lossy(&buf[start + 1..end]).into_owned();
+    Some((payload, end + 3))
+}
+
+/// `A` packet with the hex encoded program path and arguments.
+fn launch_packet(args: &[&str]) -> String {
+    let args = args
+        .iter()
+        .enumerate()
+        .map(|(i, arg)| {
+            let hex = arg
+                .bytes()
+                .map(|b| format!("{:02x}", b))
+                .collect::<String>();
+            format!("{},{},{}", hex.len(), i, hex)
+        })
+        .collect::<Vec<_>>()
+        .join(",");
+    format!("A{}", args)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn packets() {
+        assert_eq!(encode_packet("qLaunchSuccess"), b"$qLaunchSuccess#a5");
+        assert_eq!(decode_packet(b"+$OK#9a"), Some(("OK".into(), 7)));
+        assert_eq!(decode_packet(b"+$OK#9"), None);
+        assert_eq!(decode_packet(b"+"), None);
+        assert_eq!(launch_packet(&["/a b"]), "A8,0,2f612062");
+    }
+}
@@ -5,6 +5,7 @@ use core_foundation::{
 };
 
 pub type AMDeviceRef = *const raw::c_void;
+pub type AMDServiceConnectionRef = *const raw::c_void;
 
 extern "C" {
     pub fn AMDCreateDeviceList() -> CFArrayRef;
@@ -37,4 +38,53 @@ extern "C" {
         callback: *const raw::c_void,
         cbarg: *const raw::c_void,
     ) -> raw::c_int;
+    pub fn AMDeviceSecureUninstallApplication(
+        zero: raw::c_int,
+        device: AMDeviceRef,
+        bundle_id: CFStringRef,
+        unknown: raw::c_int,
+        callback: *const raw::c_void,
+        cbarg: *const raw::c_void,
+    ) -> raw::c_int;
+    pub fn AMDeviceSecureStartService(
+        device: AMDeviceRef,
+        service_name: CFStringRef,
+        options: CFDictionaryRef,
+        service: *mut AMDServiceConnectionRef,
+    ) -> raw::c_int;
+    pub fn AMDeviceLookupApplications(
+        device: AMDeviceRef,
+        options: CFDictionaryRef,
+        result: *mut CFDictionaryRef,
+    ) -> raw::c_int;
+    pub fn AMDServiceConnectionSend(
+        service: AMDServiceConnectionRef,
+        data: *const raw::c_void,
+        size: usize,
+    ) -> isize;
+    pub fn AMDServiceConnectionReceive(
+        service: AMDServiceConnectionRef,
+        data: *mut raw::c_void,
+        size: usize,
+    ) -> isize;
+    pub fn AMDServiceConnectionInvalidate(service: AMDServiceConnectionRef);
+    pub fn AMDServiceConnectionGetSocket(service: AMDServiceConnectionRef) -> raw::c_int;
+
+    pub fn setsockopt(
+        socket: raw::c_int,
+        level: raw::c_int,
+        name: raw::c_int,
+        value: *const raw::c_void,
+        len: u32,
+    ) -> raw::c_int;
+}
+
+pub const SOL_SOCKET: raw::c_int = 0xffff;
+pub const SO_RCVTIMEO: raw::c_int = 0x1006;
+
+/// `struct timeval` of macOS.
+#[repr(C)]
+pub struct Timeval {
+    pub tv_sec: i64,
+    pub tv_usec: i32,
 }
@@ -1,13 +1,15 @@
 use anyhow::Context;
 use core_foundation::{
-    array::{CFArrayGetCount, CFArrayGetValues},
+    array::{CFArray, CFArrayGetCount, CFArrayGetValues},
     base::{CFRange, TCFType},
-    dictionary::CFDictionary,
-    string::CFString,
+    dictionary::{CFDictionary, CFDictionaryGetValueIfPresent, CFDictionaryRef},
+    string::{CFString, CFStringRef},
 };
-use std::mem;
 
+mod debugserver;
 mod ffi;
+mod screenshotr;
+mod service;
 
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
 pub enum DeviceConnectionType {
@@ -92,6 +94,184 @@ impl MobileDevice {
         Ok(())
     }
 
+    pub fn uninstall_app(&self, bundle_id: &str) -> anyhow::Result<()> {
+        log::trace!(
+            "Uninstalling app {} from device {}",
+            bundle_id,
+            self.identifier
+        );
+        unsafe {
+            let session = self.start_session()?;
+            check_native_return_code(ffi::AMDeviceSecureUninstallApplication(
+                0,
+                self.ptr,
+                CFString::new(bundle_id).as_concrete_TypeRef(),
+                0,
+                std::ptr::null(),
+                std::ptr::null(),
+            ))
+            .with_context(|| "UninstallApp.AMDeviceSecureUninstallApplication".to_string())?;
+            drop(session);
+        }
+
+        Ok(())
+    }
+
+    /// Launch an installed app with `debugserver` and detach from it.
+    /// Requires the Developer Disk Image to be mounted, e.g. by Xcode.
+    pub fn launch_app(&self, bundle_id: &str) -> anyhow::Result<()> {
+        log::trace!("Launching app {} on device {}", bundle_id, self.identifier);
+        let executable = self.app_executable_path(bundle_id)?;
+
+        let session = self.start_session()?;
+        let connection = self.start_debugserver()?;
+        drop(session);
+
+        debugserver::Debugserver::new(connection)
+            .launch(&executable)
+            .with_context(|| format!("LaunchApp. {}", bundle_id))
+    }
+
+    /// Forward a local TCP port to `debugserver` on the device, for `lldb` to connect to with
+    /// `process connect connect://127.0.0.1:<port>`. Returns the port. A single connection is
+    /// accepted and forwarded in a background thread until either side closes it.
+    /// Requires the Developer Disk Image to be mounted, e.g. by Xcode.
+    pub fn forward_debugserver(&self) -> anyhow::Result<u16> {
+        let session = self.start_session()?;
+        let connection = self.start_debugserver()?;
+        drop(session);
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0")
+            .with_context(|| "Failed to listen for the debugger".to_string())?;
+        let port = listener.local_addr()?.port();
+        log::trace!("Forwarding 127.0.0.1:{} to debugserver", port);
+        std::thread::spawn(move || {
+            if let Err(err) = debugserver::forward(connection, listener) {
+                log::debug!("debugserver forwarding stopped: {:?}", err);
+            }
+        });
+
+        Ok(port)
+    }
+
+    fn start_debugserver(&self) -> anyhow::Result<service::ServiceConnection> {
+        // Secure proxy since iOS 14, plain service before.
+        self.start_service("com.apple.debugserver.DVTSecureSocketProxy")
+            .or_else(|_| self.start_service("com.apple.debugserver"))
+            .with_context(|| {
+                "Failed to start debugserver. Is the Developer Disk Image mounted? Open Xcode > Window > Devices and Simulators with the device connected.".to_string()
+            })
+    }
+
+    /// Stream the device syslog, calling `on_line` for every line until the connection closes.
+    pub fn stream_syslog(&self, mut on_line: impl FnMut(&str)) -> anyhow::Result<()> {
+        let session = self.start_session()?;
+        let connection = self
+            .start_service("com.apple.syslog_relay")
+            .with_context(|| "StreamSyslog".to_string())?;
+        drop(session);
+
+        let mut pending = Vec::new();
+        let mut chunk = [0; 4096];
+        loop {
+            let received = connection.receive(&mut chunk)?;
+            // Messages are separated by NUL bytes.
+            pending.extend(chunk[..received].iter().filter(|&&b| b != 0));
+            while let Some(end) = pending.iter().position(|&b| b == b'\n') {
+                let line = pending.drain(..=end).collect::<Vec<_>>();
+                on_line(String::from_utf8_lossy(&line).trim_end());
+            }
+        }
+    }
+
+    /// Take a screenshot and return it as PNG or TIFF data, depending on the device.
+    /// Requires the Developer Disk Image to be mounted, e.g. by Xcode.
+    pub fn screenshot(&self) -> anyhow::Result<Vec<u8>> {
+        log::trace!("Taking screenshot of device {}", self.identifier);
+        let session = self.start_session()?;
+        let connection = self
+            .start_service("com.apple.mobile.screenshotr")
+            .with_context(|| {
+                "Failed to start screenshotr. Is the Developer Disk Image mounted? Open Xcode > Window > Devices and Simulators with the device connected.".to_string()
+            })?;
+        drop(session);
+
+        screenshotr::Screenshotr::new(connection)
+            .screenshot()
+            .with_context(|| "Screenshot".to_string())
+    }
+
+    fn start_service(
+        &self,
+        service_name: &'static str,
+    ) -> anyhow::Result<service::ServiceConnection> {
+        let mut connection = std::ptr::null();
+        unsafe {
+            check_native_return_code(ffi::AMDeviceSecureStartService(
+                self.ptr,
+                CFString::from_static_string(service_name).as_concrete_TypeRef(),
+                std::ptr::null(),
+                &mut connection,
+            ))
+            .with_context(|| format!("AMDeviceSecureStartService {}", service_name))?;
+        }
+        log::trace!("Started {}", service_name);
+
+        Ok(service::ServiceConnection::from_raw_ptr(connection))
+    }
+
+    /// Path of the bundle of an installed app on the device.
+    pub fn app_bundle_path(&self, bundle_id: &str) -> anyhow::Result<String> {
+        let (path, _) = self.lookup_app(bundle_id)?;
+        Ok(path)
+    }
+
+    /// Path of the main executable of an installed app on the device.
+    fn app_executable_path(&self, bundle_id: &str) -> anyhow::Result<String> {
+        let (path, executable) = self.lookup_app(bundle_id)?;
+        Ok(format!("{}/{}", path, executable))
+    }
+
+    /// Bundle path and executable name of an installed app.
+    fn lookup_app(&self, bundle_id: &str) -> anyhow::Result<(String, String)> {
+        let attributes = CFArray::from_CFTypes(&[
+            CFString::from_static_string("CFBundleIdentifier"),
+            CFString::from_static_string("CFBundleExecutable"),
+            CFString::from_static_string("Path"),
+        ]);
+        let options = [(
+            CFString::from_static_string("ReturnAttributes"),
+            attributes.as_CFType(),
+        )];
+        let options = CFDictionary::from_CFType_pairs(&options);
+
+        unsafe {
+            let session = self.start_session()?;
+            let mut apps: CFDictionaryRef = std::ptr::null();
+            check_native_return_code(ffi::AMDeviceLookupApplications(
+                self.ptr,
+                options.as_concrete_TypeRef(),
+                &mut apps,
+            ))
+            .with_context(|| "LookupApp.AMDeviceLookupApplications".to_string())?;
+            drop(session);
+            let apps: CFDictionary = TCFType::wrap_under_create_rule(apps);
+
+            let app = dictionary_value(apps.as_concrete_TypeRef(), &CFString::new(bundle_id))
+                .with_context(|| format!("App {} is not installed", bundle_id))?
+                as CFDictionaryRef;
+            let path = dictionary_value(app, &CFString::from_static_string("Path"))
+                .with_context(|| format!("App {} has no Path", bundle_id))?;
+            let executable =
+                dictionary_value(app, &CFString::from_static_string("CFBundleExecutable"))
+                    .with_context(|| format!("App {} has no CFBundleExecutable", bundle_id))?;
+            let path = CFString::wrap_under_get_rule(path as CFStringRef);
+            let executable = CFString::wrap_under_get_rule(executable as CFStringRef);
+
+            Ok((path.to_string(), executable.to_string()))
+        }
+    }
+
     fn from_raw_ptr(ptr: ffi::AMDeviceRef) -> anyhow::Result<Self> {
         unsafe {
             check_native_return_code(ffi::AMDeviceConnect(ptr))
@@ -158,13 +338,30 @@ impl MobileDevice {
                 log::error!("Value from property `{}` is null", property);
                 return None;
             }
-            let value: CFString = TCFType::wrap_under_get_rule(mem::transmute(raw_value));
+            let value = CFString::wrap_under_get_rule(raw_value as CFStringRef);
 
             Some(format!("{}", value))
         }
     }
 }
 
+unsafe fn dictionary_value(
+    dictionary: CFDictionaryRef,
+    key: &CFString,
+) -> Option<*const std::os::raw::c_void> {
+    let mut value = std::ptr::null();
+    let present = CFDictionaryGetValueIfPresent(
+        dictionary,
+        key.as_concrete_TypeRef() as *const _,
+        &mut value,
+    );
+    if present == 0 || value.is_null() {
+        None
+    } else {
+        Some(value)
+    }
+}
+
 /// Return a list of connected devices
 pub fn get_device_list() -> Vec<MobileDevice> {
     unsafe {
@@ -208,6 +405,9 @@ fn check_native_return_code(code: std::os::raw::c_int) -> anyhow::Result<()> {
         0xe800000b => {
             anyhow::bail!("Not connected to the device. kAMDNotConnectedError")
         }
+        0xe8000022 => {
+            anyhow::bail!("The service is invalid. kAMDInvalidServiceError")
+        }
         0xe8008021 => {
             anyhow::bail!(
                 "The maximum number of apps for free development profiles has been reached."
@@ -0,0 +1,146 @@
+//! Minimal client of the DeviceLink protocol spoken by `com.apple.mobile.screenshotr`.
+
+use crate::service::ServiceConnection;
+use anyhow::Context;
+use core_foundation::{
+    array::CFArray,
+    base::{CFType, TCFType},
+    data::CFData,
+    dictionary::CFDictionary,
+    propertylist::{self, CFPropertyList},
+    string::CFString,
+};
+
+pub(crate) struct Screenshotr {
+    connection: ServiceConnection,
+    buf: Vec<u8>,
+}
+
+impl Screenshotr {
+    pub(crate) fn new(connection: ServiceConnection) -> Self {
+        Self {
+            connection,
+            buf: Vec::new(),
+        }
+    }
+
+    /// Take a screenshot and return it as PNG or TIFF data, depending on the device.
+    pub(crate) fn screenshot(&mut self) -> anyhow::Result<Vec<u8>> {
+        self.handshake()?;
+
+        let request = CFDictionary::from_CFType_pairs(&[(
+            CFString::from_static_string("MessageType"),
+            CFString::from_static_string("ScreenShotRequest").as_CFType(),
+        )]);
+        self.send(&[
+            CFString::from_static_string("DLMessageProcessMessage").as_CFType(),
+            request.as_CFType(),
+        ])?;
+
+        let reply = self.receive()?;
+        let reply = reply
+            .get(1)
+            .and_then(|value| value.downcast::<CFDictionary>())
+            .with_context(|| "Unexpected screenshot reply".to_string())?;
+        let data = unsafe {
+            crate::dictionary_value(
+                reply.as_concrete_TypeRef(),
+                &CFString::from_static_string("ScreenShotData"),
+            )
+            .map(|data| CFType::wrap_under_get_rule(data))
+        }
+        .and_then(|data| data.downcast::<CFData>())
+        .with_context(|| "Screenshot reply has no ScreenShotData".to_string())?;
+
+        Ok(data.bytes().to_vec())
+    }
+
+    /// Accept the protocol version of the device and wait until it is ready.
+    fn handshake(&mut self) -> anyhow::Result<()> {
+        let versions = self.receive()?;
+        if message_name(&versions).as_deref() != Some("DLMessageVersionExchange") {
+            anyhow::bail!("Expected DLMessageVersionExchange")
+        }
+        let major = versions
+            .get(1)
+            .map(|major| major.clone())
+            .with_context(|| "DLMessageVersionExchange has no version".to_string())?;
+        self.send(&[
+            CFString::from_static_string("DLMessageVersionExchange").as_CFType(),
+            CFString::from_static_string("DLVersionsOk").as_CFType(),
+            major,
+        ])?;
+
+        let ready = self.receive()?;
+        if message_name(&ready).as_deref() != Some("DLMessageDeviceReady") {
+            anyhow::bail!("Expected DLMessageDeviceReady")
+        }
+
+        Ok(())
+    }
+
+    fn send(&self, message: &[CFType]) -> anyhow::Result<()> {
+        let message = CFArray::from_CFTypes(message);
+        let plist = propertylist::create_data(
+            message.as_CFTypeRef(),
+            propertylist::kCFPropertyListBinaryFormat_v1_0,
+        )
+        .map_err(|err| anyhow::anyhow!("Failed to serialize message: {}", err))?;
+        self.connection.send(&encode_frame(plist.bytes()))
+    }
+
+    fn receive(&mut self) -> anyhow::Result<CFArray<CFType>> {
+        loop {
+            if let Some((payload, consumed)) = decode_frame(&self.buf) {
+                let payload = payload.to_vec();
+                self.buf.drain(..consumed);
+                let (plist, _) = propertylist::create_with_data(
+                    CFData::from_buffer(&payload),
+                    propertylist::kCFPropertyListImmutable,
+                )
+                .map_err(|err| anyhow::anyhow!("Failed to parse message: {}", err))?;
+                let message = unsafe { CFPropertyList::wrap_under_create_rule(plist) }
+                    .downcast_into::<CFArray>()
+                    .with_context(|| "Message is not an array".to_string())?;
+                return Ok(unsafe { CFArray::wrap_under_get_rule(message.as_concrete_TypeRef()) });
+            }
+            let mut chunk = [0; 64 * 1024];
+            let received = self.connection.receive(&mut chunk)?;
+            self.buf.extend_from_slice(&chunk[..received]);
+        }
+    }
+}
+
+/// First element of a DeviceLink message, e.g. `DLMessageDeviceReady`.
+fn message_name(message: &CFArray<CFType>) -> Option<String> {
+    let name = message.get(0)?.downcast::<CFString>()?;
+    Some(name.to_string())
+}
+
+/// Message prefixed with its big-endian length.
+fn encode_frame(data: &[u8]) -> Vec<u8> {
+    let mut frame = (data.len() as u32).to_be_bytes().to_vec();
+    frame.extend_from_slice(data);
+    frame
+}
+
+/// Payload of the first complete frame in `buf` and the number of bytes it takes.
+fn decode_frame(buf: &[u8]) -> Option<(&[u8], usize)> {
+    let len = u32::from_be_bytes(buf.get(..4)?.try_into().ok()?) as usize;
+    let payload = buf.get(4..4 + len)?;
+    Some((payload, 4 + len))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frames() {
+        let frame = encode_frame(b"bplist00");
+        assert_eq!(frame, b"\0\0\0\x08bplist00");
+        assert_eq!(decode_frame(&frame), Some((&b"bplist00"[..], 12)));
+        assert_eq!(decode_frame(&frame[..10]), None);
+        assert_eq!(decode_frame(b"\0\0"), None);
+    }
+}
@@ -0,0 +1,84 @@
+use crate::ffi;
+use core_foundation::base::CFRelease;
+
+/// Connection to a lockdown service started with `AMDeviceSecureStartService`.
+pub(crate) struct ServiceConnection(ffi::AMDServiceConnectionRef);
+
+// SAFETY: MobileDevice doesn't tie a service connection to the thread that started it, so it can
+// be moved to another thread. It is not `Sync`: `send` and `receive` of the same connection are
+// never called concurrently, which the SSL context of secure services doesn't support.
+unsafe impl Send for ServiceConnection {}
+
+impl ServiceConnection {
+    pub(crate) fn from_raw_ptr(ptr: ffi::AMDServiceConnectionRef) -> Self {
+        Self(ptr)
+    }
+
+    pub(crate) fn send(&self, data: &[u8]) -> anyhow::Result<()> {
+        let sent =
+            unsafe { ffi::AMDServiceConnectionSend(self.0, data.as_ptr() as *const _, data.len()) };
+        if sent != data.len() as isize {
+            anyhow::bail!("Failed to send {} bytes to service: {}", data.len(), sent)
+        }
+        Ok(())
+    }
+
+    pub(crate) fn receive(&self, buf: &mut [u8]) -> anyhow::Result<usize> {
+        match self.try_receive(buf)? {
+            Some(received) => Ok(received),
+            None => anyhow::bail!("Timed out receiving from service"),
+        }
+    }
+
+    /// Like [`ServiceConnection::receive`], but `None` if nothing arrived within the timeout set
+    /// with [`ServiceConnection::set_receive_timeout`].
+    pub(crate) fn try_receive(&self, buf: &mut [u8]) -> anyhow::Result<Option<usize>> {
+        let received = unsafe {
+            ffi::AMDServiceConnectionReceive(self.0, buf.as_mut_ptr() as *mut _, buf.len())
+        };
+        if received <= 0 {
+            let err = std::io::Error::last_os_error();
+            if received < 0 && err.kind() == std::io::ErrorKind::WouldBlock {
+                return Ok(None);
+            }
+            anyhow::bail!("Failed to receive from service: {} ({})", received, err)
+        }
+        Ok(Some(received as usize))
+    }
+
+    /// Stop waiting in [`ServiceConnection::try_receive`] after `timeout`.
+    pub(crate) fn set_receive_timeout(&self, timeout: std::time::Duration) -> anyhow::Result<()> {
+        let timeval = ffi::Timeval {
+            tv_sec: timeout.as_secs() as i64,
+            tv_usec: timeout.subsec_micros() as i32,
+        };
+        let result = unsafe {
+            let socket = ffi::AMDServiceConnectionGetSocket(self.0);
+            ffi::setsockopt(
+                socket,
+                ffi::SOL_SOCKET,
+                ffi::SO_RCVTIMEO,
+                &timeval as *const ffi::Timeval as *const _,
+                std::mem::size_of::<ffi::Timeval>() as u32,
+            )
+        };
+        if result != 0 {
+            anyhow::bail!(
+                "Failed to set the receive timeout of the service: {}",
+                std::io::Error::last_os_error()
+            )
+        }
+        Ok(())
+    }
+}
+
+impl Drop for ServiceConnection {
+    fn drop(&mut self) {
+        unsafe {
+            if !self.0.is_null() {
+                ffi::AMDServiceConnectionInvalidate(self.0);
+                CFRelease(self.0);
+            }
+        }
+    }
+}